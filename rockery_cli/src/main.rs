@@ -0,0 +1,633 @@
+use std::{path::PathBuf, process::ExitCode, str::FromStr, thread, time::Duration};
+
+use rockery_datamodel::prelude::*;
+
+const USAGE : &str = "Usage: rockery-cli <project file> [options] <command>
+
+Commands:
+    list                              List services, methods, test suites and environments
+    method <service> <method>         Send a single request
+    test-case <suite> <test case>     Run a single test case
+    suite <suite>                     Run a test suite
+    all                               Run all test suites
+    mock <service>                    Serve the mock responses of a service until the process is stopped
+    load-method <service> <method>    Load test a method
+    load-test-case <suite> <test case>
+                                      Load test a test case
+    record                            Record requests sent through a local http proxy into the project
+                                      until enter is pressed
+    docs <file>                       Write the api documentation of the project, the format is taken
+                                      from the extension (.md or .html)
+    sync <service>                    Read the definition of a service again, print the differences and
+                                      merge them into the project
+
+Options:
+    -e, --environment <name>          Select an environment of the project
+    -D, --variable <name>=<value>     Set a variable, may be given several times
+    -o, --output <format>             Output format: text (default), json, junit, html or har
+    -r, --report <file>               Write a report of the test run, the format is taken from the
+                                      extension (.xml for JUnit, .json, .html or .har), may be given several times
+    -a, --address <host:port>         Address of the mock server or proxy, defaults to 127.0.0.1:8080
+    --users <n>                       Number of concurrent virtual users of a load test, defaults to 10
+    --duration <seconds>              Duration of a load test, defaults to 10 seconds
+    --iterations <n>                  Run a load test for a number of iterations instead of a duration
+    --ramp-up <seconds>               Time until all virtual users have been started
+    --rate <n>                        Maximum number of iterations per second of all virtual users
+    --assertions                      Derive assertions for recorded methods from their first response
+    --responses                       Include the first recorded response of every method into the documentation
+    --no-history                      Do not record the requests into the history of the project
+    --dry-run                         Only print the differences found by sync, do not change the project
+    --remove-missing                  Let sync remove methods that are no longer part of the definition
+    -q, --quiet                       Only print a summary
+    -v, --verbose                     Print the responses of all requests
+    -h, --help                        Print this help
+
+Exit codes: 0 if everything passed, 1 if a request or test failed, 2 on invalid arguments or projects.";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum OutputFormat {
+    Text,
+    Report(RockeryReportFormat),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+enum Verbosity {
+    Quiet,
+    Normal,
+    Verbose,
+}
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    List,
+    Method(String, String),
+    TestCase(String, String),
+    Suite(String),
+    All,
+    Mock(String),
+    Record,
+    Load(RockeryLoadTarget),
+    Docs(PathBuf),
+    Sync(String),
+}
+
+#[derive(Debug, PartialEq)]
+struct Options {
+    project : PathBuf,
+    environment : Option<String>,
+    variables : Vec<(String, String)>,
+    format : OutputFormat,
+    reports : Vec<PathBuf>,
+    address : String,
+    assertions : bool,
+    responses : bool,
+    history : bool,
+    dry_run : bool,
+    remove_missing : bool,
+    load_test : RockeryLoadTest,
+    verbosity : Verbosity,
+    command : Command,
+}
+
+fn number<T : FromStr>(option : &str, value : &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("Invalid value '{}' for option {}.", value, option))
+}
+
+/// Parse a number of seconds and convert it to milliseconds.
+fn milliseconds(option : &str, value : &str) -> Result<u64, String> {
+    number::<u64>(option, value)?.checked_mul(1000)
+        .ok_or(format!("Invalid value '{}' for option {}. The value is too large.", value, option))
+}
+
+fn parse_args(args : &[String]) -> Result<Options, String> {
+    let mut positional = Vec::new();
+    let mut environment = None;
+    let mut variables = Vec::new();
+    let mut format = OutputFormat::Text;
+    let mut reports = Vec::new();
+    let mut address = "127.0.0.1:8080".to_owned();
+    let mut assertions = false;
+    let mut responses = false;
+    let mut history = true;
+    let mut dry_run = false;
+    let mut remove_missing = false;
+    let mut users = 10;
+    let mut limit = RockeryLoadLimit::Duration(10000);
+    let mut ramp_up = 0;
+    let mut rate_limit = None;
+    let mut verbosity = Verbosity::Normal;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = |name : &str| iter.next().cloned().ok_or(format!("Option {} requires a value.", name));
+        match arg.as_str() {
+            "-e" | "--environment" => environment = Some(value(arg)?),
+            "-D" | "--variable" => {
+                let v = value(arg)?;
+                let (name, value) = v.split_once('=').ok_or(format!("Invalid variable '{}'. Expected <name>=<value>.", v))?;
+                variables.push((name.to_owned(), value.to_owned()));
+            },
+            "-o" | "--output" => {
+                format = match value(arg)?.as_str() {
+                    "text" => OutputFormat::Text,
+                    "json" => OutputFormat::Report(RockeryReportFormat::Json),
+                    "junit" => OutputFormat::Report(RockeryReportFormat::JUnit),
+                    "html" => OutputFormat::Report(RockeryReportFormat::Html),
+                    "har" => OutputFormat::Report(RockeryReportFormat::Har),
+                    f => return Err(format!("Unknown output format '{}'.", f)),
+                };
+            },
+            "-r" | "--report" => {
+                let path = PathBuf::from(value(arg)?);
+                RockeryReportFormat::from_path(&path)?;
+                reports.push(path);
+            },
+            "-a" | "--address" => address = value(arg)?,
+            "--assertions" => assertions = true,
+            "--responses" => responses = true,
+            "--no-history" => history = false,
+            "--dry-run" => dry_run = true,
+            "--remove-missing" => remove_missing = true,
+            "--users" => users = number(arg, &value(arg)?)?,
+            "--duration" => limit = RockeryLoadLimit::Duration(milliseconds(arg, &value(arg)?)?),
+            "--iterations" => limit = RockeryLoadLimit::Iterations(number(arg, &value(arg)?)?),
+            "--ramp-up" => ramp_up = milliseconds(arg, &value(arg)?)?,
+            "--rate" => rate_limit = Some(number(arg, &value(arg)?)?),
+            "-q" | "--quiet" => verbosity = Verbosity::Quiet,
+            "-v" | "--verbose" => verbosity = Verbosity::Verbose,
+            a if a.starts_with('-') && a.len() > 1 => return Err(format!("Unknown option '{}'.", a)),
+            a => positional.push(a.to_owned()),
+        }
+    }
+
+    let mut positional = positional.into_iter();
+    let project = positional.next().ok_or("No project file given.".to_owned())?;
+    let command = match positional.next().as_deref() {
+        Some("list") => Command::List,
+        Some("method") => Command::Method(
+            positional.next().ok_or("Command method requires a service.".to_owned())?,
+            positional.next().ok_or("Command method requires a method.".to_owned())?,
+        ),
+        Some("test-case") => Command::TestCase(
+            positional.next().ok_or("Command test-case requires a test suite.".to_owned())?,
+            positional.next().ok_or("Command test-case requires a test case.".to_owned())?,
+        ),
+        Some("suite") => Command::Suite(positional.next().ok_or("Command suite requires a test suite.".to_owned())?),
+        Some("all") => Command::All,
+        Some("record") => Command::Record,
+        Some("load-method") => Command::Load(RockeryLoadTarget::Method(
+            positional.next().ok_or("Command load-method requires a service.".to_owned())?,
+            positional.next().ok_or("Command load-method requires a method.".to_owned())?,
+        )),
+        Some("load-test-case") => Command::Load(RockeryLoadTarget::TestCase(
+            positional.next().ok_or("Command load-test-case requires a test suite.".to_owned())?,
+            positional.next().ok_or("Command load-test-case requires a test case.".to_owned())?,
+        )),
+        Some("mock") => Command::Mock(positional.next().ok_or("Command mock requires a service.".to_owned())?),
+        Some("docs") => {
+            let path = PathBuf::from(positional.next().ok_or("Command docs requires a file.".to_owned())?);
+            RockeryDocumentationFormat::from_path(&path)?;
+            Command::Docs(path)
+        },
+        Some("sync") => Command::Sync(positional.next().ok_or("Command sync requires a service.".to_owned())?),
+        Some(c) => return Err(format!("Unknown command '{}'.", c)),
+        None => return Err("No command given.".to_owned()),
+    };
+
+    if let Some(a) = positional.next() {
+        return Err(format!("Unexpected argument '{}'.", a));
+    }
+
+    let target = match &command {
+        Command::Load(t) => t.clone(),
+        _ => RockeryLoadTarget::Method(String::new(), String::new()),
+    };
+    let mut load_test = RockeryLoadTest::new(target, users, limit);
+    load_test.set_ramp_up(ramp_up);
+    load_test.set_rate_limit(rate_limit);
+
+    Ok(Options { project : PathBuf::from(project), environment, variables, format, reports, address, assertions, responses, history, dry_run, remove_missing, load_test, verbosity, command })
+}
+
+fn print_response(response : &RockeryResponse) {
+    for (k, v) in response.get_headers() {
+        println!("        {}: {}", k, v);
+    }
+    for line in response.get_body().lines() {
+        println!("        {}", line);
+    }
+}
+
+fn print_request_result(name : &str, result : &RockeryRequestResult, verbosity : Verbosity) {
+    let state = if result.is_passed() { "PASS" } else { "FAIL" };
+    let response = result.get_response();
+    println!("[{}] {} ({} {}, {} ms)", state, name, response.get_status(), response.get_status_text(), response.get_duration());
+    if verbosity == Verbosity::Quiet {
+        return;
+    }
+
+    for v in result.get_violations() {
+        println!("    {}", v);
+    }
+    for a in result.get_assertions() {
+        println!("    {}", a);
+    }
+    if verbosity == Verbosity::Verbose {
+        print_response(response);
+    }
+}
+
+fn print_suite_results(results : &[RockeryTestSuiteResult], verbosity : Verbosity) {
+    for suite in results {
+        match verbosity {
+            Verbosity::Quiet => { },
+            Verbosity::Normal => print!("{}", suite),
+            Verbosity::Verbose => {
+                println!("Test suite {}: {}", suite.get_name(), if suite.is_passed() { "PASS" } else { "FAIL" });
+                for test_case in suite.get_test_cases() {
+                    println!("Test case {}: {}", test_case.get_name(), if test_case.is_passed() { "PASS" } else { "FAIL" });
+                    for step in test_case.get_steps() {
+                        print!("  {}", step);
+                        if let Some(r) = step.get_result() {
+                            print_response(r.get_response());
+                        }
+                    }
+                }
+            },
+        }
+    }
+
+    let test_cases : Vec<&RockeryTestCaseResult> = results.iter().flat_map(|s| s.get_test_cases()).collect();
+    let passed = test_cases.iter().filter(|t| t.is_passed()).count();
+    println!("{} test cases, {} passed, {} failed", test_cases.len(), passed, test_cases.len() - passed);
+}
+
+fn list(project : &mut RockeryProject) {
+    println!("Project {}", project.get_name());
+
+    let mut services : Vec<(&String, &RockeryService)> = project.get_services().iter().collect();
+    services.sort_by_key(|(id, _)| id.to_owned());
+    for (id, service) in services {
+        println!("  Service {} ({})", id, service.get_base_url());
+        for m in service.get_methods() {
+            println!("    {} {} {}", m.get_name(), m.get_request_method(), m.get_uri());
+        }
+    }
+
+    for suite in project.get_test_suites() {
+        println!("  Test suite {}", suite.get_name());
+        for test_case in suite.get_test_cases() {
+            println!("    {} ({} steps)", test_case.get_name(), test_case.get_steps().len());
+        }
+    }
+
+    for environment in project.get_environments() {
+        println!("  Environment {}", environment.get_name());
+    }
+}
+
+/// Serve the service until the process is killed and print the requests that are received.
+fn mock(service : &RockeryService, address : &str, verbosity : Verbosity) -> Result<(), String> {
+    let server = RockeryMockServer::start(service, address)?;
+    println!("Mocking {} on {}", service.get_name(), server.get_url());
+
+    let mut printed = 0;
+    loop {
+        thread::sleep(Duration::from_millis(200));
+        let received = server.get_received();
+        if verbosity > Verbosity::Quiet {
+            for r in &received[printed..] {
+                println!("{} {}", r.get_method(), r.get_url());
+                if verbosity == Verbosity::Verbose && !r.get_body().is_empty() {
+                    println!("    {}", r.get_body());
+                }
+            }
+        }
+        printed = received.len();
+    }
+}
+
+/// Record through a proxy until enter is pressed, then add the recording to the project file and save it.
+fn record(options : &Options) -> Result<(), String> {
+    let mut proxy = RockeryRecordingProxy::start(&options.address)?;
+    println!("Recording on {}. Configure it as http proxy of the client and press enter to stop.", proxy.get_url());
+
+    let mut line = String::new();
+    std::io::stdin().read_line(&mut line).map_err(|e| e.to_string())?;
+    proxy.stop();
+
+    let recorded = proxy.take_recorded();
+    if options.verbosity > Verbosity::Quiet {
+        for e in &recorded {
+            println!("{} {} -> {}", e.get_request().get_method(), e.get_request().get_url(), e.get_response().get_status());
+        }
+    }
+
+    // The project of the run has the environment and the variables of the command line applied, they must not
+    // be saved. So the recording is added to the project as it is stored.
+    let mut project = load_project(options)?;
+    let (services, warnings) = project.add_recording(&recorded);
+    for warning in &warnings {
+        eprintln!("{}", warning);
+    }
+    if options.assertions {
+        for id in &services {
+            let service = project.get_services().get_mut(id).unwrap();
+            for i in 0..service.get_methods().len() {
+                let method = service.get_method_mut(i).unwrap();
+                if method.get_assertions().is_empty() && !method.get_responses().is_empty() {
+                    method.set_assertions_from_response(0)?;
+                }
+            }
+        }
+    }
+
+    project.save(&options.project).map_err(|e| format!("Could not save project {}. Error: {}", options.project.display(), e))?;
+    println!("{} requests recorded into {} services", recorded.len() - warnings.len(), services.len());
+
+    Ok(())
+}
+
+/// Compare a service with its definition and print the differences. Unless it is a dry run the definition is
+/// merged into the service and the project file is saved.
+fn sync(options : &Options, service_name : &str) -> Result<(), String> {
+    // Like a recording the changes are applied to the project as it is stored, not to the one of the run.
+    let mut project = load_project(options)?;
+    let project_name = project.get_name().to_owned();
+    let service = project.get_services().get_mut(service_name)
+        .ok_or(format!("A service with the name {} does not exist within project {}.", service_name, project_name))?;
+
+    let diff = if options.dry_run { service.diff_definition()? } else { service.sync_definition(options.remove_missing)? };
+    if diff.is_empty() {
+        println!("Service {} matches its definition", service_name);
+        return Ok(());
+    }
+
+    print!("{}", diff);
+    if !options.dry_run {
+        project.save(&options.project).map_err(|e| format!("Could not save project {}. Error: {}", options.project.display(), e))?;
+    }
+
+    Ok(())
+}
+
+/// Run a load test and print the statistics. Passes if no iteration failed.
+fn load(project : &RockeryProject, options : &Options) -> Result<bool, String> {
+    let run = options.load_test.start(project)?;
+    if options.verbosity == Verbosity::Verbose {
+        while !run.is_finished() {
+            thread::sleep(Duration::from_secs(1));
+            let statistics = run.get_statistics();
+            println!("{:>6} s  {:>8} iterations  {:>8.1}/s  {:>6} errors  p90 {} ms",
+                run.get_elapsed() / 1000, statistics.get_iterations(), statistics.get_throughput(), statistics.get_errors(), statistics.get_p90());
+        }
+    }
+
+    let statistics = run.wait();
+    match options.format {
+        OutputFormat::Text => print!("{}", statistics),
+        OutputFormat::Report(RockeryReportFormat::Json) => println!("{}", serde_json::to_string_pretty(&statistics).map_err(|e| e.to_string())?),
+        OutputFormat::Report(_) => return Err("Load test statistics can only be printed as text or json.".to_owned()),
+    }
+
+    Ok(statistics.get_errors() == 0)
+}
+
+fn find_suite<'a>(project : &'a RockeryProject, name : &str) -> Result<&'a RockeryTestSuite, String> {
+    project.get_test_suites().iter().find(|s| s.get_name() == name)
+        .ok_or(format!("A test suite with the name {} does not exist within project {}.", name, project.get_name()))
+}
+
+fn load_project(options : &Options) -> Result<RockeryProject, String> {
+    RockeryProject::load(options.project.clone())
+        .map_err(|e| format!("Could not load project {}. Error: {}", options.project.display(), e))
+}
+
+/// Execute the command. Returns whether everything passed.
+fn run(options : &Options) -> Result<bool, String> {
+    let mut project = load_project(options)?;
+
+    if options.history {
+        project.enable_history(&RockeryHistory::path_for(&options.project))?;
+    }
+
+    if let Some(environment) = &options.environment {
+        project.apply_environment(environment)?;
+    }
+
+    // Variables given on the command line win over the variables defined by the project and its test cases.
+    for (name, value) in &options.variables {
+        project.set_variable(name, value);
+        for s in 0..project.get_test_suites().len() {
+            let suite = project.get_test_suite_mut(s).unwrap();
+            for t in 0..suite.get_test_cases().len() {
+                suite.get_test_case_mut(t).unwrap().set_variable(name, value);
+            }
+        }
+    }
+
+    let mut variables = project.get_variables().clone();
+    let results = match &options.command {
+        Command::List => {
+            list(&mut project);
+            return Ok(true);
+        },
+        Command::Method(service_name, method_name) => {
+            if !options.reports.is_empty() {
+                return Err("Reports can only be written for test suites and test cases.".to_owned());
+            }
+
+            let service = project.find_service(service_name)
+                .ok_or(format!("A service with the name {} does not exist within project {}.", service_name, project.get_name()))?;
+            let method = service.get_method_index(method_name)
+                .ok_or(format!("A method with the name {} does not exist within service {}.", method_name, service_name))?;
+
+            let result = service.with_variables(method, &variables).do_checked_request(method, &[])?;
+            match options.format {
+                OutputFormat::Text => print_request_result(&format!("{}/{}", service_name, method_name), &result, options.verbosity),
+                OutputFormat::Report(RockeryReportFormat::Json) => println!("{}", serde_json::to_string_pretty(&result).map_err(|e| e.to_string())?),
+                OutputFormat::Report(_) => return Err("A single request can only be printed as text or json.".to_owned()),
+            }
+            return Ok(result.is_passed());
+        },
+        Command::TestCase(suite_name, test_case_name) => {
+            let suite = find_suite(&project, suite_name)?;
+            let test_case = suite.get_test_cases().iter().find(|t| t.get_name() == test_case_name)
+                .ok_or(format!("A test case with the name {} does not exist within test suite {}.", test_case_name, suite_name))?;
+            vec![RockeryTestSuiteResult::new(suite_name, test_case.run_all(&project, &mut variables))]
+        },
+        Command::Suite(suite_name) => vec![find_suite(&project, suite_name)?.run_with_variables(&project, &mut variables)],
+        Command::All => project.get_test_suites().iter().map(|s| s.run_with_variables(&project, &mut variables)).collect(),
+        Command::Mock(service_name) => {
+            let service = project.find_service(service_name)
+                .ok_or(format!("A service with the name {} does not exist within project {}.", service_name, project.get_name()))?;
+            mock(service, &options.address, options.verbosity)?;
+            return Ok(true);
+        },
+        Command::Record => {
+            record(options)?;
+            return Ok(true);
+        },
+        Command::Sync(service_name) => {
+            sync(options, service_name)?;
+            return Ok(true);
+        },
+        Command::Load(_) => return load(&project, options),
+        Command::Docs(path) => {
+            RockeryDocumentationGenerator::save(&project, path, options.responses)?;
+            return Ok(true);
+        },
+    };
+
+    let report = RockeryTestReport::new(project.get_name(), results);
+    match options.format {
+        OutputFormat::Text => print_suite_results(report.get_suites(), options.verbosity),
+        OutputFormat::Report(format) => print!("{}", report.render(format)),
+    }
+
+    for path in &options.reports {
+        report.save(path)?;
+    }
+
+    Ok(report.is_passed())
+}
+
+fn main() -> ExitCode {
+    let args : Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() || args.iter().any(|a| a == "-h" || a == "--help") {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    let options = match parse_args(&args) {
+        Ok(o) => o,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        },
+    };
+
+    match run(&options) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::from(2)
+        },
+    }
+}
+
+#[cfg(test)]
+mod rockery_cli_tests {
+    use super::*;
+
+    fn args(line : &str) -> Vec<String> {
+        line.split_whitespace().map(|a| a.to_owned()).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let options = parse_args(&args("project.json -e Staging suite Smoke -D user=rex -D token=a=b -o json -v")).unwrap();
+        assert_eq!(options, Options {
+            project : PathBuf::from("project.json"),
+            environment : Some("Staging".to_owned()),
+            variables : vec![("user".to_owned(), "rex".to_owned()), ("token".to_owned(), "a=b".to_owned())],
+            format : OutputFormat::Report(RockeryReportFormat::Json),
+            reports : Vec::new(),
+            address : "127.0.0.1:8080".to_owned(),
+            assertions : false,
+            responses : false,
+            history : true,
+            dry_run : false,
+            remove_missing : false,
+            load_test : RockeryLoadTest::new(RockeryLoadTarget::Method(String::new(), String::new()), 10, RockeryLoadLimit::Duration(10000)),
+            verbosity : Verbosity::Verbose,
+            command : Command::Suite("Smoke".to_owned()),
+        });
+
+        let options = parse_args(&args("p.json method Users get")).unwrap();
+        assert_eq!(options.command, Command::Method("Users".to_owned(), "get".to_owned()));
+        assert!(parse_args(&args("p.json method Users")).is_err());
+        assert!(parse_args(&args("p.json test-case Smoke")).is_err());
+        assert!(parse_args(&args("p.json all extra")).is_err());
+        assert!(parse_args(&args("p.json all -o xml")).is_err());
+        assert!(parse_args(&args("p.json all -r report.txt")).is_err());
+        let options = parse_args(&args("p.json all -o junit -r out/report.xml -r report.html")).unwrap();
+        assert_eq!(options.format, OutputFormat::Report(RockeryReportFormat::JUnit));
+        assert_eq!(options.reports, vec![PathBuf::from("out/report.xml"), PathBuf::from("report.html")]);
+        assert!(parse_args(&args("p.json all -D novalue")).is_err());
+        assert!(parse_args(&args("p.json all --unknown")).is_err());
+        assert!(parse_args(&args("p.json")).is_err());
+        assert_eq!(parse_args(&args("p.json list -q")).unwrap().verbosity, Verbosity::Quiet);
+
+        let options = parse_args(&args("p.json mock Users -a 0.0.0.0:9000")).unwrap();
+        assert_eq!((options.command, options.address), (Command::Mock("Users".to_owned()), "0.0.0.0:9000".to_owned()));
+        assert!(parse_args(&args("p.json mock")).is_err());
+        let options = parse_args(&args("p.json record --assertions")).unwrap();
+        assert!(options.command == Command::Record && options.assertions);
+        assert!(!parse_args(&args("p.json all --no-history")).unwrap().history);
+        let options = parse_args(&args("p.json sync Users --dry-run --remove-missing")).unwrap();
+        assert!(options.command == Command::Sync("Users".to_owned()) && options.dry_run && options.remove_missing);
+        assert!(parse_args(&args("p.json sync")).is_err());
+
+        let options = parse_args(&args("p.json load-test-case Smoke Login --users 5 --iterations 100 --ramp-up 2 --rate 7.5")).unwrap();
+        let mut expected = RockeryLoadTest::new(RockeryLoadTarget::TestCase("Smoke".to_owned(), "Login".to_owned()), 5, RockeryLoadLimit::Iterations(100));
+        expected.set_ramp_up(2000);
+        expected.set_rate_limit(Some(7.5));
+        assert_eq!(options.load_test, expected);
+        assert_eq!(parse_args(&args("p.json load-method Users get --duration 3")).unwrap().load_test.get_limit(), RockeryLoadLimit::Duration(3000));
+        assert!(parse_args(&args("p.json load-method Users get --users many")).is_err());
+        assert!(parse_args(&args("p.json load-method Users get --ramp-up 18446744073709552")).unwrap_err().contains("too large"));
+        assert!(parse_args(&args("p.json load-method Users get --duration 18446744073709552")).unwrap_err().contains("too large"));
+    }
+
+    #[test]
+    fn test_run() {
+        let path = std::env::temp_dir().join(format!("rockery_cli_{}.json", std::process::id()));
+        let mut project = RockeryProject::new("CLI");
+        project.add_environment(RockeryEnvironment::new("Local")).set_variable("user", "rex");
+        project.add_test_suite(RockeryTestSuite::new("Empty")).add_test_case(RockeryTestCase::new("Nothing"));
+        project.save(&path).unwrap();
+
+        let options = |line : &str| parse_args(&args(&format!("{} {}", path.display(), line))).unwrap();
+        assert_eq!(run(&options("-e Local list")), Ok(true));
+        assert_eq!(run(&options("-q all")), Ok(true));
+        assert_eq!(run(&options("-D user=max test-case Empty Nothing")), Ok(true));
+        assert!(run(&options("-e Remote all")).is_err());
+        assert!(run(&options("suite Missing")).is_err());
+        assert!(run(&options("method Missing get")).is_err());
+        assert!(run(&options("load-method Missing get --iterations 1")).is_err());
+        assert!(run(&options("load-method Missing get --iterations 1 --rate NaN")).unwrap_err().starts_with("Invalid rate limit"));
+
+        let docs = std::env::temp_dir().join(format!("rockery_cli_{}.md", std::process::id()));
+        assert_eq!(run(&options(&format!("docs {} --responses", docs.display()))), Ok(true));
+        assert!(std::fs::read_to_string(&docs).unwrap().starts_with("# CLI\n"));
+        std::fs::remove_file(&docs).unwrap();
+        assert!(parse_args(&args("p.json docs api.txt")).is_err());
+
+        let report = std::env::temp_dir().join(format!("rockery_cli_{}.xml", std::process::id()));
+        assert_eq!(run(&options(&format!("-q suite Empty -r {}", report.display()))), Ok(true));
+        assert!(std::fs::read_to_string(&report).unwrap().contains("<testcase name=\"Nothing\" classname=\"Empty\""));
+        std::fs::remove_file(&report).unwrap();
+
+        let definition = std::env::temp_dir().join(format!("rockery_cli_{}.openapi.json", std::process::id()));
+        std::fs::write(&definition, r#"{ "openapi": "3.0.0", "info": { "title": "Users" }, "paths": { "/users": { "get": { "operationId": "listUsers" } } } }"#).unwrap();
+        let mut project = RockeryProject::load(path.clone()).unwrap();
+        let mut service = RockeryService::new(RockeryServiceType::Rest, "https://example.com");
+        service.set_name("Users");
+        service.set_definition_url(&definition.display().to_string());
+        project.add_service(service);
+        project.save(&path).unwrap();
+
+        assert_eq!(run(&options("sync Users --dry-run")), Ok(true));
+        assert!(RockeryProject::load(path.clone()).unwrap().find_service("Users").unwrap().get_methods().is_empty());
+        assert_eq!(run(&options("sync Users")), Ok(true));
+        assert_eq!(RockeryProject::load(path.clone()).unwrap().find_service("Users").unwrap().get_methods()[0].get_name(), "listUsers");
+        assert!(run(&options("sync Missing")).is_err());
+        std::fs::remove_file(&definition).unwrap();
+        assert!(run(&options("sync Users")).is_err());
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(run(&options("all")).is_err());
+    }
+}
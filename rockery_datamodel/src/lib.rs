@@ -9,6 +9,42 @@ mod rockery_request_parameter;
 mod rockery_service_type;
 mod rockery_service_authentication;
 mod rockery_project_binder;
+mod rockery_xml;
+mod rockery_definition;
+mod rockery_wadl_import;
+mod rockery_wsdl_import;
+mod rockery_yaml;
+mod rockery_openapi_import;
+mod rockery_openapi_export;
+mod rockery_response;
+mod rockery_service_sync;
+mod rockery_json_schema;
+mod rockery_xsd;
+mod rockery_schema;
+mod rockery_json_path;
+mod rockery_xpath;
+mod rockery_assertion;
+mod rockery_test_suite;
+mod rockery_property_transfer;
+mod rockery_data_source;
+mod rockery_environment;
+mod rockery_report;
+mod rockery_http;
+mod rockery_mock;
+mod rockery_proxy;
+mod rockery_load_test;
+mod rockery_history;
+mod rockery_postman_import;
+mod rockery_postman_export;
+mod rockery_curl;
+mod rockery_har;
+mod rockery_soapui_import;
+mod rockery_http_file;
+mod rockery_insomnia_import;
+mod rockery_bruno_import;
+mod rockery_code_snippet;
+mod rockery_documentation;
+mod rockery_migration;
 
 pub mod prelude {
     use crate::rockery_project;
@@ -19,20 +55,113 @@ pub mod prelude {
     use crate::rockery_service_type;
     use crate::rockery_service_authentication;
     use crate::rockery_project_binder;
+    use crate::rockery_wadl_import;
+    use crate::rockery_wsdl_import;
+    use crate::rockery_openapi_import;
+    use crate::rockery_openapi_export;
+    use crate::rockery_response;
+    use crate::rockery_service_sync;
+    use crate::rockery_schema;
+    use crate::rockery_assertion;
+    use crate::rockery_test_suite;
+    use crate::rockery_property_transfer;
+    use crate::rockery_data_source;
+    use crate::rockery_environment;
+    use crate::rockery_report;
+    use crate::rockery_mock;
+    use crate::rockery_proxy;
+    use crate::rockery_load_test;
+    use crate::rockery_history;
+    use crate::rockery_postman_import;
+    use crate::rockery_postman_export;
+    use crate::rockery_curl;
+    use crate::rockery_har;
+    use crate::rockery_soapui_import;
+    use crate::rockery_http_file;
+    use crate::rockery_insomnia_import;
+    use crate::rockery_bruno_import;
+    use crate::rockery_code_snippet;
+    use crate::rockery_documentation;
+    use crate::rockery_migration;
 
     pub use rockery_project_binder::RockeryProjectBinder;
     pub use rockery_project::RockeryProject;
+    pub use rockery_environment::RockeryEnvironment;
+    pub use rockery_test_suite::RockeryTestSuite;
+    pub use rockery_test_suite::RockeryTestCase;
+    pub use rockery_test_suite::RockeryTestStep;
+    pub use rockery_test_suite::RockeryGoto;
+    pub use rockery_test_suite::RockeryGotoCondition;
+    pub use rockery_test_suite::RockeryTestSuiteResult;
+    pub use rockery_test_suite::RockeryTestCaseResult;
+    pub use rockery_test_suite::RockeryStepResult;
+    pub use rockery_property_transfer::RockeryPropertyTransfer;
+    pub use rockery_property_transfer::RockeryPropertySource;
+    pub use rockery_property_transfer::RockeryVariableScope;
+    pub use rockery_data_source::RockeryDataSource;
+    pub use rockery_data_source::RockeryDataDrivenResult;
+    pub use rockery_data_source::RockeryRowResult;
+    pub use rockery_report::RockeryTestReport;
+    pub use rockery_report::RockeryReportFormat;
+    pub use rockery_mock::RockeryMockServer;
+    pub use rockery_mock::RockeryMockResponse;
+    pub use rockery_mock::RockeryMockRule;
+    pub use rockery_proxy::RockeryRecordingProxy;
+    pub use rockery_proxy::RockeryExchange;
+    pub use rockery_load_test::RockeryLoadTest;
+    pub use rockery_load_test::RockeryLoadTarget;
+    pub use rockery_load_test::RockeryLoadLimit;
+    pub use rockery_load_test::RockeryLoadRun;
+    pub use rockery_load_test::RockeryLoadSample;
+    pub use rockery_load_test::RockeryLoadStatistics;
+    pub use rockery_history::RockeryHistory;
+    pub use rockery_history::RockeryHistoryEntry;
     
     pub use rockery_service_type::RockeryServiceType;
     pub use rockery_service::RockeryService;
+    pub use rockery_service_sync::RockeryServiceDiff;
+    pub use rockery_service_sync::RockeryMethodChange;
     
     pub use rockery_service_method::RockeryServiceMethod;
     pub use rockery_request_method::RequestMethod;
     pub use rockery_request_parameter::RequestParameter;
+    pub use rockery_response::RockeryRequest;
+    pub use rockery_response::RockeryResponse;
+    pub use rockery_response::RockeryRequestResult;
+    pub use rockery_schema::RockerySchema;
+    pub use rockery_schema::RockeryResponseSchema;
+    pub use rockery_schema::RockerySchemaViolation;
+    pub use rockery_assertion::RockeryAssertion;
+    pub use rockery_assertion::RockeryAssertionResult;
 
     pub use rockery_service_authentication::AuthenticationType;
     pub use rockery_service_authentication::RockeryServiceAuthentication;
     pub use rockery_service_authentication::NoAuthenticationAuthentication;
+    pub use rockery_service_authentication::BasicAuthenticationAuthentication;
+    pub use rockery_service_authentication::BearerAuthenticationAuthentication;
+    pub use rockery_service_authentication::ApiKeyAuthenticationAuthentication;
+    pub use rockery_service_authentication::ApiKeyLocation;
+
+    pub use rockery_wadl_import::RockeryWadlImporter;
+    pub use rockery_wsdl_import::RockeryWsdlImporter;
+    pub use rockery_openapi_import::RockeryOpenApiImporter;
+    pub use rockery_openapi_export::RockeryOpenApiExporter;
+    pub use rockery_postman_import::RockeryPostmanImporter;
+    pub use rockery_postman_export::RockeryPostmanExporter;
+    pub use rockery_curl::RockeryCurlImporter;
+    pub use rockery_curl::RockeryCurlExporter;
+    pub use rockery_har::RockeryHarImporter;
+    pub use rockery_har::RockeryHarExporter;
+    pub use rockery_soapui_import::RockerySoapUiImporter;
+    pub use rockery_http_file::RockeryHttpFileImporter;
+    pub use rockery_http_file::RockeryHttpFileExporter;
+    pub use rockery_insomnia_import::RockeryInsomniaImporter;
+    pub use rockery_bruno_import::RockeryBrunoImporter;
+    pub use rockery_code_snippet::RockeryCodeGenerator;
+    pub use rockery_code_snippet::RockeryCodeLanguage;
+    pub use rockery_documentation::RockeryDocumentationGenerator;
+    pub use rockery_documentation::RockeryDocumentationFormat;
+    pub use rockery_migration::PROJECT_FORMAT_VERSION;
 }
 
 
@@ -0,0 +1,245 @@
+use std::fmt;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::prelude::RockeryResponse;
+use crate::rockery_json_path;
+use crate::rockery_xml::XmlElement;
+use crate::rockery_xpath;
+
+/// A check that is evaluated against every response of a method.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum RockeryAssertion {
+    /// The status code equals the given code.
+    StatusCode(u16),
+
+    /// The status code lies within the given range (both inclusive).
+    StatusRange(u16, u16),
+
+    HeaderPresent(String),
+
+    /// The header (name, value) is present and has exactly the given value.
+    HeaderEquals(String, String),
+
+    BodyContains(String),
+
+    /// The body matches the regular expression.
+    BodyMatches(String),
+
+    /// The first value selected by the json path (path, expected value) equals the expected value.
+    /// Strings are compared without quotes.
+    JsonPathEquals(String, String),
+
+    /// The first node selected by the xpath (path, expected value) has the expected string value.
+    XPathEquals(String, String),
+
+    /// The response has been received in less than the given number of milliseconds.
+    ResponseTimeBelow(u64),
+
+    /// The body is smaller than the given number of bytes.
+    BodySizeBelow(usize),
+}
+
+impl RockeryAssertion {
+    /// Derive assertions that pin down an observed response: its status code, its content type and,
+    /// for json objects, the values of all top level fields that are strings, numbers or booleans.
+    pub fn from_response(response : &RockeryResponse) -> Vec<RockeryAssertion> {
+        let mut assertions = vec![RockeryAssertion::StatusCode(response.get_status())];
+        if let Some(content_type) = response.get_header("Content-Type") {
+            assertions.push(RockeryAssertion::HeaderEquals("Content-Type".to_owned(), content_type.to_owned()));
+        }
+
+        if let Ok(Value::Object(fields)) = serde_json::from_str::<Value>(response.get_body()) {
+            for (k, v) in fields {
+                if !(v.is_string() || v.is_number() || v.is_boolean()) {
+                    continue;
+                }
+
+                let path = if !k.is_empty() && k.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                    format!("$.{}", k)
+                } else {
+                    format!("$['{}']", k)
+                };
+                assertions.push(RockeryAssertion::JsonPathEquals(path, rockery_json_path::value_text(&v)));
+            }
+        }
+
+        assertions
+    }
+}
+
+impl fmt::Display for RockeryAssertion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RockeryAssertion::StatusCode(c) => write!(f, "Status code is {}", c),
+            RockeryAssertion::StatusRange(min, max) => write!(f, "Status code is between {} and {}", min, max),
+            RockeryAssertion::HeaderPresent(h) => write!(f, "Header {} is present", h),
+            RockeryAssertion::HeaderEquals(h, v) => write!(f, "Header {} equals '{}'", h, v),
+            RockeryAssertion::BodyContains(s) => write!(f, "Body contains '{}'", s),
+            RockeryAssertion::BodyMatches(r) => write!(f, "Body matches /{}/", r),
+            RockeryAssertion::JsonPathEquals(p, v) => write!(f, "{} equals '{}'", p, v),
+            RockeryAssertion::XPathEquals(p, v) => write!(f, "{} equals '{}'", p, v),
+            RockeryAssertion::ResponseTimeBelow(ms) => write!(f, "Response time is below {} ms", ms),
+            RockeryAssertion::BodySizeBelow(b) => write!(f, "Body is smaller than {} bytes", b),
+        }
+    }
+}
+
+impl RockeryAssertion {
+    /// Evaluate the assertion against a response. Returns `Ok` with a success message or `Err` with the
+    /// reason why the assertion failed.
+    fn check(&self, response : &RockeryResponse) -> Result<String, String> {
+        match self {
+            RockeryAssertion::StatusCode(c) => {
+                match response.get_status() == *c {
+                    true => Ok(format!("Status code is {}.", c)),
+                    false => Err(format!("Expected status code {} but got {}.", c, response.get_status())),
+                }
+            },
+            RockeryAssertion::StatusRange(min, max) => {
+                match (*min..=*max).contains(&response.get_status()) {
+                    true => Ok(format!("Status code {} is between {} and {}.", response.get_status(), min, max)),
+                    false => Err(format!("Expected a status code between {} and {} but got {}.", min, max, response.get_status())),
+                }
+            },
+            RockeryAssertion::HeaderPresent(h) => {
+                match response.get_header(h) {
+                    Some(_) => Ok(format!("Header {} is present.", h)),
+                    None => Err(format!("Header {} is missing.", h)),
+                }
+            },
+            RockeryAssertion::HeaderEquals(h, v) => {
+                match response.get_header(h) {
+                    Some(actual) if actual == v => Ok(format!("Header {} is '{}'.", h, v)),
+                    Some(actual) => Err(format!("Expected header {} to be '{}' but got '{}'.", h, v, actual)),
+                    None => Err(format!("Header {} is missing.", h)),
+                }
+            },
+            RockeryAssertion::BodyContains(s) => {
+                match response.get_body().contains(s.as_str()) {
+                    true => Ok(format!("Body contains '{}'.", s)),
+                    false => Err(format!("Body does not contain '{}'.", s)),
+                }
+            },
+            RockeryAssertion::BodyMatches(r) => {
+                let regex = Regex::new(r).map_err(|e| format!("Invalid regular expression '{}'. Error: {}", r, e))?;
+                match regex.is_match(response.get_body()) {
+                    true => Ok(format!("Body matches /{}/.", r)),
+                    false => Err(format!("Body does not match /{}/.", r)),
+                }
+            },
+            RockeryAssertion::JsonPathEquals(p, v) => {
+                let body : Value = serde_json::from_str(response.get_body()).map_err(|e| format!("Body is not valid json. Error: {}", e))?;
+                let actual = rockery_json_path::select(&body, p)?.first().map(|a| rockery_json_path::value_text(a));
+                compare(p, v, actual)
+            },
+            RockeryAssertion::XPathEquals(p, v) => {
+                let body = XmlElement::parse(response.get_body()).map_err(|e| format!("Body is not valid xml. Error: {}", e))?;
+                let actual = rockery_xpath::select(&body, p)?.into_iter().next();
+                compare(p, v, actual)
+            },
+            RockeryAssertion::ResponseTimeBelow(ms) => {
+                match response.get_duration() < *ms {
+                    true => Ok(format!("Response time {} ms is below {} ms.", response.get_duration(), ms)),
+                    false => Err(format!("Response time {} ms exceeds {} ms.", response.get_duration(), ms)),
+                }
+            },
+            RockeryAssertion::BodySizeBelow(b) => {
+                match response.get_body().len() < *b {
+                    true => Ok(format!("Body size {} bytes is below {} bytes.", response.get_body().len(), b)),
+                    false => Err(format!("Body size {} bytes exceeds {} bytes.", response.get_body().len(), b)),
+                }
+            },
+        }
+    }
+
+    pub fn evaluate(&self, response : &RockeryResponse) -> RockeryAssertionResult {
+        let (passed, message) = match self.check(response) {
+            Ok(m) => (true, m),
+            Err(m) => (false, m),
+        };
+
+        RockeryAssertionResult { assertion : self.clone(), passed, message }
+    }
+}
+
+fn compare(path : &str, expected : &str, actual : Option<String>) -> Result<String, String> {
+    match actual {
+        Some(a) if a == expected => Ok(format!("{} is '{}'.", path, expected)),
+        Some(a) => Err(format!("Expected {} to be '{}' but got '{}'.", path, expected, a)),
+        None => Err(format!("{} does not select anything.", path)),
+    }
+}
+
+/// The outcome of evaluating an assertion against a response.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RockeryAssertionResult {
+    assertion : RockeryAssertion,
+    passed : bool,
+    message : String,
+}
+
+impl RockeryAssertionResult {
+    pub fn get_assertion(&self) -> &RockeryAssertion { &self.assertion }
+    pub fn is_passed(&self) -> bool { self.passed }
+    pub fn get_message(&self) -> &str { &self.message }
+}
+
+impl fmt::Display for RockeryAssertionResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = if self.passed { "PASS" } else { "FAIL" };
+        write!(f, "[{}] {}: {}", state, self.assertion, self.message)
+    }
+}
+
+#[cfg(test)]
+mod rockery_assertion_tests {
+    use std::time::Duration;
+
+    use ehttp::Headers;
+
+    use super::*;
+
+    #[test]
+    fn test_evaluate() {
+        let headers = Headers::new(&[("Content-Type", "application/json"), ("X-Id", "42")]);
+        let json = RockeryResponse::new(201, "Created", &headers, br#"{ "user": { "id": 42, "name": "Rex" } }"#, Duration::from_millis(30));
+        let xml = RockeryResponse::new(200, "OK", &headers, br#"<user id="42"><name>Rex</name></user>"#, Duration::from_millis(30));
+
+        let passed = |a : RockeryAssertion, r : &RockeryResponse| a.evaluate(r).is_passed();
+        assert!(passed(RockeryAssertion::StatusCode(201), &json));
+        assert!(!passed(RockeryAssertion::StatusCode(200), &json));
+        assert!(passed(RockeryAssertion::StatusRange(200, 299), &json));
+        assert!(passed(RockeryAssertion::HeaderPresent("x-id".to_owned()), &json));
+        assert!(!passed(RockeryAssertion::HeaderPresent("Location".to_owned()), &json));
+        assert!(passed(RockeryAssertion::HeaderEquals("X-Id".to_owned(), "42".to_owned()), &json));
+        assert!(passed(RockeryAssertion::BodyContains("Rex".to_owned()), &json));
+        assert!(passed(RockeryAssertion::BodyMatches(r#""id":\s*\d+"#.to_owned()), &json));
+        assert!(!passed(RockeryAssertion::BodyMatches("(".to_owned()), &json));
+        assert!(passed(RockeryAssertion::JsonPathEquals("$.user.name".to_owned(), "Rex".to_owned()), &json));
+        assert!(passed(RockeryAssertion::JsonPathEquals("$.user.id".to_owned(), "42".to_owned()), &json));
+        assert!(!passed(RockeryAssertion::JsonPathEquals("$.user.id".to_owned(), "42".to_owned()), &xml));
+        assert!(passed(RockeryAssertion::XPathEquals("/user/@id".to_owned(), "42".to_owned()), &xml));
+        assert!(passed(RockeryAssertion::XPathEquals("/user/name".to_owned(), "Rex".to_owned()), &xml));
+        assert!(passed(RockeryAssertion::ResponseTimeBelow(100), &json));
+        assert!(!passed(RockeryAssertion::ResponseTimeBelow(30), &json));
+        assert!(passed(RockeryAssertion::BodySizeBelow(100), &json));
+
+        let recorded = RockeryResponse::new(200, "OK", &headers, br#"{ "id": 7, "first name": "Rex", "tags": [], "active": true }"#, Duration::ZERO);
+        let assertions = RockeryAssertion::from_response(&recorded);
+        assert_eq!(assertions, vec![
+            RockeryAssertion::StatusCode(200),
+            RockeryAssertion::HeaderEquals("Content-Type".to_owned(), "application/json".to_owned()),
+            RockeryAssertion::JsonPathEquals("$.active".to_owned(), "true".to_owned()),
+            RockeryAssertion::JsonPathEquals("$['first name']".to_owned(), "Rex".to_owned()),
+            RockeryAssertion::JsonPathEquals("$.id".to_owned(), "7".to_owned()),
+        ]);
+        assert!(assertions.iter().all(|a| a.evaluate(&recorded).is_passed()));
+        assert_eq!(RockeryAssertion::from_response(&xml).len(), 2);
+
+        let result = RockeryAssertion::JsonPathEquals("$.user.name".to_owned(), "Max".to_owned()).evaluate(&json);
+        assert_eq!(result.to_string(), "[FAIL] $.user.name equals 'Max': Expected $.user.name to be 'Max' but got 'Rex'.");
+    }
+}
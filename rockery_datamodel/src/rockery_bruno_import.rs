@@ -0,0 +1,521 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use regex::{Captures, Regex};
+use serde_json::Value;
+
+use crate::prelude::*;
+use crate::rockery_http::{parse_query, split_origin};
+use crate::rockery_postman_import::{add_authentication_parameters, add_imported_method, ImportedService};
+
+/// Creates projects from Bruno collection folders.
+///
+/// The name is read from `bruno.json`. Folders are mapped like the folders of a Postman collection: every top
+/// level folder becomes a service, requests of nested folders are prefixed with the path of the folder and
+/// requests outside of folders are added to a service named after the collection. Headers and the
+/// authentication of `collection.bru` and `folder.bru` apply to all requests within. Files in `environments`
+/// become environments, pre-request variables of the collection become project variables.
+/// Bruno variables `{{name}}` become `${name}`.
+pub struct RockeryBrunoImporter;
+
+impl RockeryBrunoImporter {
+    /// Read the collection folder at the given path and create a project from it.
+    pub fn import(path : &Path) -> Result<RockeryProject, String> {
+        let config = fs::read_to_string(path.join("bruno.json"))
+            .map_err(|e| format!("Not a Bruno collection. Could not read {}. Error: {}", path.join("bruno.json").display(), e))?;
+        let config : Value = serde_json::from_str(&config).map_err(|e| format!("Could not parse bruno.json. Error: {}", e))?;
+        let name = config.get("name").and_then(Value::as_str).unwrap_or("Bruno");
+
+        let mut project = RockeryProject::new(name);
+        let collection = read_bru(&path.join("collection.bru"))?;
+        for (k, v) in dictionary(&collection, "vars:pre-request") {
+            project.set_variable(&k, &v.unwrap_or_default());
+        }
+
+        for file in bru_files(&path.join("environments"))? {
+            let blocks = read_bru(&file)?;
+            let mut environment = RockeryEnvironment::new(&file_stem(&file));
+            for (k, v) in dictionary(&blocks, "vars") {
+                environment.set_variable(&k, &v.unwrap_or_default());
+            }
+            // Secrets are not stored within the collection.
+            for k in list(&blocks, "vars:secret") {
+                environment.set_variable(&k, "");
+            }
+            project.add_environment(environment);
+        }
+
+        let inherited = Inherited {
+            service_auth : folder_authentication(&collection),
+            auth : folder_authentication(&collection),
+            headers : dictionary(&collection, "headers"),
+        };
+
+        let mut services : Vec<ImportedService> = Vec::new();
+        for file in bru_files(path)?.iter().filter(|f| !f.ends_with("collection.bru")) {
+            add_request(&mut services, name, "", file, &inherited)?;
+        }
+        for folder in sub_folders(path)? {
+            let blocks = read_bru(&folder.join("folder.bru"))?;
+            let auth = folder_authentication(&blocks).or(inherited.auth.clone());
+            let mut headers = inherited.headers.clone();
+            headers.extend(dictionary(&blocks, "headers"));
+            let inherited = Inherited { service_auth : auth.clone(), auth, headers };
+            add_folder(&mut services, &folder_name(&folder, &blocks), "", &folder, &inherited)?;
+        }
+
+        for s in services {
+            project.add_service(s.into_service());
+        }
+
+        Ok(project)
+    }
+}
+
+/// A block of a `.bru` file, e.g. `headers { .. }`, with the indentation of its lines removed.
+struct BruBlock {
+    name : String,
+    lines : Vec<String>,
+}
+
+/// The authentication mode and its values, as written in an `auth:<mode>` block.
+#[derive(Clone, PartialEq)]
+struct BruAuth {
+    mode : String,
+    values : Vec<(String, Option<String>)>,
+}
+
+/// What a request takes over from the collection and its folders.
+struct Inherited {
+    service_auth : Option<BruAuth>,
+    auth : Option<BruAuth>,
+    headers : Vec<(String, Option<String>)>,
+}
+
+fn add_folder(services : &mut Vec<ImportedService>, folder : &str, prefix : &str, path : &Path, inherited : &Inherited) -> Result<(), String> {
+    for file in bru_files(path)?.iter().filter(|f| !f.ends_with("folder.bru")) {
+        add_request(services, folder, prefix, file, inherited)?;
+    }
+
+    for sub_folder in sub_folders(path)? {
+        let blocks = read_bru(&sub_folder.join("folder.bru"))?;
+        let prefix = format!("{}{}/", prefix, folder_name(&sub_folder, &blocks));
+        let mut headers = inherited.headers.clone();
+        headers.extend(dictionary(&blocks, "headers"));
+        let inherited = Inherited {
+            service_auth : inherited.service_auth.clone(),
+            auth : folder_authentication(&blocks).or(inherited.auth.clone()),
+            headers,
+        };
+        add_folder(services, folder, &prefix, &sub_folder, &inherited)?;
+    }
+
+    Ok(())
+}
+
+fn add_request(services : &mut Vec<ImportedService>, folder : &str, prefix : &str, file : &Path, inherited : &Inherited) -> Result<(), String> {
+    let blocks = read_bru(file)?;
+    let meta = dictionary(&blocks, "meta");
+    let meta_value = |name : &str| meta.iter().find(|(k, _)| k == name).and_then(|(_, v)| v.clone());
+    let name = format!("{}{}", prefix, meta_value("name").unwrap_or_else(|| file_stem(file)));
+
+    // The request method is the name of the block with the url.
+    let (request_method, settings) = blocks.iter()
+        .find_map(|b| RequestMethod::from_str(&b.name.to_uppercase()).ok().map(|m| (m, dictionary(&blocks, &b.name))))
+        .ok_or(format!("Request {} in {} has no request method.", name, file.display()))?;
+    let setting = |name : &str| settings.iter().find(|(k, _)| k == name).and_then(|(_, v)| v.clone()).unwrap_or_default();
+
+    let url = convert_variables(&setting("url"));
+    let (origin, path, query) = split_origin(&url);
+
+    let mut method = RockeryServiceMethod::new(&name, "");
+    method.set_request_method(request_method);
+
+    // Path parameters are written as `:name`.
+    let path_values = dictionary(&blocks, "params:path");
+    let mut segments = Vec::new();
+    for segment in path.split('/').filter(|s| !s.is_empty()) {
+        match segment.strip_prefix(':') {
+            Some(variable) => {
+                let value = path_values.iter().find(|(k, _)| k == variable).and_then(|(_, v)| v.clone()).filter(|v| !v.is_empty());
+                method.add_parameter(RequestParameter::Path(variable.to_owned(), value));
+                segments.push(format!("{{{}}}", variable));
+            },
+            None => segments.push(segment.to_owned()),
+        }
+    }
+    method.set_uri(&segments.join("/"));
+
+    // The query of the url repeats the enabled query parameters, older versions write `query` blocks.
+    let mut query_params = dictionary(&blocks, "params:query");
+    query_params.extend(dictionary(&blocks, "query"));
+    if query_params.is_empty() {
+        query_params = parse_query(query).into_iter().map(|(k, v)| (k, Some(v))).collect();
+    }
+    for (k, v) in query_params {
+        method.add_parameter(RequestParameter::Url(k, v));
+    }
+
+    let mut headers = inherited.headers.clone();
+    headers.extend(dictionary(&blocks, "headers"));
+    for (k, v) in headers {
+        method.add_parameter(RequestParameter::Header(k, v));
+    }
+
+    add_body(&mut method, &blocks, &setting("body"));
+
+    // Requests inherit the authentication of their folder unless they set another mode.
+    let auth = match setting("auth").as_str() {
+        "inherit" => inherited.auth.clone(),
+        mode => Some(authentication_block(&blocks, mode)),
+    };
+    if let (Some(a), false) = (&auth, auth == inherited.service_auth) {
+        if let Some(authentication) = authentication(a) {
+            add_authentication_parameters(&mut method, &authentication);
+        }
+    }
+
+    add_imported_method(services, folder, origin, method, inherited.service_auth.as_ref().and_then(authentication));
+
+    Ok(())
+}
+
+fn add_body(method : &mut RockeryServiceMethod, blocks : &[BruBlock], mode : &str) {
+    let content_type = match mode {
+        "form-urlencoded" | "multipart-form" => {
+            for (k, v) in dictionary(blocks, &format!("body:{}", mode)) {
+                // Files can not be sent as body parameter.
+                if !v.as_deref().unwrap_or_default().starts_with("@file(") {
+                    method.add_parameter(RequestParameter::Body(k, v));
+                }
+            }
+            "application/x-www-form-urlencoded"
+        },
+        "json" | "xml" | "text" | "sparql" | "graphql" => {
+            let body = text(blocks, &format!("body:{}", mode));
+            if body.is_empty() {
+                return;
+            }
+            method.set_body(&convert_variables(&body));
+
+            match mode {
+                "json" | "graphql" => "application/json",
+                "xml" => "application/xml",
+                "sparql" => "application/sparql-query",
+                _ => "text/plain",
+            }
+        },
+        _ => return,
+    };
+
+    if !method.get_parameter().iter().any(|p| matches!(p, RequestParameter::Header(k, _) if k.eq_ignore_ascii_case("Content-Type"))) {
+        method.add_parameter(RequestParameter::Header("Content-Type".to_owned(), Some(content_type.to_owned())));
+    }
+}
+
+/// The authentication set in `auth { mode: .. }` of `collection.bru` or `folder.bru`.
+fn folder_authentication(blocks : &[BruBlock]) -> Option<BruAuth> {
+    let mode = dictionary(blocks, "auth").into_iter().find(|(k, _)| k == "mode").and_then(|(_, v)| v)?;
+    match mode.as_str() {
+        "inherit" => None,
+        mode => Some(authentication_block(blocks, mode)),
+    }
+}
+
+fn authentication_block(blocks : &[BruBlock], mode : &str) -> BruAuth {
+    BruAuth { mode : mode.to_owned(), values : dictionary(blocks, &format!("auth:{}", mode)) }
+}
+
+/// Map a Bruno authentication onto the supported authentication types. Others are skipped.
+fn authentication(auth : &BruAuth) -> Option<AuthenticationType> {
+    let value = |name : &str| auth.values.iter().find(|(k, _)| k == name).and_then(|(_, v)| v.clone()).unwrap_or_default();
+
+    match auth.mode.as_str() {
+        "none" => Some(AuthenticationType::None(NoAuthenticationAuthentication {})),
+        "basic" => Some(AuthenticationType::Basic(BasicAuthenticationAuthentication::new(&value("username"), &value("password")))),
+        "bearer" => Some(AuthenticationType::Bearer(BearerAuthenticationAuthentication::new(&value("token")))),
+        "apikey" => {
+            let location = if value("placement") == "queryparams" { ApiKeyLocation::Query } else { ApiKeyLocation::Header };
+            Some(AuthenticationType::ApiKey(ApiKeyAuthenticationAuthentication::new(&value("key"), &value("value"), location)))
+        },
+        _ => None,
+    }
+}
+
+/// Read a `.bru` file. A missing file has no blocks.
+fn read_bru(path : &Path) -> Result<Vec<BruBlock>, String> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(parse_bru(&content)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(format!("Could not read {}. Error: {}", path.display(), e)),
+    }
+}
+
+/// Split a `.bru` file into its blocks. Blocks start with `name {` or `name [` and end with a closing
+/// bracket at the start of a line, their content is indented by two spaces.
+fn parse_bru(content : &str) -> Vec<BruBlock> {
+    let mut blocks = Vec::new();
+    let mut current : Option<(BruBlock, char)> = None;
+
+    for line in content.lines() {
+        match &mut current {
+            Some((block, close)) => {
+                if line.trim_end() == close.to_string() {
+                    blocks.push(current.take().unwrap().0);
+                } else {
+                    let indent = line.len() - line.trim_start_matches(' ').len();
+                    block.lines.push(line[indent.min(2)..].to_owned());
+                }
+            },
+            None => {
+                let trimmed = line.trim();
+                let close = match trimmed.chars().last() {
+                    Some('{') => '}',
+                    Some('[') => ']',
+                    _ => continue,
+                };
+                let name = trimmed[..trimmed.len() - 1].trim().to_owned();
+                current = Some((BruBlock { name, lines : Vec::new() }, close));
+            },
+        }
+    }
+
+    blocks
+}
+
+/// The entries `name: value` of a block. Disabled entries `~name: value` have no value.
+fn dictionary(blocks : &[BruBlock], name : &str) -> Vec<(String, Option<String>)> {
+    blocks.iter().filter(|b| b.name == name).flat_map(|b| &b.lines).filter_map(|line| {
+        let (k, v) = line.split_once(':')?;
+        let v = convert_variables(v.trim());
+        match k.trim().strip_prefix('~') {
+            Some(k) => Some((k.to_owned(), None)),
+            None => Some((k.trim().to_owned(), Some(v))),
+        }
+    }).collect()
+}
+
+/// The entries of a list block, e.g. `vars:secret [ token ]`.
+fn list(blocks : &[BruBlock], name : &str) -> Vec<String> {
+    blocks.iter().filter(|b| b.name == name).flat_map(|b| &b.lines)
+        .map(|l| l.trim().trim_end_matches(',').to_owned())
+        .filter(|l| !l.is_empty())
+        .collect()
+}
+
+/// The content of a text block, e.g. `body:json { .. }`.
+fn text(blocks : &[BruBlock], name : &str) -> String {
+    blocks.iter().find(|b| b.name == name).map(|b| b.lines.join("\n").trim().to_owned()).unwrap_or_default()
+}
+
+/// The request files of a folder, ordered by their sequence number.
+fn bru_files(path : &Path) -> Result<Vec<PathBuf>, String> {
+    let entries = match fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Could not read folder {}. Error: {}", path.display(), e)),
+    };
+
+    let mut files = Vec::new();
+    for entry in entries.flatten() {
+        let file = entry.path();
+        if file.is_file() && file.extension().map(|e| e == "bru").unwrap_or(false) {
+            let seq = dictionary(&read_bru(&file)?, "meta").into_iter()
+                .find(|(k, _)| k == "seq")
+                .and_then(|(_, v)| v?.parse::<u32>().ok())
+                .unwrap_or(u32::MAX);
+            files.push((seq, file));
+        }
+    }
+    files.sort();
+
+    Ok(files.into_iter().map(|(_, f)| f).collect())
+}
+
+/// The folders with requests, ordered by name. Environments and hidden folders are skipped.
+fn sub_folders(path : &Path) -> Result<Vec<PathBuf>, String> {
+    let entries = fs::read_dir(path).map_err(|e| format!("Could not read folder {}. Error: {}", path.display(), e))?;
+    let mut folders : Vec<PathBuf> = entries.flatten().map(|e| e.path())
+        .filter(|p| p.is_dir())
+        .filter(|p| {
+            let name = file_stem(p);
+            name != "environments" && name != "node_modules" && !name.starts_with('.')
+        })
+        .collect();
+    folders.sort();
+
+    Ok(folders)
+}
+
+fn folder_name(path : &Path, blocks : &[BruBlock]) -> String {
+    dictionary(blocks, "meta").into_iter().find(|(k, _)| k == "name").and_then(|(_, v)| v).unwrap_or_else(|| file_stem(path))
+}
+
+fn file_stem(path : &Path) -> String {
+    path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default()
+}
+
+/// Replace Bruno variables `{{name}}` by `${name}`.
+fn convert_variables(text : &str) -> String {
+    let regex = Regex::new(r"\{\{\s*([^{}\s]+)\s*\}\}").unwrap();
+    regex.replace_all(text, |c : &Captures<'_>| format!("${{{}}}", &c[1])).to_string()
+}
+
+#[cfg(test)]
+mod rockery_bruno_import_tests {
+    use super::*;
+
+    const GET_USER : &str = r#"meta {
+  name: Get user
+  type: http
+  seq: 1
+}
+
+get {
+  url: {{baseUrl}}/users/:id?lang=de
+  body: none
+  auth: inherit
+}
+
+params:query {
+  lang: de
+  ~page: 1
+}
+
+params:path {
+  id: 7
+}
+
+headers {
+  Accept: application/json
+}
+"#;
+
+    const CREATE_USER : &str = r#"meta {
+  name: Create user
+  type: http
+  seq: 2
+}
+
+post {
+  url: {{baseUrl}}/users
+  body: json
+  auth: basic
+}
+
+auth:basic {
+  username: admin
+  password: secret
+}
+
+body:json {
+  {
+    "name": "{{user}}"
+  }
+}
+
+tests {
+  test("created", function() {
+    expect(res.status).to.equal(201);
+  });
+}
+"#;
+
+    #[test]
+    fn test_import() {
+        let root = std::env::temp_dir().join(format!("rockery_bruno_{}", std::process::id()));
+        let write = |path : &str, content : &str| {
+            let file = root.join(path);
+            fs::create_dir_all(file.parent().unwrap()).unwrap();
+            fs::write(file, content).unwrap();
+        };
+
+        write("bruno.json", r#"{ "version": "1", "name": "Shop", "type": "collection" }"#);
+        write("collection.bru", "headers {\n  X-Client: rockery\n}\n\nauth {\n  mode: bearer\n}\n\nauth:bearer {\n  token: {{token}}\n}\n\nvars:pre-request {\n  user: rex\n}\n");
+        write("environments/Staging.bru", "vars {\n  baseUrl: https://stage.example.com\n}\nvars:secret [\n  token\n]\n");
+        write("Health.bru", "meta {\n  name: Health\n  seq: 1\n}\n\nget {\n  url: https://status.example.com/health\n  body: none\n  auth: none\n}\n");
+        write("users/folder.bru", "meta {\n  name: Users\n}\n\nheaders {\n  X-Folder: users\n}\n");
+        write("users/Create user.bru", CREATE_USER);
+        write("users/Get user.bru", GET_USER);
+        write("users/admin/Login.bru", "meta {\n  name: Login\n}\n\npost {\n  url: {{baseUrl}}/login\n  body: form-urlencoded\n  auth: inherit\n}\n\nbody:form-urlencoded {\n  user: admin\n  ~debug: 1\n}\n");
+
+        let project = RockeryBrunoImporter::import(&root);
+        fs::remove_dir_all(&root).unwrap();
+        let project = project.unwrap();
+
+        assert_eq!(project.get_name(), "Shop");
+        assert_eq!(project.get_variables().get("user").unwrap(), "rex");
+        let environment = &project.get_environments()[0];
+        assert_eq!(environment.get_name(), "Staging");
+        assert_eq!(environment.get_variables().get("baseUrl").unwrap(), "https://stage.example.com");
+        assert_eq!(environment.get_variables().get("token").unwrap(), "");
+
+        let health = project.find_service("Shop").unwrap();
+        assert_eq!(health.get_base_url(), "https://status.example.com");
+        assert_eq!(health.get_methods()[0].get_parameter(), &vec![RequestParameter::Header("X-Client".to_owned(), Some("rockery".to_owned()))]);
+
+        let users = project.find_service("Users").unwrap();
+        assert_eq!(users.get_base_url(), "${baseUrl}");
+        assert!(matches!(users.get_authentication(), AuthenticationType::Bearer(b) if b.get_token() == "${token}"));
+        let methods = users.get_methods();
+        assert_eq!(methods.len(), 3);
+
+        assert_eq!((methods[0].get_name(), methods[0].get_uri(), methods[0].get_request_method()), ("Get user", "users/{id}", RequestMethod::Get));
+        assert_eq!(methods[0].get_parameter(), &vec![
+            RequestParameter::Path("id".to_owned(), Some("7".to_owned())),
+            RequestParameter::Url("lang".to_owned(), Some("de".to_owned())),
+            RequestParameter::Url("page".to_owned(), None),
+            RequestParameter::Header("X-Client".to_owned(), Some("rockery".to_owned())),
+            RequestParameter::Header("X-Folder".to_owned(), Some("users".to_owned())),
+            RequestParameter::Header("Accept".to_owned(), Some("application/json".to_owned())),
+        ]);
+
+        assert_eq!((methods[1].get_name(), methods[1].get_request_method()), ("Create user", RequestMethod::Post));
+        assert_eq!(methods[1].get_body(), &Some("{\n  \"name\": \"${user}\"\n}".to_owned()));
+        assert!(methods[1].get_parameter().contains(&RequestParameter::Header("Content-Type".to_owned(), Some("application/json".to_owned()))));
+        assert!(methods[1].get_parameter().contains(&RequestParameter::Header("Authorization".to_owned(), Some("Basic YWRtaW46c2VjcmV0".to_owned()))));
+
+        assert_eq!(methods[2].get_name(), "admin/Login");
+        assert_eq!(methods[2].get_parameter(), &vec![
+            RequestParameter::Header("X-Client".to_owned(), Some("rockery".to_owned())),
+            RequestParameter::Header("X-Folder".to_owned(), Some("users".to_owned())),
+            RequestParameter::Body("user".to_owned(), Some("admin".to_owned())),
+            RequestParameter::Body("debug".to_owned(), None),
+            RequestParameter::Header("Content-Type".to_owned(), Some("application/x-www-form-urlencoded".to_owned())),
+        ]);
+
+        assert!(RockeryBrunoImporter::import(Path::new("does_not_exist")).is_err());
+    }
+
+    #[test]
+    fn test_invalid_collection() {
+        let root = std::env::temp_dir().join(format!("rockery_bruno_invalid_{}", std::process::id()));
+        let import = |files : &[(&str, &str)]| {
+            for (path, content) in files {
+                let file = root.join(path);
+                fs::create_dir_all(file.parent().unwrap()).unwrap();
+                fs::write(file, content).unwrap();
+            }
+            let project = RockeryBrunoImporter::import(&root);
+            fs::remove_dir_all(&root).unwrap();
+            project
+        };
+        let config = ("bruno.json", r#"{ "name": "Shop" }"#);
+
+        assert!(import(&[("bruno.json", "{ no json")]).unwrap_err().starts_with("Could not parse bruno.json."));
+        assert!(import(&[("collection.bru", "")]).unwrap_err().starts_with("Not a Bruno collection."));
+
+        let error = import(&[config, ("Broken.bru", "meta {\n  name: Broken\n}\n\nfetch {\n  url: https://example.com\n}\n")]).unwrap_err();
+        assert!(error.starts_with("Request Broken in ") && error.ends_with("has no request method."));
+        // A block that is not closed is ignored.
+        let error = import(&[config, ("Open.bru", "meta {\n  name: Open\n}\n\nget {\n  url: https://example.com\n")]).unwrap_err();
+        assert!(error.ends_with("has no request method."));
+
+        // Lines without colon and malformed sequence numbers are skipped.
+        let mut project = import(&[config, ("Health.bru", "meta {\n  seq: first\n  no colon\n}\n\nget {\n  url: https://example.com/health\n  garbage\n}\n\nheaders {\n  broken\n}\n")]).unwrap();
+        let method = &project.get_services().get("Shop").unwrap().get_methods()[0];
+        assert_eq!((method.get_name(), method.get_uri()), ("Health", "health"));
+        assert!(method.get_parameter().is_empty());
+    }
+}
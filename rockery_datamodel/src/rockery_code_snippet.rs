@@ -0,0 +1,304 @@
+use core::fmt;
+
+use crate::prelude::*;
+
+/// The languages and libraries code snippets can be created for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RockeryCodeLanguage {
+    /// Rust with the blocking client of reqwest.
+    RustReqwest,
+    RustEhttp,
+    PythonRequests,
+    JavaScriptFetch,
+
+    /// Java 11 and later with `java.net.http.HttpClient`.
+    JavaHttpClient,
+    CSharpHttpClient,
+    PowerShell,
+}
+
+impl RockeryCodeLanguage {
+    pub const ALL : [RockeryCodeLanguage; 7] = [
+        RockeryCodeLanguage::RustReqwest,
+        RockeryCodeLanguage::RustEhttp,
+        RockeryCodeLanguage::PythonRequests,
+        RockeryCodeLanguage::JavaScriptFetch,
+        RockeryCodeLanguage::JavaHttpClient,
+        RockeryCodeLanguage::CSharpHttpClient,
+        RockeryCodeLanguage::PowerShell,
+    ];
+}
+
+impl fmt::Display for RockeryCodeLanguage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RockeryCodeLanguage::RustReqwest => write!(f, "Rust (reqwest)"),
+            RockeryCodeLanguage::RustEhttp => write!(f, "Rust (ehttp)"),
+            RockeryCodeLanguage::PythonRequests => write!(f, "Python (requests)"),
+            RockeryCodeLanguage::JavaScriptFetch => write!(f, "JavaScript (fetch)"),
+            RockeryCodeLanguage::JavaHttpClient => write!(f, "Java (HttpClient)"),
+            RockeryCodeLanguage::CSharpHttpClient => write!(f, "C# (HttpClient)"),
+            RockeryCodeLanguage::PowerShell => write!(f, "PowerShell (Invoke-RestMethod)"),
+        }
+    }
+}
+
+/// Creates code that sends the request of a method.
+pub struct RockeryCodeGenerator;
+
+impl RockeryCodeGenerator {
+    /// Create the code for the method with the given index, using the given parameters as `do_request`
+    /// would. The headers of the authentication of the service are included.
+    pub fn generate(service : &RockeryService, method : usize, params : &[RequestParameter], language : RockeryCodeLanguage) -> Result<String, String> {
+        let request = service.get_effective_request(method, params)?;
+
+        Ok(match language {
+            RockeryCodeLanguage::RustReqwest => rust_reqwest(&request),
+            RockeryCodeLanguage::RustEhttp => rust_ehttp(&request),
+            RockeryCodeLanguage::PythonRequests => python_requests(&request),
+            RockeryCodeLanguage::JavaScriptFetch => javascript_fetch(&request),
+            RockeryCodeLanguage::JavaHttpClient => java_http_client(&request),
+            RockeryCodeLanguage::CSharpHttpClient => csharp_http_client(&request),
+            RockeryCodeLanguage::PowerShell => powershell(&request),
+        })
+    }
+}
+
+fn rust_reqwest(request : &RockeryRequest) -> String {
+    let mut code = String::from("let client = reqwest::blocking::Client::new();\n");
+    code.push_str(&format!("let response = client.request(reqwest::Method::{}, {:?})\n", request.get_method(), request.get_url()));
+    for (k, v) in request.get_headers() {
+        code.push_str(&format!("    .header({:?}, {:?})\n", k, v));
+    }
+    if !request.get_body().is_empty() {
+        code.push_str(&format!("    .body({:?})\n", request.get_body()));
+    }
+    code.push_str("    .send()?;\nprintln!(\"{} {}\", response.status(), response.text()?);\n");
+
+    code
+}
+
+fn rust_ehttp(request : &RockeryRequest) -> String {
+    let mut code = format!("let mut request = ehttp::Request::get({:?});\n", request.get_url());
+    code.push_str(&format!("request.method = {:?}.to_owned();\n", request.get_method()));
+    code.push_str("request.headers = ehttp::Headers::new(&[\n");
+    for (k, v) in request.get_headers() {
+        code.push_str(&format!("    ({:?}, {:?}),\n", k, v));
+    }
+    code.push_str("]);\n");
+    if !request.get_body().is_empty() {
+        code.push_str(&format!("request.body = {:?}.as_bytes().to_vec();\n", request.get_body()));
+    }
+    code.push_str("ehttp::fetch(request, move |result : ehttp::Result<ehttp::Response>| {\n");
+    code.push_str("    match result {\n");
+    code.push_str("        Ok(response) => println!(\"{} {}\", response.status, response.text().unwrap_or_default()),\n");
+    code.push_str("        Err(e) => println!(\"{}\", e),\n");
+    code.push_str("    }\n});\n");
+
+    code
+}
+
+fn python_requests(request : &RockeryRequest) -> String {
+    let mut code = String::from("import requests\n\nresponse = requests.request(\n");
+    code.push_str(&format!("    {},\n    {},\n", quote(request.get_method()), quote(request.get_url())));
+    if !request.get_headers().is_empty() {
+        code.push_str("    headers={\n");
+        for (k, v) in request.get_headers() {
+            code.push_str(&format!("        {}: {},\n", quote(k), quote(v)));
+        }
+        code.push_str("    },\n");
+    }
+    if !request.get_body().is_empty() {
+        code.push_str(&format!("    data={},\n", quote(request.get_body())));
+    }
+    code.push_str(")\nprint(response.status_code, response.text)\n");
+
+    code
+}
+
+fn javascript_fetch(request : &RockeryRequest) -> String {
+    let mut code = format!("const response = await fetch({}, {{\n", quote(request.get_url()));
+    code.push_str(&format!("  method: {},\n", quote(request.get_method())));
+    if !request.get_headers().is_empty() {
+        code.push_str("  headers: {\n");
+        for (k, v) in request.get_headers() {
+            code.push_str(&format!("    {}: {},\n", quote(k), quote(v)));
+        }
+        code.push_str("  },\n");
+    }
+    if !request.get_body().is_empty() {
+        code.push_str(&format!("  body: {},\n", quote(request.get_body())));
+    }
+    code.push_str("});\nconsole.log(response.status, await response.text());\n");
+
+    code
+}
+
+fn java_http_client(request : &RockeryRequest) -> String {
+    let mut code = String::from("import java.net.URI;\nimport java.net.http.HttpClient;\nimport java.net.http.HttpRequest;\nimport java.net.http.HttpResponse;\n\n");
+    code.push_str("HttpClient client = HttpClient.newHttpClient();\n");
+    code.push_str("HttpRequest request = HttpRequest.newBuilder()\n");
+    code.push_str(&format!("    .uri(URI.create({}))\n", quote(request.get_url())));
+    let body = match request.get_body() {
+        "" => "HttpRequest.BodyPublishers.noBody()".to_owned(),
+        b => format!("HttpRequest.BodyPublishers.ofString({})", quote(b)),
+    };
+    code.push_str(&format!("    .method({}, {})\n", quote(request.get_method()), body));
+    for (k, v) in request.get_headers() {
+        code.push_str(&format!("    .header({}, {})\n", quote(k), quote(v)));
+    }
+    code.push_str("    .build();\n");
+    code.push_str("HttpResponse<String> response = client.send(request, HttpResponse.BodyHandlers.ofString());\n");
+    code.push_str("System.out.println(response.statusCode() + \" \" + response.body());\n");
+
+    code
+}
+
+fn csharp_http_client(request : &RockeryRequest) -> String {
+    let mut code = String::from("using var client = new HttpClient();\n");
+    code.push_str(&format!("using var request = new HttpRequestMessage(new HttpMethod({}), {});\n", quote(request.get_method()), quote(request.get_url())));
+
+    // Content headers can only be set on the content.
+    let is_content_header = |k : &str| k.to_lowercase().starts_with("content-");
+    for (k, v) in request.get_headers().iter().filter(|(k, _)| !is_content_header(k)) {
+        code.push_str(&format!("request.Headers.TryAddWithoutValidation({}, {});\n", quote(k), quote(v)));
+    }
+    if !request.get_body().is_empty() {
+        code.push_str(&format!("request.Content = new StringContent({});\n", quote(request.get_body())));
+        code.push_str("request.Content.Headers.Remove(\"Content-Type\");\n");
+        for (k, v) in request.get_headers().iter().filter(|(k, _)| is_content_header(k)) {
+            code.push_str(&format!("request.Content.Headers.TryAddWithoutValidation({}, {});\n", quote(k), quote(v)));
+        }
+    }
+    code.push_str("using var response = await client.SendAsync(request);\n");
+    code.push_str("Console.WriteLine($\"{(int)response.StatusCode} {await response.Content.ReadAsStringAsync()}\");\n");
+
+    code
+}
+
+fn powershell(request : &RockeryRequest) -> String {
+    // Windows PowerShell does not accept the content type as header.
+    let content_type = request.get_headers().iter().find(|(k, _)| k.eq_ignore_ascii_case("Content-Type")).map(|(_, v)| v);
+    let headers : Vec<&(String, String)> = request.get_headers().iter().filter(|(k, _)| !k.eq_ignore_ascii_case("Content-Type")).collect();
+
+    let mut code = String::new();
+    let mut command = format!("$response = Invoke-RestMethod -Uri {} -Method {}", quote_powershell(request.get_url()), quote_powershell(request.get_method()));
+    if !headers.is_empty() {
+        code.push_str("$headers = @{\n");
+        for (k, v) in headers {
+            code.push_str(&format!("    {} = {}\n", quote_powershell(k), quote_powershell(v)));
+        }
+        code.push_str("}\n");
+        command.push_str(" -Headers $headers");
+    }
+    if let Some(content_type) = content_type {
+        command.push_str(&format!(" -ContentType {}", quote_powershell(content_type)));
+    }
+    if !request.get_body().is_empty() {
+        code.push_str(&format!("$body = {}\n", quote_powershell(request.get_body())));
+        command.push_str(" -Body $body");
+    }
+    code.push_str(&command);
+    code.push_str("\n$response\n");
+
+    code
+}
+
+/// Quote a value as string literal in double quotes. The escapes of JSON are understood by Python,
+/// JavaScript, Java and C# alike.
+fn quote(value : &str) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
+
+/// Quote a value as string literal in single quotes for a POSIX shell.
+pub(crate) fn quote_shell(value : &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Quote a value as verbatim string literal for PowerShell.
+fn quote_powershell(value : &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+#[cfg(test)]
+mod rockery_code_snippet_tests {
+    use super::*;
+
+    fn service() -> RockeryService {
+        let mut service = RockeryService::new(RockeryServiceType::Rest, "https://api.example.com");
+        service.set_authentication(AuthenticationType::Bearer(BearerAuthenticationAuthentication::new("t")));
+        let m = service.add_method("create user", "users");
+        m.set_request_method(RequestMethod::Post);
+        m.add_parameter(RequestParameter::Url("lang".to_owned(), None));
+        m.add_parameter(RequestParameter::Header("Content-Type".to_owned(), Some("application/json".to_owned())));
+        m.set_body("{\"name\": \"O'Brien\"}");
+        service.add_method("health", "health");
+
+        service
+    }
+
+    #[test]
+    fn test_generate() {
+        let service = service();
+        let params = [RequestParameter::Url("lang".to_owned(), Some("de".to_owned()))];
+        let generate = |language| RockeryCodeGenerator::generate(&service, 0, &params, language).unwrap();
+
+        assert_eq!(generate(RockeryCodeLanguage::PythonRequests), "import requests\n\nresponse = requests.request(\n    \"POST\",\n    \"https://api.example.com/users/?lang=de\",\n    headers={\n        \"Content-Type\": \"application/json\",\n        \"Authorization\": \"Bearer t\",\n    },\n    data=\"{\\\"name\\\": \\\"O'Brien\\\"}\",\n)\nprint(response.status_code, response.text)\n");
+        assert_eq!(generate(RockeryCodeLanguage::JavaScriptFetch), "const response = await fetch(\"https://api.example.com/users/?lang=de\", {\n  method: \"POST\",\n  headers: {\n    \"Content-Type\": \"application/json\",\n    \"Authorization\": \"Bearer t\",\n  },\n  body: \"{\\\"name\\\": \\\"O'Brien\\\"}\",\n});\nconsole.log(response.status, await response.text());\n");
+        assert_eq!(generate(RockeryCodeLanguage::PowerShell), "$headers = @{\n    'Authorization' = 'Bearer t'\n}\n$body = '{\"name\": \"O''Brien\"}'\n$response = Invoke-RestMethod -Uri 'https://api.example.com/users/?lang=de' -Method 'POST' -Headers $headers -ContentType 'application/json' -Body $body\n$response\n");
+
+        let reqwest = generate(RockeryCodeLanguage::RustReqwest);
+        assert!(reqwest.contains("client.request(reqwest::Method::POST, \"https://api.example.com/users/?lang=de\")\n"));
+        assert!(reqwest.contains("    .header(\"Authorization\", \"Bearer t\")\n"));
+        assert!(reqwest.contains("    .body(\"{\\\"name\\\": \\\"O'Brien\\\"}\")\n"));
+
+        let ehttp = generate(RockeryCodeLanguage::RustEhttp);
+        assert!(ehttp.contains("request.method = \"POST\".to_owned();\n"));
+        assert!(ehttp.contains("    (\"Authorization\", \"Bearer t\"),\n"));
+
+        let java = generate(RockeryCodeLanguage::JavaHttpClient);
+        assert!(java.contains("    .method(\"POST\", HttpRequest.BodyPublishers.ofString(\"{\\\"name\\\": \\\"O'Brien\\\"}\"))\n"));
+        assert!(java.contains("    .header(\"Authorization\", \"Bearer t\")\n"));
+
+        let csharp = generate(RockeryCodeLanguage::CSharpHttpClient);
+        assert!(csharp.contains("request.Headers.TryAddWithoutValidation(\"Authorization\", \"Bearer t\");\n"));
+        assert!(csharp.contains("request.Content.Headers.TryAddWithoutValidation(\"Content-Type\", \"application/json\");\n"));
+        assert!(!csharp.contains("request.Headers.TryAddWithoutValidation(\"Content-Type\""));
+
+        // Requests without body.
+        let java = RockeryCodeGenerator::generate(&service, 1, &[], RockeryCodeLanguage::JavaHttpClient).unwrap();
+        assert!(java.contains(".method(\"GET\", HttpRequest.BodyPublishers.noBody())"));
+        let csharp = RockeryCodeGenerator::generate(&service, 1, &[], RockeryCodeLanguage::CSharpHttpClient).unwrap();
+        assert!(!csharp.contains("StringContent"));
+
+        assert!(RockeryCodeGenerator::generate(&service, 2, &[], RockeryCodeLanguage::PowerShell).is_err());
+        assert_eq!(RockeryCodeLanguage::ALL.len(), 7);
+    }
+
+    #[test]
+    fn test_generate_malformed_values() {
+        let mut service = service();
+        for language in RockeryCodeLanguage::ALL {
+            assert_eq!(RockeryCodeGenerator::generate(&service, 2, &[], language).unwrap_err(), "Invalid index. There are only 2 methods but method 2 was requested.");
+        }
+
+        // Quotes, line breaks and control characters are escaped instead of ending the string literal.
+        let m = service.get_method_mut(1).unwrap();
+        m.add_parameter(RequestParameter::Header("X-Note".to_owned(), Some("a\"b'c\\d\u{1}".to_owned())));
+        m.set_body("line 1\nline 2 ' \"");
+        let generate = |language| RockeryCodeGenerator::generate(&service, 1, &[], language).unwrap();
+
+        let python = generate(RockeryCodeLanguage::PythonRequests);
+        assert!(python.contains("        \"X-Note\": \"a\\\"b'c\\\\d\\u0001\",\n"));
+        assert!(python.contains("    data=\"line 1\\nline 2 ' \\\"\",\n"));
+        let reqwest = generate(RockeryCodeLanguage::RustReqwest);
+        assert!(reqwest.contains("    .header(\"X-Note\", \"a\\\"b'c\\\\d\\u{1}\")\n"));
+        assert!(reqwest.contains("    .body(\"line 1\\nline 2 ' \\\"\")\n"));
+        let powershell = generate(RockeryCodeLanguage::PowerShell);
+        assert!(powershell.contains("    'X-Note' = 'a\"b''c\\d\u{1}'\n"));
+        assert!(powershell.contains("$body = 'line 1\nline 2 '' \"'\n"));
+        for language in [RockeryCodeLanguage::JavaScriptFetch, RockeryCodeLanguage::JavaHttpClient, RockeryCodeLanguage::CSharpHttpClient] {
+            assert!(generate(language).contains("\"line 1\\nline 2 ' \\\"\""));
+        }
+    }
+}
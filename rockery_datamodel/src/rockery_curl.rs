@@ -0,0 +1,371 @@
+use std::str::FromStr;
+
+use crate::prelude::*;
+use crate::rockery_code_snippet::quote_shell;
+use crate::rockery_http::{encode_component, parse_query, split_url};
+
+/// Options of curl that take a value but have no meaning for a method.
+const IGNORED_VALUE_OPTIONS : [&str; 14] = ["-o", "--output", "-m", "--max-time", "--connect-timeout", "-x", "--proxy",
+    "--retry", "-w", "--write-out", "-c", "--cookie-jar", "--cacert", "--cert"];
+
+/// Options of curl without value that have no meaning for a method.
+const IGNORED_FLAGS : [&str; 18] = ["--compressed", "-s", "--silent", "-S", "--show-error", "-L", "--location", "-k", "--insecure",
+    "-i", "--include", "-v", "--verbose", "-f", "--fail", "-#", "--progress-bar", "--http1.1"];
+
+/// Creates a service with a single method from a curl command, e.g. one copied from the developer tools of a browser.
+///
+/// The scheme and host of the url become the base url of the service, the path the uri of the method and the
+/// query its url parameters. Headers, data and form fields become header parameters, body and body parameters.
+/// `-u` sets basic authentication for the service.
+pub struct RockeryCurlImporter;
+
+impl RockeryCurlImporter {
+    pub fn import_str(command : &str) -> Result<RockeryService, String> {
+        let args = split_command(command)?;
+        let mut args = args.iter().map(String::as_str).peekable();
+        if args.next() != Some("curl") {
+            return Err("Not a curl command. The command must start with 'curl'.".to_owned());
+        }
+
+        let mut url = None;
+        let mut request_method = None;
+        let mut headers : Vec<(String, String)> = Vec::new();
+        let mut data : Vec<String> = Vec::new();
+        let mut form : Vec<(String, String)> = Vec::new();
+        let mut user = None;
+        let mut get = false;
+
+        while let Some(arg) = args.next() {
+            // Short options may be followed by their value directly, e.g. `-XPOST`.
+            let short = arg.strip_prefix('-').filter(|_| !arg.starts_with("--")).and_then(|a| a.chars().next());
+            let (option, attached) = match short {
+                Some(c) if "XHdFueAb".contains(c) && arg.len() > 1 + c.len_utf8() => {
+                    let (option, value) = arg.split_at(1 + c.len_utf8());
+                    (option, Some(value))
+                },
+                _ => (arg, None),
+            };
+            let mut value = || attached.or_else(|| args.next()).ok_or(format!("Option {} requires a value.", option));
+
+            match option {
+                "-X" | "--request" => request_method = Some(value()?.to_uppercase()),
+                "-H" | "--header" => {
+                    let header = value()?;
+                    let (k, v) = header.split_once(':').ok_or(format!("Invalid header '{}'. Expected <name>: <value>.", header))?;
+                    headers.push((k.trim().to_owned(), v.trim().to_owned()));
+                },
+                "-d" | "--data" | "--data-raw" | "--data-binary" | "--data-ascii" => data.push(value()?.to_owned()),
+                "--data-urlencode" => {
+                    let v = value()?;
+                    data.push(match v.split_once('=') {
+                        Some((k, v)) => format!("{}={}", k, encode_component(v)),
+                        None => encode_component(v),
+                    });
+                },
+                "-F" | "--form" | "--form-string" => {
+                    let field = value()?;
+                    let (k, v) = field.split_once('=').ok_or(format!("Invalid form field '{}'. Expected <name>=<value>.", field))?;
+                    // Files can not be sent as body parameter.
+                    if option == "--form-string" || !v.starts_with('@') {
+                        form.push((k.to_owned(), v.to_owned()));
+                    }
+                },
+                "-u" | "--user" => user = Some(value()?.to_owned()),
+                "-A" | "--user-agent" => headers.push(("User-Agent".to_owned(), value()?.to_owned())),
+                "-e" | "--referer" => headers.push(("Referer".to_owned(), value()?.to_owned())),
+                "-b" | "--cookie" => headers.push(("Cookie".to_owned(), value()?.to_owned())),
+                "-G" | "--get" => get = true,
+                "-I" | "--head" => request_method = Some("HEAD".to_owned()),
+                "--url" => url = Some(value()?.to_owned()),
+                o if IGNORED_VALUE_OPTIONS.contains(&o) => { value()?; },
+                o if IGNORED_FLAGS.contains(&o) => { },
+                // Combined flags, e.g. `-sSL`.
+                o if o.starts_with('-') && !o.starts_with("--") && o.len() > 2 && o[1..].chars().all(|c| IGNORED_FLAGS.contains(&format!("-{}", c).as_str())) => { },
+                o if o.starts_with('-') && o.len() > 1 => return Err(format!("Unsupported curl option '{}'.", o)),
+                u => url = Some(u.to_owned()),
+            }
+        }
+
+        let url = url.ok_or("The curl command does not contain an url.".to_owned())?;
+        let url = if url.contains("://") { url } else { format!("http://{}", url) };
+        let (origin, path, query) = split_url(&url)
+            .filter(|(o, _, _)| o.split_once("://").is_some_and(|(scheme, host)| !scheme.is_empty() && !host.is_empty()))
+            .ok_or(format!("Invalid url {}.", url))?;
+
+        let request_method = match request_method {
+            Some(m) => m,
+            None if !get && (!data.is_empty() || !form.is_empty()) => "POST".to_owned(),
+            None => "GET".to_owned(),
+        };
+        let request_method = RequestMethod::from_str(&request_method).map_err(|_| format!("Unknown request method {}.", request_method))?;
+
+        let mut service = RockeryService::new(RockeryServiceType::Rest, origin);
+        service.set_name(origin.split_once("://").map(|(_, h)| h).unwrap_or(origin));
+        if let Some(user) = user {
+            let (username, password) = user.split_once(':').unwrap_or((&user, ""));
+            service.set_authentication(AuthenticationType::Basic(BasicAuthenticationAuthentication::new(username, password)));
+        }
+
+        let uri = path.trim_matches('/');
+        let method = service.add_method(&format!("{} /{}", request_method, uri), uri);
+        method.set_request_method(request_method);
+
+        for (k, v) in parse_query(query) {
+            method.add_parameter(RequestParameter::Url(k, Some(v)));
+        }
+
+        // With -G the data is sent as query.
+        if get {
+            for (k, v) in data.iter().flat_map(|d| parse_query(d)) {
+                method.add_parameter(RequestParameter::Url(k, Some(v)));
+            }
+        }
+
+        let has_content_type = headers.iter().any(|(k, _)| k.eq_ignore_ascii_case("Content-Type"));
+        for (k, v) in headers {
+            method.add_parameter(RequestParameter::Header(k, Some(v)));
+        }
+
+        if !get && !data.is_empty() {
+            method.set_body(&data.join("&"));
+        }
+        for (k, v) in &form {
+            method.add_parameter(RequestParameter::Body(k.to_owned(), Some(v.to_owned())));
+        }
+
+        // Curl sends data form url encoded unless another content type is given. Form fields are sent url encoded as well.
+        if !has_content_type && ((!get && !data.is_empty()) || !form.is_empty()) {
+            method.add_parameter(RequestParameter::Header("Content-Type".to_owned(), Some("application/x-www-form-urlencoded".to_owned())));
+        }
+
+        Ok(service)
+    }
+}
+
+/// Writes the request of a method as curl command.
+pub struct RockeryCurlExporter;
+
+impl RockeryCurlExporter {
+    /// Create the curl command for the method with the given index, using the given parameters as `do_request`
+    /// would. The credentials of the service are included.
+    pub fn export(service : &RockeryService, method : usize, params : &[RequestParameter]) -> Result<String, String> {
+        let request = service.get_effective_request(method, params)?;
+        let mut command = String::from("curl");
+        if request.get_method() != "GET" {
+            command.push_str(&format!(" -X {}", request.get_method()));
+        }
+        command.push_str(&format!(" {}", quote_shell(request.get_url())));
+
+        for (k, v) in request.get_headers() {
+            command.push_str(&format!(" \\\n  -H {}", quote_shell(&format!("{}: {}", k, v))));
+        }
+
+        if !request.get_body().is_empty() {
+            command.push_str(&format!(" \\\n  --data-raw {}", quote_shell(request.get_body())));
+        }
+
+        Ok(command)
+    }
+}
+
+/// Split a command line like a POSIX shell: single and double quotes, `$'...'` strings with escapes and
+/// escaped line breaks. Windows line continuations `^` are accepted as well.
+fn split_command(command : &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut chars = command.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_arg = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => current.push(c),
+                        None => return Err("Unterminated single quote in curl command.".to_owned()),
+                    }
+                }
+            },
+            '$' if chars.peek() == Some(&'\'') => {
+                chars.next();
+                in_arg = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => current.push('\n'),
+                            Some('r') => current.push('\r'),
+                            Some('t') => current.push('\t'),
+                            Some(c) => current.push(c),
+                            None => return Err("Unterminated quote in curl command.".to_owned()),
+                        },
+                        Some(c) => current.push(c),
+                        None => return Err("Unterminated quote in curl command.".to_owned()),
+                    }
+                }
+            },
+            '"' => {
+                in_arg = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => current.push(c),
+                            Some('\n') => { },
+                            Some(c) => {
+                                current.push('\\');
+                                current.push(c);
+                            },
+                            None => return Err("Unterminated double quote in curl command.".to_owned()),
+                        },
+                        Some(c) => current.push(c),
+                        None => return Err("Unterminated double quote in curl command.".to_owned()),
+                    }
+                }
+            },
+            '\\' | '^' if matches!(chars.peek(), Some('\n') | Some('\r')) => {
+                while matches!(chars.peek(), Some('\n') | Some('\r')) {
+                    chars.next();
+                }
+            },
+            '\\' => {
+                in_arg = true;
+                if let Some(c) = chars.next() {
+                    current.push(c);
+                }
+            },
+            c if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            },
+            c => {
+                in_arg = true;
+                current.push(c);
+            },
+        }
+    }
+
+    if in_arg {
+        args.push(current);
+    }
+
+    Ok(args)
+}
+
+#[cfg(test)]
+mod rockery_curl_tests {
+    use super::*;
+
+    #[test]
+    fn test_split_command() {
+        assert_eq!(split_command("curl -H 'A: b c' \"x\\\"y\" \\\n  --data-raw $'line\\n\\'q\\'' a\\ b").unwrap(),
+            vec!["curl", "-H", "A: b c", "x\"y", "--data-raw", "line\n'q'", "a b"]);
+        assert_eq!(split_command("curl ''").unwrap(), vec!["curl", ""]);
+        assert!(split_command("curl 'open").is_err());
+    }
+
+    #[test]
+    fn test_import() {
+        let service = RockeryCurlImporter::import_str(r#"curl 'https://api.example.com/v1/users?active=true&name=a%20b' \
+            -X PUT -H 'Accept: application/json' -H "Content-Type: application/json" --compressed -sSL \
+            --data-raw '{"name":"rex"}' -u admin:secret"#).unwrap();
+        assert_eq!(service.get_name(), "api.example.com");
+        assert_eq!(service.get_base_url(), "https://api.example.com");
+        assert!(matches!(service.get_authentication(), AuthenticationType::Basic(b) if b.get_username() == "admin" && b.get_password() == "secret"));
+
+        let method = &service.get_methods()[0];
+        assert_eq!((method.get_name(), method.get_uri(), method.get_request_method()), ("PUT /v1/users", "v1/users", RequestMethod::Put));
+        assert_eq!(method.get_body(), &Some(r#"{"name":"rex"}"#.to_owned()));
+        assert_eq!(method.get_parameter(), &vec![
+            RequestParameter::Url("active".to_owned(), Some("true".to_owned())),
+            RequestParameter::Url("name".to_owned(), Some("a b".to_owned())),
+            RequestParameter::Header("Accept".to_owned(), Some("application/json".to_owned())),
+            RequestParameter::Header("Content-Type".to_owned(), Some("application/json".to_owned())),
+        ]);
+
+        let service = RockeryCurlImporter::import_str("curl localhost:8080/login -d user=rex -d pw=1 -A agent").unwrap();
+        let method = &service.get_methods()[0];
+        assert_eq!(service.get_base_url(), "http://localhost:8080");
+        assert_eq!(method.get_request_method(), RequestMethod::Post);
+        assert_eq!(method.get_body(), &Some("user=rex&pw=1".to_owned()));
+        assert_eq!(method.get_parameter(), &vec![
+            RequestParameter::Header("User-Agent".to_owned(), Some("agent".to_owned())),
+            RequestParameter::Header("Content-Type".to_owned(), Some("application/x-www-form-urlencoded".to_owned())),
+        ]);
+
+        let service = RockeryCurlImporter::import_str("curl -G https://example.com/search --data-urlencode 'q=a b' -F 'file=@a.png'").unwrap();
+        let method = &service.get_methods()[0];
+        assert_eq!(method.get_request_method(), RequestMethod::Get);
+        assert_eq!(method.get_body(), &None);
+        assert_eq!(method.get_parameter(), &vec![RequestParameter::Url("q".to_owned(), Some("a b".to_owned()))]);
+
+        let service = RockeryCurlImporter::import_str("curl -XDELETE https://example.com/items/1 -F name=x").unwrap();
+        let method = &service.get_methods()[0];
+        assert_eq!(method.get_request_method(), RequestMethod::Delete);
+        assert_eq!(method.get_parameter()[0], RequestParameter::Body("name".to_owned(), Some("x".to_owned())));
+
+        assert!(RockeryCurlImporter::import_str("wget https://example.com").is_err());
+        assert!(RockeryCurlImporter::import_str("curl -H").is_err());
+        assert!(RockeryCurlImporter::import_str("curl --unknown https://example.com").is_err());
+        assert!(RockeryCurlImporter::import_str("curl -s").is_err());
+
+        // Options with multi byte characters are rejected instead of being split within a character.
+        assert_eq!(RockeryCurlImporter::import_str("curl -éx https://example.com").unwrap_err(), "Unsupported curl option '-éx'.");
+        assert_eq!(RockeryCurlImporter::import_str("curl -é https://example.com").unwrap_err(), "Unsupported curl option '-é'.");
+        let service = RockeryCurlImporter::import_str("curl -XPATCH -Hé:ü https://example.com").unwrap();
+        assert_eq!(service.get_methods()[0].get_parameter(), &vec![RequestParameter::Header("é".to_owned(), Some("ü".to_owned()))]);
+    }
+
+    #[test]
+    fn test_invalid_command() {
+        let error = |command : &str| RockeryCurlImporter::import_str(command).unwrap_err();
+        assert_eq!(error(""), "Not a curl command. The command must start with 'curl'.");
+        assert_eq!(error("curl"), "The curl command does not contain an url.");
+        assert_eq!(error("curl -s -H 'Accept: */*'"), "The curl command does not contain an url.");
+        assert_eq!(error("curl 'https://example.com"), "Unterminated single quote in curl command.");
+        assert_eq!(error("curl \"https://example.com\\"), "Unterminated double quote in curl command.");
+        assert_eq!(error("curl $'https://example.com"), "Unterminated quote in curl command.");
+        assert_eq!(error("curl -X FETCH https://example.com"), "Unknown request method FETCH.");
+        assert_eq!(error("curl -H NoColon https://example.com"), "Invalid header 'NoColon'. Expected <name>: <value>.");
+        assert_eq!(error("curl -F novalue https://example.com"), "Invalid form field 'novalue'. Expected <name>=<value>.");
+        assert_eq!(error("curl https://example.com --data"), "Option --data requires a value.");
+        assert_eq!(error("curl -sZ https://example.com"), "Unsupported curl option '-sZ'.");
+        assert_eq!(error("curl ://"), "Invalid url ://.");
+        assert_eq!(error("curl https:///path"), "Invalid url https:///path.");
+    }
+
+    #[test]
+    fn test_export() {
+        let mut service = RockeryService::new(RockeryServiceType::Rest, "https://api.example.com");
+        service.set_authentication(AuthenticationType::Bearer(BearerAuthenticationAuthentication::new("t")));
+        let m = service.add_method("create", "users");
+        m.set_request_method(RequestMethod::Post);
+        m.add_parameter(RequestParameter::Url("lang".to_owned(), None));
+        m.add_parameter(RequestParameter::Header("Content-Type".to_owned(), Some("application/json".to_owned())));
+        m.set_body(r#"{"name":"O'Brien"}"#);
+
+        let params = [RequestParameter::Url("lang".to_owned(), Some("de".to_owned()))];
+        let command = RockeryCurlExporter::export(&service, 0, &params).unwrap();
+        assert_eq!(command, "curl -X POST 'https://api.example.com/users/?lang=de' \\\n  -H 'Content-Type: application/json' \\\n  -H 'Authorization: Bearer t' \\\n  --data-raw '{\"name\":\"O'\\''Brien\"}'");
+        assert!(RockeryCurlExporter::export(&service, 1, &[]).is_err());
+
+        // The command can be imported again.
+        let imported = RockeryCurlImporter::import_str(&command).unwrap();
+        let method = &imported.get_methods()[0];
+        assert_eq!(method.get_body(), &Some(r#"{"name":"O'Brien"}"#.to_owned()));
+        assert!(method.get_parameter().contains(&RequestParameter::Header("Authorization".to_owned(), Some("Bearer t".to_owned()))));
+        assert!(method.get_parameter().contains(&RequestParameter::Url("lang".to_owned(), Some("de".to_owned()))));
+
+        // Query values are decoded on import and encoded again when the request is sent or exported.
+        let imported = RockeryCurlImporter::import_str("curl 'https://x.example.com/search?q=a%26b&r=hello%20world'").unwrap();
+        assert_eq!(imported.get_request_url(0, &[]), "https://x.example.com/search/?q=a%26b&r=hello+world");
+        let command = RockeryCurlExporter::export(&imported, 0, &[]).unwrap();
+        let parameters = RockeryCurlImporter::import_str(&command).unwrap().get_methods()[0].get_parameter().clone();
+        assert_eq!(&parameters, imported.get_methods()[0].get_parameter());
+        assert_eq!(parameters[1], RequestParameter::Url("r".to_owned(), Some("hello world".to_owned())));
+    }
+}
@@ -0,0 +1,310 @@
+use std::{collections::HashMap, fmt, fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::prelude::*;
+use crate::rockery_json_path::value_text;
+
+/// A file providing rows of values for data driven test cases. Each column is bound to a
+/// variable with the same name.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum RockeryDataSource {
+    /// A csv file with a header row. Fields are separated by `,` or `;`.
+    Csv(String),
+
+    /// A json file containing an array of flat objects.
+    Json(String),
+}
+
+impl RockeryDataSource {
+    pub fn get_path(&self) -> &str {
+        match self {
+            RockeryDataSource::Csv(p) | RockeryDataSource::Json(p) => p,
+        }
+    }
+
+    /// Read all rows of the file. A relative path is resolved against the given directory, usually the one
+    /// of the project file.
+    pub fn load(&self, directory : Option<&Path>) -> Result<Vec<Vec<(String, String)>>, String> {
+        let path = match directory {
+            Some(d) => d.join(self.get_path()),
+            None => Path::new(self.get_path()).to_path_buf(),
+        };
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("Could not read data source {}. Error: {}", path.display(), e))?;
+
+        match self {
+            RockeryDataSource::Csv(_) => parse_csv(&content),
+            RockeryDataSource::Json(_) => parse_json(&content),
+        }
+    }
+}
+
+/// Split csv content into records of fields. Quoted fields may contain separators, line breaks
+/// and quotes written as `""`.
+fn csv_records(content : &str, separator : char) -> Result<Vec<Vec<String>>, String> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            },
+            '"' if quoted => quoted = false,
+            '"' if field.is_empty() => quoted = true,
+            c if quoted => field.push(c),
+            c if c == separator => record.push(std::mem::take(&mut field)),
+            '\r' => { },
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            },
+            c => field.push(c),
+        }
+    }
+
+    if quoted {
+        return Err("Unterminated quoted field in csv data.".to_owned());
+    }
+
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+
+    // Ignore empty lines.
+    records.retain(|r| !(r.len() == 1 && r[0].is_empty()));
+    Ok(records)
+}
+
+pub(crate) fn parse_csv(content : &str) -> Result<Vec<Vec<(String, String)>>, String> {
+    let header_line = content.lines().next().unwrap_or_default();
+    let separator = if !header_line.contains(',') && header_line.contains(';') { ';' } else { ',' };
+
+    let mut records = csv_records(content.trim_start_matches('\u{feff}'), separator)?.into_iter();
+    let header : Vec<String> = match records.next() {
+        Some(h) => h.into_iter().map(|h| h.trim().to_owned()).collect(),
+        None => return Ok(Vec::new()),
+    };
+
+    let mut rows = Vec::new();
+    for (i, record) in records.enumerate() {
+        if record.len() != header.len() {
+            return Err(format!("Row {} has {} fields but the header defines {} columns.", i + 1, record.len(), header.len()));
+        }
+
+        rows.push(header.iter().cloned().zip(record).collect());
+    }
+
+    Ok(rows)
+}
+
+pub(crate) fn parse_json(content : &str) -> Result<Vec<Vec<(String, String)>>, String> {
+    let value : Value = serde_json::from_str(content).map_err(|e| format!("Could not parse json data. Error: {}", e))?;
+    let items = value.as_array().ok_or("Json data must be an array of objects.".to_owned())?;
+
+    let mut rows = Vec::new();
+    for (i, item) in items.iter().enumerate() {
+        let object = item.as_object().ok_or(format!("Row {} is not an object.", i + 1))?;
+        rows.push(object.iter().map(|(k, v)| {
+            let value = match v {
+                Value::Null => String::new(),
+                v => value_text(v),
+            };
+            (k.to_owned(), value)
+        }).collect());
+    }
+
+    Ok(rows)
+}
+
+/// The result of running a test case for one row of data.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RockeryRowResult {
+    row : Vec<(String, String)>,
+    result : RockeryTestCaseResult,
+}
+
+impl RockeryRowResult {
+    pub fn get_row(&self) -> &Vec<(String, String)> { &self.row }
+    pub fn get_result(&self) -> &RockeryTestCaseResult { &self.result }
+}
+
+/// The results of running a test case once for every row of data.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RockeryDataDrivenResult {
+    name : String,
+    rows : Vec<RockeryRowResult>,
+}
+
+impl RockeryDataDrivenResult {
+    pub fn get_name(&self) -> &str { &self.name }
+    pub fn get_rows(&self) -> &Vec<RockeryRowResult> { &self.rows }
+
+    pub fn get_passed(&self) -> usize { self.rows.iter().filter(|r| r.result.is_passed()).count() }
+    pub fn get_failed(&self) -> usize { self.rows.len() - self.get_passed() }
+    pub fn is_passed(&self) -> bool { self.get_failed() == 0 }
+
+    /// The results of all rows as test case results, named after the test case and the row number.
+    pub fn into_test_case_results(self) -> Vec<RockeryTestCaseResult> {
+        self.rows.into_iter().enumerate()
+            .map(|(i, r)| r.result.with_name(&format!("{} [row {}]", self.name, i + 1)))
+            .collect()
+    }
+}
+
+impl fmt::Display for RockeryDataDrivenResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Test case {}: {} rows, {} passed, {} failed", self.name, self.rows.len(), self.get_passed(), self.get_failed())?;
+        for (i, r) in self.rows.iter().enumerate() {
+            let values : Vec<String> = r.row.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+            writeln!(f, "  Row {} ({}): {}", i + 1, values.join(", "), if r.result.is_passed() { "PASS" } else { "FAIL" })?;
+            for s in r.result.get_steps().iter().filter(|s| !s.is_passed()) {
+                write!(f, "    {}", s)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl RockeryTestCase {
+    /// Create a copy of this test case for one row of data. Every step overrides the parameters of its
+    /// method that are named like a column, unless the step already overrides the parameter itself.
+    fn bind_row(&self, project : &RockeryProject, row : &[(String, String)]) -> RockeryTestCase {
+        let mut test_case = self.clone();
+        for i in 0..test_case.get_steps().len() {
+            let step = test_case.get_step_mut(i).unwrap();
+            let method = project.find_service(step.get_service())
+                .and_then(|s| s.get_method_index(step.get_method()).map(|m| s.get_methods()[m].clone()));
+            let method = match method {
+                Some(m) => m,
+                None => continue,
+            };
+
+            for p in method.get_parameter() {
+                let value = match row.iter().find(|(k, _)| k == p.get_name()) {
+                    Some((_, v)) => Some(v.clone()),
+                    None => continue,
+                };
+
+                if step.get_parameter().iter().any(|o| o.is_same_parameter(p)) {
+                    continue;
+                }
+
+                step.add_parameter(match p {
+                    RequestParameter::Url(k, _) => RequestParameter::Url(k.to_owned(), value),
+                    RequestParameter::Body(k, _) => RequestParameter::Body(k.to_owned(), value),
+                    RequestParameter::Header(k, _) => RequestParameter::Header(k.to_owned(), value),
+                    RequestParameter::Path(k, _) => RequestParameter::Path(k.to_owned(), value),
+                });
+            }
+        }
+
+        for (k, v) in row {
+            test_case.set_variable(k, v);
+        }
+
+        test_case
+    }
+
+    /// Run the test case once for every row. The columns of a row are available as test case variables
+    /// and override parameters with the same name.
+    pub fn run_with_data(&self, project : &RockeryProject, rows : &[Vec<(String, String)>], project_variables : &mut HashMap<String, String>) -> RockeryDataDrivenResult {
+        let rows = rows.iter().map(|row| RockeryRowResult {
+            row : row.clone(),
+            result : self.bind_row(project, row).run_with_variables(project, project_variables),
+        }).collect();
+
+        RockeryDataDrivenResult { name : self.get_name().to_owned(), rows }
+    }
+
+    /// Run the test case once for every row of its data source.
+    pub fn run_data_driven(&self, project : &RockeryProject) -> Result<RockeryDataDrivenResult, String> {
+        let source = self.get_data_source().as_ref().ok_or(format!("Test case {} has no data source.", self.get_name()))?;
+        let rows = source.load(project.get_directory())?;
+
+        Ok(self.run_with_data(project, &rows, &mut project.get_variables().clone()))
+    }
+}
+
+#[cfg(test)]
+mod rockery_data_source_tests {
+    use crate::rockery_test_helper::serve;
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let rows = parse_csv("id,name\r\n1,\"Doe, John\"\n\n2,\"say \"\"hi\"\"\nthere\"\n").unwrap();
+        assert_eq!(rows, vec![
+            vec![("id".to_owned(), "1".to_owned()), ("name".to_owned(), "Doe, John".to_owned())],
+            vec![("id".to_owned(), "2".to_owned()), ("name".to_owned(), "say \"hi\"\nthere".to_owned())],
+        ]);
+
+        assert_eq!(parse_csv("a;b\n1;2").unwrap()[0][1], ("b".to_owned(), "2".to_owned()));
+        assert!(parse_csv("a,b\n1").is_err());
+        assert!(parse_csv("a\n\"open").is_err());
+
+        let rows = parse_json(r#"[{ "id": 1, "name": "Rex", "tag": null }]"#).unwrap();
+        assert_eq!(rows[0], vec![
+            ("id".to_owned(), "1".to_owned()), ("name".to_owned(), "Rex".to_owned()), ("tag".to_owned(), "".to_owned()),
+        ]);
+        assert!(parse_json(r#"{ "id": 1 }"#).is_err());
+        assert!(parse_json(r#"[1]"#).is_err());
+    }
+
+    #[test]
+    fn test_run_data_driven() {
+        let (base_url, requests) = serve(vec![(200, "{}"), (404, "{}")]);
+        let mut service = RockeryService::new(RockeryServiceType::Rest, &base_url);
+        service.set_name("Users");
+        let m = service.add_method("get user", "users/{id}");
+        m.add_parameter(RequestParameter::Path("id".to_owned(), None));
+        m.add_parameter(RequestParameter::Url("lang".to_owned(), Some("${language}".to_owned())));
+
+        let mut project = RockeryProject::new("Data");
+        project.add_service(service);
+
+        let path = std::env::temp_dir().join(format!("rockery_data_{}.csv", std::process::id()));
+        std::fs::write(&path, "id,language\n1,de\n2,en\n").unwrap();
+
+        let mut test_case = RockeryTestCase::new("Users");
+        test_case.set_data_source(Some(RockeryDataSource::Csv(path.to_string_lossy().to_string())));
+        test_case.add_step(RockeryTestStep::new("Get", "Users", "get user")).add_assertion(RockeryAssertion::StatusCode(200));
+
+        let result = test_case.run_data_driven(&project).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(result.get_rows().len(), 2);
+        assert_eq!(result.get_passed(), 1);
+        assert_eq!(result.get_failed(), 1);
+        assert!(result.to_string().starts_with("Test case Users: 2 rows, 1 passed, 1 failed\n  Row 1 (id=1, language=de): PASS\n  Row 2 (id=2, language=en): FAIL"));
+
+        assert!(requests.recv().unwrap().starts_with("GET /users/1/?lang=de "));
+        assert!(requests.recv().unwrap().starts_with("GET /users/2/?lang=en "));
+
+        let names : Vec<String> = result.into_test_case_results().iter().map(|r| r.get_name().to_owned()).collect();
+        assert_eq!(names, vec!["Users [row 1]", "Users [row 2]"]);
+
+        // A relative path is read from the directory of the project file, not from the working directory.
+        let directory = std::env::temp_dir().join(format!("rockery_data_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join("rows.json"), r#"[{ "id": 3, "language": "fr" }]"#).unwrap();
+        test_case.set_data_source(Some(RockeryDataSource::Json("rows.json".to_owned())));
+        assert!(test_case.run_data_driven(&project).unwrap_err().starts_with("Could not read data source rows.json."));
+        project.save(&directory.join("data.proj.json")).unwrap();
+        let project = RockeryProject::load(directory.join("data.proj.json")).unwrap();
+        let result = test_case.run_data_driven(&project);
+        std::fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(result.unwrap().get_rows()[0].get_row()[0], ("id".to_owned(), "3".to_owned()));
+
+        test_case.set_data_source(None);
+        assert!(test_case.run_data_driven(&project).is_err());
+    }
+}
@@ -0,0 +1,40 @@
+use crate::prelude::{RockeryOpenApiImporter, RockeryService, RockeryWadlImporter, RockeryWsdlImporter};
+use crate::rockery_xml::XmlElement;
+
+/// Read a service definition (wsdl, wadl, openapi, ...) either from an url or from a local file.
+pub(crate) fn read_definition(location : &str) -> Result<String, String> {
+    if location.starts_with("http://") || location.starts_with("https://") {
+        let request = ehttp::Request::get(location);
+        return match ehttp::fetch_blocking(&request) {
+            Ok(res) => {
+                if !res.ok {
+                    return Err(format!("Could not download definition from '{}'. Status: {} {}", location, res.status, res.status_text));
+                }
+
+                Ok(String::from_utf8_lossy(&res.bytes).to_string())
+            },
+            Err(e) => Err(format!("Could not download definition from '{}'. Error: {}", location, e)),
+        };
+    }
+
+    let path = location.strip_prefix("file://").unwrap_or(location);
+    std::fs::read_to_string(path).map_err(|e| format!("Could not read definition file '{}'. Error: {}", path, e))
+}
+
+/// Read a definition and create a service from it. The format of the definition is detected from its content.
+pub(crate) fn import_definition(location : &str) -> Result<RockeryService, String> {
+    let content = read_definition(location)?;
+    let mut service = if content.trim_start().starts_with('<') {
+        match XmlElement::parse(&content)?.get_local_name() {
+            "application" => RockeryWadlImporter::import_str(&content)?,
+            "definitions" | "description" => RockeryWsdlImporter::import_str(&content)?,
+            root => return Err(format!("Unknown definition format. The root element is '{}'.", root)),
+        }
+    } else {
+        RockeryOpenApiImporter::import_content(&content, Some(location))?
+    };
+
+    service.set_definition_url(location);
+
+    Ok(service)
+}
@@ -0,0 +1,346 @@
+use std::{fmt::Write, fs, path::Path};
+
+use quick_xml::escape::escape;
+use serde_json::Value;
+
+use crate::prelude::*;
+use crate::rockery_service_authentication::REDACTED;
+
+/// The formats the documentation of a project can be written in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RockeryDocumentationFormat {
+    Markdown,
+
+    /// A single html file without external resources.
+    Html,
+}
+
+impl RockeryDocumentationFormat {
+    /// Derive the format from the extension of a file name: `.md`, `.markdown`, `.html` or `.htm`.
+    pub fn from_path(path : &Path) -> Result<Self, String> {
+        match path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase()).as_deref() {
+            Some("md") | Some("markdown") => Ok(RockeryDocumentationFormat::Markdown),
+            Some("html") | Some("htm") => Ok(RockeryDocumentationFormat::Html),
+            _ => Err(format!("Can not derive a documentation format from {}. Use .md or .html.", path.display())),
+        }
+    }
+}
+
+/// Creates the api documentation of a project.
+///
+/// For every service the base url, the type and the authentication scheme are listed, for every method the
+/// request method, the uri, the parameters with their defaults and the body. The first recorded response of
+/// a method can be included as example. Credentials are never written, the defaults of headers and query
+/// parameters carrying credentials are masked.
+pub struct RockeryDocumentationGenerator;
+
+impl RockeryDocumentationGenerator {
+    pub fn render(project : &RockeryProject, format : RockeryDocumentationFormat, responses : bool) -> String {
+        match format {
+            RockeryDocumentationFormat::Markdown => Self::to_markdown(project, responses),
+            RockeryDocumentationFormat::Html => Self::to_html(project, responses),
+        }
+    }
+
+    /// Write the documentation to a file. The format is derived from the extension of the path.
+    pub fn save(project : &RockeryProject, path : &Path, responses : bool) -> Result<(), String> {
+        let format = RockeryDocumentationFormat::from_path(path)?;
+        fs::write(path, Self::render(project, format, responses)).map_err(|e| format!("Could not write documentation {}. Error: {}", path.display(), e))
+    }
+
+    pub fn to_markdown(project : &RockeryProject, responses : bool) -> String {
+        let mut md = String::new();
+        let _ = writeln!(md, "# {}\n", project.get_name());
+
+        let ids = project.get_service_ids();
+        for id in &ids {
+            let _ = writeln!(md, "- [{}](#{})", id, anchor(id));
+        }
+
+        for id in &ids {
+            let service = project.find_service(id).unwrap();
+            let _ = writeln!(md, "\n## {}\n", id);
+            md.push_str("| | |\n|---|---|\n");
+            let _ = writeln!(md, "| Base URL | `{}` |", cell(service.get_base_url()));
+            let _ = writeln!(md, "| Type | {} |", service.get_service_type());
+            let _ = writeln!(md, "| Authentication | {} |", cell(&authentication_scheme(service.get_authentication())));
+            if let Some(definition) = service.get_definition_url() {
+                let _ = writeln!(md, "| Definition | {} |", cell(definition));
+            }
+
+            for method in service.get_methods() {
+                let _ = writeln!(md, "\n### {}\n", method.get_name());
+                let _ = writeln!(md, "`{} /{}`", method.get_request_method(), method.get_uri());
+
+                if !method.get_parameter().is_empty() {
+                    md.push_str("\n| Kind | Name | Default |\n|---|---|---|\n");
+                    for p in method.get_parameter() {
+                        let default = default_value(service.get_authentication(), p).map(|v| format!("`{}`", cell(v))).unwrap_or("-".to_owned());
+                        let _ = writeln!(md, "| {} | {} | {} |", kind(p), cell(p.get_name()), default);
+                    }
+                }
+
+                if let Some(body) = method.get_body() {
+                    md.push_str("\nRequest body:\n\n");
+                    code_block(&mut md, &content_type(method.get_parameter().iter().filter_map(header)), body);
+                }
+
+                if let (true, Some(response)) = (responses, method.get_responses().first()) {
+                    let _ = writeln!(md, "\nExample response `{} {}`:\n", response.get_status(), response.get_status_text());
+                    code_block(&mut md, &content_type(response.get_headers().iter().map(|(k, v)| (k.as_str(), v.as_str()))), response.get_body());
+                }
+            }
+        }
+
+        md
+    }
+
+    /// A self-contained html page with a table of contents.
+    pub fn to_html(project : &RockeryProject, responses : bool) -> String {
+        let mut html = String::new();
+        let _ = write!(html, "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n",
+            escape(project.get_name()), HTML_STYLE);
+        let _ = writeln!(html, "<h1>{}</h1>", escape(project.get_name()));
+
+        let ids = project.get_service_ids();
+        html.push_str("<nav>\n<ul>\n");
+        for id in &ids {
+            let service = project.find_service(id).unwrap();
+            let _ = writeln!(html, "<li><a href=\"#{}\">{}</a>\n<ul>", anchor(id), escape(id));
+            for method in service.get_methods() {
+                let _ = writeln!(html, "<li><a href=\"#{}\">{}</a></li>", anchor(&format!("{} {}", id, method.get_name())), escape(method.get_name()));
+            }
+            html.push_str("</ul>\n</li>\n");
+        }
+        html.push_str("</ul>\n</nav>\n");
+
+        for id in &ids {
+            let service = project.find_service(id).unwrap();
+            let _ = writeln!(html, "<h2 id=\"{}\">{}</h2>", anchor(id), escape(id));
+            html.push_str("<table>\n");
+            let _ = writeln!(html, "<tr><th>Base URL</th><td><code>{}</code></td></tr>", escape(service.get_base_url()));
+            let _ = writeln!(html, "<tr><th>Type</th><td>{}</td></tr>", service.get_service_type());
+            let _ = writeln!(html, "<tr><th>Authentication</th><td>{}</td></tr>", escape(&authentication_scheme(service.get_authentication())));
+            if let Some(definition) = service.get_definition_url() {
+                let _ = writeln!(html, "<tr><th>Definition</th><td>{}</td></tr>", escape(definition));
+            }
+            html.push_str("</table>\n");
+
+            for method in service.get_methods() {
+                let _ = writeln!(html, "<h3 id=\"{}\">{}</h3>", anchor(&format!("{} {}", id, method.get_name())), escape(method.get_name()));
+                let _ = writeln!(html, "<p><span class=\"method\">{}</span> <code>/{}</code></p>", method.get_request_method(), escape(method.get_uri()));
+
+                if !method.get_parameter().is_empty() {
+                    html.push_str("<table>\n<tr><th>Kind</th><th>Name</th><th>Default</th></tr>\n");
+                    for p in method.get_parameter() {
+                        let default = default_value(service.get_authentication(), p).map(|v| format!("<code>{}</code>", escape(v))).unwrap_or("-".to_owned());
+                        let _ = writeln!(html, "<tr><td>{}</td><td>{}</td><td>{}</td></tr>", kind(p), escape(p.get_name()), default);
+                    }
+                    html.push_str("</table>\n");
+                }
+
+                if let Some(body) = method.get_body() {
+                    let _ = writeln!(html, "<p>Request body:</p>\n<pre>{}</pre>", escape(&pretty(body)));
+                }
+
+                if let (true, Some(response)) = (responses, method.get_responses().first()) {
+                    let _ = writeln!(html, "<p>Example response <code>{} {}</code>:</p>\n<pre>{}</pre>",
+                        response.get_status(), escape(response.get_status_text()), escape(&pretty(response.get_body())));
+                }
+            }
+        }
+
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+}
+
+const HTML_STYLE : &str = "body { font-family: sans-serif; margin: 2em; max-width: 60em; } \
+table { border-collapse: collapse; margin-bottom: 1em; } \
+th, td { border: 1px solid #ccc; padding: 4px 8px; text-align: left; vertical-align: top; } \
+h3 { border-top: 1px solid #eee; padding-top: 1em; } \
+.method { font-weight: bold; color: #0550ae; } \
+pre { background: #f6f8fa; padding: 8px; overflow-x: auto; }";
+
+/// Describe an authentication without its credentials.
+fn authentication_scheme(authentication : &AuthenticationType) -> String {
+    match authentication {
+        AuthenticationType::None(_) => "None".to_owned(),
+        AuthenticationType::Basic(_) => "HTTP Basic".to_owned(),
+        AuthenticationType::Bearer(_) => "Bearer token".to_owned(),
+        AuthenticationType::ApiKey(a) => match a.get_location() {
+            ApiKeyLocation::Header => format!("API key in header {}", a.get_name()),
+            ApiKeyLocation::Query => format!("API key in query parameter {}", a.get_name()),
+        },
+    }
+}
+
+/// The default of a parameter, masked if the parameter carries credentials like Authorization, Cookie or an api key.
+fn default_value<'a>(authentication : &AuthenticationType, parameter : &'a RequestParameter) -> Option<&'a str> {
+    let value = parameter.get_value().as_deref()?;
+    let credential = match parameter {
+        RequestParameter::Header(k, _) => authentication.is_credential_header(k),
+        RequestParameter::Url(k, _) => authentication.is_credential_query(k),
+        _ => false,
+    };
+
+    Some(if credential { REDACTED } else { value })
+}
+
+fn kind(parameter : &RequestParameter) -> &'static str {
+    match parameter {
+        RequestParameter::Path(_, _) => "path",
+        RequestParameter::Url(_, _) => "query",
+        RequestParameter::Header(_, _) => "header",
+        RequestParameter::Body(_, _) => "body",
+    }
+}
+
+fn header(parameter : &RequestParameter) -> Option<(&str, &str)> {
+    match parameter {
+        RequestParameter::Header(k, Some(v)) => Some((k, v)),
+        _ => None,
+    }
+}
+
+/// The language of a code block derived from the content type header.
+fn content_type<'a>(mut headers : impl Iterator<Item = (&'a str, &'a str)>) -> String {
+    let content_type = headers.find(|(k, _)| k.eq_ignore_ascii_case("Content-Type")).map(|(_, v)| v.to_lowercase()).unwrap_or_default();
+    if content_type.contains("json") {
+        "json".to_owned()
+    } else if content_type.contains("xml") {
+        "xml".to_owned()
+    } else {
+        String::new()
+    }
+}
+
+fn code_block(md : &mut String, language : &str, content : &str) {
+    // The fence must be longer than any run of backticks within the content.
+    let longest = content.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest.max(2) + 1);
+    let _ = writeln!(md, "{}{}\n{}\n{}", fence, language, pretty(content).trim_end(), fence);
+}
+
+/// Pretty print json, other content is returned as is.
+fn pretty(content : &str) -> String {
+    match serde_json::from_str::<Value>(content) {
+        Ok(v) if v.is_object() || v.is_array() => serde_json::to_string_pretty(&v).unwrap_or(content.to_owned()),
+        _ => content.to_owned(),
+    }
+}
+
+/// Escape a value for a cell of a Markdown table.
+fn cell(value : &str) -> String {
+    value.replace('|', "\\|").replace('\n', " ")
+}
+
+/// The anchor of a heading as created by GitHub: lower case, spaces become dashes and punctuation is removed.
+fn anchor(text : &str) -> String {
+    text.chars().filter_map(|c| match c {
+        c if c.is_alphanumeric() => Some(c.to_lowercase().next().unwrap_or(c)),
+        ' ' | '-' => Some('-'),
+        '_' => Some('_'),
+        _ => None,
+    }).collect()
+}
+
+#[cfg(test)]
+mod rockery_documentation_tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn project() -> RockeryProject {
+        let mut project = RockeryProject::new("Shop");
+        let mut service = RockeryService::new(RockeryServiceType::Rest, "https://api.example.com");
+        service.set_name("Users");
+        service.set_authentication(AuthenticationType::ApiKey(ApiKeyAuthenticationAuthentication::new("X-Key", "secret", ApiKeyLocation::Header)));
+        let m = service.add_method("get user", "users/{id}");
+        m.add_parameter(RequestParameter::Path("id".to_owned(), Some("7".to_owned())));
+        m.add_parameter(RequestParameter::Url("filter".to_owned(), None));
+        m.add_parameter(RequestParameter::Header("Accept".to_owned(), Some("a|b".to_owned())));
+        m.add_parameter(RequestParameter::Header("X-Key".to_owned(), Some("secret".to_owned())));
+        m.add_parameter(RequestParameter::Header("Cookie".to_owned(), Some("session=secret".to_owned())));
+        m.add_response(RockeryResponse::new(200, "OK", &ehttp::Headers::new(&[("Content-Type", "application/json")]), br#"{"id":7}"#, Duration::from_millis(5)));
+
+        let m = service.add_method("create user", "users");
+        m.set_request_method(RequestMethod::Post);
+        m.add_parameter(RequestParameter::Header("Content-Type".to_owned(), Some("application/xml".to_owned())));
+        m.set_body("<user><name>rex</name></user>");
+        project.add_service(service);
+
+        project
+    }
+
+    #[test]
+    fn test_markdown() {
+        let md = RockeryDocumentationGenerator::to_markdown(&project(), true);
+        assert_eq!(md, "# Shop\n\n- [Users](#users)\n\n## Users\n\n| | |\n|---|---|\n\
+            | Base URL | `https://api.example.com` |\n| Type | REST |\n| Authentication | API key in header X-Key |\n\n\
+            ### get user\n\n`GET /users/{id}`\n\n| Kind | Name | Default |\n|---|---|---|\n\
+            | path | id | `7` |\n| query | filter | - |\n| header | Accept | `a\\|b` |\n| header | X-Key | `***` |\n| header | Cookie | `***` |\n\n\
+            Example response `200 OK`:\n\n```json\n{\n  \"id\": 7\n}\n```\n\n\
+            ### create user\n\n`POST /users`\n\n| Kind | Name | Default |\n|---|---|---|\n| header | Content-Type | `application/xml` |\n\n\
+            Request body:\n\n```xml\n<user><name>rex</name></user>\n```\n");
+        assert!(!md.contains("secret"));
+
+        let md = RockeryDocumentationGenerator::to_markdown(&project(), false);
+        assert!(!md.contains("Example response"));
+    }
+
+    #[test]
+    fn test_html() {
+        let html = RockeryDocumentationGenerator::render(&project(), RockeryDocumentationFormat::Html, true);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<li><a href=\"#users-get-user\">get user</a></li>"));
+        assert!(html.contains("<h3 id=\"users-create-user\">create user</h3>"));
+        assert!(html.contains("<tr><th>Authentication</th><td>API key in header X-Key</td></tr>"));
+        assert!(html.contains("<tr><td>query</td><td>filter</td><td>-</td></tr>"));
+        assert!(html.contains("<tr><td>header</td><td>Cookie</td><td><code>***</code></td></tr>"));
+        assert!(html.contains("<pre>&lt;user&gt;&lt;name&gt;rex&lt;/name&gt;&lt;/user&gt;</pre>"));
+        assert!(html.contains("Example response <code>200 OK</code>"));
+        assert!(!html.contains("secret"));
+    }
+
+    #[test]
+    fn test_save() {
+        assert_eq!(RockeryDocumentationFormat::from_path(Path::new("api.md")), Ok(RockeryDocumentationFormat::Markdown));
+        assert_eq!(RockeryDocumentationFormat::from_path(Path::new("api.HTM")), Ok(RockeryDocumentationFormat::Html));
+        assert!(RockeryDocumentationFormat::from_path(Path::new("api.txt")).is_err());
+
+        let path = std::env::temp_dir().join(format!("rockery_docs_{}.md", std::process::id()));
+        RockeryDocumentationGenerator::save(&project(), &path, false).unwrap();
+        assert!(fs::read_to_string(&path).unwrap().starts_with("# Shop\n"));
+        fs::remove_file(&path).unwrap();
+
+        assert!(RockeryDocumentationFormat::from_path(Path::new("api")).is_err());
+        assert!(RockeryDocumentationFormat::from_path(Path::new(".md/api")).is_err());
+        let missing = std::env::temp_dir().join(format!("rockery_docs_missing_{}", std::process::id())).join("api.md");
+        assert!(RockeryDocumentationGenerator::save(&project(), &missing, false).unwrap_err().starts_with("Could not write documentation"));
+    }
+
+    #[test]
+    fn test_malformed_content() {
+        let mut project = RockeryProject::new("<Shop>");
+        let mut service = RockeryService::new(RockeryServiceType::Rest, "https://api.example.com");
+        service.set_name("Users");
+        let m = service.add_method("<script>alert(1)</script>", "users");
+        m.set_request_method(RequestMethod::Post);
+        m.add_parameter(RequestParameter::Header("Content-Type".to_owned(), Some("application/json".to_owned())));
+        m.add_parameter(RequestParameter::Url("q".to_owned(), Some("a\nb".to_owned())));
+        m.set_body("{ not json ```` ~~~~");
+        project.add_service(service);
+
+        // Invalid json is documented as it is, with a fence longer than the backticks within the body.
+        let md = RockeryDocumentationGenerator::to_markdown(&project, false);
+        assert!(md.contains("\n`````json\n{ not json ```` ~~~~\n`````\n"));
+        assert!(md.contains("| query | q | `a b` |\n"));
+
+        let html = RockeryDocumentationGenerator::to_html(&project, false);
+        assert!(html.contains("<title>&lt;Shop&gt;</title>"));
+        assert!(html.contains("&lt;script&gt;alert(1)&lt;/script&gt;"));
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("<h3 id=\"users-scriptalert1script\">"));
+    }
+}
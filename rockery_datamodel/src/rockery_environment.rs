@@ -0,0 +1,36 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// A named set of values for a project, e.g. for a test or a production system. Selecting an
+/// environment replaces the base urls of services and sets project variables.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RockeryEnvironment {
+    name : String,
+
+    #[serde(default)]
+    variables : HashMap<String, String>,
+
+    /// Base urls by service id.
+    #[serde(default)]
+    base_urls : HashMap<String, String>,
+}
+
+impl RockeryEnvironment {
+    pub fn new(name : &str) -> Self {
+        RockeryEnvironment {
+            name : name.to_owned(),
+            variables : HashMap::new(),
+            base_urls : HashMap::new(),
+        }
+    }
+
+    pub fn get_name(&self) -> &str { &self.name }
+    pub fn set_name(&mut self, name : &str) { self.name = name.to_owned(); }
+
+    pub fn get_variables(&self) -> &HashMap<String, String> { &self.variables }
+    pub fn set_variable(&mut self, name : &str, value : &str) { self.variables.insert(name.to_owned(), value.to_owned()); }
+
+    pub fn get_base_urls(&self) -> &HashMap<String, String> { &self.base_urls }
+    pub fn set_base_url(&mut self, service : &str, url : &str) { self.base_urls.insert(service.to_owned(), url.to_owned()); }
+}
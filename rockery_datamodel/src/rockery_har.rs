@@ -0,0 +1,364 @@
+use std::time::{Duration, SystemTime};
+
+use base64::{engine::general_purpose, Engine};
+use serde_json::{json, Value};
+
+use crate::prelude::*;
+use crate::rockery_definition::read_definition;
+use crate::rockery_http::{parse_query, split_url};
+use crate::rockery_report::format_timestamp;
+
+/// Creates projects from HTTP Archive (HAR) files, e.g. captured with the developer tools of a browser.
+///
+/// The entries are added like recorded requests, see [RockeryProject::add_recording]: one service per host
+/// and one method per request method and path. Every response is stored with its method. Entries that can not
+/// be sent again, e.g. `data:` urls or requests with unknown methods, are skipped with a warning.
+pub struct RockeryHarImporter;
+
+impl RockeryHarImporter {
+    /// Read the archive from the given url or path and create a project from it. Returns the project and the
+    /// warnings for all skipped entries.
+    pub fn import(location : &str) -> Result<(RockeryProject, Vec<String>), String> {
+        Self::import_str(&read_definition(location)?)
+    }
+
+    /// Create a project from the content of an archive. The project is named after the first page.
+    pub fn import_str(har : &str) -> Result<(RockeryProject, Vec<String>), String> {
+        let root = parse(har)?;
+        let name = root.pointer("/log/pages/0/title").and_then(Value::as_str).unwrap_or("HAR");
+        let mut project = RockeryProject::new(name);
+        let (_, warnings) = project.add_recording(&exchanges(&root)?);
+
+        Ok((project, warnings))
+    }
+
+    /// Read the requests and responses of an archive, e.g. to add them to an existing project.
+    pub fn read_str(har : &str) -> Result<Vec<RockeryExchange>, String> {
+        exchanges(&parse(har)?)
+    }
+}
+
+fn parse(har : &str) -> Result<Value, String> {
+    let root : Value = serde_json::from_str(har).map_err(|e| format!("Could not parse HAR file. Error: {}", e))?;
+    if root.pointer("/log/entries").and_then(Value::as_array).is_none() {
+        return Err("Not a HAR file. The 'log.entries' field is missing.".to_owned());
+    }
+
+    Ok(root)
+}
+
+fn exchanges(root : &Value) -> Result<Vec<RockeryExchange>, String> {
+    let text = |v : &Value, pointer : &str| v.pointer(pointer).and_then(Value::as_str).unwrap_or_default().to_owned();
+    // HTTP/2 pseudo headers like `:authority` are not sent as headers.
+    let headers = |v : &Value| -> Vec<(String, String)> {
+        v.as_array().into_iter().flatten()
+            .map(|h| (text(h, "/name"), text(h, "/value")))
+            .filter(|(k, _)| !k.is_empty() && !k.starts_with(':'))
+            .collect()
+    };
+
+    let mut exchanges = Vec::new();
+    for (i, entry) in root.pointer("/log/entries").and_then(Value::as_array).into_iter().flatten().enumerate() {
+        let request = entry.get("request").ok_or(format!("Entry {} of the HAR file has no request.", i))?;
+        let mut body = text(request, "/postData/text");
+        if body.is_empty() {
+            let params : Vec<String> = request.pointer("/postData/params").and_then(Value::as_array).into_iter().flatten()
+                .map(|p| format!("{}={}", text(p, "/name"), text(p, "/value")))
+                .collect();
+            body = params.join("&");
+        }
+        let request = RockeryRequest::new(&text(request, "/method"), &text(request, "/url"), headers(&request["headers"]), &body);
+
+        let response = &entry["response"];
+        let mut response_headers = ehttp::Headers::new(&[]);
+        for (k, v) in headers(&response["headers"]) {
+            response_headers.insert(k, v);
+        }
+        let content = text(response, "/content/text");
+        let bytes = match response.pointer("/content/encoding").and_then(Value::as_str) {
+            Some("base64") => general_purpose::STANDARD.decode(content.as_bytes())
+                .map_err(|e| format!("Invalid base64 content in entry {} of the HAR file. Error: {}", i, e))?,
+            _ => content.into_bytes(),
+        };
+        let status = response.get("status").and_then(Value::as_u64).unwrap_or(0) as u16;
+        let time = entry.get("time").and_then(Value::as_f64).unwrap_or(0.0).max(0.0);
+        let response = RockeryResponse::new(status, &text(response, "/statusText"), &response_headers, &bytes, Duration::from_millis(time as u64));
+
+        exchanges.push(RockeryExchange::new(request, response));
+    }
+
+    Ok(exchanges)
+}
+
+/// Writes requests and responses as HTTP Archive (HAR) 1.2, so they can be inspected with other tools.
+pub struct RockeryHarExporter;
+
+impl RockeryHarExporter {
+    /// The requests of a history. Requests without response are written with status 0 and the error in `_error`.
+    pub fn export_history(entries : &[RockeryHistoryEntry]) -> Value {
+        let entries : Vec<Value> = entries.iter()
+            .map(|e| entry(e.get_timestamp(), e.get_request(), e.get_response().as_ref(), e.get_error().as_deref(), e.get_duration()))
+            .collect();
+
+        archive(entries)
+    }
+
+    /// The requests of all steps of a test run. The steps are not timed individually, all entries start at the
+    /// time of the report.
+    pub fn export_report(report : &RockeryTestReport) -> Value {
+        let entries : Vec<Value> = report.get_suites().iter()
+            .flat_map(|s| s.get_test_cases())
+            .flat_map(|t| t.get_steps())
+            .filter_map(|s| s.get_result().as_ref())
+            .map(|r| entry(report.get_timestamp(), r.get_request(), Some(r.get_response()), None, r.get_response().get_duration()))
+            .collect();
+
+        archive(entries)
+    }
+
+    /// Recorded requests, e.g. of the recording proxy. All entries start now.
+    pub fn export_exchanges(exchanges : &[RockeryExchange]) -> Value {
+        let now = format_timestamp(SystemTime::now());
+        let entries : Vec<Value> = exchanges.iter()
+            .map(|e| entry(&now, e.get_request(), Some(e.get_response()), None, e.get_response().get_duration()))
+            .collect();
+
+        archive(entries)
+    }
+}
+
+impl RockeryTestReport {
+    /// The traffic of the test run as HAR, see [RockeryHarExporter::export_report].
+    pub fn to_har(&self) -> String {
+        serde_json::to_string_pretty(&RockeryHarExporter::export_report(self)).unwrap_or_default()
+    }
+}
+
+fn archive(entries : Vec<Value>) -> Value {
+    json!({
+        "log": {
+            "version": "1.2",
+            "creator": { "name": "rockery", "version": env!("CARGO_PKG_VERSION") },
+            "entries": entries,
+        }
+    })
+}
+
+fn entry(started : &str, request : &RockeryRequest, response : Option<&RockeryResponse>, error : Option<&str>, time : u64) -> Value {
+    let pairs = |headers : &[(String, String)]| -> Vec<Value> {
+        headers.iter().map(|(k, v)| json!({ "name": k, "value": v })).collect()
+    };
+    let query = split_url(request.get_url()).map(|(_, _, q)| parse_query(q)).unwrap_or_default();
+
+    let mut har_request = json!({
+        "method": request.get_method(),
+        "url": request.get_url(),
+        "httpVersion": "HTTP/1.1",
+        "cookies": [],
+        "headers": pairs(request.get_headers()),
+        "queryString": pairs(&query),
+        "headersSize": -1,
+        "bodySize": request.get_body().len(),
+    });
+    if !request.get_body().is_empty() {
+        har_request["postData"] = json!({
+            "mimeType": request.get_header("Content-Type").unwrap_or_default(),
+            "text": request.get_body(),
+        });
+    }
+
+    let har_response = match response {
+        Some(r) => json!({
+            "status": r.get_status(),
+            "statusText": r.get_status_text(),
+            "httpVersion": "HTTP/1.1",
+            "cookies": [],
+            "headers": pairs(r.get_headers()),
+            "content": {
+                "size": r.get_body().len(),
+                "mimeType": r.get_content_type().unwrap_or_default(),
+                "text": r.get_body(),
+            },
+            "redirectURL": r.get_header("Location").unwrap_or_default(),
+            "headersSize": -1,
+            "bodySize": r.get_body().len(),
+        }),
+        None => json!({
+            "status": 0,
+            "statusText": "",
+            "httpVersion": "HTTP/1.1",
+            "cookies": [],
+            "headers": [],
+            "content": { "size": 0, "mimeType": "" },
+            "redirectURL": "",
+            "headersSize": -1,
+            "bodySize": -1,
+            "_error": error.unwrap_or_default(),
+        }),
+    };
+
+    json!({
+        "startedDateTime": started,
+        "time": time,
+        "request": har_request,
+        "response": har_response,
+        "cache": {},
+        "timings": { "send": 0, "wait": time, "receive": 0 },
+    })
+}
+
+#[cfg(test)]
+mod rockery_har_tests {
+    use crate::rockery_history::HistoryRecorder;
+    use crate::rockery_test_helper::serve;
+    use super::*;
+
+    const HAR : &str = r#"{
+        "log": {
+            "version": "1.2",
+            "pages": [ { "id": "page_1", "title": "Shop" } ],
+            "entries": [
+                {
+                    "startedDateTime": "2024-05-01T10:00:00.000Z",
+                    "time": 12.5,
+                    "request": {
+                        "method": "GET",
+                        "url": "https://shop.example.com/api/items/1?lang=de",
+                        "headers": [ { "name": ":authority", "value": "shop.example.com" }, { "name": "Accept", "value": "application/json" } ],
+                        "queryString": [ { "name": "lang", "value": "de" } ]
+                    },
+                    "response": {
+                        "status": 200,
+                        "statusText": "OK",
+                        "headers": [ { "name": "Content-Type", "value": "application/json" } ],
+                        "content": { "mimeType": "application/json", "text": "eyJpZCI6MX0=", "encoding": "base64" }
+                    }
+                },
+                {
+                    "time": 3,
+                    "request": { "method": "GET", "url": "https://shop.example.com/api/items/1", "headers": [] },
+                    "response": { "status": 304, "statusText": "Not Modified", "headers": [], "content": { "text": "" } }
+                },
+                {
+                    "time": 20,
+                    "request": {
+                        "method": "POST",
+                        "url": "https://auth.example.com/login",
+                        "headers": [],
+                        "postData": { "mimeType": "application/x-www-form-urlencoded", "params": [ { "name": "user", "value": "rex" } ] }
+                    },
+                    "response": { "status": 204, "statusText": "No Content", "headers": [], "content": {} }
+                }
+            ]
+        }
+    }"#;
+
+    #[test]
+    fn test_import() {
+        let (project, warnings) = RockeryHarImporter::import_str(HAR).unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(project.get_name(), "Shop");
+        assert_eq!(project.get_service_ids(), vec!["auth.example.com", "shop.example.com"]);
+
+        let shop = project.find_service("shop.example.com").unwrap();
+        assert_eq!(shop.get_base_url(), "https://shop.example.com");
+        let methods = shop.get_methods();
+        assert_eq!(methods.len(), 1);
+        assert_eq!(methods[0].get_uri(), "api/items/1");
+        assert_eq!(methods[0].get_parameter(), &vec![
+            RequestParameter::Url("lang".to_owned(), Some("de".to_owned())),
+            RequestParameter::Header("Accept".to_owned(), Some("application/json".to_owned())),
+        ]);
+        assert_eq!(methods[0].get_responses().len(), 2);
+        assert_eq!(methods[0].get_responses()[0].get_body(), r#"{"id":1}"#);
+        assert_eq!(methods[0].get_responses()[0].get_duration(), 12);
+        assert_eq!(methods[0].get_responses()[1].get_status(), 304);
+
+        let login = &project.find_service("auth.example.com").unwrap().get_methods()[0];
+        assert_eq!(login.get_request_method(), RequestMethod::Post);
+        assert_eq!(login.get_body(), &Some("user=rex".to_owned()));
+
+        assert_eq!(RockeryHarImporter::read_str(HAR).unwrap().len(), 3);
+        assert!(RockeryHarImporter::import_str("{}").is_err());
+        assert!(RockeryHarImporter::import("does_not_exist.har").is_err());
+    }
+
+    #[test]
+    fn test_invalid_archive() {
+        let error = |har : &str| RockeryHarImporter::import_str(har).unwrap_err();
+        assert!(error("no json").starts_with("Could not parse HAR file."));
+        assert_eq!(error(r#"{"log": {"entries": {}}}"#), "Not a HAR file. The 'log.entries' field is missing.");
+        assert_eq!(error(r#"{"log": {"entries": [{"response": {}}]}}"#), "Entry 0 of the HAR file has no request.");
+        assert!(error(r#"{"log": {"entries": [{"request": {"method": "GET", "url": "https://example.com/"},
+            "response": {"content": {"text": "not base64!", "encoding": "base64"}}}]}}"#).starts_with("Invalid base64 content in entry 0"));
+
+        // Entries that can not be sent again are skipped, the other entries are still imported.
+        let (project, warnings) = RockeryHarImporter::import_str(r#"{"log": {"entries": [
+            {"request": {"method": "GET", "url": "/relative"}},
+            {"request": {"method": "GET", "url": "data:image/png;base64,iVBORw0KGgo="}},
+            {"request": {"method": "GET", "url": "blob:https://example.com/0b6f"}},
+            {"request": {"method": "FETCH", "url": "https://example.com/"}},
+            {"request": {"method": "GET", "url": "https://example.com/items"}}]}}"#).unwrap();
+        assert_eq!(warnings, vec![
+            "Request GET /relative is skipped, the url is invalid.".to_owned(),
+            "Request GET data:image/png;base64,iVBORw0KGgo= is skipped, the url is invalid.".to_owned(),
+            "Request GET blob:https://example.com/0b6f is skipped, the url is invalid.".to_owned(),
+            "Request FETCH https://example.com/ is skipped, the request method is unknown.".to_owned(),
+        ]);
+        assert_eq!(project.find_service("example.com").unwrap().get_methods()[0].get_uri(), "items");
+
+        // Missing or malformed optional fields are imported with defaults.
+        assert!(RockeryHarImporter::import_str(r#"{"log": {"entries": []}}"#).unwrap().0.get_service_ids().is_empty());
+        let exchanges = RockeryHarImporter::read_str(r#"{"log": {"entries": [{"time": -5, "request": {"method": "GET", "url": "https://example.com/",
+            "headers": [{"name": ":authority", "value": "example.com"}, {"value": "no name"}, 3]}, "response": {"status": "200", "headers": "none"}}]}}"#).unwrap();
+        assert_eq!(exchanges.len(), 1);
+        assert!(exchanges[0].get_request().get_headers().is_empty());
+        assert_eq!(exchanges[0].get_response().get_status(), 0);
+        assert_eq!(exchanges[0].get_response().get_duration(), 0);
+    }
+
+    #[test]
+    fn test_export() {
+        let (base_url, requests) = serve(vec![(201, r#"{"id":3}"#)]);
+        let mut project = RockeryProject::new("HAR");
+        let mut service = RockeryService::new(RockeryServiceType::Rest, &base_url);
+        service.set_name("Items");
+        let m = service.add_method("create", "items");
+        m.set_request_method(RequestMethod::Post);
+        m.add_parameter(RequestParameter::Url("dry".to_owned(), Some("1".to_owned())));
+        m.add_parameter(RequestParameter::Header("Content-Type".to_owned(), Some("application/json".to_owned())));
+        m.set_body(r#"{"name":"a"}"#);
+        project.add_service(service);
+
+        let suite = project.add_test_suite(RockeryTestSuite::new("Suite"));
+        suite.add_test_case(RockeryTestCase::new("Case")).add_step(RockeryTestStep::new("create", "Items", "create"));
+        let result = project.run_test_suite(0).unwrap();
+        requests.recv().unwrap();
+
+        let report = RockeryTestReport::new("HAR", vec![result]);
+        let har : Value = serde_json::from_str(&report.render(RockeryReportFormat::Har)).unwrap();
+        assert_eq!(har["log"]["version"], "1.2");
+        let entry = &har["log"]["entries"][0];
+        assert_eq!(entry["startedDateTime"], report.get_timestamp());
+        assert_eq!(entry["request"]["method"], "POST");
+        assert_eq!(entry["request"]["url"], format!("{}/items/?dry=1", base_url));
+        assert_eq!(entry["request"]["queryString"], json!([{ "name": "dry", "value": "1" }]));
+        assert_eq!(entry["request"]["postData"], json!({ "mimeType": "application/json", "text": r#"{"name":"a"}"# }));
+        assert_eq!(entry["response"]["status"], 201);
+        assert_eq!(entry["response"]["content"]["text"], r#"{"id":3}"#);
+
+        // An exported archive can be imported again.
+        let imported = RockeryHarImporter::read_str(&har.to_string()).unwrap();
+        assert_eq!(imported[0].get_request().get_body(), r#"{"name":"a"}"#);
+        assert_eq!(imported[0].get_response().get_status(), 201);
+
+        let har = RockeryHarExporter::export_exchanges(&imported);
+        assert_eq!(har["log"]["entries"][0]["response"]["status"], 201);
+        let history = std::sync::Arc::new(std::sync::Mutex::new(RockeryHistory::new()));
+        HistoryRecorder::new(history.clone(), "Items").record("create", &RockeryRequest::new("GET", "http://localhost:1/", Vec::new(), ""), &AuthenticationType::default(), Err("refused".to_owned()), 1);
+        let har = RockeryHarExporter::export_history(history.lock().unwrap().get_entries());
+        assert_eq!(har["log"]["entries"][0]["response"]["status"], 0);
+        assert_eq!(har["log"]["entries"][0]["response"]["_error"], "refused");
+        assert_eq!(RockeryReportFormat::from_path(std::path::Path::new("traffic.har")), Ok(RockeryReportFormat::Har));
+    }
+}
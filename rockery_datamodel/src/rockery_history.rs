@@ -0,0 +1,470 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Instant, SystemTime},
+};
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::*;
+use crate::rockery_http::{decode_component, parse_query};
+use crate::rockery_service_authentication::REDACTED;
+use crate::rockery_report::format_timestamp;
+
+/// A request that has been sent for a method of a project.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RockeryHistoryEntry {
+    /// Time the request has been sent at in RFC 3339 format.
+    timestamp : String,
+
+    /// The environment that was applied to the project.
+    environment : Option<String>,
+
+    /// Id of the service within the project.
+    service : String,
+    method : String,
+    request : RockeryRequest,
+    response : Option<RockeryResponse>,
+
+    /// The reason why no response has been received.
+    error : Option<String>,
+
+    /// Time until the response has been received in milliseconds.
+    duration : u64,
+}
+
+impl RockeryHistoryEntry {
+    pub fn get_timestamp(&self) -> &str { &self.timestamp }
+    pub fn get_environment(&self) -> &Option<String> { &self.environment }
+    pub fn get_service(&self) -> &str { &self.service }
+    pub fn get_method(&self) -> &str { &self.method }
+    pub fn get_request(&self) -> &RockeryRequest { &self.request }
+    pub fn get_response(&self) -> &Option<RockeryResponse> { &self.response }
+    pub fn get_error(&self) -> &Option<String> { &self.error }
+    pub fn get_duration(&self) -> u64 { self.duration }
+
+    /// True if the text occurs within the service, method, url, bodies, status or environment.
+    /// The comparison is case-insensitive.
+    pub fn matches(&self, text : &str) -> bool {
+        let text = text.to_lowercase();
+        let contains = |value : &str| value.to_lowercase().contains(&text);
+
+        contains(&self.service) || contains(&self.method) || contains(self.request.get_method())
+            || contains(self.request.get_url()) || contains(self.request.get_body())
+            || self.environment.as_deref().map(contains).unwrap_or(false)
+            || self.response.as_ref().map(|r| contains(&r.get_status().to_string()) || contains(r.get_body())).unwrap_or(false)
+            || self.error.as_deref().map(contains).unwrap_or(false)
+    }
+
+    /// Send the recorded request again. Redacted credentials are left out, the given authentication adds
+    /// the current ones instead.
+    pub fn resend(&self, authentication : &AuthenticationType) -> Result<RockeryResponse, String> {
+        let url = map_query(self.request.get_url(), |_, v| (v != REDACTED).then_some(v.to_owned()));
+        let mut request = ehttp::Request::get(url);
+        request.method = self.request.get_method().to_owned();
+        request.body = self.request.get_body().as_bytes().to_vec();
+        request.headers = ehttp::Headers::new(&[]);
+        for (k, v) in self.request.get_headers().iter().filter(|(_, v)| v != REDACTED) {
+            request.headers.insert(k, v);
+        }
+        authentication.apply(&mut request);
+
+        let start = Instant::now();
+        let response = ehttp::fetch_blocking(&request).map_err(|e| format!("Could not execute request. Error: {}", e))?;
+
+        Ok(RockeryResponse::new(response.status, &response.status_text, &response.headers, &response.bytes, start.elapsed()))
+    }
+}
+
+/// Rewrite the values of the query of an url. `f` is called with the decoded name and the raw value of
+/// every parameter, parameters it returns no value for are removed.
+fn map_query(url : &str, f : impl Fn(&str, &str) -> Option<String>) -> String {
+    let (path, query) = match url.split_once('?') {
+        Some(parts) => parts,
+        None => return url.to_owned(),
+    };
+
+    let pairs : Vec<String> = query.split('&').filter(|p| !p.is_empty()).filter_map(|pair| match pair.split_once('=') {
+        Some((k, v)) => f(&decode_component(k), v).map(|v| format!("{}={}", k, v)),
+        None => f(&decode_component(pair), "").map(|_| pair.to_owned()),
+    }).collect();
+
+    if pairs.is_empty() { path.to_owned() } else { format!("{}?{}", path, pairs.join("&")) }
+}
+
+/// Replace the credentials within a request by a placeholder, so they are never written to the history.
+fn redact(request : &RockeryRequest, authentication : &AuthenticationType) -> RockeryRequest {
+    let url = map_query(request.get_url(), |k, v| Some(if authentication.is_credential_query(k) { REDACTED.to_owned() } else { v.to_owned() }));
+    let headers = request.get_headers().iter()
+        .map(|(k, v)| (k.clone(), if authentication.is_credential_header(k) { REDACTED.to_owned() } else { v.clone() }))
+        .collect();
+
+    RockeryRequest::new(request.get_method(), &url, headers, request.get_body())
+}
+
+/// All requests sent for the methods of a project. Every entry is appended to a file with one json
+/// object per line, so the history survives restarts.
+#[derive(Debug, Default)]
+pub struct RockeryHistory {
+    path : Option<PathBuf>,
+    environment : Option<String>,
+    entries : Vec<RockeryHistoryEntry>,
+}
+
+impl RockeryHistory {
+    /// A history that is kept in memory only.
+    pub fn new() -> Self {
+        RockeryHistory::default()
+    }
+
+    /// Load the history from a file. A missing file is an empty history.
+    pub fn load(path : &Path) -> Result<Self, String> {
+        let mut history = RockeryHistory { path : Some(path.to_path_buf()), ..Default::default() };
+        if !path.exists() {
+            return Ok(history);
+        }
+
+        let content = fs::read_to_string(path).map_err(|e| format!("Could not read history {}. Error: {}", path.display(), e))?;
+        for (i, line) in content.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+            let entry = serde_json::from_str(line).map_err(|e| format!("Invalid entry in line {} of history {}. Error: {}", i + 1, path.display(), e))?;
+            history.entries.push(entry);
+        }
+
+        Ok(history)
+    }
+
+    /// The file the history of a project file is stored in, e.g. `Test.proj.history.jsonl` for `Test.proj.json`.
+    pub fn path_for(project_path : &Path) -> PathBuf {
+        let stem = project_path.file_stem().and_then(|n| n.to_str()).unwrap_or("project");
+        project_path.with_file_name(format!("{}.history.jsonl", stem))
+    }
+
+    pub fn get_path(&self) -> &Option<PathBuf> { &self.path }
+    pub fn get_entries(&self) -> &Vec<RockeryHistoryEntry> { &self.entries }
+
+    pub fn get_environment(&self) -> &Option<String> { &self.environment }
+    pub(crate) fn set_environment(&mut self, environment : Option<String>) { self.environment = environment; }
+
+    /// All entries matching the text, see [RockeryHistoryEntry::matches]. An empty text matches all entries.
+    pub fn search(&self, text : &str) -> Vec<&RockeryHistoryEntry> {
+        self.entries.iter().filter(|e| text.is_empty() || e.matches(text)).collect()
+    }
+
+    pub fn add(&mut self, entry : RockeryHistoryEntry) -> Result<(), String> {
+        if let Some(path) = &self.path {
+            let line = serde_json::to_string(&entry).map_err(|e| e.to_string())?;
+            OpenOptions::new().create(true).append(true).open(path)
+                .and_then(|mut f| writeln!(f, "{}", line))
+                .map_err(|e| format!("Could not write history {}. Error: {}", path.display(), e))?;
+        }
+
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// Remove all entries, including the file.
+    pub fn clear(&mut self) -> Result<(), String> {
+        self.entries.clear();
+        match &self.path {
+            Some(path) if path.exists() => fs::remove_file(path).map_err(|e| format!("Could not remove history {}. Error: {}", path.display(), e)),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Records the requests of a single service into the history of its project.
+#[derive(Debug, Clone)]
+pub(crate) struct HistoryRecorder {
+    history : Arc<Mutex<RockeryHistory>>,
+    service : String,
+}
+
+impl HistoryRecorder {
+    pub(crate) fn new(history : Arc<Mutex<RockeryHistory>>, service : &str) -> Self {
+        HistoryRecorder { history, service : service.to_owned() }
+    }
+
+    /// Add an entry for a request. The credentials of the request are redacted.
+    pub(crate) fn record(&self, method : &str, request : &RockeryRequest, authentication : &AuthenticationType, response : Result<RockeryResponse, String>, duration : u64) {
+        let mut history = match self.history.lock() {
+            Ok(h) => h,
+            Err(_) => return,
+        };
+
+        let (response, error) = match response {
+            Ok(r) => (Some(r), None),
+            Err(e) => (None, Some(e)),
+        };
+        let entry = RockeryHistoryEntry {
+            timestamp : format_timestamp(SystemTime::now()),
+            environment : history.environment.clone(),
+            service : self.service.clone(),
+            method : method.to_owned(),
+            request : redact(request, authentication),
+            response,
+            error,
+            duration,
+        };
+
+        if let Err(e) = history.add(entry) {
+            warn!("{}", e);
+        }
+    }
+}
+
+/// Set a parameter to a value, adding it if the method does not declare it yet.
+fn set_parameter_value(method : &mut RockeryServiceMethod, parameter : RequestParameter) {
+    let mut parameters = method.get_parameter().clone();
+    match parameters.iter_mut().find(|p| p.is_same_parameter(&parameter)) {
+        Some(p) => *p = parameter,
+        None => parameters.push(parameter),
+    }
+    method.set_parameter(parameters);
+}
+
+impl RockeryProject {
+    /// Record all requests of the services of this project into the history stored at the given path.
+    pub fn enable_history(&mut self, path : &Path) -> Result<(), String> {
+        let mut history = RockeryHistory::load(path)?;
+        history.set_environment(self.get_active_environment().clone());
+        self.set_history(Some(Arc::new(Mutex::new(history))));
+
+        Ok(())
+    }
+
+    /// Send the request of a history entry again. The new request is recorded as well. If the method still
+    /// exists, the response is validated against its schema and the assertions of the method are evaluated.
+    pub fn resend_history_entry(&self, index : usize) -> Result<RockeryRequestResult, String> {
+        let history = self.get_history().as_ref().ok_or(format!("The history of project {} is not enabled.", self.get_name()))?;
+        let entry = {
+            let history = history.lock().map_err(|e| e.to_string())?;
+            history.get_entries().get(index).cloned()
+                .ok_or(format!("Invalid index. There are only {} history entries but entry {} was requested.", history.get_entries().len(), index))?
+        };
+
+        let authentication = self.find_service(entry.get_service()).map(|s| s.get_authentication().clone()).unwrap_or_default();
+        let start = Instant::now();
+        let response = entry.resend(&authentication);
+        HistoryRecorder::new(history.clone(), entry.get_service())
+            .record(entry.get_method(), entry.get_request(), &authentication, response.clone(), start.elapsed().as_millis() as u64);
+
+        let response = response?;
+        let method = self.find_service(entry.get_service())
+            .and_then(|s| s.get_method_index(entry.get_method()).map(|i| (s, i)));
+        let (violations, assertions) = match method {
+            Some((s, i)) => (s.validate_response(i, &response), s.get_methods()[i].evaluate_assertions(&response)),
+            None => (Vec::new(), Vec::new()),
+        };
+
+        let mut result = RockeryRequestResult::new(response, violations, assertions);
+        result.set_request(entry.get_request().clone());
+
+        Ok(result)
+    }
+
+    /// Take over the query, header and path values and the body of a history entry into its method.
+    /// Redacted values and the header or query parameter the authentication of the service adds itself are
+    /// skipped. The body of a method with form parameters is taken over into their values.
+    pub fn restore_history_entry(&mut self, entry : &RockeryHistoryEntry) -> Result<(), String> {
+        let service_id = entry.get_service().to_owned();
+        let project_name = self.get_name().to_owned();
+        let service = self.get_services().get_mut(&service_id)
+            .ok_or(format!("A service with the name {} does not exist within project {}.", service_id, project_name))?;
+        let base_url = service.get_base_url().trim_end_matches('/').to_owned();
+        let authentication = service.get_authentication().clone();
+        let index = service.get_method_index(entry.get_method())
+            .ok_or(format!("A method with the name {} does not exist within service {}.", entry.get_method(), service_id))?;
+        let method = service.get_method_mut(index).unwrap();
+
+        let url = entry.get_request().get_url();
+        let (path, query) = url.split_once('?').unwrap_or((url, ""));
+        let path = path.strip_prefix(&base_url).unwrap_or(path);
+
+        // Values of placeholders that make up a whole segment of the uri.
+        let uri = method.get_uri().to_owned();
+        let template : Vec<&str> = uri.split('/').filter(|s| !s.is_empty()).collect();
+        let segments : Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+        if template.len() == segments.len() {
+            for (t, s) in template.iter().zip(&segments) {
+                if let Some(name) = t.strip_prefix('{').and_then(|t| t.strip_suffix('}')) {
+                    let parameter = RequestParameter::Path(name.to_owned(), Some(s.to_string()));
+                    set_parameter_value(method, parameter);
+                }
+            }
+        }
+
+        for (k, v) in parse_query(query) {
+            if v != REDACTED && !authentication.is_credential_query(&k) {
+                set_parameter_value(method, RequestParameter::Url(k, Some(v)));
+            }
+        }
+
+        for (k, v) in entry.get_request().get_headers() {
+            let added = matches!(authentication.get_credential_parameter(), Some(RequestParameter::Header(h, _)) if h.eq_ignore_ascii_case(k));
+            if v != REDACTED && !added {
+                set_parameter_value(method, RequestParameter::Header(k.to_owned(), Some(v.to_owned())));
+            }
+        }
+
+        let body = entry.get_request().get_body();
+        let form = method.get_body().is_none() && method.get_parameter().iter().any(|p| matches!(p, RequestParameter::Body(_, _)));
+        if form {
+            for (k, v) in parse_query(body) {
+                set_parameter_value(method, RequestParameter::Body(k, Some(v)));
+            }
+        } else if !body.is_empty() {
+            method.set_body(body);
+        }
+
+        self.set_dirty();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod rockery_history_tests {
+    use crate::rockery_test_helper::serve;
+    use super::*;
+
+    #[test]
+    fn test_history() {
+        let (base_url, requests) = serve(vec![(200, r#"{"id":7}"#), (404, "{}"), (200, r#"{"id":8}"#)]);
+        let mut service = RockeryService::new(RockeryServiceType::Rest, &base_url);
+        service.set_name("Users");
+        service.set_authentication(AuthenticationType::Bearer(BearerAuthenticationAuthentication::new("secret")));
+        let m = service.add_method("get user", "users/{id}");
+        m.add_parameter(RequestParameter::Path("id".to_owned(), Some("1".to_owned())));
+        m.add_parameter(RequestParameter::Url("lang".to_owned(), None));
+        m.add_assertion(RockeryAssertion::StatusCode(200));
+        m.add_assertion(RockeryAssertion::BodyContains("\"id\":7".to_owned()));
+
+        let mut project = RockeryProject::new("History");
+        project.add_environment(RockeryEnvironment::new("Local")).set_base_url("Users", &base_url);
+        project.add_service(service);
+        project.apply_environment("Local").unwrap();
+
+        let path = std::env::temp_dir().join(format!("rockery_history_{}.jsonl", std::process::id()));
+        let _ = fs::remove_file(&path);
+        project.enable_history(&path).unwrap();
+
+        let params = [RequestParameter::Path("id".to_owned(), Some("7".to_owned())), RequestParameter::Url("lang".to_owned(), Some("de".to_owned()))];
+        project.find_service("Users").unwrap().do_request(0, &params).unwrap();
+        requests.recv().unwrap();
+        let mut unrecorded = project.find_service("Users").unwrap().clone();
+        unrecorded.set_history(None);
+        unrecorded.do_request(0, &[]).unwrap();
+        requests.recv().unwrap();
+
+        let history = RockeryHistory::load(&path).unwrap();
+        assert_eq!(history.get_entries().len(), 1);
+        let entry = history.get_entries()[0].clone();
+        assert_eq!((entry.get_service(), entry.get_method(), entry.get_environment()), ("Users", "get user", &Some("Local".to_owned())));
+        assert_eq!(entry.get_request().get_url(), format!("{}/users/7/?lang=de", base_url));
+        assert_eq!(entry.get_request().get_header("authorization"), Some("***"));
+        assert_eq!(entry.get_response().as_ref().unwrap().get_body(), r#"{"id":7}"#);
+        assert_eq!(history.search("USERS/7").len(), 1);
+        assert_eq!(history.search("404").len(), 0);
+        assert_eq!(history.search("").len(), 1);
+
+        let result = project.resend_history_entry(0).unwrap();
+        assert_eq!(result.get_response().get_body(), r#"{"id":8}"#);
+        assert_eq!(result.get_request(), entry.get_request());
+        let passed : Vec<bool> = result.get_assertions().iter().map(|a| a.is_passed()).collect();
+        assert_eq!(passed, vec![true, false]);
+        assert!(!result.is_passed());
+        let resent = requests.recv().unwrap();
+        assert!(resent.starts_with("GET /users/7/?lang=de "));
+        assert!(resent.to_lowercase().contains("authorization: bearer secret"));
+        assert_eq!(project.get_history().as_ref().unwrap().lock().unwrap().get_entries().len(), 2);
+        assert!(project.resend_history_entry(5).is_err());
+
+        project.restore_history_entry(&entry).unwrap();
+        let method = &project.find_service("Users").unwrap().get_methods()[0];
+        assert_eq!(method.get_parameter(), &vec![
+            RequestParameter::Path("id".to_owned(), Some("7".to_owned())),
+            RequestParameter::Url("lang".to_owned(), Some("de".to_owned())),
+        ]);
+
+        project.get_history().as_ref().unwrap().lock().unwrap().clear().unwrap();
+        assert!(!path.exists());
+        assert_eq!(RockeryHistory::path_for(Path::new("dir/Test.proj.json")), PathBuf::from("dir/Test.proj.history.jsonl"));
+        assert_ne!(RockeryHistory::path_for(Path::new("api.v1.json")), RockeryHistory::path_for(Path::new("api.v2.json")));
+    }
+
+    #[test]
+    fn test_redact() {
+        let (base_url, requests) = serve(vec![(200, "{}"), (200, "{}")]);
+        let mut service = RockeryService::new(RockeryServiceType::Rest, &base_url);
+        service.set_name("Users");
+        service.set_authentication(AuthenticationType::ApiKey(ApiKeyAuthenticationAuthentication::new("api_key", "secret", ApiKeyLocation::Query)));
+        let m = service.add_method("list", "users");
+        m.add_parameter(RequestParameter::Url("lang".to_owned(), Some("de".to_owned())));
+        m.add_parameter(RequestParameter::Header("Cookie".to_owned(), Some("session=secret".to_owned())));
+
+        let mut project = RockeryProject::new("Redact");
+        project.add_service(service);
+        let path = std::env::temp_dir().join(format!("rockery_history_redact_{}.jsonl", std::process::id()));
+        let _ = fs::remove_file(&path);
+        project.enable_history(&path).unwrap();
+
+        project.find_service("Users").unwrap().do_request(0, &[]).unwrap();
+        assert!(requests.recv().unwrap().starts_with("GET /users/?lang=de&api_key=secret "));
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(!content.contains("secret"));
+        let entry = RockeryHistory::load(&path).unwrap().get_entries()[0].clone();
+        assert_eq!(entry.get_request().get_url(), format!("{}/users/?lang=de&api_key=***", base_url));
+        assert_eq!(entry.get_request().get_header("cookie"), Some("***"));
+
+        // The key of the service is sent again, the redacted cookie is left out.
+        project.resend_history_entry(0).unwrap();
+        let resent = requests.recv().unwrap();
+        assert!(resent.starts_with("GET /users/?lang=de&api_key=secret "));
+        assert!(!resent.to_lowercase().contains("cookie"));
+        assert!(!fs::read_to_string(&path).unwrap().contains("secret"));
+
+        // Neither the key nor redacted values are taken over into the method.
+        project.restore_history_entry(&entry).unwrap();
+        assert_eq!(project.find_service("Users").unwrap().get_methods()[0].get_parameter(), &vec![
+            RequestParameter::Url("lang".to_owned(), Some("de".to_owned())),
+            RequestParameter::Header("Cookie".to_owned(), Some("session=secret".to_owned())),
+        ]);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_restore_body() {
+        let mut service = RockeryService::new(RockeryServiceType::Rest, "https://example.com");
+        service.set_name("Users");
+        service.add_method("create", "users").set_request_method(RequestMethod::Post);
+        service.add_method("login", "login").add_parameter(RequestParameter::Body("user".to_owned(), None));
+
+        let mut project = RockeryProject::new("Restore");
+        project.add_service(service);
+
+        let entry = |method : &str, body : &str| RockeryHistoryEntry {
+            timestamp : String::new(),
+            environment : None,
+            service : "Users".to_owned(),
+            method : method.to_owned(),
+            request : RockeryRequest::new("POST", "https://example.com/users/", Vec::new(), body),
+            response : None,
+            error : None,
+            duration : 0,
+        };
+
+        // The body is restored into a method without body, form bodies into the form parameters.
+        project.restore_history_entry(&entry("create", r#"{"name":"rex"}"#)).unwrap();
+        project.restore_history_entry(&entry("login", "user=rex&password=a%26b")).unwrap();
+
+        let methods = project.find_service("Users").unwrap().get_methods();
+        assert_eq!(methods[0].get_body(), &Some(r#"{"name":"rex"}"#.to_owned()));
+        assert_eq!(methods[1].get_body(), &None);
+        assert_eq!(methods[1].get_parameter(), &vec![
+            RequestParameter::Body("user".to_owned(), Some("rex".to_owned())),
+            RequestParameter::Body("password".to_owned(), Some("a&b".to_owned())),
+        ]);
+    }
+}
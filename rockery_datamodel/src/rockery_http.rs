@@ -0,0 +1,277 @@
+use std::io::{BufRead, Write};
+
+/// Maximum size of a request body the local servers accept.
+const MAX_BODY_SIZE : usize = 64 * 1024 * 1024;
+
+/// A request received by one of the local servers, i.e. the mock server or the recording proxy.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub(crate) struct HttpRequest {
+    pub(crate) method : String,
+
+    /// Path and query as given in the request line. Proxy requests contain the absolute url.
+    pub(crate) target : String,
+    pub(crate) headers : Vec<(String, String)>,
+    pub(crate) body : Vec<u8>,
+}
+
+impl HttpRequest {
+    /// Read a request from a stream. Returns None if the connection has been closed before a request line was sent.
+    pub(crate) fn read(stream : &mut impl BufRead) -> Result<Option<HttpRequest>, String> {
+        let mut line = String::new();
+        if read_line(stream, &mut line)? == 0 {
+            return Ok(None);
+        }
+
+        let mut parts = line.split_whitespace();
+        let (method, target) = match (parts.next(), parts.next()) {
+            (Some(m), Some(t)) => (m.to_owned(), t.to_owned()),
+            _ => return Err(format!("Invalid request line '{}'.", line.trim_end())),
+        };
+
+        let mut headers = Vec::new();
+        loop {
+            line.clear();
+            read_line(stream, &mut line)?;
+            let header = line.trim_end_matches(['\r', '\n']);
+            if header.is_empty() {
+                break;
+            }
+
+            let (k, v) = header.split_once(':').ok_or(format!("Invalid header '{}'.", header))?;
+            headers.push((k.trim().to_owned(), v.trim().to_owned()));
+        }
+
+        let mut request = HttpRequest { method, target, headers, body : Vec::new() };
+        if request.get_header("Transfer-Encoding").map(|t| t.eq_ignore_ascii_case("chunked")).unwrap_or(false) {
+            request.body = read_chunked(stream)?;
+        } else if let Some(length) = request.get_header("Content-Length") {
+            let length : usize = length.parse().map_err(|_| format!("Invalid content length '{}'.", length))?;
+            if length > MAX_BODY_SIZE {
+                return Err(format!("The body of {} bytes exceeds the maximum of {} bytes.", length, MAX_BODY_SIZE));
+            }
+            request.body = vec![0; length];
+            stream.read_exact(&mut request.body).map_err(|e| format!("Could not read the body. Error: {}", e))?;
+        }
+
+        Ok(Some(request))
+    }
+
+    /// Get the value of the first header with the given name. The lookup is case-insensitive.
+    pub(crate) fn get_header(&self, name : &str) -> Option<&str> {
+        self.headers.iter().find(|(k, _)| k.eq_ignore_ascii_case(name)).map(|(_, v)| v.as_str())
+    }
+
+    /// The path of the target without the query.
+    pub(crate) fn get_path(&self) -> &str {
+        self.target.split('?').next().unwrap_or_default()
+    }
+
+    /// The decoded parameters of the query in the order they appear.
+    pub(crate) fn get_query(&self) -> Vec<(String, String)> {
+        match self.target.split_once('?') {
+            Some((_, query)) => parse_query(query),
+            None => Vec::new(),
+        }
+    }
+
+    pub(crate) fn get_body_text(&self) -> String {
+        String::from_utf8_lossy(&self.body).to_string()
+    }
+}
+
+fn read_line(stream : &mut impl BufRead, line : &mut String) -> Result<usize, String> {
+    stream.read_line(line).map_err(|e| format!("Could not read the request. Error: {}", e))
+}
+
+fn read_chunked(stream : &mut impl BufRead) -> Result<Vec<u8>, String> {
+    let mut body = Vec::new();
+    let mut line = String::new();
+    loop {
+        line.clear();
+        read_line(stream, &mut line)?;
+        let size = line.trim().split(';').next().unwrap_or_default();
+        let size = usize::from_str_radix(size, 16).map_err(|_| format!("Invalid chunk size '{}'.", size))?;
+        if body.len() + size > MAX_BODY_SIZE {
+            return Err(format!("The body exceeds the maximum of {} bytes.", MAX_BODY_SIZE));
+        }
+
+        let start = body.len();
+        body.resize(start + size, 0);
+        stream.read_exact(&mut body[start..]).map_err(|e| format!("Could not read the body. Error: {}", e))?;
+
+        // Every chunk is followed by a line break, the last one by optional trailers.
+        line.clear();
+        read_line(stream, &mut line)?;
+        if size == 0 {
+            while !line.trim().is_empty() {
+                line.clear();
+                if read_line(stream, &mut line)? == 0 {
+                    break;
+                }
+            }
+            return Ok(body);
+        }
+    }
+}
+
+/// Split an absolute url into scheme and authority, path and query.
+pub(crate) fn split_url(url : &str) -> Option<(&str, &str, &str)> {
+    let (scheme, rest) = url.split_once("://")?;
+    let authority_end = rest.find(['/', '?']).unwrap_or(rest.len());
+    let origin = &url[..scheme.len() + 3 + authority_end];
+    let (path, query) = rest[authority_end..].split_once('?').unwrap_or((&rest[authority_end..], ""));
+
+    Some((origin, path, query))
+}
+
+/// Split a url like `split_url`, but accept urls without scheme whose origin is a variable, e.g. `${baseUrl}/users`.
+pub(crate) fn split_origin(url : &str) -> (&str, &str, &str) {
+    match split_url(url) {
+        Some(parts) => parts,
+        None => {
+            let end = url.find(['/', '?']).unwrap_or(url.len());
+            let (path, query) = url[end..].split_once('?').unwrap_or((&url[end..], ""));
+            (&url[..end], path, query)
+        },
+    }
+}
+
+/// Split a query string into decoded names and values.
+pub(crate) fn parse_query(query : &str) -> Vec<(String, String)> {
+    query.split('&')
+        .filter(|p| !p.is_empty())
+        .map(|p| {
+            let (k, v) = p.split_once('=').unwrap_or((p, ""));
+            (decode_component(k), decode_component(v))
+        })
+        .collect()
+}
+
+/// Percent encode a name or value of a query or a form url encoded body. Space is encoded as `+`.
+pub(crate) fn encode_component(value : &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for b in value.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'*' => encoded.push(b as char),
+            b' ' => encoded.push('+'),
+            _ => encoded.push_str(&format!("%{:02X}", b)),
+        }
+    }
+
+    encoded
+}
+
+/// Decode a percent encoded url component. `+` is decoded as space.
+pub(crate) fn decode_component(value : &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let hex = |b : u8| (b as char).to_digit(16);
+                match (hex(bytes[i + 1]), hex(bytes[i + 2])) {
+                    (Some(h), Some(l)) => {
+                        decoded.push((h * 16 + l) as u8);
+                        i += 2;
+                    },
+                    _ => decoded.push(b'%'),
+                }
+            },
+            b => decoded.push(b),
+        }
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+/// The reason phrase of common status codes.
+pub(crate) fn status_text(status : u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        202 => "Accepted",
+        204 => "No Content",
+        301 => "Moved Permanently",
+        302 => "Found",
+        304 => "Not Modified",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        415 => "Unsupported Media Type",
+        422 => "Unprocessable Entity",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        501 => "Not Implemented",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        504 => "Gateway Timeout",
+        _ => "Status",
+    }
+}
+
+/// Write a complete response and close the connection afterwards. Content-Length and Connection
+/// headers are always set by this function.
+pub(crate) fn write_response(stream : &mut impl Write, status : u16, status_text : &str, headers : &[(String, String)], body : &[u8]) -> std::io::Result<()> {
+    let mut head = format!("HTTP/1.1 {} {}\r\n", status, status_text);
+    for (k, v) in headers {
+        if k.eq_ignore_ascii_case("Content-Length") || k.eq_ignore_ascii_case("Connection") || k.eq_ignore_ascii_case("Transfer-Encoding") {
+            continue;
+        }
+        head.push_str(&format!("{}: {}\r\n", k, v));
+    }
+    head.push_str(&format!("Content-Length: {}\r\nConnection: close\r\n\r\n", body.len()));
+
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(body)?;
+    stream.flush()
+}
+
+#[cfg(test)]
+mod rockery_http_tests {
+    use std::io::BufReader;
+
+    use super::*;
+
+    #[test]
+    fn test_split_url() {
+        assert_eq!(split_url("http://localhost:8080/users/7?a=b"), Some(("http://localhost:8080", "/users/7", "a=b")));
+        assert_eq!(split_url("https://example.com?q"), Some(("https://example.com", "", "q")));
+        assert_eq!(split_url("/relative"), None);
+    }
+
+    #[test]
+    fn test_read_request() {
+        let raw = "POST /users/7?lang=de&q=a+b%21 HTTP/1.1\r\nHost: localhost\r\nContent-Length: 4\r\n\r\nbody";
+        let request = HttpRequest::read(&mut BufReader::new(raw.as_bytes())).unwrap().unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.get_path(), "/users/7");
+        assert_eq!(request.get_query(), vec![("lang".to_owned(), "de".to_owned()), ("q".to_owned(), "a b!".to_owned())]);
+        assert_eq!(request.get_header("host"), Some("localhost"));
+        assert_eq!(request.get_body_text(), "body");
+
+        let raw = "PUT / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n2;x=y\r\nde\r\n0\r\n\r\n";
+        let request = HttpRequest::read(&mut BufReader::new(raw.as_bytes())).unwrap().unwrap();
+        assert_eq!(request.get_body_text(), "abcde");
+
+        assert_eq!(HttpRequest::read(&mut BufReader::new("".as_bytes())), Ok(None));
+        assert!(HttpRequest::read(&mut BufReader::new("GARBAGE\r\n\r\n".as_bytes())).is_err());
+        assert_eq!(decode_component("%4"), "%4");
+        assert_eq!(decode_component("100%"), "100%");
+        assert_eq!(decode_component("%C3%A4"), "ä");
+    }
+
+    #[test]
+    fn test_write_response() {
+        let mut out = Vec::new();
+        let headers = vec![("Content-Type".to_owned(), "text/plain".to_owned()), ("Content-Length".to_owned(), "99".to_owned())];
+        write_response(&mut out, 404, status_text(404), &headers, b"missing").unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(),
+            "HTTP/1.1 404 Not Found\r\nContent-Type: text/plain\r\nContent-Length: 7\r\nConnection: close\r\n\r\nmissing");
+    }
+}
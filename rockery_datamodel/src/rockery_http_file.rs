@@ -0,0 +1,345 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use regex::{Captures, Regex};
+
+use crate::prelude::*;
+use crate::rockery_definition::read_definition;
+use crate::rockery_http::{parse_query, split_url};
+use crate::rockery_property_transfer::substitute;
+
+/// Creates services from `.http` files as used by the VS Code REST Client and the JetBrains HTTP client.
+///
+/// Requests are separated by `###`, the text after the separator or a `# @name` comment names the method.
+/// File variables `@name = value` are resolved, other variables `{{name}}` become `${name}`. Requests are
+/// grouped into one service per scheme and host, the path becomes the uri of the method and the query its
+/// url parameters. Response handlers and output redirections of the JetBrains client are skipped.
+pub struct RockeryHttpFileImporter;
+
+impl RockeryHttpFileImporter {
+    /// Read the file from the given url or path and create the services from it.
+    pub fn import(location : &str) -> Result<Vec<RockeryService>, String> {
+        Self::import_str(&read_definition(location)?)
+    }
+
+    /// Create the services from the content of a `.http` file.
+    pub fn import_str(content : &str) -> Result<Vec<RockeryService>, String> {
+        let blocks = split_blocks(content);
+
+        // File variables are visible in the whole file and may refer to variables defined before.
+        let mut variables : HashMap<String, String> = HashMap::new();
+        for block in &blocks {
+            for (name, value) in &block.variables {
+                let value = substitute(&convert_variables(value), &variables);
+                variables.insert(name.to_owned(), value);
+            }
+        }
+
+        let mut services : Vec<RockeryService> = Vec::new();
+        for block in blocks {
+            let request_line = match block.request_line {
+                Some(l) => l,
+                None => continue,
+            };
+            let resolve = |text : &str| substitute(&convert_variables(text), &variables);
+
+            // The method is optional and the request line may end with the http version.
+            let mut parts : Vec<&str> = request_line.split_whitespace().collect();
+            if parts.len() > 1 && parts[parts.len() - 1].starts_with("HTTP/") {
+                parts.pop();
+            }
+            let (request_method, url) = match RequestMethod::from_str(&parts[0].to_uppercase()) {
+                Ok(m) if parts.len() > 1 => (m, parts[1..].join(" ")),
+                _ => (RequestMethod::Get, parts.join(" ")),
+            };
+            let url = resolve(&format!("{}{}", url, block.query_lines.concat()));
+            let (origin, path, query) = split_request_url(&url).ok_or(format!("Invalid url {} in request line {}.", url, request_line))?;
+
+            let index = match services.iter().position(|s| s.get_base_url() == origin) {
+                Some(i) => i,
+                None => {
+                    let mut service = RockeryService::new(RockeryServiceType::Rest, origin);
+                    service.set_name(origin.split_once("://").map(|(_, h)| h).unwrap_or(origin));
+                    services.push(service);
+                    services.len() - 1
+                },
+            };
+
+            let uri = path.trim_matches('/');
+            let name = block.name.unwrap_or(format!("{} /{}", request_method, uri));
+            let method = services[index].add_method(&name, uri);
+            method.set_request_method(request_method);
+
+            for (k, v) in parse_query(query) {
+                method.add_parameter(RequestParameter::Url(k, Some(v)));
+            }
+            for (k, v) in &block.headers {
+                method.add_parameter(RequestParameter::Header(k.to_owned(), Some(resolve(v))));
+            }
+
+            let body = block.body.join("\n");
+            let body = body.trim_end();
+            if !body.is_empty() {
+                method.set_body(&resolve(body));
+            }
+        }
+
+        Ok(services)
+    }
+}
+
+/// Writes the methods of a service as `.http` file.
+pub struct RockeryHttpFileExporter;
+
+impl RockeryHttpFileExporter {
+    /// Create a `.http` file with all methods of a service. The base url is written as file variable
+    /// `@baseUrl`, the credentials of the service are included as headers.
+    pub fn export(service : &RockeryService) -> String {
+        let mut content = format!("@baseUrl = {}\n", convert_placeholders(service.get_base_url()));
+        for index in 0..service.get_methods().len() {
+            if let Ok(request) = Self::export_method(service, index, &[]) {
+                content.push('\n');
+                content.push_str(&request);
+            }
+        }
+
+        content
+    }
+
+    /// Create the request for the method with the given index, using the given parameters as `do_request`
+    /// would. The url starts with `{{baseUrl}}`.
+    pub fn export_method(service : &RockeryService, method : usize, params : &[RequestParameter]) -> Result<String, String> {
+        let request = service.get_effective_request(method, params)?;
+        let url = request.get_url().strip_prefix(service.get_base_url()).unwrap_or(request.get_url());
+
+        let mut content = format!("### {}\n{} {{{{baseUrl}}}}{}\n", service.get_methods()[method].get_name(), request.get_method(), convert_placeholders(url));
+        for (k, v) in request.get_headers() {
+            content.push_str(&format!("{}: {}\n", k, convert_placeholders(v)));
+        }
+
+        if !request.get_body().is_empty() {
+            content.push('\n');
+            content.push_str(&convert_placeholders(request.get_body()));
+            content.push('\n');
+        }
+
+        Ok(content)
+    }
+}
+
+/// The lines of a request between two separators.
+#[derive(Default)]
+struct HttpBlock {
+    name : Option<String>,
+    variables : Vec<(String, String)>,
+    request_line : Option<String>,
+    query_lines : Vec<String>,
+    headers : Vec<(String, String)>,
+    body : Vec<String>,
+}
+
+fn split_blocks(content : &str) -> Vec<HttpBlock> {
+    let name_regex = Regex::new(r"^(?:#|//)\s*@name\s*=?\s*(\S+)").unwrap();
+    let variable_regex = Regex::new(r"^@([A-Za-z0-9_.\-]+)\s*=\s*(.*)$").unwrap();
+
+    let mut blocks = Vec::new();
+    let mut block = HttpBlock::default();
+    // 0: before the request line, 1: query and headers, 2: body, 3: response handler.
+    let mut state = 0;
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if let Some(title) = trimmed.strip_prefix("###") {
+            blocks.push(std::mem::take(&mut block));
+            let title = title.trim();
+            if !title.is_empty() {
+                block.name = Some(title.to_owned());
+            }
+            state = 0;
+            continue;
+        }
+
+        match state {
+            0 => {
+                if let Some(c) = name_regex.captures(trimmed) {
+                    block.name = Some(c[1].to_owned());
+                } else if let Some(c) = variable_regex.captures(trimmed) {
+                    block.variables.push((c[1].to_owned(), c[2].trim().to_owned()));
+                } else if !trimmed.is_empty() && !trimmed.starts_with('#') && !trimmed.starts_with("//") {
+                    block.request_line = Some(trimmed.to_owned());
+                    state = 1;
+                }
+            },
+            1 => {
+                if trimmed.is_empty() {
+                    state = 2;
+                } else if trimmed.starts_with('?') || trimmed.starts_with('&') {
+                    block.query_lines.push(trimmed.to_owned());
+                } else if trimmed.starts_with('#') || trimmed.starts_with("//") {
+                    continue;
+                } else if let Some((k, v)) = trimmed.split_once(':') {
+                    block.headers.push((k.trim().to_owned(), v.trim().to_owned()));
+                }
+            },
+            2 => {
+                if trimmed.starts_with("> {%") {
+                    state = if trimmed.ends_with("%}") { 2 } else { 3 };
+                } else if !(trimmed.starts_with(">> ") || trimmed.starts_with(">>! ") || trimmed.starts_with("<> ")) {
+                    block.body.push(line.to_owned());
+                }
+            },
+            _ => {
+                if trimmed.ends_with("%}") {
+                    state = 2;
+                }
+            },
+        }
+    }
+    blocks.push(block);
+
+    blocks
+}
+
+/// Split a url into origin, path and query. The origin may be an unresolved variable, e.g. `${host}/users`.
+fn split_request_url(url : &str) -> Option<(&str, &str, &str)> {
+    if url.starts_with("${") {
+        let end = url.find('}')? + 1;
+        let (path, query) = url[end..].split_once('?').unwrap_or((&url[end..], ""));
+        return Some((&url[..end], path, query));
+    }
+
+    split_url(url)
+}
+
+/// Replace variables `{{name}}` by `${name}`. Dynamic variables like `{{$guid}}` are kept.
+fn convert_variables(text : &str) -> String {
+    let regex = Regex::new(r"\{\{\s*([^{}\s$][^{}\s]*)\s*\}\}").unwrap();
+    regex.replace_all(text, "$${$1}").to_string()
+}
+
+/// Replace variables `${name}` and path placeholders `{name}` by `{{name}}`.
+fn convert_placeholders(text : &str) -> String {
+    let regex = Regex::new(r"\$?\{([^{}\s]+)\}").unwrap();
+    regex.replace_all(text, |c : &Captures<'_>| format!("{{{{{}}}}}", &c[1])).to_string()
+}
+
+#[cfg(test)]
+mod rockery_http_file_tests {
+    use super::*;
+
+    const FILE : &str = r#"@host = https://api.example.com
+@api = {{host}}/v1
+
+### List users
+GET {{api}}/users?active=true HTTP/1.1
+    &name=a%20b
+Accept: application/json
+Authorization: Bearer {{token}}
+
+###
+
+# @name createUser
+POST {{api}}/users
+Content-Type: application/json
+
+{
+  "name": "rex",
+  "id": "{{$guid}}"
+}
+
+> {% client.global.set("id", response.body.id); %}
+
+### Without method
+https://other.example.com/health
+
+###
+PUT {{base}}/items/1
+// A comment
+Content-Type: text/plain
+
+item
+>> response.txt
+"#;
+
+    #[test]
+    fn test_import() {
+        let services = RockeryHttpFileImporter::import_str(FILE).unwrap();
+        assert_eq!(services.len(), 3);
+
+        let api = &services[0];
+        assert_eq!((api.get_name().as_str(), api.get_base_url()), ("api.example.com", "https://api.example.com"));
+        let methods = api.get_methods();
+        assert_eq!(methods.len(), 2);
+        assert_eq!((methods[0].get_name(), methods[0].get_uri(), methods[0].get_request_method()), ("List users", "v1/users", RequestMethod::Get));
+        assert_eq!(methods[0].get_parameter(), &vec![
+            RequestParameter::Url("active".to_owned(), Some("true".to_owned())),
+            RequestParameter::Url("name".to_owned(), Some("a b".to_owned())),
+            RequestParameter::Header("Accept".to_owned(), Some("application/json".to_owned())),
+            RequestParameter::Header("Authorization".to_owned(), Some("Bearer ${token}".to_owned())),
+        ]);
+        assert_eq!(methods[0].get_body(), &None);
+
+        assert_eq!((methods[1].get_name(), methods[1].get_request_method()), ("createUser", RequestMethod::Post));
+        assert_eq!(methods[1].get_body(), &Some("{\n  \"name\": \"rex\",\n  \"id\": \"{{$guid}}\"\n}".to_owned()));
+
+        let other = &services[1];
+        assert_eq!(other.get_base_url(), "https://other.example.com");
+        assert_eq!((other.get_methods()[0].get_name(), other.get_methods()[0].get_uri()), ("Without method", "health"));
+
+        let items = &services[2];
+        assert_eq!(items.get_base_url(), "${base}");
+        let method = &items.get_methods()[0];
+        assert_eq!((method.get_name(), method.get_request_method()), ("PUT /items/1", RequestMethod::Put));
+        assert_eq!(method.get_parameter(), &vec![RequestParameter::Header("Content-Type".to_owned(), Some("text/plain".to_owned()))]);
+        assert_eq!(method.get_body(), &Some("item".to_owned()));
+
+        assert!(RockeryHttpFileImporter::import_str("GET /relative").is_err());
+        assert!(RockeryHttpFileImporter::import_str("# only a comment").unwrap().is_empty());
+        assert!(RockeryHttpFileImporter::import("does_not_exist.http").is_err());
+    }
+
+    #[test]
+    fn test_invalid_file() {
+        let error = |content : &str| RockeryHttpFileImporter::import_str(content).unwrap_err();
+        assert_eq!(error("POST"), "Invalid url POST in request line POST.");
+        assert_eq!(error("GET ${host/items"), "Invalid url ${host/items in request line GET ${host/items.");
+        assert_eq!(error("### first\nGET https://example.com/\n\n### second\nDELETE example.com/items"),
+            "Invalid url example.com/items in request line DELETE example.com/items.");
+
+        // Headers without colon are skipped, an unclosed response handler ends the body and undefined
+        // variables are kept.
+        let services = RockeryHttpFileImporter::import_str("GET {{missing}}/items\nNo header\nAccept: */*\n\nbody\n> {% client.log(1)\nnot body").unwrap();
+        assert_eq!(services[0].get_base_url(), "${missing}");
+        let method = &services[0].get_methods()[0];
+        assert_eq!(method.get_parameter(), &vec![RequestParameter::Header("Accept".to_owned(), Some("*/*".to_owned()))]);
+        assert_eq!(method.get_body(), &Some("body".to_owned()));
+    }
+
+    #[test]
+    fn test_export() {
+        let mut service = RockeryService::new(RockeryServiceType::Rest, "${host}");
+        service.set_authentication(AuthenticationType::Basic(BasicAuthenticationAuthentication::new("rex", "secret")));
+        let m = service.add_method("get user", "users/{id}");
+        m.add_parameter(RequestParameter::Path("id".to_owned(), None));
+        m.add_parameter(RequestParameter::Url("lang".to_owned(), Some("de".to_owned())));
+        let m = service.add_method("create user", "users");
+        m.set_request_method(RequestMethod::Post);
+        m.add_parameter(RequestParameter::Header("Content-Type".to_owned(), Some("application/json".to_owned())));
+        m.set_body(r#"{"name": "${name}"}"#);
+
+        let content = RockeryHttpFileExporter::export(&service);
+        assert_eq!(content, "@baseUrl = {{host}}\n\n\
+            ### get user\nGET {{baseUrl}}/users/{{id}}/?lang=de\nAuthorization: Basic cmV4OnNlY3JldA==\n\n\
+            ### create user\nPOST {{baseUrl}}/users/\nContent-Type: application/json\nAuthorization: Basic cmV4OnNlY3JldA==\n\n{\"name\": \"{{name}}\"}\n");
+        assert!(RockeryHttpFileExporter::export_method(&service, 2, &[]).is_err());
+
+        // The file can be imported again.
+        let imported = RockeryHttpFileImporter::import_str(&content).unwrap();
+        assert_eq!(imported.len(), 1);
+        assert_eq!(imported[0].get_base_url(), "${host}");
+        let methods = imported[0].get_methods();
+        assert_eq!((methods[0].get_name(), methods[0].get_uri()), ("get user", "users/${id}"));
+        assert_eq!((methods[1].get_name(), methods[1].get_uri()), ("create user", "users"));
+        assert_eq!(methods[1].get_body(), &Some(r#"{"name": "${name}"}"#.to_owned()));
+    }
+}
@@ -0,0 +1,404 @@
+use std::{collections::HashSet, str::FromStr};
+
+use regex::{Captures, Regex};
+use serde_json::Value;
+
+use crate::prelude::*;
+use crate::rockery_definition::read_definition;
+use crate::rockery_http::{parse_query, split_origin};
+use crate::rockery_postman_import::{add_authentication_parameters, add_imported_method, ImportedService};
+
+/// Creates projects from Insomnia exports in the format v4.
+///
+/// Request groups are mapped like the folders of a Postman collection: every top level group becomes a
+/// service, requests of nested groups are prefixed with the path of the group and requests outside of
+/// groups are added to a service named after the workspace. The base environment and the environments of
+/// groups become project variables, sub environments become environments of the project.
+/// Insomnia variables `{{ _.name }}` become `${name}`.
+pub struct RockeryInsomniaImporter;
+
+impl RockeryInsomniaImporter {
+    /// Read the export from the given url or path and create a project from it.
+    pub fn import(location : &str) -> Result<RockeryProject, String> {
+        Self::import_str(&read_definition(location)?)
+    }
+
+    /// Create a project from the content of an export file.
+    pub fn import_str(export : &str) -> Result<RockeryProject, String> {
+        let root : Value = serde_json::from_str(export).map_err(|e| format!("Could not parse Insomnia export. Error: {}", e))?;
+        if root.get("_type").and_then(Value::as_str) != Some("export") {
+            return Err("Not an Insomnia export. The '_type' field must be 'export'.".to_owned());
+        }
+        let format = root.get("__export_format").and_then(Value::as_u64).unwrap_or_default();
+        if format != 4 {
+            return Err(format!("Unsupported Insomnia export format {}. Only format 4 can be imported.", format));
+        }
+
+        let resources : Vec<&Value> = root.get("resources").and_then(Value::as_array).into_iter().flatten().collect();
+        let workspace = of_type(&resources, "workspace").into_iter().next().ok_or("The Insomnia export does not contain a workspace.".to_owned())?;
+        let workspace_id = text(workspace.get("_id"));
+        let workspace_name = workspace.get("name").and_then(Value::as_str).unwrap_or("Insomnia");
+
+        let mut project = RockeryProject::new(workspace_name);
+
+        // The base environment belongs to the workspace, sub environments to the base environment.
+        let base_environments : Vec<&Value> = of_type(&resources, "environment").into_iter().filter(|e| text(e.get("parentId")) == workspace_id).collect();
+        for base in &base_environments {
+            for (k, v) in environment_variables(base.get("data")) {
+                project.set_variable(&k, &v);
+            }
+        }
+        for environment in of_type(&resources, "environment").into_iter().filter(|e| base_environments.iter().any(|b| text(b.get("_id")) == text(e.get("parentId")))) {
+            let mut rockery_environment = RockeryEnvironment::new(environment.get("name").and_then(Value::as_str).unwrap_or("Environment"));
+            for (k, v) in environment_variables(environment.get("data")) {
+                rockery_environment.set_variable(&k, &v);
+            }
+            project.add_environment(rockery_environment);
+        }
+        for group in of_type(&resources, "request_group") {
+            for (k, v) in environment_variables(group.get("environment")) {
+                project.set_variable(&k, &v);
+            }
+        }
+
+        let mut context = Context { resources : &resources, visited : HashSet::new() };
+        let mut services : Vec<ImportedService> = Vec::new();
+        for resource in context.children(&workspace_id) {
+            match resource.get("_type").and_then(Value::as_str) {
+                Some("request_group") => {
+                    let folder = resource.get("name").and_then(Value::as_str).unwrap_or("Folder");
+                    let auth = resource.get("authentication").filter(|a| has_authentication(a));
+                    context.add_group(&mut services, folder, "", resource, auth, auth)?;
+                },
+                Some("request") => add_request(&mut services, workspace_name, "", resource, None, None)?,
+                _ => { },
+            }
+        }
+
+        for s in services {
+            project.add_service(s.into_service());
+        }
+
+        Ok(project)
+    }
+}
+
+struct Context<'a> {
+    resources : &'a [&'a Value],
+    // Ids of the groups added so far, to detect groups that are their own parent.
+    visited : HashSet<String>,
+}
+
+impl<'a> Context<'a> {
+    /// The requests and groups within a group or the workspace, in the order of Insomnia.
+    fn children(&self, parent : &str) -> Vec<&'a Value> {
+        let mut children : Vec<&'a Value> = self.resources.iter().copied()
+            .filter(|r| text(r.get("parentId")) == parent)
+            .collect();
+        children.sort_by(|a, b| {
+            let sort_key = |v : &Value| v.get("metaSortKey").and_then(Value::as_f64).unwrap_or(0.0);
+            sort_key(a).total_cmp(&sort_key(b))
+        });
+        children
+    }
+
+    fn add_group(&mut self, services : &mut Vec<ImportedService>, folder : &str, prefix : &str, group : &Value, service_auth : Option<&Value>, auth : Option<&Value>) -> Result<(), String> {
+        if !self.visited.insert(text(group.get("_id"))) {
+            return Err(format!("The request group {} is contained in itself.", text(group.get("name"))));
+        }
+
+        for child in self.children(&text(group.get("_id"))) {
+            match child.get("_type").and_then(Value::as_str) {
+                Some("request_group") => {
+                    let prefix = format!("{}{}/", prefix, child.get("name").and_then(Value::as_str).unwrap_or("Folder"));
+                    let auth = child.get("authentication").filter(|a| has_authentication(a)).or(auth);
+                    self.add_group(services, folder, &prefix, child, service_auth, auth)?;
+                },
+                Some("request") => add_request(services, folder, prefix, child, service_auth, auth)?,
+                _ => { },
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn add_request(services : &mut Vec<ImportedService>, folder : &str, prefix : &str, request : &Value, service_auth : Option<&Value>, auth : Option<&Value>) -> Result<(), String> {
+    let name = format!("{}{}", prefix, request.get("name").and_then(Value::as_str).unwrap_or("Unnamed"));
+    let request_method = request.get("method").and_then(Value::as_str).unwrap_or("GET");
+    let request_method = RequestMethod::from_str(&request_method.to_uppercase())
+        .map_err(|_| format!("Unknown request method {} of request {}.", request_method, name))?;
+
+    let url = convert_variables(&text(request.get("url")));
+    let (origin, path, query) = split_origin(&url);
+
+    let mut method = RockeryServiceMethod::new(&name, "");
+    method.set_request_method(request_method);
+
+    // Path parameters are written as `:name`.
+    let path_values = entries(request.get("pathParameters"));
+    let mut segments = Vec::new();
+    for segment in path.split('/').filter(|s| !s.is_empty()) {
+        match segment.strip_prefix(':') {
+            Some(variable) => {
+                let value = path_values.iter().find(|(k, _)| k == variable).and_then(|(_, v)| v.clone()).filter(|v| !v.is_empty());
+                method.add_parameter(RequestParameter::Path(variable.to_owned(), value));
+                segments.push(format!("{{{}}}", variable));
+            },
+            None => segments.push(segment.to_owned()),
+        }
+    }
+    method.set_uri(&segments.join("/"));
+
+    for (k, v) in parse_query(query) {
+        method.add_parameter(RequestParameter::Url(k, Some(v)));
+    }
+    for (k, v) in entries(request.get("parameters")) {
+        method.add_parameter(RequestParameter::Url(k, v));
+    }
+    for (k, v) in entries(request.get("headers")) {
+        method.add_parameter(RequestParameter::Header(k, v));
+    }
+
+    if let Some(body) = request.get("body") {
+        add_body(&mut method, body);
+    }
+
+    // A request without authentication inherits the one of its group.
+    let request_auth = request.get("authentication").filter(|a| has_authentication(a)).or(auth);
+    if let (Some(a), false) = (request_auth, request_auth == service_auth) {
+        if let Some(authentication) = authentication(a) {
+            add_authentication_parameters(&mut method, &authentication);
+        }
+    }
+
+    add_imported_method(services, folder, origin, method, service_auth.and_then(authentication));
+
+    Ok(())
+}
+
+fn add_body(method : &mut RockeryServiceMethod, body : &Value) {
+    let mime_type = text(body.get("mimeType"));
+    match mime_type.as_str() {
+        "application/x-www-form-urlencoded" | "multipart/form-data" => {
+            for param in body.get("params").and_then(Value::as_array).into_iter().flatten() {
+                if param.get("type").and_then(Value::as_str) == Some("file") {
+                    continue;
+                }
+                if let Some((k, v)) = entry(param) {
+                    method.add_parameter(RequestParameter::Body(k, v));
+                }
+            }
+        },
+        _ => {
+            let content = convert_variables(&text(body.get("text")));
+            if content.is_empty() {
+                return;
+            }
+            method.set_body(&content);
+        },
+    }
+
+    // Form data is sent url encoded.
+    let content_type = match mime_type.as_str() {
+        "" => return,
+        "multipart/form-data" => "application/x-www-form-urlencoded",
+        m => m,
+    };
+    if !method.get_parameter().iter().any(|p| matches!(p, RequestParameter::Header(k, _) if k.eq_ignore_ascii_case("Content-Type"))) {
+        method.add_parameter(RequestParameter::Header("Content-Type".to_owned(), Some(content_type.to_owned())));
+    } else {
+        // Insomnia adds the multipart content type as header as well.
+        let parameter = method.get_parameter().iter().map(|p| match p {
+            RequestParameter::Header(k, Some(v)) if k.eq_ignore_ascii_case("Content-Type") && v.starts_with("multipart/form-data") =>
+                RequestParameter::Header(k.to_owned(), Some(content_type.to_owned())),
+            p => p.clone(),
+        }).collect();
+        method.set_parameter(parameter);
+    }
+}
+
+fn of_type<'a>(resources : &[&'a Value], resource_type : &str) -> Vec<&'a Value> {
+    resources.iter().copied().filter(|r| r.get("_type").and_then(Value::as_str) == Some(resource_type)).collect()
+}
+
+fn has_authentication(auth : &Value) -> bool {
+    auth.get("type").and_then(Value::as_str).is_some() && !auth.get("disabled").and_then(Value::as_bool).unwrap_or(false)
+}
+
+/// Map an Insomnia authentication onto the supported authentication types. Others are skipped.
+fn authentication(auth : &Value) -> Option<AuthenticationType> {
+    let value = |name : &str| convert_variables(&text(auth.get(name)));
+
+    match auth.get("type").and_then(Value::as_str)? {
+        "none" => Some(AuthenticationType::None(NoAuthenticationAuthentication {})),
+        "basic" => Some(AuthenticationType::Basic(BasicAuthenticationAuthentication::new(&value("username"), &value("password")))),
+        "bearer" => Some(AuthenticationType::Bearer(BearerAuthenticationAuthentication::new(&value("token")))),
+        "apikey" => {
+            let location = if value("addTo") == "queryParams" { ApiKeyLocation::Query } else { ApiKeyLocation::Header };
+            Some(AuthenticationType::ApiKey(ApiKeyAuthenticationAuthentication::new(&value("key"), &value("value"), location)))
+        },
+        _ => None,
+    }
+}
+
+/// Variables of an environment. Nested objects are flattened, e.g. `{"api": {"url": ..}}` becomes `api.url`.
+fn environment_variables(data : Option<&Value>) -> Vec<(String, String)> {
+    fn flatten(prefix : &str, value : &Value, variables : &mut Vec<(String, String)>) {
+        match value {
+            Value::Object(o) => {
+                for (k, v) in o {
+                    let name = if prefix.is_empty() { k.to_owned() } else { format!("{}.{}", prefix, k) };
+                    flatten(&name, v, variables);
+                }
+            },
+            v => variables.push((prefix.to_owned(), convert_variables(&text(Some(v))))),
+        }
+    }
+
+    let mut variables = Vec::new();
+    if let Some(data) = data {
+        flatten("", data, &mut variables);
+    }
+    variables
+}
+
+/// The name and value of a parameter, the value is None if the parameter is disabled.
+fn entry(parameter : &Value) -> Option<(String, Option<String>)> {
+    let name = parameter.get("name").and_then(Value::as_str).filter(|n| !n.is_empty())?;
+    let value = match parameter.get("disabled").and_then(Value::as_bool).unwrap_or(false) {
+        true => None,
+        false => Some(convert_variables(&text(parameter.get("value")))),
+    };
+    Some((name.to_owned(), value))
+}
+
+fn entries(parameters : Option<&Value>) -> Vec<(String, Option<String>)> {
+    parameters.and_then(Value::as_array).into_iter().flatten().filter_map(entry).collect()
+}
+
+fn text(value : Option<&Value>) -> String {
+    match value {
+        Some(Value::String(s)) => s.to_owned(),
+        Some(Value::Null) | None => String::new(),
+        Some(v) => v.to_string(),
+    }
+}
+
+/// Replace Insomnia variables `{{ _.name }}` or `{{name}}` by `${name}`.
+fn convert_variables(text : &str) -> String {
+    let regex = Regex::new(r"\{\{\s*(?:_\.)?([^{}\s]+)\s*\}\}").unwrap();
+    regex.replace_all(text, |c : &Captures<'_>| format!("${{{}}}", &c[1])).to_string()
+}
+
+#[cfg(test)]
+mod rockery_insomnia_import_tests {
+    use super::*;
+
+    const EXPORT : &str = r#"{
+        "_type": "export",
+        "__export_format": 4,
+        "__export_source": "insomnia.desktop.app:v2023.5.8",
+        "resources": [
+            { "_id": "wrk_1", "_type": "workspace", "parentId": null, "name": "Shop" },
+            { "_id": "env_base", "_type": "environment", "parentId": "wrk_1", "name": "Base Environment", "data": { "baseUrl": "https://shop.example.com", "api": { "version": 2 } } },
+            { "_id": "env_stage", "_type": "environment", "parentId": "env_base", "name": "Staging", "data": { "baseUrl": "https://stage.example.com" } },
+            { "_id": "fld_users", "_type": "request_group", "parentId": "wrk_1", "name": "Users", "metaSortKey": 1,
+              "environment": { "user": "rex" },
+              "authentication": { "type": "bearer", "token": "{{ _.token }}" } },
+            { "_id": "fld_admin", "_type": "request_group", "parentId": "fld_users", "name": "Admin", "metaSortKey": 3 },
+            { "_id": "req_2", "_type": "request", "parentId": "fld_users", "name": "Create user", "metaSortKey": 2, "method": "POST",
+              "url": "{{ _.baseUrl }}/users",
+              "body": { "mimeType": "application/json", "text": "{\"name\": \"{{ _.user }}\"}" },
+              "headers": [ { "name": "Content-Type", "value": "application/json" } ],
+              "authentication": {} },
+            { "_id": "req_1", "_type": "request", "parentId": "fld_users", "name": "Get user", "metaSortKey": 1, "method": "GET",
+              "url": "{{ _.baseUrl }}/users/:id?expand=true",
+              "pathParameters": [ { "name": "id", "value": "7" } ],
+              "parameters": [ { "name": "lang", "value": "de" }, { "name": "page", "value": "1", "disabled": true } ],
+              "headers": [ { "name": "Accept", "value": "application/json" } ],
+              "authentication": {} },
+            { "_id": "req_3", "_type": "request", "parentId": "fld_admin", "name": "Login", "method": "POST",
+              "url": "{{ _.baseUrl }}/login",
+              "body": { "mimeType": "multipart/form-data", "params": [ { "name": "user", "value": "admin" }, { "name": "avatar", "type": "file", "fileName": "a.png" } ] },
+              "headers": [ { "name": "Content-Type", "value": "multipart/form-data" } ],
+              "authentication": { "type": "basic", "username": "admin", "password": "secret" } },
+            { "_id": "req_4", "_type": "request", "parentId": "wrk_1", "name": "Health", "method": "GET", "url": "https://status.example.com/health",
+              "authentication": { "type": "apikey", "key": "api_key", "value": "k", "addTo": "queryParams" } },
+            { "_id": "jar_1", "_type": "cookie_jar", "parentId": "wrk_1", "name": "Default Jar" }
+        ]
+    }"#;
+
+    #[test]
+    fn test_import() {
+        let mut project = RockeryInsomniaImporter::import_str(EXPORT).unwrap();
+        assert_eq!(project.get_name(), "Shop");
+        assert_eq!(project.get_variables().get("baseUrl").unwrap(), "https://shop.example.com");
+        assert_eq!(project.get_variables().get("api.version").unwrap(), "2");
+        assert_eq!(project.get_variables().get("user").unwrap(), "rex");
+        assert_eq!(project.get_environments().len(), 1);
+        assert_eq!(project.get_environments()[0].get_name(), "Staging");
+        assert_eq!(project.get_environments()[0].get_variables().get("baseUrl").unwrap(), "https://stage.example.com");
+
+        let users = project.find_service("Users").unwrap();
+        assert_eq!(users.get_base_url(), "${baseUrl}");
+        assert!(matches!(users.get_authentication(), AuthenticationType::Bearer(b) if b.get_token() == "${token}"));
+
+        let methods = users.get_methods();
+        assert_eq!(methods.len(), 3);
+        assert_eq!((methods[0].get_name(), methods[0].get_uri()), ("Get user", "users/{id}"));
+        assert_eq!(methods[0].get_parameter(), &vec![
+            RequestParameter::Path("id".to_owned(), Some("7".to_owned())),
+            RequestParameter::Url("expand".to_owned(), Some("true".to_owned())),
+            RequestParameter::Url("lang".to_owned(), Some("de".to_owned())),
+            RequestParameter::Url("page".to_owned(), None),
+            RequestParameter::Header("Accept".to_owned(), Some("application/json".to_owned())),
+        ]);
+
+        assert_eq!((methods[1].get_name(), methods[1].get_request_method()), ("Create user", RequestMethod::Post));
+        assert_eq!(methods[1].get_body(), &Some(r#"{"name": "${user}"}"#.to_owned()));
+        assert_eq!(methods[1].get_parameter(), &vec![RequestParameter::Header("Content-Type".to_owned(), Some("application/json".to_owned()))]);
+
+        // The request of the nested group has its own authentication.
+        assert_eq!(methods[2].get_name(), "Admin/Login");
+        assert_eq!(methods[2].get_parameter(), &vec![
+            RequestParameter::Header("Content-Type".to_owned(), Some("application/x-www-form-urlencoded".to_owned())),
+            RequestParameter::Body("user".to_owned(), Some("admin".to_owned())),
+            RequestParameter::Header("Authorization".to_owned(), Some("Basic YWRtaW46c2VjcmV0".to_owned())),
+        ]);
+
+        let health = project.find_service("Shop").unwrap();
+        assert_eq!(health.get_base_url(), "https://status.example.com");
+        assert_eq!(health.get_methods()[0].get_parameter(), &vec![RequestParameter::Url("api_key".to_owned(), Some("k".to_owned()))]);
+
+        project.apply_environment("Staging").unwrap();
+        assert_eq!(project.get_variables().get("baseUrl").unwrap(), "https://stage.example.com");
+    }
+
+    #[test]
+    fn test_invalid_export() {
+        assert!(RockeryInsomniaImporter::import_str("{}").is_err());
+        assert!(RockeryInsomniaImporter::import_str("no json").is_err());
+        assert!(RockeryInsomniaImporter::import_str(r#"{ "_type": "export", "__export_format": 3, "resources": [] }"#).is_err());
+        assert!(RockeryInsomniaImporter::import_str(r#"{ "_type": "export", "__export_format": 4, "resources": [] }"#).is_err());
+        assert!(RockeryInsomniaImporter::import_str(r#"{ "_type": "export", "__export_format": "4", "resources": [] }"#).is_err());
+
+        let export = |resources : &str| format!(r#"{{ "_type": "export", "__export_format": 4, "resources": [{{ "_id": "wrk", "_type": "workspace", "name": "W" }}, {}] }}"#, resources);
+        let unknown = RockeryInsomniaImporter::import_str(&export(r#"{ "_id": "req", "_type": "request", "parentId": "wrk", "name": "Fetch", "method": "FETCH" }"#));
+        assert_eq!(unknown.unwrap_err(), "Unknown request method FETCH of request Fetch.");
+
+        // Groups that are their own parent would be added endlessly.
+        let cycle = RockeryInsomniaImporter::import_str(&export(r#"{ "_id": "a", "_type": "request_group", "parentId": "wrk", "name": "A" },
+            { "_id": "wrk", "_type": "request_group", "parentId": "a", "name": "B" }"#));
+        assert_eq!(cycle.unwrap_err(), "The request group A is contained in itself.");
+        let no_ids = r#"{ "_type": "export", "__export_format": 4, "resources": [{ "_type": "workspace" }, { "_type": "request_group", "name": "G" }] }"#;
+        assert_eq!(RockeryInsomniaImporter::import_str(no_ids).unwrap_err(), "The request group G is contained in itself.");
+
+        // Malformed resources are skipped or imported with defaults.
+        let mut project = RockeryInsomniaImporter::import_str(&export(r#"3, { "_type": "unknown", "parentId": "wrk" },
+            { "_id": "req", "_type": "request", "parentId": "wrk", "url": 5, "headers": "none", "body": [] }"#)).unwrap();
+        let methods : Vec<&RockeryServiceMethod> = project.get_services().values().flat_map(|s| s.get_methods()).collect();
+        assert_eq!(methods.len(), 1);
+        assert_eq!((methods[0].get_name(), methods[0].get_request_method(), methods[0].get_body()), ("Unnamed", RequestMethod::Get, &None));
+    }
+}
@@ -0,0 +1,154 @@
+use serde_json::Value;
+
+/// A single step of a json path.
+#[derive(Debug, PartialEq)]
+enum Segment {
+    Child(String),
+    Index(i64),
+    Wildcard,
+    Descendant(String),
+}
+
+fn parse(path : &str) -> Result<Vec<Segment>, String> {
+    let invalid = || format!("Invalid json path '{}'.", path);
+    let chars : Vec<char> = path.trim().chars().collect();
+    if chars.first() != Some(&'$') {
+        return Err(invalid());
+    }
+
+    let read_name = |start : usize| -> (String, usize) {
+        let mut end = start;
+        while end < chars.len() && chars[end] != '.' && chars[end] != '[' {
+            end += 1;
+        }
+        (chars[start..end].iter().collect(), end)
+    };
+
+    let mut segments = Vec::new();
+    let mut i = 1;
+    while i < chars.len() {
+        match chars[i] {
+            '.' if chars.get(i + 1) == Some(&'.') => {
+                let (name, end) = read_name(i + 2);
+                if name.is_empty() {
+                    return Err(invalid());
+                }
+                segments.push(Segment::Descendant(name));
+                i = end;
+            },
+            '.' => {
+                let (name, end) = read_name(i + 1);
+                match name.as_str() {
+                    "" => return Err(invalid()),
+                    "*" => segments.push(Segment::Wildcard),
+                    _ => segments.push(Segment::Child(name)),
+                }
+                i = end;
+            },
+            '[' => {
+                let end = (i..chars.len()).find(|j| chars[*j] == ']').ok_or_else(invalid)?;
+                let inner : String = chars[i + 1..end].iter().collect();
+                let inner = inner.trim();
+                if inner == "*" {
+                    segments.push(Segment::Wildcard);
+                } else if let Ok(index) = inner.parse::<i64>() {
+                    segments.push(Segment::Index(index));
+                } else if inner.len() >= 2 && (inner.starts_with('\'') && inner.ends_with('\'') || inner.starts_with('"') && inner.ends_with('"')) {
+                    segments.push(Segment::Child(inner[1..inner.len() - 1].to_owned()));
+                } else {
+                    return Err(invalid());
+                }
+                i = end + 1;
+            },
+            _ => return Err(invalid()),
+        }
+    }
+
+    Ok(segments)
+}
+
+fn descendants<'a>(value : &'a Value, name : &str, result : &mut Vec<&'a Value>) {
+    match value {
+        Value::Object(o) => {
+            if let Some(v) = o.get(name) {
+                result.push(v);
+            }
+            for v in o.values() {
+                descendants(v, name, result);
+            }
+        },
+        Value::Array(a) => {
+            for v in a {
+                descendants(v, name, result);
+            }
+        },
+        _ => { },
+    }
+}
+
+/// Select all values matching a json path. Supported are child access (`.name`, `['name']`),
+/// indices (`[0]`, `[-1]`), wildcards (`.*`, `[*]`) and recursive descent (`..name`).
+pub(crate) fn select<'a>(value : &'a Value, path : &str) -> Result<Vec<&'a Value>, String> {
+    let mut current = vec![value];
+    for segment in parse(path)? {
+        let mut next = Vec::new();
+        for v in current {
+            match (&segment, v) {
+                (Segment::Child(name), Value::Object(o)) => next.extend(o.get(name)),
+                (Segment::Index(i), Value::Array(a)) => {
+                    let index = if *i < 0 { a.len() as i64 + i } else { *i };
+                    if index >= 0 {
+                        next.extend(a.get(index as usize));
+                    }
+                },
+                (Segment::Wildcard, Value::Array(a)) => next.extend(a.iter()),
+                (Segment::Wildcard, Value::Object(o)) => next.extend(o.values()),
+                (Segment::Descendant(name), v) => descendants(v, name, &mut next),
+                _ => { },
+            }
+        }
+        current = next;
+    }
+
+    Ok(current)
+}
+
+/// The text representation of a selected value. Strings are returned without quotes.
+pub(crate) fn value_text(value : &Value) -> String {
+    match value {
+        Value::String(s) => s.to_owned(),
+        v => v.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod rockery_json_path_tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_select() {
+        let document = json!({
+            "store": {
+                "books": [
+                    { "title": "A", "price": 8 },
+                    { "title": "B", "price": 12.5, "author": { "name": "X" } }
+                ],
+                "name": "Shop"
+            }
+        });
+
+        let texts = |path : &str| -> Vec<String> { select(&document, path).unwrap().into_iter().map(value_text).collect() };
+        assert_eq!(texts("$.store.name"), vec!["Shop"]);
+        assert_eq!(texts("$['store']['books'][1].title"), vec!["B"]);
+        assert_eq!(texts("$.store.books[-1].price"), vec!["12.5"]);
+        assert_eq!(texts("$.store.books[*].title"), vec!["A", "B"]);
+        assert_eq!(texts("$..name"), vec!["Shop", "X"]);
+        assert_eq!(texts("$.store.missing"), Vec::<String>::new());
+        assert_eq!(select(&document, "$").unwrap(), vec![&document]);
+
+        assert!(select(&document, "store").is_err());
+        assert!(select(&document, "$.store[").is_err());
+    }
+}
@@ -0,0 +1,272 @@
+use regex::Regex;
+use serde_json::Value;
+
+use crate::prelude::RockerySchemaViolation;
+
+/// Validate a json value against a json schema. The most commonly used keywords of json schema
+/// and the OpenAPI dialect are supported. Unknown keywords are ignored, references must have been
+/// resolved before.
+pub(crate) fn validate_json(schema : &Value, value : &Value) -> Vec<RockerySchemaViolation> {
+    let mut violations = Vec::new();
+    validate(schema, value, "$", &mut violations);
+
+    violations
+}
+
+fn type_name(value : &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn matches_type(expected : &str, value : &Value) -> bool {
+    let actual = type_name(value);
+    expected == actual || (expected == "number" && actual == "integer")
+        || (expected == "integer" && value.as_f64().map(|f| f.fract() == 0.0).unwrap_or(false))
+}
+
+/// Short representation of a value for violation messages.
+fn describe(value : &Value) -> String {
+    let text = value.to_string();
+    if text.chars().count() > 40 {
+        return format!("{}...", text.chars().take(40).collect::<String>());
+    }
+
+    text
+}
+
+fn validate(schema : &Value, value : &Value, path : &str, violations : &mut Vec<RockerySchemaViolation>) {
+    let schema = match schema {
+        Value::Bool(true) => return,
+        Value::Bool(false) => {
+            violations.push(RockerySchemaViolation::new(path, "no value", &describe(value)));
+            return;
+        },
+        Value::Object(s) => s,
+        _ => return,
+    };
+
+    // The OpenAPI 3.0 way of saying that a value may be null.
+    if value.is_null() && schema.get("nullable").and_then(Value::as_bool) == Some(true) {
+        return;
+    }
+
+    let types : Vec<&str> = match schema.get("type") {
+        Some(Value::String(t)) => vec![t.as_str()],
+        Some(Value::Array(t)) => t.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    };
+    if !types.is_empty() && !types.iter().any(|t| matches_type(t, value)) {
+        violations.push(RockerySchemaViolation::new(path, &format!("type {}", types.join(" or ")), &format!("{} {}", type_name(value), describe(value))));
+        return;
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            let expected : Vec<String> = allowed.iter().map(describe).collect();
+            violations.push(RockerySchemaViolation::new(path, &format!("one of {}", expected.join(", ")), &describe(value)));
+        }
+    }
+
+    if let Some(constant) = schema.get("const") {
+        if constant != value {
+            violations.push(RockerySchemaViolation::new(path, &describe(constant), &describe(value)));
+        }
+    }
+
+    validate_combinations(schema, value, path, violations);
+
+    match value {
+        Value::String(s) => validate_string(schema, s, path, violations),
+        Value::Number(_) => validate_number(schema, value.as_f64().unwrap_or_default(), path, violations),
+        Value::Array(items) => validate_array(schema, items, path, violations),
+        Value::Object(o) => validate_object(schema, o, path, violations),
+        _ => { },
+    }
+}
+
+fn validate_combinations(schema : &serde_json::Map<String, Value>, value : &Value, path : &str, violations : &mut Vec<RockerySchemaViolation>) {
+    if let Some(all) = schema.get("allOf").and_then(Value::as_array) {
+        for s in all {
+            validate(s, value, path, violations);
+        }
+    }
+
+    let valid_count = |schemas : &Vec<Value>| schemas.iter().filter(|s| validate_json(s, value).is_empty()).count();
+
+    if let Some(any) = schema.get("anyOf").and_then(Value::as_array) {
+        if valid_count(any) == 0 {
+            violations.push(RockerySchemaViolation::new(path, "a value matching any of the schemas", &describe(value)));
+        }
+    }
+
+    if let Some(one) = schema.get("oneOf").and_then(Value::as_array) {
+        let count = valid_count(one);
+        if count != 1 {
+            violations.push(RockerySchemaViolation::new(path, "a value matching exactly one of the schemas", &format!("{} matching schemas", count)));
+        }
+    }
+
+    if let Some(not) = schema.get("not") {
+        if validate_json(not, value).is_empty() {
+            violations.push(RockerySchemaViolation::new(path, "a value not matching the schema", &describe(value)));
+        }
+    }
+}
+
+fn validate_string(schema : &serde_json::Map<String, Value>, s : &str, path : &str, violations : &mut Vec<RockerySchemaViolation>) {
+    let length = s.chars().count() as u64;
+    if let Some(min) = schema.get("minLength").and_then(Value::as_u64) {
+        if length < min {
+            violations.push(RockerySchemaViolation::new(path, &format!("at least {} characters", min), &format!("{} characters", length)));
+        }
+    }
+
+    if let Some(max) = schema.get("maxLength").and_then(Value::as_u64) {
+        if length > max {
+            violations.push(RockerySchemaViolation::new(path, &format!("at most {} characters", max), &format!("{} characters", length)));
+        }
+    }
+
+    if let Some(pattern) = schema.get("pattern").and_then(Value::as_str) {
+        if let Ok(r) = Regex::new(pattern) {
+            if !r.is_match(s) {
+                violations.push(RockerySchemaViolation::new(path, &format!("a string matching {}", pattern), s));
+            }
+        }
+    }
+}
+
+fn validate_number(schema : &serde_json::Map<String, Value>, n : f64, path : &str, violations : &mut Vec<RockerySchemaViolation>) {
+    // OpenAPI 3.0 uses booleans for the exclusive flags while newer json schema drafts use numbers.
+    let exclusive = |key : &str| schema.get(key).and_then(Value::as_bool).unwrap_or(false);
+
+    if let Some(min) = schema.get("minimum").and_then(Value::as_f64) {
+        if n < min || (exclusive("exclusiveMinimum") && n == min) {
+            violations.push(RockerySchemaViolation::new(path, &format!("a number >= {}", min), &n.to_string()));
+        }
+    }
+
+    if let Some(max) = schema.get("maximum").and_then(Value::as_f64) {
+        if n > max || (exclusive("exclusiveMaximum") && n == max) {
+            violations.push(RockerySchemaViolation::new(path, &format!("a number <= {}", max), &n.to_string()));
+        }
+    }
+
+    if let Some(min) = schema.get("exclusiveMinimum").and_then(Value::as_f64) {
+        if n <= min {
+            violations.push(RockerySchemaViolation::new(path, &format!("a number > {}", min), &n.to_string()));
+        }
+    }
+
+    if let Some(max) = schema.get("exclusiveMaximum").and_then(Value::as_f64) {
+        if n >= max {
+            violations.push(RockerySchemaViolation::new(path, &format!("a number < {}", max), &n.to_string()));
+        }
+    }
+
+    if let Some(multiple) = schema.get("multipleOf").and_then(Value::as_f64) {
+        if multiple > 0.0 && (n / multiple).fract() != 0.0 {
+            violations.push(RockerySchemaViolation::new(path, &format!("a multiple of {}", multiple), &n.to_string()));
+        }
+    }
+}
+
+fn validate_array(schema : &serde_json::Map<String, Value>, items : &[Value], path : &str, violations : &mut Vec<RockerySchemaViolation>) {
+    if let Some(min) = schema.get("minItems").and_then(Value::as_u64) {
+        if (items.len() as u64) < min {
+            violations.push(RockerySchemaViolation::new(path, &format!("at least {} items", min), &format!("{} items", items.len())));
+        }
+    }
+
+    if let Some(max) = schema.get("maxItems").and_then(Value::as_u64) {
+        if (items.len() as u64) > max {
+            violations.push(RockerySchemaViolation::new(path, &format!("at most {} items", max), &format!("{} items", items.len())));
+        }
+    }
+
+    if schema.get("uniqueItems").and_then(Value::as_bool) == Some(true) {
+        for (i, item) in items.iter().enumerate() {
+            if items[..i].contains(item) {
+                violations.push(RockerySchemaViolation::new(&format!("{}[{}]", path, i), "a unique item", &describe(item)));
+            }
+        }
+    }
+
+    if let Some(item_schema) = schema.get("items") {
+        for (i, item) in items.iter().enumerate() {
+            validate(item_schema, item, &format!("{}[{}]", path, i), violations);
+        }
+    }
+}
+
+fn validate_object(schema : &serde_json::Map<String, Value>, object : &serde_json::Map<String, Value>, path : &str, violations : &mut Vec<RockerySchemaViolation>) {
+    if let Some(required) = schema.get("required").and_then(Value::as_array) {
+        for name in required.iter().filter_map(Value::as_str) {
+            if !object.contains_key(name) {
+                violations.push(RockerySchemaViolation::new(&format!("{}.{}", path, name), "a required property", "nothing"));
+            }
+        }
+    }
+
+    let properties = schema.get("properties").and_then(Value::as_object);
+    for (name, value) in object {
+        let child_path = format!("{}.{}", path, name);
+        match properties.and_then(|p| p.get(name)) {
+            Some(property) => validate(property, value, &child_path, violations),
+            None => {
+                match schema.get("additionalProperties") {
+                    Some(Value::Bool(false)) => violations.push(RockerySchemaViolation::new(&child_path, "no additional property", &describe(value))),
+                    Some(additional) => validate(additional, value, &child_path, violations),
+                    None => { },
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod rockery_json_schema_tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        let schema = json!({
+            "type": "object",
+            "required": ["id", "name"],
+            "additionalProperties": false,
+            "properties": {
+                "id": { "type": "integer", "minimum": 1 },
+                "name": { "type": "string", "minLength": 2, "pattern": "^[A-Z]" },
+                "tags": { "type": "array", "maxItems": 2, "items": { "enum": ["dog", "cat"] } },
+                "owner": { "type": "string", "nullable": true },
+                "price": { "anyOf": [{ "type": "number" }, { "type": "string" }] }
+            }
+        });
+
+        let valid = json!({ "id": 1, "name": "Rex", "tags": ["dog"], "owner": null, "price": 1.5 });
+        assert!(validate_json(&schema, &valid).is_empty());
+
+        let invalid = json!({ "id": 0, "name": "rex", "tags": ["dog", "cow", "cat"], "price": true, "color": "brown" });
+        let violations = validate_json(&schema, &invalid);
+        let paths : Vec<&str> = violations.iter().map(|v| v.get_path()).collect();
+        assert_eq!(paths, vec!["$.color", "$.id", "$.name", "$.price", "$.tags", "$.tags[1]"]);
+        assert_eq!(violations[1].get_expected(), "a number >= 1");
+        assert_eq!(violations[1].get_actual(), "0");
+
+        let violations = validate_json(&schema, &json!({ "id": "1", "name": "Rex" }));
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].to_string(), "$.id: expected type integer, got string \"1\"");
+
+        let violations = validate_json(&schema, &json!([]));
+        assert_eq!(violations[0].get_expected(), "type object");
+    }
+}
@@ -0,0 +1,442 @@
+use std::{
+    fmt,
+    sync::{atomic::{AtomicBool, AtomicU64, Ordering}, Arc, Mutex},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::*;
+
+/// What a virtual user executes in each iteration.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum RockeryLoadTarget {
+    /// A method (service id, method name) of the project.
+    Method(String, String),
+
+    /// A test case (test suite name, test case name) of the project. All steps form one iteration.
+    TestCase(String, String),
+}
+
+/// When a load test ends.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum RockeryLoadLimit {
+    /// Run for the given number of milliseconds.
+    Duration(u64),
+
+    /// Run the given number of iterations shared by all users.
+    Iterations(u64),
+}
+
+/// Executes a method or a test case with a number of concurrent virtual users.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RockeryLoadTest {
+    target : RockeryLoadTarget,
+    users : usize,
+    limit : RockeryLoadLimit,
+
+    /// Time in milliseconds until all users have been started. Users are started at even intervals.
+    #[serde(default)]
+    ramp_up : u64,
+
+    /// Maximum number of iterations per second of all users together.
+    #[serde(default)]
+    rate_limit : Option<f64>,
+}
+
+impl RockeryLoadTest {
+    pub fn new(target : RockeryLoadTarget, users : usize, limit : RockeryLoadLimit) -> Self {
+        RockeryLoadTest { target, users, limit, ramp_up : 0, rate_limit : None }
+    }
+
+    pub fn get_target(&self) -> &RockeryLoadTarget { &self.target }
+    pub fn get_users(&self) -> usize { self.users }
+    pub fn get_limit(&self) -> RockeryLoadLimit { self.limit }
+
+    pub fn get_ramp_up(&self) -> u64 { self.ramp_up }
+    pub fn set_ramp_up(&mut self, ramp_up : u64) { self.ramp_up = ramp_up; }
+
+    pub fn get_rate_limit(&self) -> Option<f64> { self.rate_limit }
+    pub fn set_rate_limit(&mut self, rate_limit : Option<f64>) { self.rate_limit = rate_limit; }
+
+    /// Start the load test in the background. The project is copied, so it can be edited while the test runs.
+    pub fn start(&self, project : &RockeryProject) -> Result<RockeryLoadRun, String> {
+        if self.users == 0 {
+            return Err("A load test needs at least one user.".to_owned());
+        }
+        if let Some(rate) = self.rate_limit {
+            if !(rate.is_finite() && rate > 0.0) {
+                return Err(format!("Invalid rate limit {}. The rate limit must be a number greater than zero.", rate));
+            }
+        }
+
+        let iteration = Iteration::new(&self.target, project)?;
+        RockeryLoadRun::start(self, iteration)
+    }
+
+    /// Run the load test and wait until it has finished.
+    pub fn run(&self, project : &RockeryProject) -> Result<RockeryLoadStatistics, String> {
+        Ok(self.start(project)?.wait())
+    }
+}
+
+/// The work of a single iteration, prepared once before the users are started.
+enum Iteration {
+    Method(RockeryService, usize),
+    TestCase(Box<RockeryProject>, RockeryTestCase),
+}
+
+impl Iteration {
+    fn new(target : &RockeryLoadTarget, project : &RockeryProject) -> Result<Iteration, String> {
+        match target {
+            RockeryLoadTarget::Method(service_name, method_name) => {
+                let service = project.find_service(service_name)
+                    .ok_or(format!("A service with the name {} does not exist within project {}.", service_name, project.get_name()))?;
+                let method = service.get_method_index(method_name)
+                    .ok_or(format!("A method with the name {} does not exist within service {}.", method_name, service_name))?;
+
+                // Load tests are not recorded into the history.
+                let mut service = service.with_variables(method, project.get_variables());
+                service.set_history(None);
+
+                Ok(Iteration::Method(service, method))
+            },
+            RockeryLoadTarget::TestCase(suite_name, test_case_name) => {
+                let test_case = project.get_test_suites().iter()
+                    .find(|s| s.get_name() == suite_name)
+                    .ok_or(format!("A test suite with the name {} does not exist within project {}.", suite_name, project.get_name()))?
+                    .get_test_cases().iter()
+                    .find(|t| t.get_name() == test_case_name)
+                    .ok_or(format!("A test case with the name {} does not exist within test suite {}.", test_case_name, suite_name))?;
+
+                let mut project = project.clone();
+                project.set_history(None);
+
+                Ok(Iteration::TestCase(Box::new(project), test_case.clone()))
+            },
+        }
+    }
+
+    /// Execute the iteration and return whether it passed.
+    fn execute(&self) -> bool {
+        match self {
+            Iteration::Method(service, method) => service.do_checked_request(*method, &[]).map(|r| r.is_passed()).unwrap_or(false),
+            Iteration::TestCase(project, test_case) => test_case.run_with_variables(project, &mut project.get_variables().clone()).is_passed(),
+        }
+    }
+}
+
+/// A single iteration executed by a virtual user.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct RockeryLoadSample {
+    /// Time since the start of the load test in milliseconds at which the iteration finished.
+    offset : u64,
+
+    /// Time the iteration took in milliseconds.
+    latency : u64,
+    passed : bool,
+}
+
+impl RockeryLoadSample {
+    pub fn new(offset : u64, latency : u64, passed : bool) -> Self {
+        RockeryLoadSample { offset, latency, passed }
+    }
+
+    pub fn get_offset(&self) -> u64 { self.offset }
+    pub fn get_latency(&self) -> u64 { self.latency }
+    pub fn is_passed(&self) -> bool { self.passed }
+}
+
+/// Figures describing a load test. Latencies are in milliseconds.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct RockeryLoadStatistics {
+    iterations : usize,
+    errors : usize,
+
+    /// Time in milliseconds from the start of the test until the last iteration finished.
+    duration : u64,
+    min : u64,
+    max : u64,
+    mean : f64,
+    p50 : u64,
+    p90 : u64,
+    p99 : u64,
+}
+
+impl RockeryLoadStatistics {
+    /// Calculate the statistics of the given samples. Percentiles use the nearest rank.
+    pub fn from_samples(samples : &[RockeryLoadSample], duration : u64) -> Self {
+        if samples.is_empty() {
+            return RockeryLoadStatistics { duration, ..Default::default() };
+        }
+
+        let mut latencies : Vec<u64> = samples.iter().map(|s| s.latency).collect();
+        latencies.sort_unstable();
+        let percentile = |p : f64| {
+            let rank = (p / 100.0 * latencies.len() as f64).ceil() as usize;
+            latencies[rank.clamp(1, latencies.len()) - 1]
+        };
+
+        RockeryLoadStatistics {
+            iterations : samples.len(),
+            errors : samples.iter().filter(|s| !s.passed).count(),
+            duration,
+            min : latencies[0],
+            max : latencies[latencies.len() - 1],
+            mean : latencies.iter().sum::<u64>() as f64 / latencies.len() as f64,
+            p50 : percentile(50.0),
+            p90 : percentile(90.0),
+            p99 : percentile(99.0),
+        }
+    }
+
+    pub fn get_iterations(&self) -> usize { self.iterations }
+    pub fn get_errors(&self) -> usize { self.errors }
+    pub fn get_duration(&self) -> u64 { self.duration }
+    pub fn get_min(&self) -> u64 { self.min }
+    pub fn get_max(&self) -> u64 { self.max }
+    pub fn get_mean(&self) -> f64 { self.mean }
+    pub fn get_p50(&self) -> u64 { self.p50 }
+    pub fn get_p90(&self) -> u64 { self.p90 }
+    pub fn get_p99(&self) -> u64 { self.p99 }
+
+    /// Iterations per second.
+    pub fn get_throughput(&self) -> f64 {
+        if self.duration == 0 { 0.0 } else { self.iterations as f64 * 1000.0 / self.duration as f64 }
+    }
+
+    /// Share of failed iterations between 0 and 1.
+    pub fn get_error_rate(&self) -> f64 {
+        if self.iterations == 0 { 0.0 } else { self.errors as f64 / self.iterations as f64 }
+    }
+}
+
+impl fmt::Display for RockeryLoadStatistics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Iterations: {} in {} ms ({:.1}/s)", self.iterations, self.duration, self.get_throughput())?;
+        writeln!(f, "Errors: {} ({:.1} %)", self.errors, self.get_error_rate() * 100.0)?;
+        writeln!(f, "Latency: min {} ms, mean {:.1} ms, max {} ms", self.min, self.mean, self.max)?;
+        writeln!(f, "Percentiles: p50 {} ms, p90 {} ms, p99 {} ms", self.p50, self.p90, self.p99)
+    }
+}
+
+/// A running load test. Samples can be read while the test runs, e.g. to draw a live chart.
+pub struct RockeryLoadRun {
+    start : Instant,
+    running : Arc<AtomicBool>,
+    samples : Arc<Mutex<Vec<RockeryLoadSample>>>,
+    handles : Vec<JoinHandle<()>>,
+}
+
+impl RockeryLoadRun {
+    fn start(test : &RockeryLoadTest, iteration : Iteration) -> Result<Self, String> {
+        let start = Instant::now();
+        let running = Arc::new(AtomicBool::new(true));
+        let samples = Arc::new(Mutex::new(Vec::new()));
+        let iteration = Arc::new(iteration);
+
+        // Iterations are numbered across all users. The number decides whether the iteration limit has been
+        // reached and when the iteration may start if the rate is limited.
+        let counter = Arc::new(AtomicU64::new(0));
+
+        let mut handles = Vec::new();
+        for user in 0..test.users {
+            let delay = Duration::from_millis((test.ramp_up as u128 * user as u128 / test.users as u128) as u64);
+            let (running, samples, iteration, counter) = (running.clone(), samples.clone(), iteration.clone(), counter.clone());
+            let (limit, rate_limit) = (test.limit, test.rate_limit);
+
+            let handle = thread::Builder::new().name(format!("rockery load user {}", user)).spawn(move || {
+                sleep_until(&running, start + delay);
+                while running.load(Ordering::SeqCst) {
+                    let number = counter.fetch_add(1, Ordering::SeqCst);
+                    match limit {
+                        RockeryLoadLimit::Iterations(n) if number >= n => break,
+                        RockeryLoadLimit::Duration(d) if start.elapsed() >= Duration::from_millis(d) => break,
+                        _ => { },
+                    }
+
+                    if let Some(rate) = rate_limit {
+                        // A very low rate schedules iterations too far in the future to be represented.
+                        let scheduled = match Duration::try_from_secs_f64(number as f64 / rate) {
+                            Ok(s) => s,
+                            Err(_) => break,
+                        };
+                        if matches!(limit, RockeryLoadLimit::Duration(d) if scheduled >= Duration::from_millis(d)) {
+                            break;
+                        }
+
+                        sleep_until(&running, start + scheduled);
+                        if !running.load(Ordering::SeqCst) {
+                            break;
+                        }
+                    }
+
+                    let begin = Instant::now();
+                    let passed = iteration.execute();
+                    let sample = RockeryLoadSample::new(start.elapsed().as_millis() as u64, begin.elapsed().as_millis() as u64, passed);
+                    if let Ok(mut samples) = samples.lock() {
+                        samples.push(sample);
+                    }
+                }
+            }).map_err(|e| format!("Could not start a virtual user. Error: {}", e))?;
+            handles.push(handle);
+        }
+
+        Ok(RockeryLoadRun { start, running, samples, handles })
+    }
+
+    /// All samples collected so far, ordered by the time the iterations finished.
+    pub fn get_samples(&self) -> Vec<RockeryLoadSample> {
+        self.samples.lock().map(|s| s.clone()).unwrap_or_default()
+    }
+
+    /// Time in milliseconds since the test has been started.
+    pub fn get_elapsed(&self) -> u64 { self.start.elapsed().as_millis() as u64 }
+
+    /// True once all users are done.
+    pub fn is_finished(&self) -> bool { self.handles.iter().all(|h| h.is_finished()) }
+
+    /// The statistics of the samples collected so far.
+    pub fn get_statistics(&self) -> RockeryLoadStatistics {
+        let samples = self.get_samples();
+        let duration = if self.is_finished() {
+            samples.iter().map(|s| s.offset).max().unwrap_or(0)
+        } else {
+            self.get_elapsed()
+        };
+
+        RockeryLoadStatistics::from_samples(&samples, duration)
+    }
+
+    /// Ask all users to stop after their current iteration.
+    pub fn stop(&self) {
+        self.running.store(false, Ordering::SeqCst);
+    }
+
+    /// Wait until all users are done and return the final statistics.
+    pub fn wait(mut self) -> RockeryLoadStatistics {
+        for handle in self.handles.drain(..) {
+            let _ = handle.join();
+        }
+
+        self.get_statistics()
+    }
+}
+
+impl Drop for RockeryLoadRun {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Sleep until the given time in short steps, so a stopped test ends quickly.
+fn sleep_until(running : &AtomicBool, time : Instant) {
+    while running.load(Ordering::SeqCst) {
+        let now = Instant::now();
+        if now >= time {
+            return;
+        }
+        thread::sleep((time - now).min(Duration::from_millis(50)));
+    }
+}
+
+#[cfg(test)]
+mod rockery_load_test_tests {
+    use std::{io::{Read, Write}, net::TcpListener};
+
+    use super::*;
+
+    /// A server answering every request with the given status until the test ends.
+    fn serve_forever(status : u16) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut buffer = [0u8; 4096];
+                let _ = stream.read(&mut buffer);
+                let response = format!("HTTP/1.1 {} Status\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{{}}", status);
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+
+        base_url
+    }
+
+    fn get_project(base_url : &str) -> RockeryProject {
+        let mut service = RockeryService::new(RockeryServiceType::Rest, base_url);
+        service.set_name("Users");
+        service.add_method("get users", "users").add_assertion(RockeryAssertion::StatusCode(200));
+
+        let mut project = RockeryProject::new("Load");
+        project.add_service(service);
+        project.add_test_suite(RockeryTestSuite::new("Suite")).add_test_case(RockeryTestCase::new("Case"))
+            .add_step(RockeryTestStep::new("Get", "Users", "get users"));
+
+        project
+    }
+
+    #[test]
+    fn test_statistics() {
+        let samples : Vec<RockeryLoadSample> = (1..=100).map(|i| RockeryLoadSample::new(i * 10, i, i % 10 != 0)).collect();
+        let statistics = RockeryLoadStatistics::from_samples(&samples, 1000);
+        assert_eq!((statistics.get_iterations(), statistics.get_errors()), (100, 10));
+        assert_eq!((statistics.get_min(), statistics.get_max(), statistics.get_mean()), (1, 100, 50.5));
+        assert_eq!((statistics.get_p50(), statistics.get_p90(), statistics.get_p99()), (50, 90, 99));
+        assert_eq!(statistics.get_throughput(), 100.0);
+        assert_eq!(statistics.get_error_rate(), 0.1);
+        assert!(statistics.to_string().contains("Percentiles: p50 50 ms, p90 90 ms, p99 99 ms"));
+
+        let single = RockeryLoadStatistics::from_samples(&[RockeryLoadSample::new(5, 7, true)], 5);
+        assert_eq!((single.get_p50(), single.get_p99()), (7, 7));
+        assert_eq!(RockeryLoadStatistics::from_samples(&[], 0).get_throughput(), 0.0);
+    }
+
+    #[test]
+    fn test_iterations() {
+        let project = get_project(&serve_forever(200));
+        let test = RockeryLoadTest::new(RockeryLoadTarget::Method("Users".to_owned(), "get users".to_owned()), 4, RockeryLoadLimit::Iterations(20));
+        let statistics = test.run(&project).unwrap();
+        assert_eq!((statistics.get_iterations(), statistics.get_errors()), (20, 0));
+
+        let project = get_project(&serve_forever(500));
+        let test = RockeryLoadTest::new(RockeryLoadTarget::TestCase("Suite".to_owned(), "Case".to_owned()), 2, RockeryLoadLimit::Iterations(6));
+        let statistics = test.run(&project).unwrap();
+        assert_eq!((statistics.get_iterations(), statistics.get_errors()), (6, 6));
+
+        assert!(RockeryLoadTest::new(RockeryLoadTarget::Method("Users".to_owned(), "missing".to_owned()), 1, RockeryLoadLimit::Iterations(1)).run(&project).is_err());
+        assert!(RockeryLoadTest::new(RockeryLoadTarget::TestCase("Suite".to_owned(), "missing".to_owned()), 1, RockeryLoadLimit::Iterations(1)).run(&project).is_err());
+        assert!(RockeryLoadTest::new(RockeryLoadTarget::Method("Users".to_owned(), "get users".to_owned()), 0, RockeryLoadLimit::Iterations(1)).run(&project).is_err());
+
+        for rate in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            let mut test = RockeryLoadTest::new(RockeryLoadTarget::Method("Users".to_owned(), "get users".to_owned()), 1, RockeryLoadLimit::Iterations(1));
+            test.set_rate_limit(Some(rate));
+            assert!(matches!(test.start(&project), Err(e) if e.starts_with("Invalid rate limit")));
+        }
+    }
+
+    #[test]
+    fn test_rate_limit_and_ramp_up() {
+        let project = get_project(&serve_forever(200));
+        let mut test = RockeryLoadTest::new(RockeryLoadTarget::Method("Users".to_owned(), "get users".to_owned()), 4, RockeryLoadLimit::Duration(500));
+        test.set_rate_limit(Some(20.0));
+        test.set_ramp_up(200);
+
+        let run = test.start(&project).unwrap();
+        let statistics = run.wait();
+
+        // 20 iterations per second for half a second.
+        assert_eq!(statistics.get_iterations(), 10, "{}", statistics);
+
+        let mut test = RockeryLoadTest::new(RockeryLoadTarget::Method("Users".to_owned(), "get users".to_owned()), 2, RockeryLoadLimit::Duration(60000));
+        test.set_rate_limit(Some(10.0));
+        let run = test.start(&project).unwrap();
+        thread::sleep(Duration::from_millis(250));
+        assert!(!run.is_finished());
+        run.stop();
+        let start = Instant::now();
+        let statistics = run.wait();
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert!(statistics.get_iterations() >= 2);
+    }
+}
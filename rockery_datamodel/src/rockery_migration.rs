@@ -0,0 +1,110 @@
+use serde_json::{json, Map, Value};
+
+/// The version of the project file format written by this version of rockery. Increase it together with
+/// a new entry in `MIGRATIONS` whenever the serialization of a project changes incompatibly.
+pub const PROJECT_FORMAT_VERSION : u64 = 1;
+
+/// A migration upgrades a project document by one version.
+type Migration = fn(&mut Map<String, Value>) -> Result<(), String>;
+
+/// The migration at index `i` upgrades documents of version `i` to version `i + 1`.
+const MIGRATIONS : [Migration; PROJECT_FORMAT_VERSION as usize] = [
+    migrate_authentication,
+];
+
+/// Upgrade a project document of any older format version to the current one. Files without version are
+/// version 0. Files of a newer version are rejected, as they can not be read without losing data.
+pub(crate) fn migrate(mut document : Value) -> Result<Value, String> {
+    let project = document.as_object_mut().ok_or("Invalid project file. The project must be a json object.".to_owned())?;
+    let version = match project.get("format_version") {
+        None => 0,
+        Some(v) => v.as_u64().ok_or(format!("Invalid project file. The format version must be a number but is {}.", v))?,
+    };
+
+    if version > PROJECT_FORMAT_VERSION {
+        return Err(format!("The project file has format version {} but this version of rockery only supports format version {} and older. Please update rockery.",
+            version, PROJECT_FORMAT_VERSION));
+    }
+
+    for (from, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        migration(project).map_err(|e| format!("Could not migrate the project file from format version {} to {}. {}", from, from + 1, e))?;
+    }
+    project.insert("format_version".to_owned(), json!(PROJECT_FORMAT_VERSION));
+
+    Ok(document)
+}
+
+/// Version 0 wrote the authentication of a service tagged with the name of its type, e.g.
+/// `{"authentication": "BasicAuthenticationAuthentication", "username": ..}`. Version 1 writes the
+/// variant of `AuthenticationType`, e.g. `{"Basic": {"username": ..}}`.
+fn migrate_authentication(project : &mut Map<String, Value>) -> Result<(), String> {
+    let services = match project.get_mut("services").and_then(Value::as_object_mut) {
+        Some(s) => s,
+        None => return Ok(()),
+    };
+
+    for (id, service) in services.iter_mut() {
+        let authentication = match service.get_mut("authentication").and_then(Value::as_object_mut) {
+            Some(a) => a,
+            None => continue,
+        };
+        let tag = match authentication.remove("authentication") {
+            Some(Value::String(tag)) => tag,
+            Some(tag) => return Err(format!("Service {} has an invalid authentication type {}.", id, tag)),
+            None => continue,
+        };
+
+        let variant = match tag.as_str() {
+            "NoAuthenticationAuthentication" => "None",
+            "BasicAuthenticationAuthentication" => "Basic",
+            "BearerAuthenticationAuthentication" => "Bearer",
+            "ApiKeyAuthenticationAuthentication" => "ApiKey",
+            t => return Err(format!("Service {} has the unknown authentication type {}.", id, t)),
+        };
+        let values = Value::Object(std::mem::take(authentication));
+        service["authentication"] = json!({ variant : values });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod rockery_migration_tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate() {
+        let legacy = json!({
+            "file": "Test.proj.json",
+            "name": "Legacy",
+            "services": {
+                "None": { "authentication": { "authentication": "NoAuthenticationAuthentication" } },
+                "Basic": { "authentication": { "authentication": "BasicAuthenticationAuthentication", "username": "rex", "password": "secret" } },
+                "Current": { "authentication": { "Bearer": { "token": "t" } } },
+            }
+        });
+
+        let migrated = migrate(legacy).unwrap();
+        assert_eq!(migrated["format_version"], json!(PROJECT_FORMAT_VERSION));
+        assert_eq!(migrated["services"]["None"]["authentication"], json!({ "None": {} }));
+        assert_eq!(migrated["services"]["Basic"]["authentication"], json!({ "Basic": { "username": "rex", "password": "secret" } }));
+        assert_eq!(migrated["services"]["Current"]["authentication"], json!({ "Bearer": { "token": "t" } }));
+
+        // Current documents are left as they are.
+        assert_eq!(migrate(migrated.clone()).unwrap(), migrated);
+    }
+
+    #[test]
+    fn test_invalid_versions() {
+        let newer = migrate(json!({ "name": "Future", "format_version": PROJECT_FORMAT_VERSION + 1 })).unwrap_err();
+        assert!(newer.contains(&format!("format version {}", PROJECT_FORMAT_VERSION + 1)));
+        assert!(newer.contains("Please update rockery"));
+
+        assert!(migrate(json!({ "format_version": "one" })).is_err());
+        assert!(migrate(json!([])).is_err());
+
+        let unknown = migrate(json!({ "services": { "S": { "authentication": { "authentication": "OAuthAuthentication" } } } })).unwrap_err();
+        assert!(unknown.contains("from format version 0 to 1"));
+        assert!(unknown.contains("unknown authentication type OAuthAuthentication"));
+    }
+}
@@ -0,0 +1,398 @@
+use std::{
+    fmt,
+    io::BufReader,
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{atomic::{AtomicBool, Ordering}, Arc, Mutex},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::prelude::*;
+use crate::rockery_http::{status_text, write_response, HttpRequest};
+use crate::rockery_json_path;
+
+/// A condition on a request received by the mock server.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum RockeryMockRule {
+    /// The query contains the parameter with the given value.
+    QueryEquals(String, String),
+
+    /// The request has the header with the given value. The name is case-insensitive.
+    HeaderEquals(String, String),
+    BodyContains(String),
+
+    /// The regular expression matches somewhere within the body.
+    BodyMatches(String),
+
+    /// The first value the json path selects within the body equals the given text.
+    JsonPathEquals(String, String),
+}
+
+impl RockeryMockRule {
+    fn matches(&self, request : &HttpRequest) -> bool {
+        match self {
+            RockeryMockRule::QueryEquals(k, v) => request.get_query().iter().any(|(key, value)| key == k && value == v),
+            RockeryMockRule::HeaderEquals(k, v) => request.headers.iter().any(|(key, value)| key.eq_ignore_ascii_case(k) && value == v),
+            RockeryMockRule::BodyContains(text) => request.get_body_text().contains(text),
+            RockeryMockRule::BodyMatches(r) => Regex::new(r).map(|r| r.is_match(&request.get_body_text())).unwrap_or(false),
+            RockeryMockRule::JsonPathEquals(path, expected) => {
+                let body : Value = match serde_json::from_str(&request.get_body_text()) {
+                    Ok(b) => b,
+                    Err(_) => return false,
+                };
+                match rockery_json_path::select(&body, path) {
+                    Ok(values) => values.first().map(|v| rockery_json_path::value_text(v) == *expected).unwrap_or(false),
+                    Err(_) => false,
+                }
+            },
+        }
+    }
+}
+
+impl fmt::Display for RockeryMockRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RockeryMockRule::QueryEquals(k, v) => write!(f, "query {} = {}", k, v),
+            RockeryMockRule::HeaderEquals(k, v) => write!(f, "header {} = {}", k, v),
+            RockeryMockRule::BodyContains(t) => write!(f, "body contains '{}'", t),
+            RockeryMockRule::BodyMatches(r) => write!(f, "body matches /{}/", r),
+            RockeryMockRule::JsonPathEquals(p, v) => write!(f, "{} = {}", p, v),
+        }
+    }
+}
+
+/// A canned response the mock server returns for a method. The first response of a method whose rules
+/// all match the request is returned, so a response without rules should come last as default.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RockeryMockResponse {
+    name : String,
+    status : u16,
+
+    #[serde(default)]
+    headers : Vec<(String, String)>,
+
+    #[serde(default)]
+    body : String,
+
+    /// Time to wait before the response is sent in milliseconds.
+    #[serde(default)]
+    delay : u64,
+
+    #[serde(default)]
+    rules : Vec<RockeryMockRule>,
+}
+
+impl RockeryMockResponse {
+    pub fn new(name : &str, status : u16, body : &str) -> Self {
+        RockeryMockResponse { name : name.to_owned(), status, headers : Vec::new(), body : body.to_owned(), delay : 0, rules : Vec::new() }
+    }
+
+    pub fn get_name(&self) -> &str { &self.name }
+    pub fn set_name(&mut self, name : &str) { self.name = name.to_owned(); }
+
+    pub fn get_status(&self) -> u16 { self.status }
+    pub fn set_status(&mut self, status : u16) { self.status = status; }
+
+    pub fn get_headers(&self) -> &Vec<(String, String)> { &self.headers }
+    pub fn add_header(&mut self, name : &str, value : &str) { self.headers.push((name.to_owned(), value.to_owned())); }
+
+    pub fn get_body(&self) -> &str { &self.body }
+    pub fn set_body(&mut self, body : &str) { self.body = body.to_owned(); }
+
+    pub fn get_delay(&self) -> u64 { self.delay }
+    pub fn set_delay(&mut self, delay : u64) { self.delay = delay; }
+
+    pub fn get_rules(&self) -> &Vec<RockeryMockRule> { &self.rules }
+    pub fn add_rule(&mut self, rule : RockeryMockRule) { self.rules.push(rule); }
+
+    fn matches(&self, request : &HttpRequest) -> bool {
+        self.rules.iter().all(|r| r.matches(request))
+    }
+}
+
+impl From<&RockeryResponse> for RockeryMockResponse {
+    /// Replay a recorded response.
+    fn from(response : &RockeryResponse) -> Self {
+        RockeryMockResponse {
+            name : format!("{} {}", response.get_status(), response.get_status_text()),
+            status : response.get_status(),
+            headers : response.get_headers().clone(),
+            body : response.get_body().to_owned(),
+            delay : 0,
+            rules : Vec::new(),
+        }
+    }
+}
+
+/// The number of placeholders within the uri template if the path matches it. Trailing slashes are ignored.
+pub(crate) fn match_uri(template : &str, path : &str) -> Option<usize> {
+    let template : Vec<&str> = template.split('/').filter(|s| !s.is_empty()).collect();
+    let path : Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    if template.len() != path.len() {
+        return None;
+    }
+
+    let mut placeholders = 0;
+    for (t, p) in template.iter().zip(path) {
+        if !t.contains('{') {
+            if t != &p {
+                return None;
+            }
+            continue;
+        }
+
+        // Build an expression for segments like `{id}` or `{id}.json`.
+        let mut pattern = String::from("^");
+        for (i, part) in t.split(['{', '}']).enumerate() {
+            if i % 2 == 0 {
+                pattern.push_str(&regex::escape(part));
+            } else {
+                pattern.push_str("[^/]+");
+                placeholders += 1;
+            }
+        }
+        pattern.push('$');
+
+        if !Regex::new(&pattern).map(|r| r.is_match(p)).unwrap_or(false) {
+            return None;
+        }
+    }
+
+    Some(placeholders)
+}
+
+impl RockeryService {
+    /// Find the method a request is routed to. If several uris match, the one with the fewest placeholders wins.
+    /// The path of the base url is stripped from the request path if present.
+    fn route(&self, request_method : &str, path : &str) -> Option<usize> {
+        let base_url = self.get_base_url();
+        let without_scheme = base_url.split_once("://").map(|(_, r)| r).unwrap_or(base_url);
+        let base_path = without_scheme.find('/').map(|i| without_scheme[i..].trim_end_matches('/')).unwrap_or_default();
+        let path = match path.strip_prefix(base_path) {
+            Some(p) if !base_path.is_empty() && (p.is_empty() || p.starts_with('/')) => p,
+            _ => path,
+        };
+
+        self.get_methods().iter().enumerate()
+            .filter(|(_, m)| m.get_request_method().to_string() == request_method)
+            .filter_map(|(i, m)| match_uri(m.get_uri(), path).map(|p| (p, i)))
+            .min()
+            .map(|(_, i)| i)
+    }
+
+    /// The response the mock server returns for a request: the first mock response whose rules match,
+    /// otherwise the first recorded response of the method.
+    fn mock_response(&self, request : &HttpRequest) -> RockeryMockResponse {
+        let method = match self.route(&request.method, request.get_path()) {
+            Some(m) => &self.get_methods()[m],
+            None => return RockeryMockResponse::new("No route", 404,
+                &format!("No method of service {} matches {} {}.", self.get_name(), request.method, request.get_path())),
+        };
+
+        if let Some(response) = method.get_mock_responses().iter().find(|r| r.matches(request)) {
+            return response.clone();
+        }
+
+        match method.get_responses().first() {
+            Some(r) => RockeryMockResponse::from(r),
+            None => RockeryMockResponse::new("No response", 501, &format!("There is no mock response for method {} that matches the request.", method.get_name())),
+        }
+    }
+}
+
+fn handle_connection(service : &RockeryService, stream : TcpStream, received : &Mutex<Vec<RockeryRequest>>) {
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(10)));
+    let mut writer = match stream.try_clone() {
+        Ok(s) => s,
+        Err(_) => return,
+    };
+
+    let request = match HttpRequest::read(&mut BufReader::new(stream)) {
+        Ok(Some(r)) => r,
+        Ok(None) => return,
+        Err(e) => {
+            let _ = write_response(&mut writer, 400, status_text(400), &[], e.as_bytes());
+            return;
+        },
+    };
+
+    if let Ok(mut received) = received.lock() {
+        received.push(RockeryRequest::new(&request.method, &request.target, request.headers.clone(), &request.get_body_text()));
+    }
+
+    let response = service.mock_response(&request);
+    if response.delay > 0 {
+        thread::sleep(Duration::from_millis(response.delay));
+    }
+
+    let mut headers = response.headers.clone();
+    if !headers.iter().any(|(k, _)| k.eq_ignore_ascii_case("Content-Type")) {
+        let body = response.body.trim_start();
+        let content_type = if body.starts_with('{') || body.starts_with('[') {
+            "application/json"
+        } else if body.starts_with('<') {
+            "application/xml"
+        } else {
+            "text/plain"
+        };
+        headers.push(("Content-Type".to_owned(), content_type.to_owned()));
+    }
+
+    let _ = write_response(&mut writer, response.status, status_text(response.status), &headers, response.body.as_bytes());
+}
+
+/// Serves the methods of a service on a local address. Every method is a route matching its request method
+/// and uri. The server stops when it is dropped.
+pub struct RockeryMockServer {
+    address : SocketAddr,
+    running : Arc<AtomicBool>,
+    received : Arc<Mutex<Vec<RockeryRequest>>>,
+    handle : Option<JoinHandle<()>>,
+}
+
+impl RockeryMockServer {
+    /// Start serving a copy of the service, e.g. on `127.0.0.1:8080`. Use port 0 to pick a free port.
+    pub fn start(service : &RockeryService, address : &str) -> Result<Self, String> {
+        let listener = TcpListener::bind(address).map_err(|e| format!("Could not listen on {}. Error: {}", address, e))?;
+        let address = listener.local_addr().map_err(|e| e.to_string())?;
+
+        let running = Arc::new(AtomicBool::new(true));
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let service = Arc::new(service.clone());
+
+        let (r, rec) = (running.clone(), received.clone());
+        let handle = thread::spawn(move || {
+            for stream in listener.incoming() {
+                if !r.load(Ordering::SeqCst) {
+                    break;
+                }
+
+                if let Ok(stream) = stream {
+                    let (service, rec) = (service.clone(), rec.clone());
+                    thread::spawn(move || handle_connection(&service, stream, &rec));
+                }
+            }
+        });
+
+        Ok(RockeryMockServer { address, running, received, handle : Some(handle) })
+    }
+
+    pub fn get_address(&self) -> SocketAddr { self.address }
+
+    /// The url to use as base url of the service while developing against the mock.
+    pub fn get_url(&self) -> String { format!("http://{}", self.address) }
+
+    /// All requests the server has received so far.
+    pub fn get_received(&self) -> Vec<RockeryRequest> {
+        self.received.lock().map(|r| r.clone()).unwrap_or_default()
+    }
+
+    pub fn is_running(&self) -> bool { self.running.load(Ordering::SeqCst) }
+
+    pub fn stop(&mut self) {
+        if !self.running.swap(false, Ordering::SeqCst) {
+            return;
+        }
+
+        // Wake up the listener so it notices that it has been stopped.
+        let _ = TcpStream::connect(self.address);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for RockeryMockServer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod rockery_mock_tests {
+    use std::time::Instant;
+
+    use super::*;
+
+    #[test]
+    fn test_match_uri() {
+        assert_eq!(match_uri("users/{id}", "/users/7/"), Some(1));
+        assert_eq!(match_uri("/users/me", "/users/me"), Some(0));
+        assert_eq!(match_uri("files/{name}.json", "/files/a.json"), Some(1));
+        assert_eq!(match_uri("files/{name}.json", "/files/a.xml"), None);
+        assert_eq!(match_uri("users/{id}", "/users"), None);
+        assert_eq!(match_uri("users", "/posts"), None);
+    }
+
+    #[test]
+    fn test_mock_server() {
+        let mut service = RockeryService::new(RockeryServiceType::Rest, "https://api.example.com/v1");
+        service.set_name("Users");
+        let m = service.add_method("get user", "users/{id}");
+        let mut admin = RockeryMockResponse::new("Admin", 200, r#"{"role":"admin"}"#);
+        admin.add_rule(RockeryMockRule::QueryEquals("role".to_owned(), "admin".to_owned()));
+        admin.add_rule(RockeryMockRule::HeaderEquals("x-tenant".to_owned(), "a".to_owned()));
+        m.add_mock_response(admin);
+        let mut default = RockeryMockResponse::new("Default", 200, r#"{"role":"user"}"#);
+        default.add_header("X-Mock", "yes");
+        default.set_delay(50);
+        m.add_mock_response(default);
+
+        service.add_method("get me", "users/me");
+        let m = service.add_method("create user", "users");
+        m.set_request_method(RequestMethod::Post);
+        let mut conflict = RockeryMockResponse::new("Conflict", 409, "<error/>");
+        conflict.add_rule(RockeryMockRule::JsonPathEquals("$.name".to_owned(), "taken".to_owned()));
+        m.add_mock_response(conflict);
+        m.add_response(RockeryResponse::new(201, "Created", &ehttp::Headers::new(&[]), b"created", Duration::ZERO));
+
+        let mut server = RockeryMockServer::start(&service, "127.0.0.1:0").unwrap();
+        let mut client = service.clone();
+        client.set_base_url(&format!("{}/v1", server.get_url()));
+        client.get_method_mut(0).unwrap().add_parameter(RequestParameter::Path("id".to_owned(), Some("7".to_owned())));
+        client.get_method_mut(0).unwrap().add_parameter(RequestParameter::Url("role".to_owned(), Some("admin".to_owned())));
+        client.get_method_mut(0).unwrap().add_parameter(RequestParameter::Header("X-Tenant".to_owned(), None));
+
+        let start = Instant::now();
+        let (status, _, headers, body) = client.do_request(0, &[]).unwrap();
+        assert_eq!((status, String::from_utf8(body).unwrap()), (200, r#"{"role":"user"}"#.to_owned()));
+        assert_eq!(headers.get("x-mock"), Some("yes"));
+        assert_eq!(headers.get("content-type"), Some("application/json"));
+        assert!(start.elapsed() >= Duration::from_millis(50));
+
+        let tenant = [RequestParameter::Header("X-Tenant".to_owned(), Some("a".to_owned()))];
+        let (_, _, _, body) = client.do_request(0, &tenant).unwrap();
+        assert_eq!(body, br#"{"role":"admin"}"#);
+
+        // Without responses the literal route wins, but there is nothing to return.
+        let (status, _, _, _) = client.do_request(1, &[]).unwrap();
+        assert_eq!(status, 501);
+
+        client.get_method_mut(2).unwrap().set_body(r#"{"name":"taken"}"#);
+        let (status, _, headers, _) = client.do_request(2, &[]).unwrap();
+        assert_eq!((status, headers.get("content-type")), (409, Some("application/xml")));
+        client.get_method_mut(2).unwrap().set_body(r#"{"name":"free"}"#);
+        let (status, _, _, body) = client.do_request(2, &[]).unwrap();
+        assert_eq!((status, body), (201, b"created".to_vec()));
+
+        client.set_base_url(&server.get_url());
+        let (status, _, _, _) = client.do_request(2, &[]).unwrap();
+        assert_eq!(status, 201);
+        client.get_method_mut(2).unwrap().set_request_method(RequestMethod::Delete);
+        let (status, _, _, _) = client.do_request(2, &[]).unwrap();
+        assert_eq!(status, 404);
+
+        let received = server.get_received();
+        assert_eq!(received.len(), 7);
+        assert_eq!(received[0].get_url(), "/v1/users/7/?role=admin");
+        assert_eq!(received[4].get_body(), r#"{"name":"free"}"#);
+
+        server.stop();
+        assert!(!server.is_running());
+        assert!(client.do_request(2, &[]).is_err());
+    }
+}
@@ -0,0 +1,264 @@
+use serde_json::{json, Map, Value};
+
+use crate::prelude::*;
+use crate::rockery_yaml::to_yaml;
+
+/// Headers that are described by other parts of an OpenAPI document and must not be listed as parameters.
+const RESERVED_HEADERS : [&str; 3] = ["content-type", "accept", "authorization"];
+
+/// Writes a service as OpenAPI 3.1 document.
+pub struct RockeryOpenApiExporter;
+
+impl RockeryOpenApiExporter {
+    /// Create the OpenAPI document for a service. If `infer_response_schemas` is set, the responses
+    /// recorded for each method are used to describe the responses including a schema for json bodies.
+    /// Returns the document and the warnings for the methods that could not be exported.
+    pub fn export(service : &RockeryService, infer_response_schemas : bool) -> (Value, Vec<String>) {
+        let mut warnings = Vec::new();
+        let mut paths = Map::new();
+        let mut operation_ids : Vec<String> = Vec::new();
+
+        for m in service.get_methods() {
+            let path = format!("/{}", m.get_uri().trim_matches('/'));
+            let method = m.get_request_method().to_string().to_lowercase();
+
+            let item = paths.entry(path.clone()).or_insert(json!({}));
+            if item.get(&method).is_some() {
+                // Only one operation per path and method can be described.
+                warnings.push(format!("Method {} is not exported, another method already describes {} {}.",
+                    m.get_name(), method.to_uppercase(), path));
+                continue;
+            }
+
+            let mut operation_id = m.get_name().to_owned();
+            let mut i = 1;
+            while operation_ids.contains(&operation_id) {
+                operation_id = format!("{}_{}", m.get_name(), i);
+                i += 1;
+            }
+            operation_ids.push(operation_id.clone());
+
+            item[&method] = export_operation(m, &operation_id, infer_response_schemas);
+        }
+
+        let mut document = json!({
+            "openapi": "3.1.0",
+            "info": {
+                "title": service.get_name(),
+                "version": "1.0.0",
+            },
+            "servers": [{ "url": service.get_base_url() }],
+            "paths": paths,
+        });
+
+        if let Some((name, scheme)) = security_scheme(service.get_authentication()) {
+            document["components"] = json!({ "securitySchemes": { name: scheme } });
+            document["security"] = json!([{ name: [] }]);
+        }
+
+        (document, warnings)
+    }
+
+    pub fn export_json(service : &RockeryService, infer_response_schemas : bool) -> (String, Vec<String>) {
+        let (document, warnings) = Self::export(service, infer_response_schemas);
+        (serde_json::to_string_pretty(&document).unwrap_or_default(), warnings)
+    }
+
+    pub fn export_yaml(service : &RockeryService, infer_response_schemas : bool) -> (String, Vec<String>) {
+        let (document, warnings) = Self::export(service, infer_response_schemas);
+        (to_yaml(&document), warnings)
+    }
+}
+
+fn export_operation(m : &RockeryServiceMethod, operation_id : &str, infer_response_schemas : bool) -> Value {
+    let mut operation = json!({ "operationId": operation_id, "summary": m.get_name() });
+
+    let mut parameters = Vec::new();
+    let mut content_type = None;
+    let mut form_fields = Map::new();
+    for p in m.get_parameter() {
+        let (location, required) = match p {
+            RequestParameter::Path(_, _) => ("path", true),
+            // A default value does not make a parameter required, only path parameters always are.
+            RequestParameter::Url(_, _) => ("query", false),
+            RequestParameter::Header(k, v) => {
+                if k.eq_ignore_ascii_case("content-type") {
+                    content_type = v.clone();
+                }
+                if RESERVED_HEADERS.contains(&k.to_lowercase().as_str()) {
+                    continue;
+                }
+                ("header", false)
+            },
+            RequestParameter::Body(k, v) => {
+                let mut schema = json!({ "type": "string" });
+                if let Some(v) = v {
+                    schema["default"] = json!(v);
+                }
+                form_fields.insert(k.to_owned(), schema);
+                continue;
+            },
+        };
+
+        let mut schema = json!({ "type": "string" });
+        let mut parameter = json!({ "name": p.get_name(), "in": location, "required": required });
+        if let Some(v) = p.get_value() {
+            schema["default"] = json!(v);
+            parameter["example"] = json!(v);
+        }
+        parameter["schema"] = schema;
+        parameters.push(parameter);
+    }
+
+    if !parameters.is_empty() {
+        operation["parameters"] = Value::Array(parameters);
+    }
+
+    if !form_fields.is_empty() {
+        let media_type = content_type.clone().unwrap_or("application/x-www-form-urlencoded".to_owned());
+        operation["requestBody"] = json!({
+            "content": { media_type: { "schema": { "type": "object", "properties": form_fields } } }
+        });
+    } else if let Some(body) = m.get_body() {
+        let media = match serde_json::from_str::<Value>(body) {
+            Ok(example) => (content_type.unwrap_or("application/json".to_owned()), json!({ "schema": infer_schema(&example), "example": example })),
+            Err(_) => (content_type.unwrap_or("text/plain".to_owned()), json!({ "example": body })),
+        };
+        operation["requestBody"] = json!({ "content": { media.0: media.1 } });
+    }
+
+    let mut responses = Map::new();
+    if infer_response_schemas {
+        for r in m.get_responses() {
+            let status = r.get_status().to_string();
+            if responses.contains_key(&status) {
+                continue;
+            }
+
+            let description = match r.get_status_text() {
+                "" => "Response".to_owned(),
+                t => t.to_owned(),
+            };
+            let mut response = json!({ "description": description });
+
+            if !r.get_body().is_empty() {
+                let media = match serde_json::from_str::<Value>(r.get_body()) {
+                    Ok(body) => (r.get_content_type().unwrap_or("application/json").to_owned(), json!({ "schema": infer_schema(&body) })),
+                    Err(_) => (r.get_content_type().unwrap_or("text/plain").to_owned(), json!({ "schema": { "type": "string" } })),
+                };
+                response["content"] = json!({ media.0: media.1 });
+            }
+
+            responses.insert(status, response);
+        }
+    }
+
+    if responses.is_empty() {
+        responses.insert("default".to_owned(), json!({ "description": "Response" }));
+    }
+    operation["responses"] = Value::Object(responses);
+
+    operation
+}
+
+/// Derive a json schema that describes the given value.
+pub(crate) fn infer_schema(value : &Value) -> Value {
+    match value {
+        Value::Null => json!({ "type": "null" }),
+        Value::Bool(_) => json!({ "type": "boolean" }),
+        Value::Number(n) if n.is_f64() => json!({ "type": "number" }),
+        Value::Number(_) => json!({ "type": "integer" }),
+        Value::String(_) => json!({ "type": "string" }),
+        Value::Array(items) => {
+            match items.first() {
+                Some(first) => json!({ "type": "array", "items": infer_schema(first) }),
+                None => json!({ "type": "array" }),
+            }
+        },
+        Value::Object(o) => {
+            let properties : Map<String, Value> = o.iter().map(|(k, v)| (k.to_owned(), infer_schema(v))).collect();
+            json!({ "type": "object", "properties": properties })
+        },
+    }
+}
+
+fn security_scheme(authentication : &AuthenticationType) -> Option<(&'static str, Value)> {
+    match authentication {
+        AuthenticationType::None(_) => None,
+        AuthenticationType::Basic(_) => Some(("basicAuth", json!({ "type": "http", "scheme": "basic" }))),
+        AuthenticationType::Bearer(_) => Some(("bearerAuth", json!({ "type": "http", "scheme": "bearer" }))),
+        AuthenticationType::ApiKey(key) => {
+            let location = match key.get_location() {
+                ApiKeyLocation::Header => "header",
+                ApiKeyLocation::Query => "query",
+            };
+            Some(("apiKey", json!({ "type": "apiKey", "name": key.get_name(), "in": location })))
+        },
+    }
+}
+
+#[cfg(test)]
+mod rockery_openapi_export_tests {
+    use std::time::Duration;
+
+    use ehttp::Headers;
+
+    use crate::rockery_test_helper::get_feiertage_service;
+    use super::*;
+
+    #[test]
+    fn test_export() {
+        let mut service = get_feiertage_service();
+        service.set_authentication(AuthenticationType::Bearer(BearerAuthenticationAuthentication::new("token")));
+
+        let m = service.add_method("create user", "users/{id}");
+        m.set_request_method(RequestMethod::Post);
+        m.add_parameter(RequestParameter::Path("id".to_owned(), None));
+        m.add_parameter(RequestParameter::Header("Content-Type".to_owned(), Some("application/json".to_owned())));
+        m.set_body(r#"{ "name": "Rex", "age": 3 }"#);
+
+        let headers = Headers::new(&[("Content-Type", "application/json; charset=utf-8")]);
+        m.add_response(RockeryResponse::new(201, "Created", &headers, br#"{ "id": 1, "tags": ["a"] }"#, Duration::from_millis(5)));
+
+        let (document, warnings) = RockeryOpenApiExporter::export(&service, true);
+        assert!(warnings.is_empty());
+        assert_eq!(document["openapi"], "3.1.0");
+        assert_eq!(document["servers"][0]["url"], "https://feiertage-api.de");
+        assert_eq!(document["components"]["securitySchemes"]["bearerAuth"]["scheme"], "bearer");
+
+        let get = &document["paths"]["/api"]["get"];
+        assert_eq!(get["operationId"], "get feiertage");
+        assert_eq!(get["parameters"][0], json!({
+            "name": "jahr", "in": "query", "required": false, "example": "2024",
+            "schema": { "type": "string", "default": "2024" }
+        }));
+        assert_eq!(get["parameters"][1]["required"], false);
+        assert_eq!(get["responses"]["default"]["description"], "Response");
+
+        let post = &document["paths"]["/users/{id}"]["post"];
+        assert_eq!(post["parameters"].as_array().unwrap().len(), 1);
+        assert_eq!(post["parameters"][0]["required"], true);
+        assert_eq!(post["requestBody"]["content"]["application/json"]["schema"]["properties"]["age"]["type"], "integer");
+        assert_eq!(post["responses"]["201"]["content"]["application/json"]["schema"], json!({
+            "type": "object",
+            "properties": { "id": { "type": "integer" }, "tags": { "type": "array", "items": { "type": "string" } } }
+        }));
+
+        // The exported document can be imported again.
+        let imported = RockeryOpenApiImporter::import_str(&RockeryOpenApiExporter::export_yaml(&service, true).0).unwrap();
+        assert_eq!(imported.get_methods().len(), 2);
+        assert_eq!(imported.get_base_url(), "https://feiertage-api.de");
+        assert!(RockeryOpenApiImporter::import_str(&RockeryOpenApiExporter::export_json(&service, false).0).is_ok());
+    }
+
+    #[test]
+    fn test_export_duplicate_operation() {
+        let mut service = get_feiertage_service();
+        service.add_method("get feiertage again", "/api/");
+
+        let (document, warnings) = RockeryOpenApiExporter::export(&service, false);
+        assert_eq!(document["paths"].as_object().unwrap().len(), 1);
+        assert_eq!(document["paths"]["/api"]["get"]["operationId"], "get feiertage");
+        assert_eq!(warnings, vec!["Method get feiertage again is not exported, another method already describes GET /api.".to_owned()]);
+    }
+}
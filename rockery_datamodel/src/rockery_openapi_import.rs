@@ -0,0 +1,651 @@
+use std::str::FromStr;
+
+use serde_json::Value;
+
+use crate::prelude::*;
+use crate::rockery_definition::read_definition;
+use crate::rockery_yaml::parse_yaml;
+
+/// The http methods an operation can be defined for, in the order they are imported.
+const OPERATIONS : [&str; 8] = ["get", "put", "post", "delete", "options", "head", "patch", "trace"];
+
+/// Maximum depth when following references or generating examples from recursive schemas.
+const MAX_DEPTH : usize = 16;
+
+/// Creates rest services from OpenAPI 3 and Swagger 2 specifications, written either in json or yaml.
+pub struct RockeryOpenApiImporter;
+
+impl RockeryOpenApiImporter {
+    /// Read the specification from the given url or path and create a service from it.
+    pub fn import(location : &str) -> Result<RockeryService, String> {
+        let content = read_definition(location)?;
+        let mut service = Self::import_content(&content, Some(location))?;
+        service.set_definition_url(location);
+
+        Ok(service)
+    }
+
+    /// Create a service from the content of a specification file.
+    pub fn import_str(definition : &str) -> Result<RockeryService, String> {
+        Self::import_content(definition, None)
+    }
+
+    /// Create a service from the content of a specification file. Relative server urls are resolved
+    /// against the location the specification was read from.
+    pub(crate) fn import_content(definition : &str, location : Option<&str>) -> Result<RockeryService, String> {
+        Self::convert(&Self::parse(definition)?, location)
+    }
+
+    /// Parse a json or yaml document.
+    pub(crate) fn parse(definition : &str) -> Result<Value, String> {
+        if definition.trim_start().starts_with('{') {
+            return serde_json::from_str(definition).map_err(|e| format!("Could not parse specification. Error: {}", e));
+        }
+
+        parse_yaml(definition).map_err(|e| format!("Could not parse specification. Error: {}", e))
+    }
+
+    fn convert(root : &Value, location : Option<&str>) -> Result<RockeryService, String> {
+        let swagger = match (root.get("openapi"), root.get("swagger")) {
+            (Some(_), _) => false,
+            (None, Some(_)) => true,
+            _ => return Err("Not an OpenAPI or Swagger document. The 'openapi' or 'swagger' field is missing.".to_owned()),
+        };
+
+        let base_url = if swagger { swagger_base_url(root, location) } else { openapi_base_url(root, location) };
+        let mut service = RockeryService::new(RockeryServiceType::Rest, &base_url);
+        if let Some(title) = root.pointer("/info/title").and_then(Value::as_str) {
+            service.set_name(title);
+        }
+
+        if let Some(authentication) = service_authentication(root, swagger) {
+            service.set_authentication(authentication);
+        }
+
+        let paths = match root.get("paths").and_then(Value::as_object) {
+            Some(p) => p,
+            None => return Ok(service),
+        };
+
+        for (path, item) in paths {
+            let item = resolve(root, item);
+            for operation in OPERATIONS {
+                let op = match item.get(operation) {
+                    Some(op) => op,
+                    None => continue,
+                };
+
+                let mut params : Vec<RequestParameter> = Vec::new();
+                let declared = item.get("parameters").and_then(Value::as_array).into_iter().flatten()
+                    .chain(op.get("parameters").and_then(Value::as_array).into_iter().flatten());
+
+                let mut body = None;
+                let mut content_type = None;
+                for p in declared {
+                    let p = resolve(root, p);
+                    if swagger && p.get("in").and_then(Value::as_str) == Some("body") {
+                        body = p.get("schema").map(|s| body_text(&example_from_schema(root, s, 0)));
+                        continue;
+                    }
+
+                    if let Some(p) = convert_parameter(root, p, swagger) {
+                        match params.iter().position(|e| e.is_same_parameter(&p)) {
+                            Some(i) => params[i] = p,
+                            None => params.push(p),
+                        }
+                    }
+                }
+
+                if swagger {
+                    let consumes = op.get("consumes").or(root.get("consumes"))
+                        .and_then(Value::as_array)
+                        .and_then(|c| c.first())
+                        .and_then(Value::as_str);
+                    if body.is_some() || params.iter().any(|p| matches!(p, RequestParameter::Body(_, _))) {
+                        content_type = consumes.map(|c| c.to_owned());
+                    }
+                } else if let Some(request_body) = op.get("requestBody") {
+                    let (media, text, form) = convert_request_body(root, resolve(root, request_body));
+                    content_type = media;
+                    body = text;
+                    params.extend(form);
+                }
+
+                if let Some(content_type) = content_type {
+                    if !params.iter().any(|p| matches!(p, RequestParameter::Header(k, _) if k.eq_ignore_ascii_case("content-type"))) {
+                        params.push(RequestParameter::Header("Content-Type".to_owned(), Some(content_type)));
+                    }
+                }
+
+                let name = match op.get("operationId").and_then(Value::as_str) {
+                    Some(id) => id.to_owned(),
+                    None => format!("{} {}", operation.to_uppercase(), path),
+                };
+
+                let m = service.add_method(&name, path.trim_matches('/'));
+                m.set_request_method(RequestMethod::from_str(&operation.to_uppercase()).unwrap());
+                for p in params {
+                    m.add_parameter(p);
+                }
+
+                if let Some(body) = body {
+                    m.set_body(&body);
+                }
+
+                m.set_response_schemas(convert_responses(root, op, swagger));
+            }
+        }
+
+        Ok(service)
+    }
+}
+
+/// Follow local references (`#/components/...`) until a value without reference is found.
+pub(crate) fn resolve<'a>(root : &'a Value, value : &'a Value) -> &'a Value {
+    let mut value = value;
+    for _ in 0..MAX_DEPTH {
+        match value.get("$ref").and_then(Value::as_str) {
+            Some(r) if r.starts_with('#') => {
+                match root.pointer(&r[1..]) {
+                    Some(v) => value = v,
+                    None => return value,
+                }
+            },
+            _ => return value,
+        }
+    }
+
+    value
+}
+
+/// Replace all local references within a schema by the schema they refer to. Recursive schemas
+/// are cut off at `MAX_DEPTH` and accept any value from there on.
+fn inline_schema(root : &Value, schema : &Value, depth : usize) -> Value {
+    if depth > MAX_DEPTH {
+        return Value::Bool(true);
+    }
+
+    match resolve(root, schema) {
+        Value::Object(o) => Value::Object(o.iter()
+            .filter(|(k, _)| k.as_str() != "$ref")
+            .map(|(k, v)| (k.to_owned(), inline_schema(root, v, depth + 1)))
+            .collect()),
+        Value::Array(a) => Value::Array(a.iter().map(|v| inline_schema(root, v, depth + 1)).collect()),
+        v => v.clone(),
+    }
+}
+
+/// Collect the json schemas declared for the responses of an operation.
+fn convert_responses(root : &Value, op : &Value, swagger : bool) -> Vec<RockeryResponseSchema> {
+    let responses = match op.get("responses").and_then(Value::as_object) {
+        Some(r) => r,
+        None => return Vec::new(),
+    };
+
+    let mut schemas = Vec::new();
+    for (status, response) in responses {
+        let response = resolve(root, response);
+        if swagger {
+            let produces = op.get("produces").or(root.get("produces"))
+                .and_then(Value::as_array)
+                .and_then(|p| p.iter().filter_map(Value::as_str).find(|p| p.contains("json")));
+            if let Some(schema) = response.get("schema") {
+                schemas.push(RockeryResponseSchema::new(status, produces, RockerySchema::Json(inline_schema(root, schema, 0))));
+            }
+            continue;
+        }
+
+        let content = response.get("content").and_then(Value::as_object).into_iter().flatten();
+        for (media_type, media) in content.filter(|(m, _)| m.contains("json")) {
+            if let Some(schema) = media.get("schema") {
+                schemas.push(RockeryResponseSchema::new(status, Some(media_type), RockerySchema::Json(inline_schema(root, schema, 0))));
+            }
+        }
+    }
+
+    schemas
+}
+
+fn origin(location : Option<&str>) -> Option<String> {
+    let location = location?;
+    let (scheme, rest) = location.split_once("://")?;
+    if scheme != "http" && scheme != "https" {
+        return None;
+    }
+
+    Some(format!("{}://{}", scheme, rest.split('/').next().unwrap_or("")))
+}
+
+fn openapi_base_url(root : &Value, location : Option<&str>) -> String {
+    let server = root.pointer("/servers/0");
+    let mut url = server.and_then(|s| s.get("url")).and_then(Value::as_str).unwrap_or("").to_owned();
+
+    // Replace server variables with their default values.
+    if let Some(variables) = server.and_then(|s| s.get("variables")).and_then(Value::as_object) {
+        for (name, variable) in variables {
+            if let Some(default) = variable.get("default").and_then(value_to_string) {
+                url = url.replace(&format!("{{{}}}", name), &default);
+            }
+        }
+    }
+
+    if !url.contains("://") {
+        if let Some(origin) = origin(location) {
+            url = format!("{}/{}", origin, url.trim_start_matches('/'));
+        }
+    }
+
+    url.trim_end_matches('/').to_owned()
+}
+
+fn swagger_base_url(root : &Value, location : Option<&str>) -> String {
+    let base_path = root.get("basePath").and_then(Value::as_str).unwrap_or("").trim_matches('/');
+    let host = match root.get("host").and_then(Value::as_str) {
+        Some(host) => {
+            let scheme = root.pointer("/schemes/0").and_then(Value::as_str).unwrap_or("https");
+            format!("{}://{}", scheme, host)
+        },
+        None => origin(location).unwrap_or_default(),
+    };
+
+    if base_path.is_empty() {
+        return host;
+    }
+
+    format!("{}/{}", host, base_path)
+}
+
+/// Convert a scalar (or a list of scalars) into the textual representation used by request parameters.
+pub(crate) fn value_to_string(value : &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        Value::Array(items) => Some(items.iter().filter_map(value_to_string).collect::<Vec<String>>().join(",")),
+        v => Some(v.to_string()),
+    }
+}
+
+fn body_text(value : &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        v => serde_json::to_string_pretty(v).unwrap_or_default(),
+    }
+}
+
+/// Get an example for a parameter, either declared directly or taken from its schema.
+fn parameter_example(root : &Value, param : &Value, swagger : bool) -> Option<String> {
+    let mut candidates = vec![param.get("example")];
+    if swagger {
+        candidates.insert(0, param.get("default"));
+        candidates.push(param.get("x-example"));
+    } else {
+        let first_example = param.get("examples")
+            .and_then(Value::as_object)
+            .and_then(|e| e.values().next())
+            .and_then(|e| resolve(root, e).get("value"));
+        let schema = param.get("schema").map(|s| resolve(root, s));
+
+        candidates.push(first_example);
+        candidates.push(schema.and_then(|s| s.get("default")));
+        candidates.push(schema.and_then(|s| s.get("example")));
+    }
+
+    candidates.into_iter().flatten().find_map(value_to_string)
+}
+
+fn convert_parameter(root : &Value, param : &Value, swagger : bool) -> Option<RequestParameter> {
+    let name = param.get("name").and_then(Value::as_str)?.to_owned();
+    let value = parameter_example(root, param, swagger);
+
+    match param.get("in").and_then(Value::as_str)? {
+        "path" => Some(RequestParameter::Path(name, value)),
+        "query" => Some(RequestParameter::Url(name, value)),
+        "header" => Some(RequestParameter::Header(name, value)),
+        "formData" => Some(RequestParameter::Body(name, value)),
+        _ => None,
+    }
+}
+
+/// Convert an OpenAPI 3 request body. Returns the media type, the body text and, for form encoded
+/// bodies, the form fields as body parameters.
+fn convert_request_body(root : &Value, request_body : &Value) -> (Option<String>, Option<String>, Vec<RequestParameter>) {
+    let content = match request_body.get("content").and_then(Value::as_object) {
+        Some(c) if !c.is_empty() => c,
+        _ => return (None, None, Vec::new()),
+    };
+
+    let (media_type, media) = content.iter()
+        .find(|(k, _)| k.contains("json"))
+        .or(content.iter().next())
+        .unwrap();
+
+    if media_type.starts_with("application/x-www-form-urlencoded") || media_type.starts_with("multipart/form-data") {
+        let schema = media.get("schema").map(|s| resolve(root, s)).unwrap_or(&Value::Null);
+        let example = example_from_schema(root, schema, 0);
+        let fields = schema.get("properties").and_then(Value::as_object).into_iter().flatten()
+            .map(|(k, _)| RequestParameter::Body(k.to_owned(), example.get(k).and_then(value_to_string)))
+            .collect();
+
+        return (Some(media_type.to_owned()), None, fields);
+    }
+
+    let example = media.get("example").cloned()
+        .or(media.get("examples")
+            .and_then(Value::as_object)
+            .and_then(|e| e.values().next())
+            .and_then(|e| resolve(root, e).get("value").cloned()));
+
+    let text = match example {
+        Some(e) => Some(body_text(&e)),
+        // Examples generated from a schema are json, which does not make sense for other formats.
+        None if media_type.contains("json") => media.get("schema").map(|s| body_text(&example_from_schema(root, s, 0))),
+        None => None,
+    };
+
+    (Some(media_type.to_owned()), text, Vec::new())
+}
+
+/// Build an example value for a schema, preferring examples and defaults declared in the schema.
+pub(crate) fn example_from_schema(root : &Value, schema : &Value, depth : usize) -> Value {
+    let schema = resolve(root, schema);
+    if depth > MAX_DEPTH {
+        return Value::Null;
+    }
+
+    for key in ["example", "default", "const"] {
+        if let Some(v) = schema.get(key) {
+            return v.clone();
+        }
+    }
+
+    if let Some(v) = schema.get("enum").and_then(|e| e.get(0)) {
+        return v.clone();
+    }
+
+    if let Some(v) = schema.get("examples").and_then(|e| e.get(0)) {
+        return v.clone();
+    }
+
+    if let Some(all) = schema.get("allOf").and_then(Value::as_array) {
+        let mut merged = serde_json::Map::new();
+        for s in all {
+            if let Value::Object(o) = example_from_schema(root, s, depth + 1) {
+                merged.extend(o);
+            }
+        }
+        return Value::Object(merged);
+    }
+
+    for key in ["oneOf", "anyOf"] {
+        if let Some(first) = schema.get(key).and_then(|s| s.get(0)) {
+            return example_from_schema(root, first, depth + 1);
+        }
+    }
+
+    let schema_type = match schema.get("type") {
+        Some(Value::Array(types)) => types.iter().filter_map(Value::as_str).find(|t| *t != "null").unwrap_or("null"),
+        Some(Value::String(t)) => t.as_str(),
+        _ if schema.get("properties").is_some() => "object",
+        _ if schema.get("items").is_some() => "array",
+        _ => "",
+    };
+
+    match schema_type {
+        "object" => {
+            let properties = schema.get("properties").and_then(Value::as_object).into_iter().flatten()
+                .map(|(k, v)| (k.to_owned(), example_from_schema(root, v, depth + 1)))
+                .collect();
+            Value::Object(properties)
+        },
+        "array" => {
+            match schema.get("items") {
+                Some(items) => Value::Array(vec![example_from_schema(root, items, depth + 1)]),
+                None => Value::Array(Vec::new()),
+            }
+        },
+        "string" => {
+            let example = match schema.get("format").and_then(Value::as_str) {
+                Some("date") => "2024-01-01",
+                Some("date-time") => "2024-01-01T00:00:00Z",
+                Some("uuid") => "00000000-0000-0000-0000-000000000000",
+                Some("email") => "user@example.com",
+                Some("uri") => "https://example.com",
+                _ => "string",
+            };
+            Value::String(example.to_owned())
+        },
+        "integer" | "number" => Value::Number(0.into()),
+        "boolean" => Value::Bool(true),
+        _ => Value::Null,
+    }
+}
+
+fn convert_security_scheme(scheme : &Value) -> Option<AuthenticationType> {
+    let scheme_type = scheme.get("type").and_then(Value::as_str)?;
+    match scheme_type {
+        "basic" => Some(AuthenticationType::Basic(BasicAuthenticationAuthentication::new("", ""))),
+        "http" => {
+            match scheme.get("scheme").and_then(Value::as_str).map(|s| s.to_lowercase()).as_deref() {
+                Some("basic") => Some(AuthenticationType::Basic(BasicAuthenticationAuthentication::new("", ""))),
+                Some("bearer") => Some(AuthenticationType::Bearer(BearerAuthenticationAuthentication::new(""))),
+                _ => None,
+            }
+        },
+        "apiKey" => {
+            let name = scheme.get("name").and_then(Value::as_str)?;
+            let location = match scheme.get("in").and_then(Value::as_str)? {
+                "header" => ApiKeyLocation::Header,
+                "query" => ApiKeyLocation::Query,
+                _ => return None,
+            };
+            Some(AuthenticationType::ApiKey(ApiKeyAuthenticationAuthentication::new(name, "", location)))
+        },
+        // Tokens of OAuth2 or OpenID Connect flows are sent as bearer token.
+        "oauth2" | "openIdConnect" => Some(AuthenticationType::Bearer(BearerAuthenticationAuthentication::new(""))),
+        _ => None,
+    }
+}
+
+/// A service only has one authentication. The scheme required by the whole api is used, and if there
+/// is none, the scheme of the first operation that requires one.
+fn service_authentication(root : &Value, swagger : bool) -> Option<AuthenticationType> {
+    let schemes = if swagger { root.get("securityDefinitions") } else { root.pointer("/components/securitySchemes") };
+    let schemes = schemes.and_then(Value::as_object)?;
+
+    let operations = root.get("paths").and_then(Value::as_object).into_iter().flatten()
+        .flat_map(|(_, item)| OPERATIONS.iter().filter_map(move |o| item.get(*o)));
+    let requirements = root.get("security").into_iter()
+        .chain(operations.filter_map(|o| o.get("security")))
+        .filter_map(Value::as_array)
+        .flatten()
+        .filter_map(Value::as_object);
+
+    for requirement in requirements {
+        for name in requirement.keys() {
+            if let Some(auth) = schemes.get(name).map(|s| resolve(root, s)).and_then(convert_security_scheme) {
+                return Some(auth);
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod rockery_openapi_import_tests {
+    use super::*;
+
+    const OPENAPI : &str = r##"
+openapi: 3.0.3
+info:
+  title: Pet Store
+  version: 1.0.0
+servers:
+  - url: https://{environment}.example.com/v1/
+    variables:
+      environment:
+        default: api
+security:
+  - token: []
+paths:
+  /pets/{petId}:
+    parameters:
+      - $ref: '#/components/parameters/PetId'
+    get:
+      operationId: getPet
+      parameters:
+        - name: X-Trace
+          in: header
+          example: abc
+        - name: details
+          in: query
+          schema:
+            type: boolean
+            default: false
+      responses:
+        '200':
+          description: The pet
+          content:
+            application/json:
+              schema:
+                $ref: '#/components/schemas/Pet'
+    put:
+      operationId: updatePet
+      requestBody:
+        content:
+          application/json:
+            schema:
+              $ref: '#/components/schemas/Pet'
+  /pets:
+    post:
+      requestBody:
+        content:
+          application/x-www-form-urlencoded:
+            schema:
+              type: object
+              properties:
+                name:
+                  type: string
+                  example: Rex
+components:
+  parameters:
+    PetId:
+      name: petId
+      in: path
+      required: true
+      schema:
+        type: integer
+        example: 42
+  schemas:
+    Pet:
+      type: object
+      properties:
+        name:
+          type: string
+        tags:
+          type: array
+          items:
+            type: string
+            enum: [dog, cat]
+  securitySchemes:
+    token:
+      type: http
+      scheme: bearer
+"##;
+
+    const SWAGGER : &str = r##"{
+        "swagger": "2.0",
+        "info": { "title": "Legacy" },
+        "host": "legacy.example.com",
+        "basePath": "/api",
+        "schemes": ["http"],
+        "consumes": ["application/json"],
+        "securityDefinitions": { "key": { "type": "apiKey", "name": "api_key", "in": "query" } },
+        "paths": {
+            "/orders": {
+                "post": {
+                    "operationId": "createOrder",
+                    "security": [{ "key": [] }],
+                    "parameters": [
+                        { "name": "body", "in": "body", "schema": { "$ref": "#/definitions/Order" } },
+                        { "name": "dry_run", "in": "query", "type": "boolean", "default": true }
+                    ]
+                }
+            }
+        },
+        "definitions": { "Order": { "type": "object", "properties": { "amount": { "type": "integer", "example": 3 } } } }
+    }"##;
+
+    #[test]
+    fn test_openapi_import() {
+        let service = RockeryOpenApiImporter::import_str(OPENAPI).unwrap();
+        assert_eq!(service.get_name(), "Pet Store");
+        assert_eq!(service.get_base_url(), "https://api.example.com/v1");
+        assert!(matches!(service.get_authentication(), AuthenticationType::Bearer(_)));
+
+        let methods = service.get_methods();
+        assert_eq!(methods.len(), 3);
+
+        assert_eq!(methods[0].get_name(), "POST /pets");
+        assert_eq!(methods[0].get_request_method(), RequestMethod::Post);
+        assert_eq!(methods[0].get_parameter(), &vec![
+            RequestParameter::Body("name".to_owned(), Some("Rex".to_owned())),
+            RequestParameter::Header("Content-Type".to_owned(), Some("application/x-www-form-urlencoded".to_owned())),
+        ]);
+
+        assert_eq!(methods[1].get_name(), "getPet");
+        assert_eq!(methods[1].get_uri(), "pets/{petId}");
+        assert_eq!(methods[1].get_parameter(), &vec![
+            RequestParameter::Path("petId".to_owned(), Some("42".to_owned())),
+            RequestParameter::Header("X-Trace".to_owned(), Some("abc".to_owned())),
+            RequestParameter::Url("details".to_owned(), Some("false".to_owned())),
+        ]);
+        assert_eq!(service.get_request_url(1, &Vec::new()), "https://api.example.com/v1/pets/42/?details=false");
+
+        let schemas = methods[1].get_response_schemas();
+        assert_eq!(schemas.len(), 1);
+        assert_eq!(schemas[0].get_status(), "200");
+        assert_eq!(schemas[0].get_schema(), &RockerySchema::Json(serde_json::json!({
+            "type": "object",
+            "properties": { "name": { "type": "string" }, "tags": { "type": "array", "items": { "type": "string", "enum": ["dog", "cat"] } } }
+        })));
+
+        assert_eq!(methods[2].get_name(), "updatePet");
+        assert_eq!(methods[2].get_request_method(), RequestMethod::Put);
+        let body : Value = serde_json::from_str(methods[2].get_body().as_ref().unwrap()).unwrap();
+        assert_eq!(body, serde_json::json!({ "name": "string", "tags": ["dog"] }));
+    }
+
+    #[test]
+    fn test_swagger_import() {
+        let service = RockeryOpenApiImporter::import_str(SWAGGER).unwrap();
+        assert_eq!(service.get_name(), "Legacy");
+        assert_eq!(service.get_base_url(), "http://legacy.example.com/api");
+        match service.get_authentication() {
+            AuthenticationType::ApiKey(key) => {
+                assert_eq!(key.get_name(), "api_key");
+                assert_eq!(key.get_location(), ApiKeyLocation::Query);
+            },
+            a => panic!("Unexpected authentication {:?}", a),
+        }
+
+        let methods = service.get_methods();
+        assert_eq!(methods.len(), 1);
+        assert_eq!(methods[0].get_name(), "createOrder");
+        assert_eq!(methods[0].get_parameter(), &vec![
+            RequestParameter::Url("dry_run".to_owned(), Some("true".to_owned())),
+            RequestParameter::Header("Content-Type".to_owned(), Some("application/json".to_owned())),
+        ]);
+
+        let body : Value = serde_json::from_str(methods[0].get_body().as_ref().unwrap()).unwrap();
+        assert_eq!(body, serde_json::json!({ "amount": 3 }));
+    }
+
+    #[test]
+    fn test_invalid_document() {
+        assert!(RockeryOpenApiImporter::import_str("{ \"info\": {} }").is_err());
+        assert!(RockeryOpenApiImporter::import_str("{ invalid").is_err());
+        assert!(RockeryOpenApiImporter::import_str("openapi: 3.0.0\ninfo: { ]\n").is_err());
+        assert!(RockeryOpenApiImporter::import_str("openapi: 3.0.0\ninfo: [ }\n").is_err());
+    }
+}
@@ -0,0 +1,305 @@
+use regex::{Captures, Regex};
+use serde_json::{json, Value};
+
+use crate::prelude::*;
+
+const SCHEMA : &str = "https://schema.getpostman.com/json/collection/v2.1.0/collection.json";
+
+/// Writes a project as Postman v2.1 collection.
+///
+/// Every service becomes a folder with the authentication of the service, every method a request. Parameters
+/// without default value are exported disabled, recorded responses become examples. Variables `${name}`
+/// become `{{name}}`. Everything that has no counterpart in a collection, e.g. assertions or test suites,
+/// is reported as warning.
+pub struct RockeryPostmanExporter;
+
+impl RockeryPostmanExporter {
+    /// Create the collection for a project. Returns the collection and the warnings for everything that
+    /// could not be exported.
+    pub fn export(project : &RockeryProject) -> (Value, Vec<String>) {
+        let mut warnings = Vec::new();
+        let mut folders = Vec::new();
+
+        for id in project.get_service_ids() {
+            let service = project.find_service(&id).unwrap();
+            let items : Vec<Value> = service.get_methods().iter()
+                .map(|m| export_request(service, m, &mut warnings))
+                .collect();
+
+            folders.push(json!({
+                "name": id,
+                "item": items,
+                "auth": export_authentication(service.get_authentication()),
+            }));
+        }
+
+        let mut names : Vec<&String> = project.get_variables().keys().collect();
+        names.sort();
+        let variables : Vec<Value> = names.into_iter()
+            .map(|k| json!({ "key": k, "value": convert_variables(&project.get_variables()[k]) }))
+            .collect();
+
+        for suite in project.get_test_suites() {
+            warnings.push(format!("Test suite {} is not exported, Postman collections do not contain test suites.", suite.get_name()));
+        }
+        for environment in project.get_environments() {
+            warnings.push(format!("Environment {} is not exported, Postman environments are separate files.", environment.get_name()));
+        }
+
+        let collection = json!({
+            "info": {
+                "name": project.get_name(),
+                "schema": SCHEMA,
+            },
+            "item": folders,
+            "variable": variables,
+        });
+
+        (collection, warnings)
+    }
+
+    pub fn export_json(project : &RockeryProject) -> (String, Vec<String>) {
+        let (collection, warnings) = Self::export(project);
+        (serde_json::to_string_pretty(&collection).unwrap_or_default(), warnings)
+    }
+}
+
+fn export_request(service : &RockeryService, method : &RockeryServiceMethod, warnings : &mut Vec<String>) -> Value {
+    let parameters = method.get_parameter();
+    let value = |v : &Option<String>| convert_variables(v.as_deref().unwrap_or_default());
+    let entry = |k : &str, v : &Option<String>| {
+        let mut e = json!({ "key": k, "value": value(v) });
+        if v.is_none() {
+            e["disabled"] = json!(true);
+        }
+        e
+    };
+
+    // Placeholders `{name}` are written as `:name` by Postman.
+    let mut path = Vec::new();
+    let mut variables = Vec::new();
+    for segment in method.get_uri().split('/').filter(|s| !s.is_empty()) {
+        match segment.strip_prefix('{').and_then(|s| s.strip_suffix('}')) {
+            Some(name) => {
+                let default = parameters.iter().find_map(|p| match p {
+                    RequestParameter::Path(k, v) if k == name => Some(v.clone()),
+                    _ => None,
+                }).flatten();
+                path.push(format!(":{}", name));
+                variables.push(json!({ "key": name, "value": value(&default) }));
+            },
+            None => path.push(convert_variables(segment)),
+        }
+    }
+
+    let query : Vec<Value> = parameters.iter().filter_map(|p| match p {
+        RequestParameter::Url(k, v) => Some(entry(k, v)),
+        _ => None,
+    }).collect();
+    let headers : Vec<Value> = parameters.iter().filter_map(|p| match p {
+        RequestParameter::Header(k, v) => Some(entry(k, v)),
+        _ => None,
+    }).collect();
+    let fields : Vec<Value> = parameters.iter().filter_map(|p| match p {
+        RequestParameter::Body(k, v) => Some(entry(k, v)),
+        _ => None,
+    }).collect();
+
+    let host = convert_variables(service.get_base_url().trim_end_matches('/'));
+    let mut raw = format!("{}/{}", host, path.join("/"));
+    let enabled : Vec<String> = parameters.iter().filter_map(|p| match p {
+        RequestParameter::Url(k, Some(v)) => Some(format!("{}={}", k, convert_variables(v))),
+        _ => None,
+    }).collect();
+    if !enabled.is_empty() {
+        raw = format!("{}?{}", raw, enabled.join("&"));
+    }
+
+    let mut url = json!({ "raw": raw, "host": [host], "path": path });
+    if !query.is_empty() {
+        url["query"] = json!(query);
+    }
+    if !variables.is_empty() {
+        url["variable"] = json!(variables);
+    }
+
+    let mut request = json!({
+        "method": method.get_request_method().to_string(),
+        "header": headers,
+        "url": url,
+    });
+
+    match method.get_body() {
+        Some(body) => {
+            let content_type = parameters.iter().find_map(|p| match p {
+                RequestParameter::Header(k, Some(v)) if k.eq_ignore_ascii_case("Content-Type") => Some(v.to_lowercase()),
+                _ => None,
+            }).unwrap_or_default();
+            let trimmed = body.trim_start();
+            let language = if content_type.contains("json") || (content_type.is_empty() && (trimmed.starts_with('{') || trimmed.starts_with('['))) {
+                "json"
+            } else if content_type.contains("xml") || (content_type.is_empty() && trimmed.starts_with('<')) {
+                "xml"
+            } else {
+                "text"
+            };
+
+            request["body"] = json!({ "mode": "raw", "raw": convert_variables(body), "options": { "raw": { "language": language } } });
+            if !fields.is_empty() {
+                warnings.push(format!("Body parameters of method {} of service {} are not exported, because the method has a body.", method.get_name(), service.get_name()));
+            }
+        },
+        None if !fields.is_empty() => request["body"] = json!({ "mode": "urlencoded", "urlencoded": fields }),
+        None => { },
+    }
+
+    let responses : Vec<Value> = method.get_responses().iter().map(|r| json!({
+        "name": format!("{} {}", r.get_status(), r.get_status_text()),
+        "code": r.get_status(),
+        "status": r.get_status_text(),
+        "header": r.get_headers().iter().map(|(k, v)| json!({ "key": k, "value": v })).collect::<Vec<Value>>(),
+        "body": r.get_body(),
+    })).collect();
+
+    let unsupported = [
+        (method.get_assertions().len(), "assertions"),
+        (method.get_response_schemas().len(), "response schemas"),
+        (method.get_mock_responses().len(), "mock responses"),
+    ];
+    for (count, what) in unsupported {
+        if count > 0 {
+            warnings.push(format!("The {} {} of method {} of service {} are not exported.", count, what, method.get_name(), service.get_name()));
+        }
+    }
+
+    json!({
+        "name": method.get_name(),
+        "request": request,
+        "response": responses,
+    })
+}
+
+fn export_authentication(authentication : &AuthenticationType) -> Value {
+    let values = |values : &[(&str, &str)]| -> Vec<Value> {
+        values.iter().map(|(k, v)| json!({ "key": k, "value": convert_variables(v), "type": "string" })).collect()
+    };
+
+    match authentication {
+        AuthenticationType::None(_) => json!({ "type": "noauth" }),
+        AuthenticationType::Basic(b) => json!({ "type": "basic", "basic": values(&[("username", b.get_username()), ("password", b.get_password())]) }),
+        AuthenticationType::Bearer(b) => json!({ "type": "bearer", "bearer": values(&[("token", b.get_token())]) }),
+        AuthenticationType::ApiKey(a) => {
+            let location = match a.get_location() {
+                ApiKeyLocation::Header => "header",
+                ApiKeyLocation::Query => "query",
+            };
+            json!({ "type": "apikey", "apikey": values(&[("key", a.get_name()), ("value", a.get_key()), ("in", location)]) })
+        },
+    }
+}
+
+/// Replace variables `${name}` by `{{name}}`.
+fn convert_variables(text : &str) -> String {
+    let regex = Regex::new(r"\$\{([^{}\s]+)\}").unwrap();
+    regex.replace_all(text, |c : &Captures<'_>| format!("{{{{{}}}}}", &c[1])).to_string()
+}
+
+#[cfg(test)]
+mod rockery_postman_export_tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_export() {
+        let mut project = RockeryProject::new("Shop");
+        project.set_variable("host", "https://shop.example.com");
+        project.add_environment(RockeryEnvironment::new("Staging"));
+        project.add_test_suite(RockeryTestSuite::new("Smoke"));
+
+        let mut service = RockeryService::new(RockeryServiceType::Rest, "${host}");
+        service.set_name("Users");
+        service.set_authentication(AuthenticationType::ApiKey(ApiKeyAuthenticationAuthentication::new("api_key", "${key}", ApiKeyLocation::Query)));
+        let m = service.add_method("get user", "users/{id}");
+        m.add_parameter(RequestParameter::Path("id".to_owned(), Some("7".to_owned())));
+        m.add_parameter(RequestParameter::Url("lang".to_owned(), Some("de".to_owned())));
+        m.add_parameter(RequestParameter::Url("page".to_owned(), None));
+        m.add_parameter(RequestParameter::Header("Accept".to_owned(), Some("application/json".to_owned())));
+        m.add_response(RockeryResponse::new(200, "OK", &ehttp::Headers::new(&[("Content-Type", "application/json")]), br#"{"id":7}"#, Duration::from_millis(5)));
+        m.add_assertion(RockeryAssertion::StatusCode(200));
+
+        let m = service.add_method("create user", "users");
+        m.set_request_method(RequestMethod::Post);
+        m.set_body(r#"{"name": "${name}"}"#);
+        m.add_parameter(RequestParameter::Body("ignored".to_owned(), None));
+
+        let m = service.add_method("login", "login");
+        m.set_request_method(RequestMethod::Post);
+        m.add_parameter(RequestParameter::Body("user".to_owned(), Some("rex".to_owned())));
+        project.add_service(service);
+
+        let (collection, warnings) = RockeryPostmanExporter::export(&project);
+        assert_eq!(collection["info"]["schema"], SCHEMA);
+        assert_eq!(collection["variable"], json!([{ "key": "host", "value": "https://shop.example.com" }]));
+
+        let folder = &collection["item"][0];
+        assert_eq!(folder["name"], "Users");
+        assert_eq!(folder["auth"]["apikey"][1], json!({ "key": "value", "value": "{{key}}", "type": "string" }));
+
+        let request = &folder["item"][0]["request"];
+        assert_eq!(request["method"], "GET");
+        assert_eq!(request["url"]["raw"], "{{host}}/users/:id?lang=de");
+        assert_eq!(request["url"]["query"][1], json!({ "key": "page", "value": "", "disabled": true }));
+        assert_eq!(request["url"]["variable"], json!([{ "key": "id", "value": "7" }]));
+        assert_eq!(request["header"], json!([{ "key": "Accept", "value": "application/json" }]));
+        assert_eq!(folder["item"][0]["response"][0]["code"], 200);
+        assert_eq!(folder["item"][0]["response"][0]["body"], r#"{"id":7}"#);
+
+        assert_eq!(folder["item"][1]["request"]["body"]["raw"], r#"{"name": "{{name}}"}"#);
+        assert_eq!(folder["item"][1]["request"]["body"]["options"]["raw"]["language"], "json");
+        assert_eq!(folder["item"][2]["request"]["body"]["urlencoded"], json!([{ "key": "user", "value": "rex" }]));
+
+        assert_eq!(warnings.len(), 4);
+        assert!(warnings.iter().any(|w| w.contains("1 assertions of method get user")));
+        assert!(warnings.iter().any(|w| w.contains("Body parameters of method create user")));
+        assert!(warnings.iter().any(|w| w.contains("Test suite Smoke")));
+        assert!(warnings.iter().any(|w| w.contains("Environment Staging")));
+
+        // The collection can be imported again.
+        let (json, _) = RockeryPostmanExporter::export_json(&project);
+        let imported = RockeryPostmanImporter::import_str(&json).unwrap();
+        assert_eq!(imported.get_variables(), project.get_variables());
+        let users = imported.find_service("Users").unwrap();
+        assert_eq!(users.get_base_url(), "${host}");
+        assert!(matches!(users.get_authentication(), AuthenticationType::ApiKey(a) if a.get_key() == "${key}"));
+        assert_eq!(users.get_methods()[0].get_uri(), "users/{id}");
+        assert_eq!(users.get_methods()[0].get_parameter(), project.find_service("Users").unwrap().get_methods()[0].get_parameter());
+        assert_eq!(users.get_methods()[1].get_body(), &Some(r#"{"name": "${name}"}"#.to_owned()));
+    }
+
+    #[test]
+    fn test_export_malformed_input() {
+        let (collection, warnings) = RockeryPostmanExporter::export(&RockeryProject::new("Empty"));
+        assert_eq!(collection["item"], json!([]));
+        assert_eq!(collection["variable"], json!([]));
+        assert!(warnings.is_empty());
+
+        // Unclosed placeholders and variables are exported as they are and do not break the collection.
+        let mut project = RockeryProject::new("Broken");
+        let mut service = RockeryService::new(RockeryServiceType::Rest, "https://example.com//");
+        let m = service.add_method("unclosed", "//items/{id/${page//");
+        m.add_parameter(RequestParameter::Header("X-Quote".to_owned(), Some("\"${x".to_owned())));
+        m.set_body("<not xml");
+        project.add_service(service);
+
+        let (json, warnings) = RockeryPostmanExporter::export_json(&project);
+        assert!(warnings.is_empty());
+        let collection : Value = serde_json::from_str(&json).unwrap();
+        let request = &collection["item"][0]["item"][0]["request"];
+        assert_eq!(request["url"]["raw"], "https://example.com/items/{id/${page");
+        assert_eq!(request["url"].get("variable"), None);
+        assert_eq!(request["header"], json!([{ "key": "X-Quote", "value": "\"${x" }]));
+        assert_eq!(request["body"]["options"]["raw"]["language"], "xml");
+        assert!(RockeryPostmanImporter::import_str(&json).is_ok());
+    }
+}
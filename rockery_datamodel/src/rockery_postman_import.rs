@@ -0,0 +1,501 @@
+use std::str::FromStr;
+
+use regex::{Captures, Regex};
+use serde_json::Value;
+
+use crate::prelude::*;
+use crate::rockery_definition::read_definition;
+use crate::rockery_http::{parse_query, split_origin};
+
+/// Creates projects from Postman collections in the formats v2.0 and v2.1.
+///
+/// Every top level folder becomes a service, requests outside of folders are added to a service named
+/// after the collection. Requests of nested folders are added to the service of their top level folder,
+/// their names are prefixed with the path of the folder. Requests with another origin than the first
+/// request of a folder are added to another service of the same name.
+///
+/// The authentication of a folder or the collection becomes the authentication of the service. A request
+/// with its own authentication gets the header or query parameter the authentication would add.
+/// Form data is sent url encoded, files are skipped. Postman variables `{{name}}` become `${name}`.
+pub struct RockeryPostmanImporter;
+
+/// A service that is created for a top level folder, before it is added to the project.
+pub(crate) struct ImportedService {
+    folder : String,
+    origin : String,
+    service : RockeryService,
+}
+
+impl ImportedService {
+    pub(crate) fn into_service(self) -> RockeryService { self.service }
+}
+
+impl RockeryPostmanImporter {
+    /// Read the collection from the given url or path and create a project from it.
+    pub fn import(location : &str) -> Result<RockeryProject, String> {
+        Self::import_str(&read_definition(location)?)
+    }
+
+    /// Create a project from the content of a collection file.
+    pub fn import_str(collection : &str) -> Result<RockeryProject, String> {
+        let root : Value = serde_json::from_str(collection).map_err(|e| format!("Could not parse collection. Error: {}", e))?;
+        let schema = root.pointer("/info/schema").and_then(Value::as_str).unwrap_or_default();
+        if !schema.contains("schema.getpostman.com") || root.get("item").is_none() {
+            return Err("Not a Postman collection. The 'info.schema' or 'item' field is missing.".to_owned());
+        }
+
+        if !schema.contains("v2.0") && !schema.contains("v2.1") {
+            return Err(format!("Unsupported Postman collection format {}. Only v2.0 and v2.1 can be imported.", schema));
+        }
+
+        let name = root.pointer("/info/name").and_then(Value::as_str).unwrap_or("Postman");
+        let mut project = RockeryProject::new(name);
+        for variable in root.get("variable").and_then(Value::as_array).into_iter().flatten() {
+            if let Some(key) = variable.get("key").and_then(Value::as_str) {
+                project.set_variable(key, &convert_variables(&text(variable.get("value"))));
+            }
+        }
+
+        let collection_auth = root.get("auth");
+        let mut services : Vec<ImportedService> = Vec::new();
+        for item in root.get("item").and_then(Value::as_array).into_iter().flatten() {
+            if item.get("item").is_some() {
+                let folder = item.get("name").and_then(Value::as_str).unwrap_or("Folder");
+                let auth = item.get("auth").or(collection_auth);
+                add_items(&mut services, folder, "", item, auth, auth)?;
+            } else {
+                add_request(&mut services, name, "", item, collection_auth, collection_auth)?;
+            }
+        }
+
+        for s in services {
+            project.add_service(s.service);
+        }
+
+        Ok(project)
+    }
+}
+
+/// Add all requests of a folder and its sub folders.
+fn add_items(services : &mut Vec<ImportedService>, folder : &str, prefix : &str, item : &Value, service_auth : Option<&Value>, auth : Option<&Value>) -> Result<(), String> {
+    for child in item.get("item").and_then(Value::as_array).into_iter().flatten() {
+        let name = child.get("name").and_then(Value::as_str).unwrap_or("Unnamed");
+        if child.get("item").is_some() {
+            let prefix = format!("{}{}/", prefix, name);
+            add_items(services, folder, &prefix, child, service_auth, child.get("auth").or(auth))?;
+        } else {
+            add_request(services, folder, prefix, child, service_auth, auth)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn add_request(services : &mut Vec<ImportedService>, folder : &str, prefix : &str, item : &Value, service_auth : Option<&Value>, auth : Option<&Value>) -> Result<(), String> {
+    let name = format!("{}{}", prefix, item.get("name").and_then(Value::as_str).unwrap_or("Unnamed"));
+    let request = match item.get("request") {
+        Some(r) => r,
+        None => return Ok(()),
+    };
+
+    // A v2.0 request may be just the url.
+    let (request_method, url) = match request {
+        Value::String(url) => ("GET", Value::String(url.to_owned())),
+        r => (r.get("method").and_then(Value::as_str).unwrap_or("GET"), r.get("url").cloned().unwrap_or(Value::Null)),
+    };
+    let request_method = RequestMethod::from_str(&request_method.to_uppercase())
+        .map_err(|_| format!("Unknown request method {} of request {}.", request_method, name))?;
+
+    let raw = match &url {
+        Value::String(s) => s.to_owned(),
+        Value::Object(_) => url.get("raw").and_then(Value::as_str).map(str::to_owned).unwrap_or_else(|| build_url(&url)),
+        _ => String::new(),
+    };
+    let raw = convert_variables(&raw);
+    let (origin, path, query) = split_origin(&raw);
+
+    let mut method = RockeryServiceMethod::new(&name, "");
+    method.set_request_method(request_method);
+
+    // Path variables are written as `:name` by Postman.
+    let path_values : Vec<(String, String)> = url.get("variable").and_then(Value::as_array).into_iter().flatten()
+        .filter_map(|v| v.get("key").and_then(Value::as_str).map(|k| (k.to_owned(), convert_variables(&text(v.get("value"))))))
+        .collect();
+    let mut segments = Vec::new();
+    for segment in path.split('/').filter(|s| !s.is_empty()) {
+        match segment.strip_prefix(':') {
+            Some(variable) => {
+                let value = path_values.iter().find(|(k, _)| k == variable).map(|(_, v)| v.to_owned()).filter(|v| !v.is_empty());
+                method.add_parameter(RequestParameter::Path(variable.to_owned(), value));
+                segments.push(format!("{{{}}}", variable));
+            },
+            None => segments.push(segment.to_owned()),
+        }
+    }
+    method.set_uri(&segments.join("/"));
+
+    // The query of a url object contains disabled parameters as well, which are not sent by default.
+    match url.get("query").and_then(Value::as_array) {
+        Some(query) => {
+            for q in query {
+                if let Some(key) = q.get("key").and_then(Value::as_str) {
+                    method.add_parameter(RequestParameter::Url(key.to_owned(), enabled_value(q)));
+                }
+            }
+        },
+        None => {
+            for (k, v) in parse_query(query) {
+                method.add_parameter(RequestParameter::Url(k, Some(v)));
+            }
+        },
+    }
+
+    match request.get("header") {
+        Some(Value::Array(headers)) => {
+            for h in headers {
+                if let Some(key) = h.get("key").and_then(Value::as_str) {
+                    method.add_parameter(RequestParameter::Header(key.to_owned(), enabled_value(h)));
+                }
+            }
+        },
+        // v2.0 allows headers in the raw format `Name: value`.
+        Some(Value::String(headers)) => {
+            for (k, v) in headers.lines().filter_map(|l| l.split_once(':')) {
+                method.add_parameter(RequestParameter::Header(k.trim().to_owned(), Some(convert_variables(v.trim()))));
+            }
+        },
+        _ => { },
+    }
+
+    if let Some(body) = request.get("body") {
+        add_body(&mut method, body);
+    }
+
+    // The authentication of the request itself, unless it is the same as the one of the service.
+    let request_auth = request.get("auth").or(auth);
+    if let (Some(a), false) = (request_auth, request_auth == service_auth) {
+        if let Some(authentication) = authentication(a) {
+            add_authentication_parameters(&mut method, &authentication);
+        }
+    }
+
+    add_imported_method(services, folder, origin, method, service_auth.and_then(authentication));
+
+    Ok(())
+}
+
+/// Add a method to the service of its folder and origin. The service is created with the given
+/// authentication if there is none yet.
+pub(crate) fn add_imported_method(services : &mut Vec<ImportedService>, folder : &str, origin : &str, method : RockeryServiceMethod, authentication : Option<AuthenticationType>) {
+    let index = match services.iter().position(|s| s.folder == folder && s.origin == origin) {
+        Some(i) => i,
+        None => {
+            let mut service = RockeryService::new(RockeryServiceType::Rest, origin.trim_end_matches('/'));
+            service.set_name(folder);
+            if let Some(authentication) = authentication {
+                service.set_authentication(authentication);
+            }
+            services.push(ImportedService { folder : folder.to_owned(), origin : origin.to_owned(), service });
+            services.len() - 1
+        },
+    };
+    services[index].service.add_service_method(method);
+}
+
+/// Build the url of a url object without `raw` field.
+fn build_url(url : &Value) -> String {
+    let join = |field : &str, separator : &str| match url.get(field) {
+        Some(Value::Array(parts)) => parts.iter().map(|p| text(Some(p))).collect::<Vec<String>>().join(separator),
+        Some(v) => text(Some(v)),
+        None => String::new(),
+    };
+
+    let mut result = String::new();
+    if let Some(protocol) = url.get("protocol").and_then(Value::as_str) {
+        result.push_str(&format!("{}://", protocol));
+    }
+    result.push_str(&join("host", "."));
+    if let Some(port) = url.get("port").and_then(Value::as_str) {
+        result.push_str(&format!(":{}", port));
+    }
+    let path = join("path", "/");
+    if !path.is_empty() {
+        result.push('/');
+        result.push_str(&path);
+    }
+
+    result
+}
+
+fn add_body(method : &mut RockeryServiceMethod, body : &Value) {
+    let content_type = match body.get("mode").and_then(Value::as_str) {
+        Some("raw") => {
+            let raw = convert_variables(&text(body.get("raw")));
+            if raw.is_empty() {
+                return;
+            }
+            method.set_body(&raw);
+
+            match body.pointer("/options/raw/language").and_then(Value::as_str) {
+                Some("json") => "application/json",
+                Some("xml") => "application/xml",
+                _ => return,
+            }
+        },
+        Some(mode @ ("urlencoded" | "formdata")) => {
+            for field in body.get(mode).and_then(Value::as_array).into_iter().flatten() {
+                if field.get("type").and_then(Value::as_str) == Some("file") {
+                    continue;
+                }
+                if let Some(key) = field.get("key").and_then(Value::as_str) {
+                    method.add_parameter(RequestParameter::Body(key.to_owned(), enabled_value(field)));
+                }
+            }
+            "application/x-www-form-urlencoded"
+        },
+        _ => return,
+    };
+
+    if !method.get_parameter().iter().any(|p| matches!(p, RequestParameter::Header(k, _) if k.eq_ignore_ascii_case("Content-Type"))) {
+        method.add_parameter(RequestParameter::Header("Content-Type".to_owned(), Some(content_type.to_owned())));
+    }
+}
+
+/// Add the headers or query parameters an authentication sets to a method.
+pub(crate) fn add_authentication_parameters(method : &mut RockeryServiceMethod, authentication : &AuthenticationType) {
+    let mut request = ehttp::Request::get("");
+    request.headers = ehttp::Headers::new(&[]);
+    authentication.apply(&mut request);
+
+    for (k, v) in request.headers.headers {
+        method.add_parameter(RequestParameter::Header(k, Some(v)));
+    }
+    for (k, v) in parse_query(request.url.trim_start_matches('?')) {
+        method.add_parameter(RequestParameter::Url(k, Some(v)));
+    }
+}
+
+/// Map a Postman authentication onto the supported authentication types. Others are skipped.
+fn authentication(auth : &Value) -> Option<AuthenticationType> {
+    let kind = auth.get("type").and_then(Value::as_str)?;
+    let value = |name : &str| auth_value(auth, kind, name);
+
+    match kind {
+        "noauth" => Some(AuthenticationType::None(NoAuthenticationAuthentication {})),
+        "basic" => Some(AuthenticationType::Basic(BasicAuthenticationAuthentication::new(&value("username"), &value("password")))),
+        "bearer" => Some(AuthenticationType::Bearer(BearerAuthenticationAuthentication::new(&value("token")))),
+        "apikey" => {
+            let location = if value("in") == "query" { ApiKeyLocation::Query } else { ApiKeyLocation::Header };
+            let name = value("key");
+            let name = if name.is_empty() { "X-API-Key".to_owned() } else { name };
+            Some(AuthenticationType::ApiKey(ApiKeyAuthenticationAuthentication::new(&name, &value("value"), location)))
+        },
+        _ => None,
+    }
+}
+
+/// Values of an authentication are a list of key value pairs in v2.1 and an object in v2.0.
+fn auth_value(auth : &Value, kind : &str, name : &str) -> String {
+    let value = match auth.get(kind) {
+        Some(Value::Array(values)) => values.iter().find(|v| v.get("key").and_then(Value::as_str) == Some(name)).and_then(|v| v.get("value")),
+        Some(values) => values.get(name),
+        None => None,
+    };
+
+    convert_variables(&text(value))
+}
+
+/// The value of a parameter, or None if it is disabled.
+fn enabled_value(parameter : &Value) -> Option<String> {
+    if parameter.get("disabled").and_then(Value::as_bool).unwrap_or(false) {
+        return None;
+    }
+
+    Some(convert_variables(&text(parameter.get("value"))))
+}
+
+fn text(value : Option<&Value>) -> String {
+    match value {
+        Some(Value::String(s)) => s.to_owned(),
+        Some(Value::Null) | None => String::new(),
+        Some(v) => v.to_string(),
+    }
+}
+
+/// Replace Postman variables `{{name}}` by `${name}`.
+fn convert_variables(text : &str) -> String {
+    let regex = Regex::new(r"\{\{\s*([^{}\s]+)\s*\}\}").unwrap();
+    regex.replace_all(text, |c : &Captures<'_>| format!("${{{}}}", &c[1])).to_string()
+}
+
+#[cfg(test)]
+mod rockery_postman_import_tests {
+    use super::*;
+
+    const COLLECTION : &str = r#"{
+        "info": { "name": "Shop", "schema": "https://schema.getpostman.com/json/collection/v2.1.0/collection.json" },
+        "auth": { "type": "bearer", "bearer": [ { "key": "token", "value": "{{token}}", "type": "string" } ] },
+        "variable": [ { "key": "baseUrl", "value": "https://shop.example.com/api" }, { "key": "token", "value": "secret" } ],
+        "item": [
+            {
+                "name": "Users",
+                "item": [
+                    {
+                        "name": "Get user",
+                        "request": {
+                            "method": "GET",
+                            "header": [ { "key": "Accept", "value": "application/json" }, { "key": "X-Debug", "value": "1", "disabled": true } ],
+                            "url": {
+                                "raw": "{{baseUrl}}/users/:id?lang=de",
+                                "host": [ "{{baseUrl}}" ],
+                                "path": [ "users", ":id" ],
+                                "query": [ { "key": "lang", "value": "de" }, { "key": "page", "value": "2", "disabled": true } ],
+                                "variable": [ { "key": "id", "value": "7" } ]
+                            }
+                        }
+                    },
+                    {
+                        "name": "Admin",
+                        "item": [
+                            {
+                                "name": "Create user",
+                                "request": {
+                                    "method": "POST",
+                                    "auth": { "type": "basic", "basic": [ { "key": "username", "value": "admin" }, { "key": "password", "value": "pw" } ] },
+                                    "body": { "mode": "raw", "raw": "{\"name\": \"{{user}}\", \"tags\": {\"a\": {}}}", "options": { "raw": { "language": "json" } } },
+                                    "url": "{{baseUrl}}/users"
+                                }
+                            }
+                        ]
+                    }
+                ]
+            },
+            {
+                "name": "Login",
+                "request": {
+                    "method": "POST",
+                    "auth": { "type": "apikey", "apikey": [ { "key": "key", "value": "api_key" }, { "key": "value", "value": "k" }, { "key": "in", "value": "query" } ] },
+                    "body": {
+                        "mode": "urlencoded",
+                        "urlencoded": [ { "key": "user", "value": "rex" }, { "key": "remember", "value": "1", "disabled": true } ]
+                    },
+                    "url": { "protocol": "https", "host": [ "auth", "example", "com" ], "port": "8443", "path": [ "login" ] }
+                }
+            },
+            {
+                "name": "Upload",
+                "request": {
+                    "method": "PUT",
+                    "body": { "mode": "formdata", "formdata": [ { "key": "title", "value": "x", "type": "text" }, { "key": "file", "src": "a.png", "type": "file" } ] },
+                    "url": "https://auth.example.com:8443/upload"
+                }
+            }
+        ]
+    }"#;
+
+    const COLLECTION_V2_0 : &str = r#"{
+        "info": { "name": "Old", "schema": "https://schema.getpostman.com/json/collection/v2.0.0/collection.json" },
+        "item": [
+            {
+                "name": "Status",
+                "request": {
+                    "method": "get",
+                    "header": "Accept: text/plain\nX-Trace: {{trace}}",
+                    "auth": { "type": "basic", "basic": { "username": "u", "password": "p" } },
+                    "url": "http://localhost:8080/status?verbose=true"
+                }
+            },
+            { "name": "Ping", "request": "http://localhost:8080/ping" }
+        ]
+    }"#;
+
+    #[test]
+    fn test_import() {
+        let mut project = RockeryPostmanImporter::import_str(COLLECTION).unwrap();
+        assert_eq!(project.get_name(), "Shop");
+        assert_eq!(project.get_variables().get("baseUrl").unwrap(), "https://shop.example.com/api");
+        assert_eq!(project.get_services().len(), 2);
+
+        let users = project.find_service("Users").unwrap();
+        assert_eq!(users.get_base_url(), "${baseUrl}");
+        assert!(matches!(users.get_authentication(), AuthenticationType::Bearer(b) if b.get_token() == "${token}"));
+        let methods = users.get_methods();
+        assert_eq!((methods[0].get_name(), methods[0].get_uri(), methods[0].get_request_method()), ("Get user", "users/{id}", RequestMethod::Get));
+        assert_eq!(methods[0].get_parameter(), &vec![
+            RequestParameter::Path("id".to_owned(), Some("7".to_owned())),
+            RequestParameter::Url("lang".to_owned(), Some("de".to_owned())),
+            RequestParameter::Url("page".to_owned(), None),
+            RequestParameter::Header("Accept".to_owned(), Some("application/json".to_owned())),
+            RequestParameter::Header("X-Debug".to_owned(), None),
+        ]);
+
+        assert_eq!((methods[1].get_name(), methods[1].get_uri(), methods[1].get_request_method()), ("Admin/Create user", "users", RequestMethod::Post));
+        assert_eq!(methods[1].get_body(), &Some("{\"name\": \"${user}\", \"tags\": {\"a\": {}}}".to_owned()));
+        assert_eq!(methods[1].get_parameter(), &vec![
+            RequestParameter::Header("Content-Type".to_owned(), Some("application/json".to_owned())),
+            RequestParameter::Header("Authorization".to_owned(), Some("Basic YWRtaW46cHc=".to_owned())),
+        ]);
+
+        let shop = project.find_service("Shop").unwrap();
+        assert_eq!(shop.get_base_url(), "https://auth.example.com:8443");
+        let methods = shop.get_methods();
+        assert_eq!(methods.len(), 2);
+        assert_eq!(methods[0].get_body(), &None);
+        assert_eq!(methods[0].get_parameter(), &vec![
+            RequestParameter::Body("user".to_owned(), Some("rex".to_owned())),
+            RequestParameter::Body("remember".to_owned(), None),
+            RequestParameter::Header("Content-Type".to_owned(), Some("application/x-www-form-urlencoded".to_owned())),
+            RequestParameter::Url("api_key".to_owned(), Some("k".to_owned())),
+        ]);
+        assert_eq!((methods[1].get_uri(), methods[1].get_request_method()), ("upload", RequestMethod::Put));
+        assert_eq!(methods[1].get_parameter()[0], RequestParameter::Body("title".to_owned(), Some("x".to_owned())));
+        assert_eq!(methods[1].get_parameter().len(), 2);
+
+        // Variables of the project are substituted when requests are sent.
+        let service = project.find_service("Users").unwrap().with_variables(0, project.get_variables());
+        assert_eq!(service.get_request_url(0, &[]), "https://shop.example.com/api/users/7/?lang=de");
+    }
+
+    #[test]
+    fn test_import_v2_0() {
+        let mut project = RockeryPostmanImporter::import_str(COLLECTION_V2_0).unwrap();
+        let service = project.find_service("Old").unwrap();
+        assert_eq!(service.get_base_url(), "http://localhost:8080");
+        assert!(matches!(service.get_authentication(), AuthenticationType::None(_)));
+
+        let methods = service.get_methods();
+        assert_eq!(methods[0].get_parameter(), &vec![
+            RequestParameter::Url("verbose".to_owned(), Some("true".to_owned())),
+            RequestParameter::Header("Accept".to_owned(), Some("text/plain".to_owned())),
+            RequestParameter::Header("X-Trace".to_owned(), Some("${trace}".to_owned())),
+            RequestParameter::Header("Authorization".to_owned(), Some("Basic dTpw".to_owned())),
+        ]);
+        assert_eq!((methods[1].get_name(), methods[1].get_uri(), methods[1].get_request_method()), ("Ping", "ping", RequestMethod::Get));
+        assert_eq!(project.get_services().len(), 1);
+    }
+
+    #[test]
+    fn test_invalid_collection() {
+        assert!(RockeryPostmanImporter::import_str("{}").is_err());
+        assert!(RockeryPostmanImporter::import_str("no json").is_err());
+        assert!(RockeryPostmanImporter::import_str(r#"{"info": {"schema": "https://schema.getpostman.com/json/collection/v1.0.0/"}, "item": []}"#).is_err());
+        assert!(RockeryPostmanImporter::import("does_not_exist.postman_collection.json").is_err());
+        assert!(RockeryPostmanImporter::import_str(r#"{"info": {"schema": "https://schema.getpostman.com/json/collection/v2.1.0/"}"#).is_err());
+
+        let schema = r#""info": {"name": "Broken", "schema": "https://schema.getpostman.com/json/collection/v2.1.0/"}"#;
+        let unknown = RockeryPostmanImporter::import_str(&format!(r#"{{{}, "item": [{{"name": "Fetch", "request": {{"method": "FETCH", "url": "https://example.com"}}}}]}}"#, schema));
+        assert_eq!(unknown.unwrap_err(), "Unknown request method FETCH of request Fetch.");
+
+        // Malformed items are skipped or imported with defaults instead of failing the whole import.
+        let mut project = RockeryPostmanImporter::import_str(&format!(r#"{{{}, "item": [1, "x", {{"name": 2, "request": 5}}, {{"name": "No request"}},
+            {{"name": "Folder", "item": {{"not": "an array"}}}}], "variable": [{{"value": "no key"}}, 3]}}"#, schema)).unwrap();
+        assert_eq!(project.get_name(), "Broken");
+        assert!(project.get_variables().is_empty());
+        let methods : Vec<&RockeryServiceMethod> = project.get_services().values().flat_map(|s| s.get_methods()).collect();
+        assert_eq!(methods.len(), 1);
+        assert_eq!((methods[0].get_name(), methods[0].get_request_method()), ("Unnamed", RequestMethod::Get));
+
+        let mut project = RockeryPostmanImporter::import_str(&format!(r#"{{{}, "item": {{"not": "an array"}}}}"#, schema)).unwrap();
+        assert!(project.get_services().is_empty());
+    }
+}
@@ -1,22 +1,55 @@
-use std::{collections::HashMap, fs::File, io::{BufReader, Read, Write}, path::PathBuf};
+use std::{collections::HashMap, fs::File, io::{BufReader, Read, Write}, path::{Path, PathBuf}, sync::{Arc, Mutex}};
 
 use serde::{ser::Error, Deserialize, Serialize};
 use serde_json::Result;
 
-use crate::prelude::RockeryService;
+use crate::prelude::{RockeryEnvironment, RockeryHistory, RockeryService, RockeryTestSuite, RockeryTestSuiteResult};
+use crate::rockery_history::HistoryRecorder;
+use crate::rockery_migration::{migrate, PROJECT_FORMAT_VERSION};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct RockeryProject {
+    /// Version of the file format, older files are migrated on load.
+    #[serde(default)]
+    format_version : u64,
+
     name : String,
 
     #[serde(skip)]
     dirty : bool,
 
     services : HashMap<String, RockeryService>,
+
+    #[serde(default)]
+    test_suites : Vec<RockeryTestSuite>,
+
+    /// Variables available to all test cases of the project.
+    #[serde(default)]
+    variables : HashMap<String, String>,
+
+    #[serde(default)]
+    environments : Vec<RockeryEnvironment>,
+
+    /// Name of the environment applied last.
+    #[serde(skip)]
+    active_environment : Option<String>,
+
+    #[serde(skip)]
+    history : Option<Arc<Mutex<RockeryHistory>>>,
+
+    /// Directory of the file the project was loaded from or saved to. Relative paths of data sources
+    /// are resolved against it.
+    #[serde(skip)]
+    directory : Option<PathBuf>,
 }
 
 impl RockeryProject {
     pub fn is_dirty(&self) -> bool  { self.dirty }
+    pub(crate) fn set_dirty(&mut self) { self.dirty = true; }
+
+    pub fn get_format_version(&self) -> u64 { self.format_version }
+
+    pub fn get_directory(&self) -> Option<&Path> { self.directory.as_deref() }
 
     pub fn get_name(&self) -> &str { &self.name }
     pub fn set_name(&mut self, name : &str) {
@@ -26,10 +59,17 @@ impl RockeryProject {
 
     pub fn new(name : &str) -> Self  {
         RockeryProject {
+            format_version : PROJECT_FORMAT_VERSION,
             name : name.to_owned(),
             dirty : true,
 
             services : HashMap::new(),
+            test_suites : Vec::new(),
+            variables : HashMap::new(),
+            environments : Vec::new(),
+            active_environment : None,
+            history : None,
+            directory : None,
         }
     }
 
@@ -38,6 +78,13 @@ impl RockeryProject {
         &mut self.services
     }
 
+    /// The ids of all services in alphabetical order.
+    pub fn get_service_ids(&self) -> Vec<String> {
+        let mut ids : Vec<String> = self.services.keys().cloned().collect();
+        ids.sort();
+        ids
+    }
+
     /// Add the given service to the project and return its id within this project.
     pub fn add_service(&mut self, service : RockeryService) -> String {
         let mut name = service.get_name().to_owned();
@@ -47,6 +94,8 @@ impl RockeryProject {
             i += 1;
         }
 
+        let mut service = service;
+        service.set_history(self.history.as_ref().map(|h| HistoryRecorder::new(h.clone(), &name)));
         self.services.insert(name.clone(), service);
 
         name
@@ -66,6 +115,119 @@ impl RockeryProject {
         &self.services[name]
     }
 
+    /// Find a service by its id within this project.
+    pub fn find_service(&self, name : &str) -> Option<&RockeryService> {
+        self.services.get(name)
+    }
+
+    pub fn get_test_suites(&self) -> &Vec<RockeryTestSuite> { &self.test_suites }
+
+    pub fn get_test_suite_mut(&mut self, index : usize) -> Option<&mut RockeryTestSuite> {
+        self.dirty = true;
+        self.test_suites.get_mut(index)
+    }
+
+    /// Add a test suite to the project and return it so it can be configured.
+    pub fn add_test_suite(&mut self, suite : RockeryTestSuite) -> &mut RockeryTestSuite {
+        self.dirty = true;
+        self.test_suites.push(suite);
+        let index = self.test_suites.len() - 1;
+
+        &mut self.test_suites[index]
+    }
+
+    pub fn remove_test_suite(&mut self, index : usize) -> std::result::Result<RockeryTestSuite, String> {
+        if index >= self.test_suites.len() {
+            return Err(format!("Invalid index. There are only {} test suites but test suite {} was requested.", self.test_suites.len(), index));
+        }
+
+        self.dirty = true;
+        Ok(self.test_suites.remove(index))
+    }
+
+    pub fn get_variables(&self) -> &HashMap<String, String> { &self.variables }
+    pub fn set_variable(&mut self, name : &str, value : &str) {
+        self.variables.insert(name.to_owned(), value.to_owned());
+        self.dirty = true;
+    }
+
+    pub fn remove_variable(&mut self, name : &str) -> Option<String> {
+        self.dirty = true;
+        self.variables.remove(name)
+    }
+
+    pub fn get_environments(&self) -> &Vec<RockeryEnvironment> { &self.environments }
+    pub fn get_active_environment(&self) -> &Option<String> { &self.active_environment }
+
+    /// The history all requests of the services are recorded into, if enabled.
+    pub fn get_history(&self) -> &Option<Arc<Mutex<RockeryHistory>>> { &self.history }
+    pub fn set_history(&mut self, history : Option<Arc<Mutex<RockeryHistory>>>) {
+        for (id, service) in self.services.iter_mut() {
+            service.set_history(history.as_ref().map(|h| HistoryRecorder::new(h.clone(), id)));
+        }
+        self.history = history;
+    }
+
+    /// Add an environment to the project and return it so it can be configured.
+    pub fn add_environment(&mut self, environment : RockeryEnvironment) -> &mut RockeryEnvironment {
+        self.dirty = true;
+        self.environments.push(environment);
+        let index = self.environments.len() - 1;
+
+        &mut self.environments[index]
+    }
+
+    pub fn remove_environment(&mut self, index : usize) -> std::result::Result<RockeryEnvironment, String> {
+        if index >= self.environments.len() {
+            return Err(format!("Invalid index. There are only {} environments but environment {} was requested.", self.environments.len(), index));
+        }
+
+        self.dirty = true;
+        Ok(self.environments.remove(index))
+    }
+
+    /// Select an environment: the base urls of the services are replaced and the variables of the
+    /// environment are added to the project variables.
+    pub fn apply_environment(&mut self, name : &str) -> std::result::Result<(), String> {
+        let environment = self.environments.iter().find(|e| e.get_name() == name)
+            .ok_or(format!("An environment with the name {} does not exist within project {}.", name, self.name))?
+            .clone();
+
+        for (service, url) in environment.get_base_urls() {
+            match self.services.get_mut(service) {
+                Some(s) => s.set_base_url(url),
+                None => return Err(format!("Environment {} refers to service {} which does not exist within project {}.", name, service, self.name)),
+            }
+        }
+
+        self.variables.extend(environment.get_variables().clone());
+        self.active_environment = Some(name.to_owned());
+        if let Some(Ok(mut history)) = self.history.as_ref().map(|h| h.lock()) {
+            history.set_environment(self.active_environment.clone());
+        }
+        self.dirty = true;
+
+        Ok(())
+    }
+
+    /// Run all test cases of the test suite with the given index. Values transferred into project
+    /// variables are kept after the run.
+    pub fn run_test_suite(&mut self, index : usize) -> std::result::Result<RockeryTestSuiteResult, String> {
+        let suite = match self.test_suites.get(index) {
+            Some(suite) => suite,
+            None => return Err(format!("Invalid index. There are only {} test suites but test suite {} was requested.", self.test_suites.len(), index)),
+        };
+
+        let mut variables = self.variables.clone();
+        let result = suite.run_with_variables(self, &mut variables);
+        if variables != self.variables {
+            self.variables = variables;
+            self.dirty = true;
+        }
+
+        Ok(result)
+    }
+
     pub fn save(&mut self, path : &PathBuf) -> Result<()> {
         let v = serde_json::to_string(self)?;
         match File::create(path) {
@@ -83,20 +245,22 @@ impl RockeryProject {
         };
 
         self.dirty = false;
+        self.directory = path.parent().map(|p| p.to_path_buf());
 
         Ok(())
     }
 
     pub fn load(path : PathBuf) -> Result<Self> {
+        let directory = path.parent().map(|p| p.to_path_buf());
         match File::open(path) {
             Ok(f) => {
                 let mut buf_reader = BufReader::new(f);
                 let mut contents = String::new();
                 match buf_reader.read_to_string(&mut contents) {
                     Ok(_s) => {
-                        let mut value: RockeryProject = serde_json::from_str(contents.as_str())?;
-                        value.dirty = false;
-                        Ok(value)
+                        let mut project = Self::from_json(&contents)?;
+                        project.directory = directory;
+                        Ok(project)
                     },
 
                     Err(e) => {
@@ -109,6 +273,16 @@ impl RockeryProject {
             }
         }
     }
+
+    /// Read a project from the content of a project file. Files of older format versions are migrated.
+    pub fn from_json(contents : &str) -> Result<Self> {
+        let document = serde_json::from_str(contents)?;
+        let document = migrate(document).map_err(serde_json::Error::custom)?;
+
+        let mut value : RockeryProject = serde_json::from_value(document)?;
+        value.dirty = false;
+        Ok(value)
+    }
 }
 
 impl Default for RockeryProject {
@@ -119,8 +293,8 @@ impl Default for RockeryProject {
 
 #[cfg(test)]
 mod tests {
-    use std::str::FromStr;
 
+    use crate::prelude::AuthenticationType;
     use crate::rockery_test_helper::get_feiertage_service;
     use super::*;
 
@@ -130,7 +304,7 @@ mod tests {
         assert!(subject.is_dirty());
 
         let name = "REST-Test".to_owned();
-        let file = "Test.proj.json";
+        let file = std::env::temp_dir().join(format!("rockery_project_{}.proj.json", std::process::id()));
         assert!(subject.get_services().is_empty());
 
         let id = subject.add_service(get_feiertage_service());
@@ -143,7 +317,7 @@ mod tests {
         assert_eq!(subject.get_name(), name);
         assert!(subject.is_dirty());
 
-        if subject.save(&PathBuf::from_str("Test.proj.json").unwrap()).is_err() { 
+        if subject.save(&file).is_err() { 
             panic!("Could not save file even so i should be able to do so.");
         }
 
@@ -153,8 +327,46 @@ mod tests {
         assert!(subject.remove_service(&id).is_ok());
         assert!(subject.get_services().is_empty());
 
-        let check = RockeryProject::load(file.into()).unwrap();
+        let check = RockeryProject::load(file.clone()).unwrap();
         assert!(!check.is_dirty());
         assert_eq!(check.get_name(), subject.get_name());
+
+        std::fs::remove_file(&file).unwrap();
+    }
+
+    #[test]
+    fn test_load_legacy() {
+        // Written before the format version was introduced, with the authentication tagged by its type.
+        let legacy = RockeryProject::load("../Test.proj.json".into()).unwrap();
+        assert_eq!(legacy.get_format_version(), PROJECT_FORMAT_VERSION);
+        assert!(!legacy.is_dirty());
+        let service = legacy.find_service("Feiertage API").unwrap();
+        assert!(matches!(service.get_authentication(), AuthenticationType::None(_)));
+        assert_eq!(service.get_methods()[0].get_parameter().len(), 4);
+
+        let newer = RockeryProject::from_json(&format!(r#"{{"format_version": {}, "name": "Future", "services": {{}}}}"#, PROJECT_FORMAT_VERSION + 1));
+        assert!(newer.unwrap_err().to_string().contains("Please update rockery"));
+
+        let current = serde_json::to_string(&RockeryProject::new("Current")).unwrap();
+        assert!(current.contains(&format!(r#""format_version":{}"#, PROJECT_FORMAT_VERSION)));
+        assert_eq!(RockeryProject::from_json(&current).unwrap().get_name(), "Current");
+    }
+
+    #[test]
+    fn test_environment() {
+        let mut subject = RockeryProject::new("Environments");
+        let id = subject.add_service(get_feiertage_service());
+        subject.set_variable("year", "2023");
+
+        let staging = subject.add_environment(RockeryEnvironment::new("Staging"));
+        staging.set_base_url(&id, "https://staging.feiertage-api.de");
+        staging.set_variable("year", "2024");
+        subject.add_environment(RockeryEnvironment::new("Broken")).set_base_url("Unknown", "https://example.com");
+
+        assert!(subject.apply_environment("Production").is_err());
+        assert!(subject.apply_environment("Broken").is_err());
+        assert!(subject.apply_environment("Staging").is_ok());
+        assert_eq!(subject.find_service(&id).unwrap().get_base_url(), "https://staging.feiertage-api.de");
+        assert_eq!(subject.get_variables()["year"], "2024");
     }
 }
\ No newline at end of file
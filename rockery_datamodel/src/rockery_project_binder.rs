@@ -143,6 +143,11 @@ impl RockeryProjectBinder {
 
     /// Tries to save all projects then the binder itself.
     pub fn save(&mut self) -> Result<(), String> {
+        self.save_to(&PathBuf::from(Self::DEFAULT_PROJECT_PATH))
+    }
+
+    /// Tries to save all projects then the binder itself to the given file.
+    fn save_to(&mut self, file : &PathBuf) -> Result<(), String> {
         for (i, p ) in &mut self.projects.iter_mut().enumerate() {
             let path = &self.project_paths[i];
 
@@ -156,7 +161,7 @@ impl RockeryProjectBinder {
 
         match serde_json::to_string(&self) {
             Ok(e) => {
-                match std::fs::write(Self::DEFAULT_PROJECT_PATH, e) {
+                match std::fs::write(file, e) {
                     Ok(_) => { },
                     Err(e) => {
                         return Err(format!("Could not store projects binder. Error: {}", e));
@@ -173,7 +178,12 @@ impl RockeryProjectBinder {
     }
 
     pub fn load() -> Result<Self, String> {
-        match std::fs::read_to_string(Self::DEFAULT_PROJECT_PATH) {
+        Self::load_from(&PathBuf::from(Self::DEFAULT_PROJECT_PATH))
+    }
+
+    /// Load the binder from the given file and all projects it knows about.
+    fn load_from(file : &PathBuf) -> Result<Self, String> {
+        match std::fs::read_to_string(file) {
             Ok(e) => {
                 match serde_json::from_str::<RockeryProjectBinder>(&e) {
                     Ok(mut e) => {
@@ -271,21 +281,24 @@ mod tests {
         assert_eq!(binder.create_project().get_name(), "Unnamed_1");
 
         // Set a file path...
-        assert!(binder.set_project_file(0, &PathBuf::from_str("Test_2.json").unwrap()).is_ok());
-        assert!(binder.set_project_file(1, &PathBuf::from_str("Feiertage.json").unwrap()).is_ok());
+        let dir = std::env::temp_dir().join(format!("rockery_binder_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("projects.json");
+        assert!(binder.set_project_file(0, &dir.join("Test_2.json")).is_ok());
+        assert!(binder.set_project_file(1, &dir.join("Feiertage.json")).is_ok());
 
         binder.rename_project("Unnamed", "Test_1").unwrap();
         binder.rename_project("Unnamed_1", "Feiertage").unwrap();
 
         // ... now we can store the binder.
-        match binder.save() {
+        match binder.save_to(&file) {
             Ok(_) => {},
             Err(e) => println!("{}", e),
         }
-        assert!(binder.save().is_ok());
+        assert!(binder.save_to(&file).is_ok());
 
         // Load the binder again and check the result.
-        let res = RockeryProjectBinder::load();
+        let res = RockeryProjectBinder::load_from(&file);
         assert!(res.is_ok());
 
         let mut binder_2 = res.unwrap();
@@ -296,5 +309,6 @@ mod tests {
         assert!(binder.get_project(0).is_some());
         assert_eq!(binder.get_project(0).unwrap().get_name(), "Test_1");
 
+        std::fs::remove_dir_all(&dir).unwrap();
     }
 }
@@ -0,0 +1,159 @@
+use std::collections::HashMap;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::prelude::{RequestParameter, RockeryResponse};
+use crate::rockery_json_path;
+use crate::rockery_xml::XmlElement;
+use crate::rockery_xpath;
+
+/// Where the value of a property transfer is taken from.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum RockeryPropertySource {
+    /// The first value the json path selects within the body.
+    JsonPath(String),
+
+    /// The string value of the first node the xpath selects within the body.
+    XPath(String),
+
+    /// The first capture group of the regular expression within the body, or the whole match
+    /// if the expression has no groups.
+    Regex(String),
+
+    Header(String),
+}
+
+/// Variables can be stored within the running test case or within the project, so they
+/// are available for other test cases as well.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum RockeryVariableScope {
+    #[default]
+    TestCase,
+    Project,
+}
+
+/// Extracts a value from a response and saves it as variable. Variables are referenced as `${name}`
+/// within parameter values, uris and bodies of later steps.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RockeryPropertyTransfer {
+    /// Name of the variable the value is saved to.
+    name : String,
+    source : RockeryPropertySource,
+
+    #[serde(default)]
+    scope : RockeryVariableScope,
+}
+
+impl RockeryPropertyTransfer {
+    pub fn new(name : &str, source : RockeryPropertySource, scope : RockeryVariableScope) -> Self {
+        RockeryPropertyTransfer { name : name.to_owned(), source, scope }
+    }
+
+    pub fn get_name(&self) -> &str { &self.name }
+    pub fn get_source(&self) -> &RockeryPropertySource { &self.source }
+    pub fn get_scope(&self) -> RockeryVariableScope { self.scope }
+
+    /// Extract the value from a response.
+    pub fn extract(&self, response : &RockeryResponse) -> Result<String, String> {
+        let value = match &self.source {
+            RockeryPropertySource::JsonPath(p) => {
+                let body : Value = serde_json::from_str(response.get_body()).map_err(|e| format!("Body is not valid json. Error: {}", e))?;
+                rockery_json_path::select(&body, p)?.first().map(|v| rockery_json_path::value_text(v))
+            },
+            RockeryPropertySource::XPath(p) => {
+                let body = XmlElement::parse(response.get_body()).map_err(|e| format!("Body is not valid xml. Error: {}", e))?;
+                rockery_xpath::select(&body, p)?.into_iter().next()
+            },
+            RockeryPropertySource::Regex(r) => {
+                let regex = Regex::new(r).map_err(|e| format!("Invalid regular expression '{}'. Error: {}", r, e))?;
+                regex.captures(response.get_body())
+                    .and_then(|c| c.get(1).or(c.get(0)))
+                    .map(|m| m.as_str().to_owned())
+            },
+            RockeryPropertySource::Header(h) => response.get_header(h).map(|v| v.to_owned()),
+        };
+
+        value.ok_or(format!("Could not extract a value for {}.", self.name))
+    }
+}
+
+/// Replace all `${name}` placeholders within a text by the value of the variable. Placeholders
+/// of unknown variables are left untouched.
+pub(crate) fn substitute(text : &str, variables : &HashMap<String, String>) -> String {
+    if variables.is_empty() || !text.contains("${") {
+        return text.to_owned();
+    }
+
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("${") {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        match after.find('}') {
+            Some(end) => {
+                match variables.get(after[..end].trim()) {
+                    Some(v) => result.push_str(v),
+                    None => result.push_str(&rest[start..start + end + 3]),
+                }
+                rest = &after[end + 1..];
+            },
+            None => {
+                result.push_str(&rest[start..]);
+                rest = "";
+            },
+        }
+    }
+    result.push_str(rest);
+
+    result
+}
+
+/// Replace the placeholders within the value of a parameter.
+pub(crate) fn substitute_parameter(parameter : &RequestParameter, variables : &HashMap<String, String>) -> RequestParameter {
+    let value = |v : &Option<String>| v.as_ref().map(|v| substitute(v, variables));
+    match parameter {
+        RequestParameter::Url(k, v) => RequestParameter::Url(k.to_owned(), value(v)),
+        RequestParameter::Body(k, v) => RequestParameter::Body(k.to_owned(), value(v)),
+        RequestParameter::Header(k, v) => RequestParameter::Header(k.to_owned(), value(v)),
+        RequestParameter::Path(k, v) => RequestParameter::Path(k.to_owned(), value(v)),
+    }
+}
+
+#[cfg(test)]
+mod rockery_property_transfer_tests {
+    use std::time::Duration;
+
+    use ehttp::Headers;
+
+    use super::*;
+
+    #[test]
+    fn test_substitute() {
+        let variables : HashMap<String, String> = [("id".to_owned(), "7".to_owned()), ("token".to_owned(), "abc".to_owned())].into();
+        assert_eq!(substitute("users/${id}/posts", &variables), "users/7/posts");
+        assert_eq!(substitute("Bearer ${ token }${id}", &variables), "Bearer abc7");
+        assert_eq!(substitute("${unknown} and ${id", &variables), "${unknown} and ${id");
+        assert_eq!(substitute_parameter(&RequestParameter::Header("X".to_owned(), Some("${token}".to_owned())), &variables),
+            RequestParameter::Header("X".to_owned(), Some("abc".to_owned())));
+    }
+
+    #[test]
+    fn test_extract() {
+        let headers = Headers::new(&[("Location", "/users/7")]);
+        let json = RockeryResponse::new(201, "Created", &headers, br#"{ "token": "abc", "user": { "id": 7 } }"#, Duration::ZERO);
+        let xml = RockeryResponse::new(200, "OK", &headers, br#"<login><token>xyz</token></login>"#, Duration::ZERO);
+
+        let extract = |source : RockeryPropertySource, response : &RockeryResponse| {
+            RockeryPropertyTransfer::new("v", source, RockeryVariableScope::TestCase).extract(response)
+        };
+        assert_eq!(extract(RockeryPropertySource::JsonPath("$.user.id".to_owned()), &json), Ok("7".to_owned()));
+        assert_eq!(extract(RockeryPropertySource::XPath("/login/token".to_owned()), &xml), Ok("xyz".to_owned()));
+        assert_eq!(extract(RockeryPropertySource::Regex(r#""token": "(\w+)""#.to_owned()), &json), Ok("abc".to_owned()));
+        assert_eq!(extract(RockeryPropertySource::Regex(r"\d+".to_owned()), &json), Ok("7".to_owned()));
+        assert_eq!(extract(RockeryPropertySource::Header("location".to_owned()), &json), Ok("/users/7".to_owned()));
+        assert!(extract(RockeryPropertySource::JsonPath("$.missing".to_owned()), &json).is_err());
+        assert!(extract(RockeryPropertySource::JsonPath("$.token".to_owned()), &xml).is_err());
+    }
+}
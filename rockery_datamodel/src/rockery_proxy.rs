@@ -0,0 +1,413 @@
+use std::{
+    io::BufReader,
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::{atomic::{AtomicBool, Ordering}, Arc, Mutex},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::*;
+use crate::rockery_http::{parse_query, split_url, status_text, write_response, HttpRequest};
+use crate::rockery_mock::match_uri;
+
+/// Headers that are managed by the connection and neither forwarded nor recorded.
+const CONNECTION_HEADERS : [&str; 9] = [
+    "Host", "Connection", "Proxy-Connection", "Keep-Alive", "Proxy-Authorization", "Content-Length", "Transfer-Encoding", "Accept-Encoding", "Upgrade",
+];
+
+/// Headers that are sent by almost every client and therefore not recorded as parameters.
+const CLIENT_HEADERS : [&str; 3] = ["User-Agent", "Accept-Language", "Cache-Control"];
+
+fn is_one_of(name : &str, headers : &[&str]) -> bool {
+    headers.iter().any(|h| h.eq_ignore_ascii_case(name))
+}
+
+/// A request that passed through the proxy together with the response of the server.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RockeryExchange {
+    request : RockeryRequest,
+    response : RockeryResponse,
+}
+
+impl RockeryExchange {
+    pub fn new(request : RockeryRequest, response : RockeryResponse) -> Self {
+        RockeryExchange { request, response }
+    }
+
+    pub fn get_request(&self) -> &RockeryRequest { &self.request }
+    pub fn get_response(&self) -> &RockeryResponse { &self.response }
+}
+
+/// Whether the host of the url resolves to the address the proxy listens on.
+fn is_proxy_address(url : &str, proxy : SocketAddr) -> bool {
+    let host = match split_url(url) {
+        Some((origin, _, _)) => origin.split_once("://").map(|(_, h)| h).unwrap_or(origin),
+        None => return false,
+    };
+    let host = if host.rsplit_once(':').is_some_and(|(_, p)| p.parse::<u16>().is_ok()) { host.to_owned() } else { format!("{}:80", host) };
+
+    host.to_socket_addrs().map(|mut addresses| addresses.any(|a| {
+        a == proxy || (a.port() == proxy.port() && proxy.ip().is_unspecified() && a.ip().is_loopback())
+    })).unwrap_or(false)
+}
+
+/// Send the request to its server. Returns the recorded exchange and the raw body of the response.
+fn forward(request : &HttpRequest, proxy : SocketAddr) -> Result<(RockeryExchange, Vec<u8>), String> {
+    // Proxy requests contain the absolute url, other requests are forwarded to their host.
+    let url = if request.target.contains("://") {
+        request.target.clone()
+    } else {
+        let host = request.get_header("Host").ok_or("The request has neither an absolute url nor a host header.".to_owned())?;
+        format!("http://{}{}", host, request.target)
+    };
+
+    // A request to the proxy itself would be forwarded to the proxy again and again.
+    if is_proxy_address(&url, proxy) {
+        return Err(format!("The request to {} is addressed to the proxy itself.", url));
+    }
+
+    let mut forwarded = ehttp::Request::get(&url);
+    forwarded.method = request.method.clone();
+    forwarded.body = request.body.clone();
+    forwarded.headers = ehttp::Headers::new(&[]);
+    for (k, v) in request.headers.iter().filter(|(k, _)| !is_one_of(k, &CONNECTION_HEADERS)) {
+        forwarded.headers.insert(k, v);
+    }
+
+    let start = Instant::now();
+    let response = ehttp::fetch_blocking(&forwarded).map_err(|e| format!("Could not forward the request to {}. Error: {}", url, e))?;
+    let recorded = RockeryResponse::new(response.status, &response.status_text, &response.headers, &response.bytes, start.elapsed());
+
+    Ok((RockeryExchange::new(RockeryRequest::from(&forwarded), recorded), response.bytes))
+}
+
+fn handle_connection(stream : TcpStream, recorded : &Mutex<Vec<RockeryExchange>>, proxy : SocketAddr) {
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(30)));
+    let mut writer = match stream.try_clone() {
+        Ok(s) => s,
+        Err(_) => return,
+    };
+
+    let request = match HttpRequest::read(&mut BufReader::new(stream)) {
+        Ok(Some(r)) => r,
+        Ok(None) => return,
+        Err(e) => {
+            let _ = write_response(&mut writer, 400, status_text(400), &[], e.as_bytes());
+            return;
+        },
+    };
+
+    if request.method == "CONNECT" {
+        let message = "Encrypted traffic can not be recorded. Send plain http requests through the proxy.";
+        let _ = write_response(&mut writer, 501, status_text(501), &[], message.as_bytes());
+        return;
+    }
+
+    match forward(&request, proxy) {
+        Ok((exchange, bytes)) => {
+            let response = exchange.get_response();
+            let headers : Vec<(String, String)> = response.get_headers().iter()
+                .filter(|(k, _)| !is_one_of(k, &CONNECTION_HEADERS) && !k.eq_ignore_ascii_case("Content-Encoding"))
+                .cloned()
+                .collect();
+            let _ = write_response(&mut writer, response.get_status(), response.get_status_text(), &headers, &bytes);
+
+            if let Ok(mut recorded) = recorded.lock() {
+                recorded.push(exchange);
+            }
+        },
+        Err(e) => {
+            let _ = write_response(&mut writer, 502, status_text(502), &[], e.as_bytes());
+        },
+    }
+}
+
+/// A local http proxy that forwards all requests and records them together with their responses.
+/// Clients are configured to use it as http proxy. The proxy stops when it is dropped.
+pub struct RockeryRecordingProxy {
+    address : SocketAddr,
+    running : Arc<AtomicBool>,
+    recorded : Arc<Mutex<Vec<RockeryExchange>>>,
+    handle : Option<JoinHandle<()>>,
+}
+
+impl RockeryRecordingProxy {
+    /// Start listening, e.g. on `127.0.0.1:8888`. Use port 0 to pick a free port.
+    pub fn start(address : &str) -> Result<Self, String> {
+        let listener = TcpListener::bind(address).map_err(|e| format!("Could not listen on {}. Error: {}", address, e))?;
+        let address = listener.local_addr().map_err(|e| e.to_string())?;
+
+        let running = Arc::new(AtomicBool::new(true));
+        let recorded = Arc::new(Mutex::new(Vec::new()));
+
+        let (r, rec) = (running.clone(), recorded.clone());
+        let handle = thread::spawn(move || {
+            for stream in listener.incoming() {
+                if !r.load(Ordering::SeqCst) {
+                    break;
+                }
+
+                if let Ok(stream) = stream {
+                    let rec = rec.clone();
+                    thread::spawn(move || handle_connection(stream, &rec, address));
+                }
+            }
+        });
+
+        Ok(RockeryRecordingProxy { address, running, recorded, handle : Some(handle) })
+    }
+
+    pub fn get_address(&self) -> SocketAddr { self.address }
+    pub fn get_url(&self) -> String { format!("http://{}", self.address) }
+
+    /// All exchanges recorded so far, in the order the responses have been received.
+    pub fn get_recorded(&self) -> Vec<RockeryExchange> {
+        self.recorded.lock().map(|r| r.clone()).unwrap_or_default()
+    }
+
+    /// Remove and return all exchanges recorded so far.
+    pub fn take_recorded(&self) -> Vec<RockeryExchange> {
+        self.recorded.lock().map(|mut r| std::mem::take(&mut *r)).unwrap_or_default()
+    }
+
+    pub fn is_running(&self) -> bool { self.running.load(Ordering::SeqCst) }
+
+    pub fn stop(&mut self) {
+        if !self.running.swap(false, Ordering::SeqCst) {
+            return;
+        }
+
+        // Wake up the listener so it notices that it has been stopped.
+        let _ = TcpStream::connect(self.address);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for RockeryRecordingProxy {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+impl RockeryProject {
+    /// Add recorded exchanges to the project. Requests are grouped into one service per host. A request is
+    /// added to an existing method if the request method matches and the uri template matches its path,
+    /// otherwise a new method is created. Query parameters, headers and the body of the first request become
+    /// parameters and body of a new method; every response is stored with its method. Exchanges with an
+    /// invalid url or an unknown request method are skipped. Returns the ids of all services that have been
+    /// changed and a warning for every skipped exchange.
+    pub fn add_recording(&mut self, exchanges : &[RockeryExchange]) -> (Vec<String>, Vec<String>) {
+        let mut changed : Vec<String> = Vec::new();
+        let mut warnings = Vec::new();
+        for exchange in exchanges {
+            let request = exchange.get_request();
+            // Only http requests can be sent again, not e.g. `data:` or `blob:` urls of a browser.
+            let url = split_url(request.get_url()).filter(|(origin, _, _)| origin.starts_with("http://") || origin.starts_with("https://"));
+            let (origin, path, query) = match url {
+                Some(parts) => parts,
+                None => {
+                    warnings.push(format!("Request {} {} is skipped, the url is invalid.", request.get_method(), request.get_url()));
+                    continue;
+                },
+            };
+            let host = origin.split_once("://").map(|(_, h)| h).unwrap_or(origin).to_owned();
+            let request_method : RequestMethod = match request.get_method().parse() {
+                Ok(m) => m,
+                Err(_) => {
+                    warnings.push(format!("Request {} {} is skipped, the request method is unknown.", request.get_method(), request.get_url()));
+                    continue;
+                },
+            };
+
+            let id = match self.find_service(&host) {
+                Some(_) => host.clone(),
+                None => {
+                    let mut service = RockeryService::new(RockeryServiceType::Rest, origin);
+                    service.set_name(&host);
+                    self.add_service(service)
+                },
+            };
+
+            let service = self.get_services().get_mut(&id).unwrap();
+            let existing = service.get_methods().iter()
+                .position(|m| m.get_request_method() == request_method && match_uri(m.get_uri(), path).is_some());
+
+            let method = match existing {
+                Some(i) => service.get_method_mut(i).unwrap(),
+                None => {
+                    let uri = path.trim_matches('/');
+                    let m = service.add_method(&format!("{} /{}", request_method, uri), uri);
+                    m.set_request_method(request_method);
+                    if !request.get_body().is_empty() {
+                        m.set_body(request.get_body());
+                    }
+                    m
+                },
+            };
+
+            for (k, v) in parse_query(query) {
+                let parameter = RequestParameter::Url(k, Some(v));
+                if !method.get_parameter().iter().any(|p| p.is_same_parameter(&parameter)) {
+                    method.add_parameter(parameter);
+                }
+            }
+
+            for (k, v) in request.get_headers().iter().filter(|(k, _)| !is_one_of(k, &CONNECTION_HEADERS) && !is_one_of(k, &CLIENT_HEADERS)) {
+                let parameter = RequestParameter::Header(k.to_owned(), Some(v.to_owned()));
+                if !method.get_parameter().iter().any(|p| p.is_same_parameter(&parameter)) {
+                    method.add_parameter(parameter);
+                }
+            }
+
+            method.add_response(exchange.get_response().clone());
+            if !changed.contains(&id) {
+                changed.push(id);
+            }
+        }
+
+        if !changed.is_empty() {
+            self.set_dirty();
+        }
+
+        (changed, warnings)
+    }
+}
+
+#[cfg(test)]
+mod rockery_proxy_tests {
+    use std::io::{Read, Write};
+
+    use crate::rockery_test_helper::serve;
+    use super::*;
+
+    fn send(proxy : &RockeryRecordingProxy, raw : &str) -> String {
+        let mut stream = TcpStream::connect(proxy.get_address()).unwrap();
+        stream.write_all(raw.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn test_record() {
+        let (base_url, requests) = serve(vec![(200, r#"{"id":7}"#), (201, r#"{"id":8}"#), (200, r#"{"id":9}"#)]);
+        let host = base_url.trim_start_matches("http://").to_owned();
+        let mut proxy = RockeryRecordingProxy::start("127.0.0.1:0").unwrap();
+
+        let response = send(&proxy, &format!("GET {}/users/7?lang=de HTTP/1.1\r\nHost: {}\r\nX-Tenant: a\r\nUser-Agent: test\r\nProxy-Connection: keep-alive\r\n\r\n", base_url, host));
+        assert!(response.starts_with("HTTP/1.1 200 "));
+        assert!(response.ends_with(r#"{"id":7}"#));
+        let forwarded = requests.recv().unwrap();
+        assert!(forwarded.starts_with("GET /users/7?lang=de HTTP/1.1"));
+        assert!(forwarded.to_lowercase().contains("x-tenant: a"));
+        assert!(!forwarded.to_lowercase().contains("proxy-connection"));
+
+        send(&proxy, &format!("POST /users HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: 12\r\n\r\n{{\"name\":\"a\"}}", host));
+        assert!(requests.recv().unwrap().ends_with(r#"{"name":"a"}"#));
+        assert!(send(&proxy, "CONNECT example.com:443 HTTP/1.1\r\n\r\n").starts_with("HTTP/1.1 501 "));
+
+        let mut project = RockeryProject::new("Recorded");
+        let mut existing = RockeryService::new(RockeryServiceType::Rest, &base_url);
+        existing.set_name(&host);
+        existing.add_method("get user", "users/{id}");
+        project.add_service(existing);
+
+        let recorded = proxy.take_recorded();
+        assert_eq!(recorded.len(), 2);
+        assert_eq!(project.add_recording(&recorded), (vec![host.clone()], Vec::new()));
+
+        send(&proxy, &format!("GET {}/users/9 HTTP/1.1\r\nHost: {}\r\n\r\n", base_url, host));
+        project.add_recording(&proxy.get_recorded());
+        proxy.stop();
+
+        let service = project.find_service(&host).unwrap();
+        assert_eq!(service.get_methods().len(), 2);
+        let get = &service.get_methods()[0];
+        assert_eq!(get.get_parameter(), &vec![
+            RequestParameter::Url("lang".to_owned(), Some("de".to_owned())),
+            RequestParameter::Header("X-Tenant".to_owned(), Some("a".to_owned())),
+        ]);
+        let bodies : Vec<&str> = get.get_responses().iter().map(|r| r.get_body()).collect();
+        assert_eq!(bodies, vec![r#"{"id":7}"#, r#"{"id":9}"#]);
+
+        let post = &service.get_methods()[1];
+        assert_eq!((post.get_name(), post.get_uri(), post.get_request_method()), ("POST /users", "users", RequestMethod::Post));
+        assert_eq!(post.get_body(), &Some(r#"{"name":"a"}"#.to_owned()));
+        assert_eq!(post.get_responses()[0].get_status(), 201);
+
+        // Recorded responses are replayed by the mock server.
+        let mut mock = RockeryMockServer::start(service, "127.0.0.1:0").unwrap();
+        let mut client = service.clone();
+        client.set_base_url(&mock.get_url());
+        let (status, _, _, body) = client.do_request(1, &[]).unwrap();
+        assert_eq!((status, body), (201, br#"{"id":8}"#.to_vec()));
+        mock.stop();
+
+        let mut post = post.clone();
+        post.set_assertions_from_response(0).unwrap();
+        assert_eq!(post.get_assertions()[0], RockeryAssertion::StatusCode(201));
+        assert!(post.set_assertions_from_response(1).is_err());
+    }
+
+    #[test]
+    fn test_binary_response() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let server = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buffer = [0u8; 4096];
+            let _ = stream.read(&mut buffer);
+            let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Type: image/png\r\nContent-Length: 4\r\nConnection: close\r\n\r\n\x89PNG");
+        });
+
+        // The client receives the bytes of the server, only the recording contains the body as text.
+        let mut proxy = RockeryRecordingProxy::start("127.0.0.1:0").unwrap();
+        let mut stream = TcpStream::connect(proxy.get_address()).unwrap();
+        stream.write_all(format!("GET http://{}/logo.png HTTP/1.1\r\nHost: {}\r\n\r\n", server, server).as_bytes()).unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).unwrap();
+        assert!(response.ends_with(b"\r\n\r\n\x89PNG"));
+        assert_eq!(proxy.take_recorded()[0].get_response().get_body(), "\u{fffd}PNG");
+
+        // Requests to the proxy itself are not forwarded.
+        let response = send(&proxy, &format!("GET {}/loop HTTP/1.1\r\n\r\n", proxy.get_url()));
+        assert!(response.starts_with("HTTP/1.1 502 "));
+        assert!(response.ends_with("is addressed to the proxy itself."));
+        let response = send(&proxy, &format!("GET /loop HTTP/1.1\r\nHost: localhost:{}\r\n\r\n", proxy.get_address().port()));
+        assert!(response.ends_with("is addressed to the proxy itself."));
+        assert!(proxy.take_recorded().is_empty());
+        proxy.stop();
+    }
+
+    #[test]
+    fn test_invalid_requests() {
+        assert!(RockeryRecordingProxy::start("not an address").is_err());
+
+        let mut proxy = RockeryRecordingProxy::start("127.0.0.1:0").unwrap();
+        assert!(send(&proxy, "garbage\r\n\r\n").starts_with("HTTP/1.1 400 "));
+        assert!(send(&proxy, "GET /users HTTP/1.1\r\nContent-Length: many\r\n\r\n").starts_with("HTTP/1.1 400 "));
+        let response = send(&proxy, "GET /users HTTP/1.1\r\nAccept: */*\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 502 "));
+        assert!(response.ends_with("The request has neither an absolute url nor a host header."));
+        assert!(proxy.take_recorded().is_empty());
+        proxy.stop();
+
+        // Exchanges that can not be added are skipped, the others are still added.
+        let exchange = |method : &str, url : &str| RockeryExchange::new(RockeryRequest::new(method, url, Vec::new(), ""),
+            RockeryResponse::new(200, "OK", &ehttp::Headers::new(&[]), b"", Duration::ZERO));
+        let mut project = RockeryProject::from_json(r#"{"name": "Recorded", "services": {}}"#).unwrap();
+        let (changed, warnings) = project.add_recording(&[exchange("GET", "/relative"), exchange("FETCH", "http://example.com/items")]);
+        assert!(changed.is_empty() && !project.is_dirty());
+        assert_eq!(warnings, vec![
+            "Request GET /relative is skipped, the url is invalid.".to_owned(),
+            "Request FETCH http://example.com/items is skipped, the request method is unknown.".to_owned(),
+        ]);
+        let (changed, warnings) = project.add_recording(&[exchange("FETCH", "http://example.com/items"), exchange("GET", "http://example.com/items")]);
+        assert_eq!((changed, warnings.len()), (vec!["example.com".to_owned()], 1));
+        assert!(project.is_dirty());
+    }
+}
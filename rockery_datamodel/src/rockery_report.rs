@@ -0,0 +1,326 @@
+use std::{fmt::Write, fs, path::Path, time::{SystemTime, UNIX_EPOCH}};
+
+use quick_xml::escape::escape;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::prelude::*;
+
+/// The formats a test report can be written in.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum RockeryReportFormat {
+    /// JUnit XML as understood by most CI servers. Every test case becomes a JUnit test case.
+    JUnit,
+    Json,
+
+    /// A single html file without external resources.
+    Html,
+
+    /// The requests and responses of all steps as HTTP Archive.
+    Har,
+}
+
+impl RockeryReportFormat {
+    /// Derive the format from the extension of a file name: `.xml`, `.json`, `.html`, `.htm` or `.har`.
+    pub fn from_path(path : &Path) -> Result<Self, String> {
+        match path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase()).as_deref() {
+            Some("xml") => Ok(RockeryReportFormat::JUnit),
+            Some("json") => Ok(RockeryReportFormat::Json),
+            Some("html") | Some("htm") => Ok(RockeryReportFormat::Html),
+            Some("har") => Ok(RockeryReportFormat::Har),
+            _ => Err(format!("Can not derive a report format from {}. Use .xml, .json, .html or .har.", path.display())),
+        }
+    }
+}
+
+/// The results of one or more test suites together with the time they have been run at.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RockeryTestReport {
+    name : String,
+
+    /// Time the report has been created at in RFC 3339 format.
+    timestamp : String,
+    suites : Vec<RockeryTestSuiteResult>,
+}
+
+impl RockeryTestReport {
+    pub fn new(name : &str, suites : Vec<RockeryTestSuiteResult>) -> Self {
+        RockeryTestReport { name : name.to_owned(), timestamp : format_timestamp(SystemTime::now()), suites }
+    }
+
+    pub fn get_name(&self) -> &str { &self.name }
+    pub fn get_timestamp(&self) -> &str { &self.timestamp }
+    pub fn get_suites(&self) -> &Vec<RockeryTestSuiteResult> { &self.suites }
+
+    pub fn get_test_cases(&self) -> usize { self.suites.iter().map(|s| s.get_test_cases().len()).sum() }
+    pub fn get_passed(&self) -> usize { self.suites.iter().map(|s| s.get_test_cases().iter().filter(|t| t.is_passed()).count()).sum() }
+    pub fn get_failed(&self) -> usize { self.get_test_cases() - self.get_passed() }
+    pub fn get_steps(&self) -> usize { self.suites.iter().flat_map(|s| s.get_test_cases()).map(|t| t.get_steps().len()).sum() }
+    pub fn get_duration(&self) -> u64 { self.suites.iter().map(|s| s.get_duration()).sum() }
+    pub fn is_passed(&self) -> bool { self.get_failed() == 0 }
+
+    pub fn render(&self, format : RockeryReportFormat) -> String {
+        match format {
+            RockeryReportFormat::JUnit => self.to_junit(),
+            RockeryReportFormat::Json => self.to_json(),
+            RockeryReportFormat::Html => self.to_html(),
+            RockeryReportFormat::Har => self.to_har(),
+        }
+    }
+
+    /// Write the report to a file. The format is derived from the extension of the path.
+    pub fn save(&self, path : &Path) -> Result<(), String> {
+        let format = RockeryReportFormat::from_path(path)?;
+        fs::write(path, self.render(format)).map_err(|e| format!("Could not write report {}. Error: {}", path.display(), e))
+    }
+
+    /// JUnit XML. Test cases with steps that could not be executed are reported as errors, test cases
+    /// with failed checks as failures.
+    pub fn to_junit(&self) -> String {
+        let errors = |t : &RockeryTestCaseResult| t.get_steps().iter().any(|s| s.get_error().is_some());
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+
+        let all : Vec<&RockeryTestCaseResult> = self.suites.iter().flat_map(|s| s.get_test_cases()).collect();
+        let error_count = all.iter().filter(|t| errors(t)).count();
+        let _ = writeln!(xml, "<testsuites name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{}\" timestamp=\"{}\">",
+            escape(&self.name), all.len(), self.get_failed() - error_count, error_count, seconds(self.get_duration()), self.timestamp);
+
+        for suite in &self.suites {
+            let test_cases = suite.get_test_cases();
+            let failed = test_cases.iter().filter(|t| !t.is_passed()).count();
+            let error_count = test_cases.iter().filter(|t| errors(t)).count();
+            let _ = writeln!(xml, "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{}\" timestamp=\"{}\">",
+                escape(suite.get_name()), test_cases.len(), failed - error_count, error_count, seconds(suite.get_duration()), self.timestamp);
+
+            for t in test_cases {
+                let _ = writeln!(xml, "    <testcase name=\"{}\" classname=\"{}\" time=\"{}\">",
+                    escape(t.get_name()), escape(suite.get_name()), seconds(t.get_duration()));
+
+                if !t.is_passed() {
+                    let failed_steps : Vec<&RockeryStepResult> = t.get_steps().iter().filter(|s| !s.is_passed()).collect();
+                    let message = format!("{} of {} steps failed", failed_steps.len(), t.get_steps().len());
+                    let details : String = failed_steps.iter().map(|s| s.to_string()).collect();
+                    let tag = if errors(t) { "error" } else { "failure" };
+                    let _ = writeln!(xml, "      <{} message=\"{}\" type=\"{}\">{}</{}>", tag, message, tag, escape(&details), tag);
+                }
+
+                let _ = writeln!(xml, "      <system-out>{}</system-out>", escape(&t.to_string()));
+                xml.push_str("    </testcase>\n");
+            }
+
+            xml.push_str("  </testsuite>\n");
+        }
+
+        xml.push_str("</testsuites>\n");
+        xml
+    }
+
+    /// The results together with the overall statistics.
+    pub fn to_json(&self) -> String {
+        let report = json!({
+            "name" : self.name,
+            "timestamp" : self.timestamp,
+            "statistics" : {
+                "test_cases" : self.get_test_cases(),
+                "passed" : self.get_passed(),
+                "failed" : self.get_failed(),
+                "steps" : self.get_steps(),
+                "duration" : self.get_duration(),
+            },
+            "suites" : self.suites,
+        });
+
+        serde_json::to_string_pretty(&report).unwrap_or_default()
+    }
+
+    /// A self-contained html page. Failed steps show the request that has been sent and the response received.
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        let _ = write!(html, "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n",
+            escape(&self.name), HTML_STYLE);
+
+        let _ = writeln!(html, "<h1>{} <span class=\"{}\">{}</span></h1>", escape(&self.name), state_class(self.is_passed()), state_text(self.is_passed()));
+        let _ = writeln!(html, "<p>{}</p>", self.timestamp);
+
+        let rate = if self.get_test_cases() == 0 { 100.0 } else { self.get_passed() as f64 * 100.0 / self.get_test_cases() as f64 };
+        html.push_str("<table class=\"stats\">\n");
+        for (name, value) in [
+            ("Test suites", self.suites.len().to_string()),
+            ("Test cases", self.get_test_cases().to_string()),
+            ("Passed", self.get_passed().to_string()),
+            ("Failed", self.get_failed().to_string()),
+            ("Steps", self.get_steps().to_string()),
+            ("Pass rate", format!("{:.1} %", rate)),
+            ("Duration", format!("{} ms", self.get_duration())),
+        ] {
+            let _ = writeln!(html, "<tr><th>{}</th><td>{}</td></tr>", name, value);
+        }
+        html.push_str("</table>\n");
+
+        for suite in &self.suites {
+            let _ = writeln!(html, "<h2>{} <span class=\"{}\">{}</span> <small>{} ms</small></h2>",
+                escape(suite.get_name()), state_class(suite.is_passed()), state_text(suite.is_passed()), suite.get_duration());
+
+            for t in suite.get_test_cases() {
+                let _ = writeln!(html, "<details{}>\n<summary>{} <span class=\"{}\">{}</span> <small>{} ms</small></summary>",
+                    if t.is_passed() { "" } else { " open" }, escape(t.get_name()), state_class(t.is_passed()), state_text(t.is_passed()), t.get_duration());
+                html.push_str("<table class=\"steps\">\n<tr><th>Step</th><th>Result</th><th>Status</th><th>Time</th></tr>\n");
+
+                for s in t.get_steps() {
+                    let status = s.get_result().as_ref()
+                        .map(|r| format!("{} {}", r.get_response().get_status(), r.get_response().get_status_text()))
+                        .unwrap_or_default();
+                    let _ = writeln!(html, "<tr><td>{}</td><td class=\"{}\">{}</td><td>{}</td><td>{} ms</td></tr>",
+                        escape(s.get_name()), state_class(s.is_passed()), state_text(s.is_passed()), escape(&status), s.get_duration());
+
+                    if !s.is_passed() {
+                        html.push_str("<tr><td colspan=\"4\">");
+                        write_step_details(&mut html, s);
+                        html.push_str("</td></tr>\n");
+                    }
+                }
+
+                html.push_str("</table>\n</details>\n");
+            }
+        }
+
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+}
+
+const HTML_STYLE : &str = "body { font-family: sans-serif; margin: 2em; } \
+table { border-collapse: collapse; margin-bottom: 1em; } \
+th, td { border: 1px solid #ccc; padding: 4px 8px; text-align: left; vertical-align: top; } \
+.steps { width: 100%; } \
+.pass { color: #1a7f37; font-weight: bold; } \
+.fail { color: #cf222e; font-weight: bold; } \
+pre { background: #f6f8fa; padding: 8px; overflow-x: auto; margin: 4px 0; } \
+summary { cursor: pointer; font-size: 1.1em; margin: 0.5em 0; }";
+
+fn state_class(passed : bool) -> &'static str { if passed { "pass" } else { "fail" } }
+fn state_text(passed : bool) -> &'static str { if passed { "PASS" } else { "FAIL" } }
+
+fn seconds(milliseconds : u64) -> String {
+    format!("{:.3}", milliseconds as f64 / 1000.0)
+}
+
+/// The error, the checks, the request and the response of a step.
+fn write_step_details(html : &mut String, step : &RockeryStepResult) {
+    if let Some(e) = step.get_error() {
+        let _ = writeln!(html, "<p class=\"fail\">{}</p>", escape(e));
+    }
+
+    let result = match step.get_result() {
+        Some(r) => r,
+        None => return,
+    };
+
+    let checks : Vec<String> = result.get_violations().iter().map(|v| v.to_string())
+        .chain(result.get_assertions().iter().map(|a| a.to_string()))
+        .chain(step.get_transfers().iter().filter(|(_, v)| v.is_none()).map(|(n, _)| format!("${{{}}}: nothing extracted", n)))
+        .collect();
+    if !checks.is_empty() {
+        html.push_str("<ul>\n");
+        for c in checks {
+            let _ = writeln!(html, "<li>{}</li>", escape(&c));
+        }
+        html.push_str("</ul>\n");
+    }
+
+    let request = result.get_request();
+    let mut text = format!("{} {}\n", request.get_method(), request.get_url());
+    for (k, v) in request.get_headers() {
+        let _ = writeln!(text, "{}: {}", k, v);
+    }
+    if !request.get_body().is_empty() {
+        let _ = write!(text, "\n{}", request.get_body());
+    }
+    let _ = writeln!(html, "<b>Request</b>\n<pre>{}</pre>", escape(&text));
+
+    let response = result.get_response();
+    let mut text = format!("{} {}\n", response.get_status(), response.get_status_text());
+    for (k, v) in response.get_headers() {
+        let _ = writeln!(text, "{}: {}", k, v);
+    }
+    if !response.get_body().is_empty() {
+        let _ = write!(text, "\n{}", response.get_body());
+    }
+    let _ = writeln!(html, "<b>Response</b>\n<pre>{}</pre>", escape(&text));
+}
+
+/// Format a point in time as RFC 3339 in UTC, e.g. `2024-05-01T12:30:00Z`.
+pub(crate) fn format_timestamp(time : SystemTime) -> String {
+    let seconds = time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let (days, rest) = ((seconds / 86400) as i64, seconds % 86400);
+
+    // Convert days since 1970-01-01 into a civil date.
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", year, month, day, rest / 3600, rest % 3600 / 60, rest % 60)
+}
+
+#[cfg(test)]
+mod rockery_report_tests {
+    use std::time::Duration;
+
+    use crate::rockery_test_helper::serve;
+    use super::*;
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+        assert_eq!(format_timestamp(UNIX_EPOCH + Duration::from_secs(951782400 + 3723)), "2000-02-29T01:02:03Z");
+        assert_eq!(format_timestamp(UNIX_EPOCH + Duration::from_secs(1735689599)), "2024-12-31T23:59:59Z");
+    }
+
+    #[test]
+    fn test_reports() {
+        let (base_url, _requests) = serve(vec![(200, r#"{"id":1}"#), (500, r#"{"error":"<oops>"}"#)]);
+        let mut service = RockeryService::new(RockeryServiceType::Rest, &base_url);
+        service.set_name("Users");
+        service.add_method("get user", "users/1");
+
+        let mut project = RockeryProject::new("Report");
+        project.add_service(service);
+
+        let mut suite = RockeryTestSuite::new("Smoke & more");
+        suite.add_test_case(RockeryTestCase::new("Passing")).add_step(RockeryTestStep::new("Get", "Users", "get user"))
+            .add_assertion(RockeryAssertion::StatusCode(200));
+        suite.add_test_case(RockeryTestCase::new("Failing")).add_step(RockeryTestStep::new("Get", "Users", "get user"))
+            .add_assertion(RockeryAssertion::StatusCode(200));
+        suite.add_test_case(RockeryTestCase::new("Broken")).add_step(RockeryTestStep::new("Get", "Users", "missing"));
+
+        let report = RockeryTestReport::new("Report", vec![suite.run(&project)]);
+        assert_eq!((report.get_test_cases(), report.get_passed(), report.get_failed(), report.get_steps()), (3, 1, 2, 3));
+        assert!(!report.is_passed());
+
+        let junit = report.to_junit();
+        assert!(junit.contains("<testsuite name=\"Smoke &amp; more\" tests=\"3\" failures=\"1\" errors=\"1\""));
+        assert!(junit.contains("<failure message=\"1 of 1 steps failed\" type=\"failure\">"));
+        assert!(junit.contains("<error message=\"1 of 1 steps failed\" type=\"error\">"));
+        assert!(crate::rockery_xml::XmlElement::parse(&junit).is_ok());
+
+        let json : serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+        assert_eq!(json["statistics"]["failed"], 2);
+        assert_eq!(json["suites"][0]["test_cases"][1]["steps"][0]["result"]["request"]["method"], "GET");
+
+        let html = report.to_html();
+        assert!(html.contains("<title>Report</title>"));
+        assert!(html.contains("<tr><th>Failed</th><td>2</td></tr>"));
+        assert!(html.contains(&format!("GET {}", base_url)));
+        assert!(html.contains("{&quot;error&quot;:&quot;&lt;oops&gt;&quot;}"));
+        assert!(!html.contains(r#"{"id":1}"#));
+
+        assert_eq!(RockeryReportFormat::from_path(Path::new("out/report.XML")), Ok(RockeryReportFormat::JUnit));
+        assert!(RockeryReportFormat::from_path(Path::new("report.txt")).is_err());
+    }
+}
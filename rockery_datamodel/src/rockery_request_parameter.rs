@@ -7,6 +7,27 @@ pub enum RequestParameter {
     Url(String, Option<String>),
     Header(String, Option<String>),
     Body(String, Option<String>),
+    /// A placeholder within the method uri, e.g. `{id}` in `users/{id}`.
+    Path(String, Option<String>),
+}
+
+impl RequestParameter {
+    pub fn get_name(&self) -> &str {
+        match self {
+            RequestParameter::Url(k, _) | RequestParameter::Header(k, _) | RequestParameter::Body(k, _) | RequestParameter::Path(k, _) => k,
+        }
+    }
+
+    pub fn get_value(&self) -> &Option<String> {
+        match self {
+            RequestParameter::Url(_, v) | RequestParameter::Header(_, v) | RequestParameter::Body(_, v) | RequestParameter::Path(_, v) => v,
+        }
+    }
+
+    /// Checks if both parameters are of the same kind and have the same name, regardless of their values.
+    pub fn is_same_parameter(&self, other : &RequestParameter) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other) && self.get_name() == other.get_name()
+    }
 }
 
 impl fmt::Display for RequestParameter {
@@ -15,6 +36,7 @@ impl fmt::Display for RequestParameter {
             RequestParameter::Url(k, v) => write!(f, "URL({} : {})", k, v.clone().unwrap_or("None".to_owned())),
             RequestParameter::Header(k, v) => write!(f, "HEADER({} : {})", k, v.clone().unwrap_or("None".to_owned())),
             RequestParameter::Body(k,v ) => write!(f, "BODY({} : {})", k, v.clone().unwrap_or("None".to_owned())),
+            RequestParameter::Path(k, v) => write!(f, "PATH({} : {})", k, v.clone().unwrap_or("None".to_owned())),
         }
     }
 }
@@ -52,6 +74,11 @@ impl FromStr for RequestParameter {
             return Ok(RequestParameter::Body(k, v)); 
         }
 
+        if input.starts_with("PATH") {
+            let (k, v) = deserialization_helper(input, "PATH").unwrap();
+            return Ok(RequestParameter::Path(k, v)); 
+        }
+
         Err(())
     }
 }
@@ -79,5 +106,11 @@ mod rockery_request_parameter_tests {
 
         subject = RequestParameter::Body("API".to_owned(), Some("Something".to_owned()));
         assert_eq!(subject, RequestParameter::from_str(&subject.to_string()).unwrap());
+
+        subject = RequestParameter::Path("API".to_owned(), None);
+        assert_eq!(subject, RequestParameter::from_str(&subject.to_string()).unwrap());
+
+        subject = RequestParameter::Path("API".to_owned(), Some("Something".to_owned()));
+        assert_eq!(subject, RequestParameter::from_str(&subject.to_string()).unwrap());
     }
 }
\ No newline at end of file
@@ -0,0 +1,123 @@
+use std::time::Duration;
+
+use ehttp::{Headers, Request};
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::{RockeryAssertionResult, RockerySchemaViolation};
+
+/// A request as it has been sent, i.e. with all parameters, variables and credentials applied.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct RockeryRequest {
+    method : String,
+    url : String,
+    headers : Vec<(String, String)>,
+    body : String,
+}
+
+impl RockeryRequest {
+    pub fn new(method : &str, url : &str, headers : Vec<(String, String)>, body : &str) -> Self {
+        RockeryRequest { method : method.to_owned(), url : url.to_owned(), headers, body : body.to_owned() }
+    }
+
+    pub fn get_method(&self) -> &str { &self.method }
+    pub fn get_url(&self) -> &str { &self.url }
+    pub fn get_headers(&self) -> &Vec<(String, String)> { &self.headers }
+    pub fn get_body(&self) -> &str { &self.body }
+
+    /// Get the value of the first header with the given name. The lookup is case-insensitive.
+    pub fn get_header(&self, name : &str) -> Option<&str> {
+        self.headers.iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+impl From<&Request> for RockeryRequest {
+    fn from(request : &Request) -> Self {
+        RockeryRequest {
+            method : request.method.clone(),
+            url : request.url.clone(),
+            headers : request.headers.headers.clone(),
+            body : String::from_utf8_lossy(&request.body).to_string(),
+        }
+    }
+}
+
+/// A response received for a request. Responses can be stored with their method, e.g. to derive
+/// a definition of the method from what the service actually returned.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct RockeryResponse {
+    status : u16,
+    status_text : String,
+    headers : Vec<(String, String)>,
+    body : String,
+
+    /// Time it took to receive the response in milliseconds.
+    #[serde(default)]
+    duration : u64,
+}
+
+impl RockeryResponse {
+    pub fn new(status : u16, status_text : &str, headers : &Headers, bytes : &[u8], duration : Duration) -> Self {
+        RockeryResponse {
+            status,
+            status_text : status_text.to_owned(),
+            headers : headers.headers.clone(),
+            body : String::from_utf8_lossy(bytes).to_string(),
+            duration : duration.as_millis() as u64,
+        }
+    }
+
+    pub fn get_status(&self) -> u16 { self.status }
+    pub fn get_status_text(&self) -> &str { &self.status_text }
+    pub fn get_headers(&self) -> &Vec<(String, String)> { &self.headers }
+    pub fn get_body(&self) -> &str { &self.body }
+    pub fn get_duration(&self) -> u64 { self.duration }
+
+    /// Get the value of the first header with the given name. The lookup is case-insensitive.
+    pub fn get_header(&self, name : &str) -> Option<&str> {
+        self.headers.iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// The media type of the body without parameters like the charset.
+    pub fn get_content_type(&self) -> Option<&str> {
+        self.get_header("Content-Type").map(|c| c.split(';').next().unwrap_or(c).trim())
+    }
+}
+
+/// A response together with the result of checking it against the schema and the assertions of its method.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct RockeryRequestResult {
+    /// The request that has been sent. Empty for results stored before requests have been recorded.
+    #[serde(default)]
+    request : RockeryRequest,
+    response : RockeryResponse,
+
+    /// Differences between the body of the response and the schema declared by the definition.
+    violations : Vec<RockerySchemaViolation>,
+
+    #[serde(default)]
+    assertions : Vec<RockeryAssertionResult>,
+}
+
+impl RockeryRequestResult {
+    pub fn new(response : RockeryResponse, violations : Vec<RockerySchemaViolation>, assertions : Vec<RockeryAssertionResult>) -> Self {
+        RockeryRequestResult { request : RockeryRequest::default(), response, violations, assertions }
+    }
+
+    pub fn get_request(&self) -> &RockeryRequest { &self.request }
+    pub fn set_request(&mut self, request : RockeryRequest) { self.request = request; }
+
+    pub fn get_response(&self) -> &RockeryResponse { &self.response }
+    pub fn get_violations(&self) -> &Vec<RockerySchemaViolation> { &self.violations }
+    pub fn get_assertions(&self) -> &Vec<RockeryAssertionResult> { &self.assertions }
+    pub fn add_assertions(&mut self, assertions : Vec<RockeryAssertionResult>) { self.assertions.extend(assertions); }
+
+    /// True if the response matches its schema.
+    pub fn is_valid(&self) -> bool { self.violations.is_empty() }
+
+    /// True if the response matches its schema and all assertions passed.
+    pub fn is_passed(&self) -> bool { self.is_valid() && self.assertions.iter().all(|a| a.is_passed()) }
+}
@@ -0,0 +1,116 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::prelude::RockeryResponse;
+use crate::rockery_json_schema::validate_json;
+use crate::rockery_xsd::validate_xml;
+
+/// A schema describing the body of a response.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum RockerySchema {
+    /// A json schema. References must be resolved, i.e. the schema has to be self-contained.
+    Json(Value),
+
+    /// A xml schema (or a wsdl document containing one) and optionally the name of the global element
+    /// the response is expected to contain.
+    Xsd(String, Option<String>),
+
+    /// The xml schema of the service the method belongs to (see `RockeryService::get_xml_schema`) and
+    /// optionally the name of the global element. Large wsdl documents are stored only once that way.
+    ServiceXsd(Option<String>),
+}
+
+/// The schema a method declares for the responses with a certain status code.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RockeryResponseSchema {
+    /// Status code the schema applies to. Besides exact codes, ranges like `2XX` and `default` are supported.
+    status : String,
+    media_type : Option<String>,
+    schema : RockerySchema,
+}
+
+impl RockeryResponseSchema {
+    pub fn new(status : &str, media_type : Option<&str>, schema : RockerySchema) -> Self {
+        RockeryResponseSchema {
+            status : status.to_owned(),
+            media_type : media_type.map(|m| m.to_owned()),
+            schema,
+        }
+    }
+
+    pub fn get_status(&self) -> &str { &self.status }
+    pub fn get_media_type(&self) -> &Option<String> { &self.media_type }
+    pub fn get_schema(&self) -> &RockerySchema { &self.schema }
+
+    /// How well this schema fits a status code. Exact matches win over ranges, ranges over `default`.
+    pub(crate) fn status_rank(&self, status : u16) -> Option<u8> {
+        let code = status.to_string();
+        if self.status == code {
+            return Some(3);
+        }
+
+        let range = self.status.to_uppercase();
+        if range.len() == 3 && range.ends_with("XX") && code.starts_with(&range[..1]) {
+            return Some(2);
+        }
+
+        match self.status.as_str() {
+            "default" => Some(1),
+            _ => None,
+        }
+    }
+
+    /// Validate the body of a response against this schema.
+    pub fn validate(&self, response : &RockeryResponse) -> Vec<RockerySchemaViolation> {
+        self.validate_with(response, None)
+    }
+
+    /// Validate the body of a response against this schema, using the given xml schema of the service
+    /// for `ServiceXsd`.
+    pub(crate) fn validate_with(&self, response : &RockeryResponse, service_xsd : Option<&str>) -> Vec<RockerySchemaViolation> {
+        match &self.schema {
+            RockerySchema::Json(schema) => {
+                match serde_json::from_str::<Value>(response.get_body()) {
+                    Ok(body) => validate_json(schema, &body),
+                    Err(e) => vec![RockerySchemaViolation::new("$", "a json document", &format!("invalid json ({})", e))],
+                }
+            },
+            RockerySchema::Xsd(xsd, element) => validate_xml(xsd, element.as_deref(), response.get_body()),
+            RockerySchema::ServiceXsd(element) => match service_xsd {
+                Some(xsd) => validate_xml(xsd, element.as_deref(), response.get_body()),
+                None => vec![RockerySchemaViolation::new("/", "the xml schema of the service", "a service without xml schema")],
+            },
+        }
+    }
+}
+
+/// A part of a response that does not match the declared schema.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RockerySchemaViolation {
+    /// Location of the offending value, a json path like `$.items[0].id` or a xml path like `/order/item[1]`.
+    path : String,
+    expected : String,
+    actual : String,
+}
+
+impl RockerySchemaViolation {
+    pub fn new(path : &str, expected : &str, actual : &str) -> Self {
+        RockerySchemaViolation {
+            path : path.to_owned(),
+            expected : expected.to_owned(),
+            actual : actual.to_owned(),
+        }
+    }
+
+    pub fn get_path(&self) -> &str { &self.path }
+    pub fn get_expected(&self) -> &str { &self.expected }
+    pub fn get_actual(&self) -> &str { &self.actual }
+}
+
+impl fmt::Display for RockerySchemaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: expected {}, got {}", self.path, self.expected, self.actual)
+    }
+}
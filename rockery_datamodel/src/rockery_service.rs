@@ -1,8 +1,11 @@
-use std::{collections::HashMap, fmt};
+use std::{collections::HashMap, fmt, time::Instant};
 use ehttp::Headers;
 use serde::{Deserialize, Serialize};
 
 use crate::prelude::*;
+use crate::rockery_history::HistoryRecorder;
+use crate::rockery_property_transfer::{substitute, substitute_parameter};
+use crate::rockery_http::encode_component;
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct RockeryService
@@ -14,7 +17,15 @@ pub struct RockeryService
     base_url : String,
     definition_url : Option<String>, // Url or path to wsdl or wadl file
 
+    /// Xml schema, e.g. a wsdl document, the `ServiceXsd` response schemas of the methods refer to.
+    #[serde(default)]
+    xml_schema : Option<String>,
+
     methods : Vec<RockeryServiceMethod>,
+
+    /// Records the requests of this service into the history of its project.
+    #[serde(skip)]
+    history : Option<HistoryRecorder>,
 }
 
 impl RockeryService {
@@ -25,7 +36,9 @@ impl RockeryService {
             authentication : AuthenticationType::None(NoAuthenticationAuthentication {}),
             base_url : base_url.to_owned(),
             definition_url : None,
+            xml_schema : None,
             methods : Vec::new(),
+            history : None,
         }
     }
 
@@ -35,8 +48,23 @@ impl RockeryService {
     pub fn get_service_type(&self) -> &RockeryServiceType { &self.service_type }
     pub fn set_service_type(&mut self, t : RockeryServiceType) { self.service_type = t; }
 
+    pub fn get_authentication(&self) -> &AuthenticationType { &self.authentication }
     pub fn set_authentication(&mut self, t : AuthenticationType) { self.authentication = t; }
 
+    pub fn get_base_url(&self) -> &str { &self.base_url }
+    pub fn set_base_url(&mut self, url : &str) { self.base_url = url.to_owned(); }
+
+    pub fn get_definition_url(&self) -> &Option<String> { &self.definition_url }
+    pub fn set_definition_url(&mut self, url : &str) { self.definition_url = Some(url.to_owned()); }
+
+    pub fn get_xml_schema(&self) -> &Option<String> { &self.xml_schema }
+    pub fn set_xml_schema(&mut self, schema : Option<&str>) { self.xml_schema = schema.map(str::to_owned); }
+
+    pub(crate) fn set_history(&mut self, history : Option<HistoryRecorder>) { self.history = history; }
+
+    pub fn get_methods(&self) -> &Vec<RockeryServiceMethod> { &self.methods }
+    pub fn get_method_mut(&mut self, index : usize) -> Option<&mut RockeryServiceMethod> { self.methods.get_mut(index) }
+
     /// Add another metho to the list of methods and return the newly created item
     /// so it can be configured.
     pub fn add_method(&mut self, name : &str, uri : &str) -> &mut RockeryServiceMethod {
@@ -46,9 +74,32 @@ impl RockeryService {
         &mut self.methods[index]
     }
 
+    /// Add an existing method to the list of methods and return it.
+    pub fn add_service_method(&mut self, method : RockeryServiceMethod) -> &mut RockeryServiceMethod {
+        self.methods.push(method);
+        let index = self.methods.len() - 1;
+
+        &mut self.methods[index]
+    }
+
+    /// Removes the method with the given index and returns it.
+    pub fn remove_method(&mut self, index : usize) -> Result<RockeryServiceMethod, String> {
+        if index >= self.methods.len() {
+            return Err(format!("Invalid index. There are only {} methods but method {} was requested.", self.methods.len(), index));
+        }
+
+        Ok(self.methods.remove(index))
+    }
+
+    /// Get the index of the first method with the given name.
+    pub fn get_method_index(&self, name : &str) -> Option<usize> {
+        self.methods.iter().position(|m| m.get_name() == name)
+    }
+
+    /// Get the url of a request including the query. Names and values of url parameters are percent encoded.
     pub fn get_request_url(&self, method : usize, params : &[RequestParameter]) -> String {
         let m = &self.methods[method];
-        let mut url = format!("{}/{}/", self.base_url, m.get_uri());
+        let mut url = format!("{}/{}/", self.base_url, self.get_request_uri(method, params));
         let mut first = true;
 
         // Prepare a list of url parameters to check if we got default values.
@@ -78,17 +129,39 @@ impl RockeryService {
                 }
 
                 if url_params.contains_key(k) {
-                    url.push_str(format!("{}={}", k, url_params[k].as_ref().unwrap()).as_str());
+                    url.push_str(format!("{}={}", encode_component(k), encode_component(url_params[k].as_ref().unwrap())).as_str());
                     continue;
                 }
 
-                url.push_str(format!("{}={}", k, v.as_ref().unwrap()).as_str());
+                url.push_str(format!("{}={}", encode_component(k), encode_component(v.as_ref().unwrap())).as_str());
             }
         }
 
         url
     }
 
+    /// Get the uri of a method with all path placeholders replaced by either the provided value
+    /// or the default value of the parameter. Placeholders without a value are left untouched.
+    pub fn get_request_uri(&self, method : usize, params : &[RequestParameter]) -> String {
+        let m = &self.methods[method];
+        let mut uri = m.get_uri().to_owned();
+
+        for p in m.get_parameter() {
+            if let RequestParameter::Path(k, v) = p {
+                let value = params.iter().find_map(|f| match f {
+                    RequestParameter::Path(key, Some(value)) if key == k => Some(value),
+                    _ => None,
+                });
+
+                if let Some(value) = value.or(v.as_ref()) {
+                    uri = uri.replace(&format!("{{{}}}", k), value);
+                }
+            }
+        }
+
+        uri
+    }
+
     fn get_request_header(&self, method : usize, params : &[RequestParameter]) -> Headers {
         let header_params : HashMap<String, Option<String>> = 
             params.iter().filter_map(|f| {
@@ -121,6 +194,70 @@ impl RockeryService {
         headers
     }
 
+    /// Get the body of a request. If the method has a body, it is sent as is. Otherwise all body
+    /// parameters with a value are sent form url encoded.
+    pub fn get_request_body(&self, method : usize, params : &[RequestParameter]) -> Vec<u8> {
+        let m = &self.methods[method];
+        if let Some(body) = m.get_body() {
+            return body.as_bytes().to_vec();
+        }
+
+        let mut fields = Vec::new();
+        for p in m.get_parameter() {
+            if let RequestParameter::Body(k, v) = p {
+                let value = params.iter().find_map(|f| match f {
+                    RequestParameter::Body(key, Some(value)) if key == k => Some(value),
+                    _ => None,
+                });
+
+                if let Some(value) = value.or(v.as_ref()) {
+                    fields.push(format!("{}={}", encode_component(k), encode_component(value)));
+                }
+            }
+        }
+
+        fields.join("&").into_bytes()
+    }
+
+    /// Create a copy of this service in which all `${name}` placeholders within the base url and the
+    /// uri, parameters and body of the given method are replaced by the values of the variables.
+    pub fn with_variables(&self, method : usize, variables : &HashMap<String, String>) -> RockeryService {
+        let mut service = self.clone();
+        service.base_url = substitute(&self.base_url, variables);
+
+        if let Some(m) = service.methods.get_mut(method) {
+            m.set_uri(&substitute(m.get_uri(), variables));
+            let parameter = m.get_parameter().iter().map(|p| substitute_parameter(p, variables)).collect();
+            m.set_parameter(parameter);
+            if let Some(body) = m.get_body().clone() {
+                m.set_body(&substitute(&body, variables));
+            }
+        }
+
+        service
+    }
+
+    fn build_request(&self, method : usize, params : &[RequestParameter]) -> ehttp::Request {
+        let mut request = ehttp::Request::get(self.get_request_url(method, params));
+        request.method = self.methods[method].get_request_method().to_string();
+        request.body = self.get_request_body(method, params);
+        request.headers = self.get_request_header(method, params);
+
+        request
+    }
+
+    /// Get the request that is sent for the method identified by the provided index, including the credentials.
+    pub fn get_effective_request(&self, method : usize, params : &[RequestParameter]) -> Result<RockeryRequest, String> {
+        if method >= self.methods.len() {
+            return Err(format!("Invalid index. There are only {} methods but method {} was requested.", self.methods.len(), method));
+        }
+
+        let mut request = self.build_request(method, params);
+        self.authentication.apply(&mut request);
+
+        Ok(RockeryRequest::from(&request))
+    }
+
     /// Do a request for the method identified by the provided index.
     pub fn do_request(&self, method : usize, params : &[RequestParameter]) -> Result<(u16, String, Headers, Vec<u8>), String> {
         let status : u16;
@@ -133,16 +270,18 @@ impl RockeryService {
            return Err(message);
         }
 
-        let url = self.get_request_url(method, params);
-
-        let mut request = ehttp::Request::get(url);
-        request.method = self.methods[method].get_request_method().to_string();
-        request.url = self.get_request_url(method, params);
-        request.body = vec![];
-        request.headers = self.get_request_header(method, params);
-
+        let mut request = self.build_request(method, params);
+        let start = Instant::now();
         let r = self.authentication.do_request(&mut request);
 
+        if let Some(history) = &self.history {
+            let response = match &r {
+                Ok(res) => Ok(RockeryResponse::new(res.status, &res.status_text, &res.headers, &res.bytes, start.elapsed())),
+                Err(e) => Err(e.to_string()),
+            };
+            history.record(self.methods[method].get_name(), &RockeryRequest::from(&request), &self.authentication, response, start.elapsed().as_millis() as u64);
+        }
+
         match r {
             Ok(res) => {
                 status = res.status;
@@ -158,6 +297,31 @@ impl RockeryService {
 
         Ok((status, status_text, headers, bytes))
     }
+
+    /// Validate a response of a method against the schema the method declares for its status code.
+    /// If there is no schema for the response, there are no violations.
+    pub fn validate_response(&self, method : usize, response : &RockeryResponse) -> Vec<RockerySchemaViolation> {
+        match self.methods.get(method).and_then(|m| m.get_response_schema(response)) {
+            Some(schema) => schema.validate_with(response, self.xml_schema.as_deref()),
+            None => Vec::new(),
+        }
+    }
+
+    /// Do a request for the method identified by the provided index, validate the response and
+    /// evaluate the assertions of the method.
+    pub fn do_checked_request(&self, method : usize, params : &[RequestParameter]) -> Result<RockeryRequestResult, String> {
+        let request = self.get_effective_request(method, params)?;
+        let start = Instant::now();
+        let (status, status_text, headers, bytes) = self.do_request(method, params)?;
+        let response = RockeryResponse::new(status, &status_text, &headers, &bytes, start.elapsed());
+        let violations = self.validate_response(method, &response);
+        let assertions = self.methods[method].evaluate_assertions(&response);
+
+        let mut result = RockeryRequestResult::new(response, violations, assertions);
+        result.set_request(request);
+
+        Ok(result)
+    }
 }
 
 impl fmt::Debug for RockeryService {
@@ -197,6 +361,38 @@ mod rockery_service_test {
         assert_eq!("https://feiertage-api.de/api/?jahr=2016&nur_land=NI&nur_daten=1", url);
     }
 
+    #[test]
+    fn test_path_parameter() {
+        let mut service = RockeryService::new(RockeryServiceType::Rest, "https://example.com");
+        let m = service.add_method("get user", "users/{id}/posts/{post}");
+        m.add_parameter(RequestParameter::Path("id".to_owned(), Some("1".to_owned())));
+        m.add_parameter(RequestParameter::Path("post".to_owned(), None));
+
+        assert_eq!("https://example.com/users/1/posts/{post}/", service.get_request_url(0, &Vec::new()));
+
+        let params_override = vec![RequestParameter::Path("post".to_owned(), Some("7".to_owned()))];
+        assert_eq!("https://example.com/users/1/posts/7/", service.get_request_url(0, &params_override));
+    }
+
+    #[test]
+    fn test_request_body() {
+        let mut service = RockeryService::new(RockeryServiceType::Rest, "https://example.com");
+        let m = service.add_method("login", "login");
+        m.add_parameter(RequestParameter::Body("user".to_owned(), Some("rex".to_owned())));
+        m.add_parameter(RequestParameter::Body("password".to_owned(), None));
+
+        assert_eq!(service.get_request_body(0, &Vec::new()), b"user=rex".to_vec());
+        let params_override = vec![RequestParameter::Body("password".to_owned(), Some("a&b c".to_owned()))];
+        assert_eq!(service.get_request_body(0, &params_override), b"user=rex&password=a%26b+c".to_vec());
+
+        service.get_method_mut(0).unwrap().set_body("{ \"user\": \"${user}\" }");
+        assert_eq!(service.get_request_body(0, &params_override), b"{ \"user\": \"${user}\" }".to_vec());
+
+        let variables : HashMap<String, String> = [("user".to_owned(), "max".to_owned())].into();
+        let copy = service.with_variables(0, &variables);
+        assert_eq!(copy.get_request_body(0, &Vec::new()), b"{ \"user\": \"max\" }".to_vec());
+    }
+
     #[test]
     fn test_feiertage_request() {
         let service = get_feiertage_service();
@@ -205,6 +401,23 @@ mod rockery_service_test {
         assert_eq!(code, 200);
     }
 
+    #[test]
+    fn test_validate_response() {
+        let mut service = get_feiertage_service();
+        let schema = serde_json::json!({ "type": "object", "additionalProperties": { "type": "object", "required": ["datum"] } });
+        service.get_method_mut(0).unwrap().add_response_schema(RockeryResponseSchema::new("200", None, RockerySchema::Json(schema)));
+
+        let headers = Headers::new(&[("Content-Type", "application/json")]);
+        let valid = RockeryResponse::new(200, "OK", &headers, br#"{ "Neujahrstag": { "datum": "2024-01-01" } }"#, std::time::Duration::ZERO);
+        assert!(service.validate_response(0, &valid).is_empty());
+
+        let invalid = RockeryResponse::new(200, "OK", &headers, br#"{ "Neujahrstag": {} }"#, std::time::Duration::ZERO);
+        assert_eq!(service.validate_response(0, &invalid)[0].get_path(), "$.Neujahrstag.datum");
+
+        let error = RockeryResponse::new(404, "Not Found", &headers, b"", std::time::Duration::ZERO);
+        assert!(service.validate_response(0, &error).is_empty());
+    }
+
     #[test]
     fn test_invalid_index() {
         let service = get_feiertage_service();
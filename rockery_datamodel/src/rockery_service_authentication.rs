@@ -2,9 +2,24 @@ use base64::{engine::general_purpose, Engine};
 use ehttp::{Request, Response};
 use serde::{Deserialize, Serialize};
 
+use crate::prelude::RequestParameter;
+use crate::rockery_http::encode_component;
+
+/// Headers that carry credentials whatever the authentication of a service is.
+pub(crate) const CREDENTIAL_HEADERS : [&str; 2] = ["Authorization", "Cookie"];
+
+/// Replaces the value of a credential wherever it is stored or shown.
+pub(crate) const REDACTED : &str = "***";
+
 #[typetag::serde(tag = "authentication")]
 pub trait RockeryServiceAuthentication {
-    fn autenticate(&self, request : &mut Request)-> Result<Response, String> ;
+    /// Add the credentials to the request without sending it.
+    fn apply(&self, request : &mut Request);
+
+    fn autenticate(&self, request : &mut Request)-> Result<Response, String> {
+        self.apply(request);
+        ehttp::fetch_blocking(request)
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
@@ -14,9 +29,7 @@ pub struct NoAuthenticationAuthentication {
 
 #[typetag::serde]
 impl RockeryServiceAuthentication for NoAuthenticationAuthentication {
-    fn autenticate(&self, request : &mut Request) -> Result<Response, String> {
-        ehttp::fetch_blocking(request)
-    }
+    fn apply(&self, _request : &mut Request) { }
 }
 
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
@@ -25,14 +38,89 @@ pub struct BasicAuthenticationAuthentication {
     password : String,
 }
 
+impl BasicAuthenticationAuthentication {
+    pub fn new(username : &str, password : &str) -> Self {
+        BasicAuthenticationAuthentication {
+            username : username.to_owned(),
+            password : password.to_owned(),
+        }
+    }
+
+    pub fn get_username(&self) -> &str { &self.username }
+    pub fn get_password(&self) -> &str { &self.password }
+}
+
 #[typetag::serde]
 impl RockeryServiceAuthentication for BasicAuthenticationAuthentication {
-    fn autenticate(&self, request : &mut Request) -> Result<Response, String> {
-        let auth = format!("Basic {}:{}", self.username, self.password);
-        let base4 = general_purpose::STANDARD.encode(auth);
-        request.headers.insert("Authorization", base4);
+    fn apply(&self, request : &mut Request) {
+        let credentials = general_purpose::STANDARD.encode(format!("{}:{}", self.username, self.password));
+        request.headers.insert("Authorization", format!("Basic {}", credentials));
+    }
+}
 
-        ehttp::fetch_blocking(request)
+/// Sends a token in the authorization header, e.g. an OAuth2 access token or a JWT.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct BearerAuthenticationAuthentication {
+    token : String,
+}
+
+impl BearerAuthenticationAuthentication {
+    pub fn new(token : &str) -> Self {
+        BearerAuthenticationAuthentication { token : token.to_owned() }
+    }
+
+    pub fn get_token(&self) -> &str { &self.token }
+}
+
+#[typetag::serde]
+impl RockeryServiceAuthentication for BearerAuthenticationAuthentication {
+    fn apply(&self, request : &mut Request) {
+        request.headers.insert("Authorization", format!("Bearer {}", self.token));
+    }
+}
+
+/// Where an api key is sent.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+pub enum ApiKeyLocation {
+    #[default]
+    Header,
+    Query,
+}
+
+/// Sends a static key either as header or as query parameter.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct ApiKeyAuthenticationAuthentication {
+    name : String,
+    key : String,
+    location : ApiKeyLocation,
+}
+
+impl ApiKeyAuthenticationAuthentication {
+    pub fn new(name : &str, key : &str, location : ApiKeyLocation) -> Self {
+        ApiKeyAuthenticationAuthentication {
+            name : name.to_owned(),
+            key : key.to_owned(),
+            location,
+        }
+    }
+
+    pub fn get_name(&self) -> &str { &self.name }
+    pub fn get_key(&self) -> &str { &self.key }
+    pub fn get_location(&self) -> ApiKeyLocation { self.location }
+}
+
+#[typetag::serde]
+impl RockeryServiceAuthentication for ApiKeyAuthenticationAuthentication {
+    fn apply(&self, request : &mut Request) {
+        match self.location {
+            ApiKeyLocation::Header => {
+                request.headers.insert(&self.name, &self.key);
+            },
+            ApiKeyLocation::Query => {
+                let separator = if request.url.contains('?') { '&' } else { '?' };
+                request.url = format!("{}{}{}={}", request.url, separator, encode_component(&self.name), encode_component(&self.key));
+            },
+        }
     }
 }
 
@@ -40,18 +128,98 @@ impl RockeryServiceAuthentication for BasicAuthenticationAuthentication {
 pub enum AuthenticationType {
     None(NoAuthenticationAuthentication),
     Basic(BasicAuthenticationAuthentication),
+    Bearer(BearerAuthenticationAuthentication),
+    ApiKey(ApiKeyAuthenticationAuthentication),
 }
 
 impl AuthenticationType {
+    fn get_authentication(&self) -> &dyn RockeryServiceAuthentication {
+        match self {
+            AuthenticationType::None(no_authentication_authentication) => no_authentication_authentication,
+            AuthenticationType::Basic(basic_authentication_authentication) => basic_authentication_authentication,
+            AuthenticationType::Bearer(bearer_authentication_authentication) => bearer_authentication_authentication,
+            AuthenticationType::ApiKey(api_key_authentication_authentication) => api_key_authentication_authentication,
+        }
+    }
+
+    /// Add the credentials to the request without sending it.
+    pub fn apply(&self, request : &mut Request) {
+        self.get_authentication().apply(request)
+    }
+
     pub fn do_request(&self, request : &mut Request) -> Result<Response, String> {
+        self.get_authentication().autenticate(request)
+    }
+
+    /// The header or query parameter the credentials are sent in, without value.
+    pub fn get_credential_parameter(&self) -> Option<RequestParameter> {
         match self {
-            AuthenticationType::None(no_authentication_authentication) => {
-                no_authentication_authentication.autenticate(request)
-            },
-            
-            AuthenticationType::Basic(basic_authentication_authentication) => {
-                basic_authentication_authentication.autenticate(request)
+            AuthenticationType::None(_) => None,
+            AuthenticationType::Basic(_) | AuthenticationType::Bearer(_) => Some(RequestParameter::Header("Authorization".to_owned(), None)),
+            AuthenticationType::ApiKey(a) => match a.location {
+                ApiKeyLocation::Header => Some(RequestParameter::Header(a.name.clone(), None)),
+                ApiKeyLocation::Query => Some(RequestParameter::Url(a.name.clone(), None)),
             },
         }
     }
+
+    /// True if the header carries credentials, either like Authorization and Cookie always do or because
+    /// this authentication sends its key in it.
+    pub fn is_credential_header(&self, name : &str) -> bool {
+        CREDENTIAL_HEADERS.iter().any(|h| h.eq_ignore_ascii_case(name)) || match self.get_credential_parameter() {
+            Some(RequestParameter::Header(k, _)) => k.eq_ignore_ascii_case(name),
+            _ => false,
+        }
+    }
+
+    /// True if the query parameter carries the key of this authentication.
+    pub fn is_credential_query(&self, name : &str) -> bool {
+        matches!(self.get_credential_parameter(), Some(RequestParameter::Url(k, _)) if k == name)
+    }
+}
+
+impl Default for AuthenticationType {
+    fn default() -> Self {
+        AuthenticationType::None(NoAuthenticationAuthentication {})
+    }
+}
+
+#[cfg(test)]
+mod rockery_service_authentication_tests {
+    use super::*;
+
+    #[test]
+    fn test_apply() {
+        let mut request = Request::get("https://example.com/api");
+        AuthenticationType::Basic(BasicAuthenticationAuthentication::new("user", "pass")).apply(&mut request);
+        assert_eq!(request.headers.get("Authorization"), Some("Basic dXNlcjpwYXNz"));
+
+        let mut request = Request::get("https://example.com/api");
+        AuthenticationType::Bearer(BearerAuthenticationAuthentication::new("token")).apply(&mut request);
+        assert_eq!(request.headers.get("Authorization"), Some("Bearer token"));
+
+        let mut request = Request::get("https://example.com/api");
+        AuthenticationType::ApiKey(ApiKeyAuthenticationAuthentication::new("X-API-KEY", "key", ApiKeyLocation::Header)).apply(&mut request);
+        assert_eq!(request.headers.get("x-api-key"), Some("key"));
+
+        let mut request = Request::get("https://example.com/api?a=1");
+        AuthenticationType::ApiKey(ApiKeyAuthenticationAuthentication::new("api_key", "key", ApiKeyLocation::Query)).apply(&mut request);
+        assert_eq!(request.url, "https://example.com/api?a=1&api_key=key");
+
+        let mut request = Request::get("https://example.com/api");
+        AuthenticationType::ApiKey(ApiKeyAuthenticationAuthentication::new("api key", "a&b=c", ApiKeyLocation::Query)).apply(&mut request);
+        assert_eq!(request.url, "https://example.com/api?api+key=a%26b%3Dc");
+    }
+
+    #[test]
+    fn test_credential_parameter() {
+        let query = AuthenticationType::ApiKey(ApiKeyAuthenticationAuthentication::new("api_key", "key", ApiKeyLocation::Query));
+        assert_eq!(query.get_credential_parameter(), Some(RequestParameter::Url("api_key".to_owned(), None)));
+        assert!(query.is_credential_query("api_key") && !query.is_credential_header("api_key") && query.is_credential_header("cookie"));
+
+        let header = AuthenticationType::ApiKey(ApiKeyAuthenticationAuthentication::new("X-API-KEY", "key", ApiKeyLocation::Header));
+        assert!(header.is_credential_header("x-api-key") && !header.is_credential_query("X-API-KEY"));
+        assert!(AuthenticationType::default().is_credential_header("Authorization"));
+        assert_eq!(AuthenticationType::default().get_credential_parameter(), None);
+    }
 }
\ No newline at end of file
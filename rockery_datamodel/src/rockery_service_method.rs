@@ -1,11 +1,11 @@
 use std::fmt;
 use serde::{Deserialize, Serialize};
 
-use crate::prelude::{ RequestMethod, RequestParameter };
+use crate::prelude::{ RequestMethod, RequestParameter, RockeryAssertion, RockeryAssertionResult, RockeryMockResponse, RockeryResponse, RockeryResponseSchema };
 
 /// Each service consists of several service method. 
 /// Each method represents a single request against a service, that can be parameterized.
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct RockeryServiceMethod {
     name : String,
     method_uri : String,
@@ -19,6 +19,22 @@ pub struct RockeryServiceMethod {
     parameter : Vec<RequestParameter>,
 
     body : Option<String>,
+
+    /// Responses that have been recorded for this method.
+    #[serde(default)]
+    responses : Vec<RockeryResponse>,
+
+    /// Schemas the responses of this method are expected to match, usually taken from the service definition.
+    #[serde(default)]
+    response_schemas : Vec<RockeryResponseSchema>,
+
+    /// Checks that are evaluated against every response of this method.
+    #[serde(default)]
+    assertions : Vec<RockeryAssertion>,
+
+    /// Canned responses the mock server returns for this method.
+    #[serde(default)]
+    mock_responses : Vec<RockeryMockResponse>,
 }
 
 impl RockeryServiceMethod {
@@ -29,6 +45,10 @@ impl RockeryServiceMethod {
             request_method : RequestMethod::Get,
             parameter : Vec::new(),
             body : None,
+            responses : Vec::new(),
+            response_schemas : Vec::new(),
+            assertions : Vec::new(),
+            mock_responses : Vec::new(),
         }
     }
 
@@ -46,6 +66,10 @@ impl RockeryServiceMethod {
         &self.parameter
     }
 
+    pub fn set_parameter(&mut self, parameter : Vec<RequestParameter>) {
+        self.parameter = parameter;
+    }
+
     pub fn get_request_method(&self) -> RequestMethod {
         self.request_method
     }
@@ -56,7 +80,53 @@ impl RockeryServiceMethod {
 
     pub fn get_body(&self) -> &Option<String> { &self.body }
     pub fn set_body(&mut self, body : &str) { self.body = Some(body.to_owned()); }
+    pub fn clear_body(&mut self) { self.body = None; }
+
+    pub fn get_responses(&self) -> &Vec<RockeryResponse> { &self.responses }
+    pub fn add_response(&mut self, response : RockeryResponse) { self.responses.push(response); }
+    pub fn clear_responses(&mut self) { self.responses.clear(); }
+
+    pub fn get_response_schemas(&self) -> &Vec<RockeryResponseSchema> { &self.response_schemas }
+    pub fn add_response_schema(&mut self, schema : RockeryResponseSchema) { self.response_schemas.push(schema); }
+    pub fn set_response_schemas(&mut self, schemas : Vec<RockeryResponseSchema>) { self.response_schemas = schemas; }
+
+    /// Find the schema that applies to a response. The most specific status wins, i.e. `200` is
+    /// preferred over `2XX` and `default`. If there are schemas for several media types, the one
+    /// matching the content type of the response is used.
+    pub fn get_response_schema(&self, response : &RockeryResponse) -> Option<&RockeryResponseSchema> {
+        let rank = self.response_schemas.iter().filter_map(|s| s.status_rank(response.get_status())).max()?;
+        let candidates : Vec<&RockeryResponseSchema> = self.response_schemas.iter()
+            .filter(|s| s.status_rank(response.get_status()) == Some(rank))
+            .collect();
+
+        let content_type = response.get_content_type();
+        candidates.iter()
+            .find(|s| s.get_media_type().as_deref() == content_type)
+            .or(candidates.first())
+            .copied()
+    }
+
+    pub fn get_assertions(&self) -> &Vec<RockeryAssertion> { &self.assertions }
+    pub fn add_assertion(&mut self, assertion : RockeryAssertion) { self.assertions.push(assertion); }
+    pub fn set_assertions(&mut self, assertions : Vec<RockeryAssertion>) { self.assertions = assertions; }
 
+    /// Replace the assertions by the ones derived from a recorded response.
+    pub fn set_assertions_from_response(&mut self, index : usize) -> Result<(), String> {
+        let response = self.responses.get(index)
+            .ok_or(format!("Invalid index. There are only {} responses but response {} was requested.", self.responses.len(), index))?;
+        self.assertions = RockeryAssertion::from_response(response);
+
+        Ok(())
+    }
+
+    pub fn get_mock_responses(&self) -> &Vec<RockeryMockResponse> { &self.mock_responses }
+    pub fn add_mock_response(&mut self, response : RockeryMockResponse) { self.mock_responses.push(response); }
+    pub fn set_mock_responses(&mut self, responses : Vec<RockeryMockResponse>) { self.mock_responses = responses; }
+
+    /// Evaluate all assertions of this method against a response.
+    pub fn evaluate_assertions(&self, response : &RockeryResponse) -> Vec<RockeryAssertionResult> {
+        self.assertions.iter().map(|a| a.evaluate(response)).collect()
+    }
 }
 
 impl fmt::Display for RockeryServiceMethod {
@@ -71,6 +141,12 @@ impl fmt::Display for RockeryServiceMethod {
 
 #[cfg(test)]
 mod rockery_service_method_tests {
+    use std::time::Duration;
+
+    use ehttp::Headers;
+    use serde_json::json;
+
+    use crate::prelude::RockerySchema;
     use super::*;
 
     #[test]
@@ -100,6 +176,25 @@ mod rockery_service_method_tests {
         }
     }
 
+    #[test]
+    fn test_response_schema() {
+        let mut subject = RockeryServiceMethod::new("test","api");
+        subject.add_response_schema(RockeryResponseSchema::new("default", None, RockerySchema::Json(json!({ "type": "string" }))));
+        subject.add_response_schema(RockeryResponseSchema::new("2XX", Some("application/xml"), RockerySchema::Xsd("<schema/>".to_owned(), None)));
+        subject.add_response_schema(RockeryResponseSchema::new("2XX", Some("application/json"), RockerySchema::Json(json!({ "type": "object" }))));
+
+        let headers = Headers::new(&[("Content-Type", "application/json")]);
+        let ok = RockeryResponse::new(201, "Created", &headers, b"{}", Duration::ZERO);
+        let schema = subject.get_response_schema(&ok).unwrap();
+        assert_eq!(schema.get_media_type(), &Some("application/json".to_owned()));
+        assert!(schema.validate(&ok).is_empty());
+
+        let error = RockeryResponse::new(500, "Error", &headers, b"{}", Duration::ZERO);
+        let schema = subject.get_response_schema(&error).unwrap();
+        assert_eq!(schema.get_status(), "default");
+        assert_eq!(schema.validate(&error)[0].to_string(), "$: expected type string, got object {}");
+    }
+
 
 }
 
@@ -0,0 +1,365 @@
+use std::fmt;
+
+use crate::prelude::*;
+use crate::rockery_definition::import_definition;
+
+/// Describes how a method of a service differs from the same operation in its definition.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RockeryMethodChange {
+    name : String,
+    request_method : Option<(RequestMethod, RequestMethod)>,
+    uri : Option<(String, String)>,
+    added_parameters : Vec<RequestParameter>,
+    removed_parameters : Vec<RequestParameter>,
+
+    /// Parameters whose default in the definition differs from the current value (current, definition).
+    changed_parameters : Vec<(RequestParameter, RequestParameter)>,
+}
+
+impl RockeryMethodChange {
+    pub fn get_name(&self) -> &str { &self.name }
+    pub fn get_request_method(&self) -> &Option<(RequestMethod, RequestMethod)> { &self.request_method }
+    pub fn get_uri(&self) -> &Option<(String, String)> { &self.uri }
+    pub fn get_added_parameters(&self) -> &Vec<RequestParameter> { &self.added_parameters }
+    pub fn get_removed_parameters(&self) -> &Vec<RequestParameter> { &self.removed_parameters }
+    pub fn get_changed_parameters(&self) -> &Vec<(RequestParameter, RequestParameter)> { &self.changed_parameters }
+
+    fn is_empty(&self) -> bool {
+        self.request_method.is_none() && self.uri.is_none() && self.added_parameters.is_empty()
+            && self.removed_parameters.is_empty() && self.changed_parameters.is_empty()
+    }
+}
+
+/// The differences between a service and a newer version of its definition.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RockeryServiceDiff {
+    added : Vec<RockeryServiceMethod>,
+    removed : Vec<String>,
+    changed : Vec<RockeryMethodChange>,
+}
+
+impl RockeryServiceDiff {
+    /// Operations that are only part of the definition.
+    pub fn get_added(&self) -> &Vec<RockeryServiceMethod> { &self.added }
+
+    /// Names of the methods that are no longer part of the definition.
+    pub fn get_removed(&self) -> &Vec<String> { &self.removed }
+
+    pub fn get_changed(&self) -> &Vec<RockeryMethodChange> { &self.changed }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl fmt::Display for RockeryServiceDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for m in &self.added {
+            writeln!(f, "+ {} ({} {})", m.get_name(), m.get_request_method(), m.get_uri())?;
+        }
+
+        for m in &self.removed {
+            writeln!(f, "- {}", m)?;
+        }
+
+        for c in &self.changed {
+            writeln!(f, "~ {}", c.name)?;
+            if let Some((old, new)) = &c.request_method {
+                writeln!(f, "    method: {} -> {}", old, new)?;
+            }
+            if let Some((old, new)) = &c.uri {
+                writeln!(f, "    uri: {} -> {}", old, new)?;
+            }
+            for p in &c.added_parameters {
+                writeln!(f, "    + {}", p)?;
+            }
+            for p in &c.removed_parameters {
+                writeln!(f, "    - {}", p)?;
+            }
+            for (old, new) in &c.changed_parameters {
+                writeln!(f, "    ~ {} -> {}", old, new)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl RockeryService {
+    /// Find the operation of the definition that corresponds to a method. Operations are matched by
+    /// name first, and if a method has been renamed, by request method and uri. Every operation is
+    /// matched to one method at most.
+    fn match_methods(&self, definition : &RockeryService) -> Vec<Option<usize>> {
+        let mut matches : Vec<Option<usize>> = vec![None; self.get_methods().len()];
+
+        for (i, m) in self.get_methods().iter().enumerate() {
+            matches[i] = definition.get_methods().iter().enumerate()
+                .position(|(j, d)| !matches.contains(&Some(j)) && d.get_name() == m.get_name());
+        }
+
+        for (i, m) in self.get_methods().iter().enumerate() {
+            if matches[i].is_some() {
+                continue;
+            }
+
+            matches[i] = definition.get_methods().iter().enumerate()
+                .position(|(j, d)| !matches.contains(&Some(j)) && d.get_request_method() == m.get_request_method() && d.get_uri() == m.get_uri());
+        }
+
+        matches
+    }
+
+    /// Compare this service with a service created from a (newer) definition.
+    pub fn diff_with(&self, definition : &RockeryService) -> RockeryServiceDiff {
+        let matches = self.match_methods(definition);
+        let mut diff = RockeryServiceDiff::default();
+
+        for (i, m) in self.get_methods().iter().enumerate() {
+            let d = match matches[i] {
+                Some(j) => &definition.get_methods()[j],
+                None => {
+                    diff.removed.push(m.get_name().to_owned());
+                    continue;
+                },
+            };
+
+            let mut change = RockeryMethodChange { name : m.get_name().to_owned(), ..Default::default() };
+            if m.get_request_method() != d.get_request_method() {
+                change.request_method = Some((m.get_request_method(), d.get_request_method()));
+            }
+            if m.get_uri() != d.get_uri() {
+                change.uri = Some((m.get_uri().to_owned(), d.get_uri().to_owned()));
+            }
+
+            for p in d.get_parameter() {
+                match m.get_parameter().iter().find(|c| c.is_same_parameter(p)) {
+                    Some(c) if c != p => change.changed_parameters.push((c.clone(), p.clone())),
+                    Some(_) => { },
+                    None => change.added_parameters.push(p.clone()),
+                }
+            }
+
+            for p in m.get_parameter() {
+                if !d.get_parameter().iter().any(|c| c.is_same_parameter(p)) {
+                    change.removed_parameters.push(p.clone());
+                }
+            }
+
+            if !change.is_empty() {
+                diff.changed.push(change);
+            }
+        }
+
+        for (j, d) in definition.get_methods().iter().enumerate() {
+            if !matches.contains(&Some(j)) {
+                diff.added.push(d.clone());
+            }
+        }
+
+        diff
+    }
+
+    /// Update this service to match a (newer) definition and return what has changed.
+    /// New operations and parameters are added, the request method and uri of existing methods is updated.
+    /// Values of existing parameters, bodies, recorded responses and the authentication are preserved.
+    /// Methods and parameters that are not part of the definition are only removed if `remove_missing` is set,
+    /// the diff lists them in both cases.
+    pub fn merge_definition(&mut self, definition : &RockeryService, remove_missing : bool) -> RockeryServiceDiff {
+        let diff = self.diff_with(definition);
+        let matches = self.match_methods(definition);
+
+        for (i, j) in matches.iter().enumerate() {
+            let d = match j {
+                Some(j) => &definition.get_methods()[*j],
+                None => continue,
+            };
+
+            let m = self.get_method_mut(i).unwrap();
+            m.set_request_method(d.get_request_method());
+            m.set_uri(d.get_uri());
+
+            let mut parameter : Vec<RequestParameter> = d.get_parameter().iter()
+                .map(|p| m.get_parameter().iter().find(|c| c.is_same_parameter(p)).unwrap_or(p).clone())
+                .collect();
+            if !remove_missing {
+                let custom : Vec<RequestParameter> = m.get_parameter().iter()
+                    .filter(|p| !d.get_parameter().iter().any(|c| c.is_same_parameter(p)))
+                    .cloned()
+                    .collect();
+                parameter.extend(custom);
+            }
+            m.set_parameter(parameter);
+            m.set_response_schemas(d.get_response_schemas().clone());
+
+            if m.get_body().is_none() {
+                if let Some(body) = d.get_body() {
+                    m.set_body(body);
+                }
+            }
+        }
+
+        if remove_missing {
+            for i in (0..matches.len()).rev() {
+                if matches[i].is_none() {
+                    let _ = self.remove_method(i);
+                }
+            }
+        }
+
+        for m in &diff.added {
+            self.add_service_method(m.clone());
+        }
+
+        self.set_xml_schema(definition.get_xml_schema().as_deref());
+
+        // Keep a configured authentication, but take over the one of the definition if there is none.
+        if matches!(self.get_authentication(), AuthenticationType::None(_)) {
+            self.set_authentication(definition.get_authentication().clone());
+        }
+
+        diff
+    }
+
+    /// Read the definition this service was created from again and compare it with the service.
+    pub fn diff_definition(&self) -> Result<RockeryServiceDiff, String> {
+        let location = self.get_definition_url().as_ref().ok_or("The service has no definition.".to_owned())?;
+        let definition = import_definition(location)?;
+
+        Ok(self.diff_with(&definition))
+    }
+
+    /// Read the definition this service was created from again and merge it into the service.
+    pub fn sync_definition(&mut self, remove_missing : bool) -> Result<RockeryServiceDiff, String> {
+        let location = self.get_definition_url().as_ref().ok_or("The service has no definition.".to_owned())?;
+        let definition = import_definition(location)?;
+
+        Ok(self.merge_definition(&definition, remove_missing))
+    }
+}
+
+#[cfg(test)]
+mod rockery_service_sync_tests {
+    use super::*;
+
+    const VERSION_1 : &str = r#"{
+        "openapi": "3.0.0",
+        "info": { "title": "Users" },
+        "servers": [{ "url": "https://example.com" }],
+        "paths": {
+            "/users": {
+                "get": { "operationId": "listUsers", "parameters": [{ "name": "limit", "in": "query", "example": 10 }] },
+                "post": { "operationId": "createUser", "requestBody": { "content": { "application/json": { "example": { "name": "a" } } } } }
+            },
+            "/users/{id}": {
+                "delete": { "operationId": "deleteUser", "parameters": [{ "name": "id", "in": "path" }] }
+            }
+        }
+    }"#;
+
+    const VERSION_2 : &str = r#"{
+        "openapi": "3.0.0",
+        "info": { "title": "Users" },
+        "servers": [{ "url": "https://example.com" }],
+        "components": { "securitySchemes": { "token": { "type": "http", "scheme": "bearer" } } },
+        "security": [{ "token": [] }],
+        "paths": {
+            "/users": {
+                "get": { "operationId": "listUsers", "parameters": [
+                    { "name": "limit", "in": "query", "example": 20 },
+                    { "name": "active", "in": "query" }
+                ] },
+                "post": { "operationId": "createUser", "requestBody": { "content": { "application/json": { "example": { "name": "b" } } } } }
+            },
+            "/users/{userId}": {
+                "get": { "operationId": "getUser", "parameters": [{ "name": "userId", "in": "path" }] }
+            }
+        }
+    }"#;
+
+    #[test]
+    fn test_diff_and_merge() {
+        let mut service = RockeryOpenApiImporter::import_str(VERSION_1).unwrap();
+        let definition = RockeryOpenApiImporter::import_str(VERSION_2).unwrap();
+
+        // Customise the service before syncing.
+        let index = service.get_method_index("createUser").unwrap();
+        service.get_method_mut(index).unwrap().set_body("custom");
+        service.get_method_mut(index).unwrap().set_name("Create a user");
+
+        let diff = service.diff_with(&definition);
+        assert_eq!(diff.get_added().len(), 1);
+        assert_eq!(diff.get_added()[0].get_name(), "getUser");
+        assert_eq!(diff.get_removed(), &vec!["deleteUser".to_owned()]);
+        assert_eq!(diff.get_changed().len(), 1);
+
+        let change = &diff.get_changed()[0];
+        assert_eq!(change.get_name(), "listUsers");
+        assert_eq!(change.get_added_parameters(), &vec![RequestParameter::Url("active".to_owned(), None)]);
+        assert_eq!(change.get_changed_parameters().len(), 1);
+        assert!(diff.to_string().contains("+ getUser (GET users/{userId})"));
+
+        let merged = service.merge_definition(&definition, true);
+        assert_eq!(merged, diff);
+        assert!(matches!(service.get_authentication(), AuthenticationType::Bearer(_)));
+
+        let names : Vec<&str> = service.get_methods().iter().map(|m| m.get_name()).collect();
+        assert_eq!(names, vec!["listUsers", "Create a user", "getUser"]);
+
+        let create = &service.get_methods()[1];
+        assert_eq!(create.get_body(), &Some("custom".to_owned()));
+
+        let list = &service.get_methods()[0];
+        assert_eq!(list.get_parameter(), &vec![
+            RequestParameter::Url("limit".to_owned(), Some("10".to_owned())),
+            RequestParameter::Url("active".to_owned(), None),
+        ]);
+
+        assert!(service.diff_definition().is_err());
+    }
+
+    #[test]
+    fn test_merge_keeps_custom_parameters() {
+        let mut service = RockeryOpenApiImporter::import_str(VERSION_1).unwrap();
+        let definition = RockeryOpenApiImporter::import_str(VERSION_2).unwrap();
+
+        let index = service.get_method_index("listUsers").unwrap();
+        service.get_method_mut(index).unwrap().add_parameter(RequestParameter::Header("X-Trace".to_owned(), Some("1".to_owned())));
+
+        let diff = service.merge_definition(&definition, false);
+        let change = diff.get_changed().iter().find(|c| c.get_name() == "listUsers").unwrap();
+        assert_eq!(change.get_removed_parameters(), &vec![RequestParameter::Header("X-Trace".to_owned(), Some("1".to_owned()))]);
+        assert!(service.get_method_index("deleteUser").is_some());
+
+        let list = &service.get_methods()[service.get_method_index("listUsers").unwrap()];
+        assert_eq!(list.get_parameter(), &vec![
+            RequestParameter::Url("limit".to_owned(), Some("10".to_owned())),
+            RequestParameter::Url("active".to_owned(), None),
+            RequestParameter::Header("X-Trace".to_owned(), Some("1".to_owned())),
+        ]);
+
+        // With `remove_missing` the parameter is dropped.
+        service.merge_definition(&definition, true);
+        let list = &service.get_methods()[service.get_method_index("listUsers").unwrap()];
+        assert_eq!(list.get_parameter().len(), 2);
+    }
+
+    #[test]
+    fn test_match_methods_unique() {
+        let mut service = RockeryOpenApiImporter::import_str(VERSION_1).unwrap();
+        let definition = RockeryOpenApiImporter::import_str(VERSION_2).unwrap();
+
+        // A copy with the same name must not be matched to the same operation.
+        let copy = service.get_methods()[service.get_method_index("listUsers").unwrap()].clone();
+        service.add_service_method(copy);
+
+        let matches = service.match_methods(&definition);
+        let matched : Vec<usize> = matches.iter().flatten().copied().collect();
+        let mut unique = matched.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(matched.len(), unique.len());
+
+        let diff = service.diff_with(&definition);
+        assert_eq!(diff.get_removed(), &vec!["deleteUser".to_owned(), "listUsers".to_owned()]);
+    }
+}
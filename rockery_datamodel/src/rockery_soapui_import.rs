@@ -0,0 +1,583 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use regex::Regex;
+
+use crate::prelude::*;
+use crate::rockery_definition::read_definition;
+use crate::rockery_http::split_url;
+use crate::rockery_wsdl_import::soap_headers;
+use crate::rockery_xml::XmlElement;
+
+/// Creates projects from SoapUI project files.
+///
+/// Every interface becomes a service: the first endpoint is the base url, SOAP operations and REST methods
+/// become methods with one method per request. Test suites and test cases are imported with their request
+/// steps, delays and the assertions rockery supports: valid status codes, contains, XPath and JsonPath
+/// matches and response SLAs. A step that sends another body than its method gets a method of its own.
+/// Property expansions like `${#Project#name}` become `${name}`.
+pub struct RockerySoapUiImporter;
+
+impl RockerySoapUiImporter {
+    /// Read the project file from the given url or path and create a project from it.
+    pub fn import(location : &str) -> Result<RockeryProject, String> {
+        Self::import_str(&read_definition(location)?)
+    }
+
+    /// Create a project from the content of a project file.
+    pub fn import_str(content : &str) -> Result<RockeryProject, String> {
+        let root = XmlElement::parse(content)?;
+        if root.get_local_name() != "soapui-project" {
+            return Err(format!("Not a SoapUI project. Expected a soapui-project element but found {}.", root.get_local_name()));
+        }
+
+        let mut project = RockeryProject::new(root.get_attribute("name").unwrap_or("SoapUI"));
+        for (name, value) in properties(&root) {
+            project.set_variable(&name, &value);
+        }
+
+        // Ids of the services by the name of their interface.
+        let mut services : HashMap<String, String> = HashMap::new();
+        for interface in root.children_named("interface") {
+            let name = interface.get_attribute("name").unwrap_or("Interface").to_owned();
+            let service = match interface.get_attribute("type") {
+                Some(t) if t.ends_with("RestService") => import_rest_interface(interface),
+                _ => import_soap_interface(interface),
+            };
+            services.insert(name, project.add_service(service));
+        }
+
+        for suite in root.children_named("testSuite") {
+            import_test_suite(&mut project, &services, suite)?;
+        }
+
+        Ok(project)
+    }
+}
+
+/// Split an endpoint into the base url of a service and a path the uris of its methods start with.
+fn split_endpoint(endpoint : &str) -> (String, String) {
+    match split_url(endpoint) {
+        Some((origin, path, _)) => (origin.to_owned(), path.trim_matches('/').to_owned()),
+        None => (endpoint.trim_end_matches('/').to_owned(), String::new()),
+    }
+}
+
+fn join_path(a : &str, b : &str) -> String {
+    let parts : Vec<&str> = [a, b].into_iter().map(|p| p.trim_matches('/')).filter(|p| !p.is_empty()).collect();
+    parts.join("/")
+}
+
+fn first_endpoint(interface : &XmlElement) -> String {
+    interface.first_child("endpoints").and_then(|e| e.first_child("endpoint")).map(|e| expand(e.get_text())).unwrap_or_default()
+}
+
+fn import_soap_interface(interface : &XmlElement) -> RockeryService {
+    let (base_url, path) = split_endpoint(&first_endpoint(interface));
+    let mut service = RockeryService::new(RockeryServiceType::Soap, &base_url);
+    service.set_name(interface.get_attribute("name").unwrap_or("Interface"));
+    if let Some(definition) = interface.get_attribute("definition") {
+        service.set_definition_url(definition);
+    }
+
+    let soap_12 = interface.get_attribute("soapVersion") == Some("1_2");
+    for operation in interface.children_named("operation") {
+        let name = operation.get_attribute("name").unwrap_or("Operation");
+        let action = operation.get_attribute("action").unwrap_or_default();
+        let calls : Vec<&XmlElement> = operation.children_named("call").collect();
+
+        let mut add = |method_name : &str, body : &str| {
+            let m = service.add_method(method_name, &path);
+            m.set_request_method(RequestMethod::Post);
+            for p in soap_headers(action, soap_12) {
+                m.add_parameter(p);
+            }
+            if !body.is_empty() {
+                m.set_body(body);
+            }
+        };
+
+        match calls.len() {
+            0 => add(name, ""),
+            1 => add(name, &expand(request_body(calls[0]))),
+            _ => {
+                for call in calls {
+                    add(&format!("{}: {}", name, call.get_attribute("name").unwrap_or("Request")), &expand(request_body(call)));
+                }
+            },
+        }
+    }
+
+    service
+}
+
+fn import_rest_interface(interface : &XmlElement) -> RockeryService {
+    let (base_url, path) = split_endpoint(&first_endpoint(interface));
+    let mut service = RockeryService::new(RockeryServiceType::Rest, &base_url);
+    service.set_name(interface.get_attribute("name").unwrap_or("Interface"));
+    if let Some(definition) = interface.get_attribute("definitionUrl") {
+        service.set_definition_url(definition);
+    }
+
+    let base_path = join_path(&path, interface.get_attribute("basePath").unwrap_or_default());
+    for resource in interface.children_named("resource") {
+        import_resource(&mut service, resource, &base_path, &[]);
+    }
+
+    service
+}
+
+/// Add the methods of a resource and its sub resources. Parameters of resources apply to all their methods.
+fn import_resource(service : &mut RockeryService, resource : &XmlElement, parent_path : &str, parent_parameters : &[RequestParameter]) {
+    let path = join_path(parent_path, resource.get_attribute("path").unwrap_or_default());
+    let mut parameters = parent_parameters.to_vec();
+    parameters.extend(rest_parameters(resource));
+
+    for method in resource.children_named("method") {
+        let name = method.get_attribute("name").unwrap_or("Method");
+        let request_method = RequestMethod::from_str(&method.get_attribute("method").unwrap_or("GET").to_uppercase()).unwrap_or(RequestMethod::Get);
+        let mut method_parameters = parameters.clone();
+        method_parameters.extend(rest_parameters(method));
+
+        let requests : Vec<&XmlElement> = method.children_named("request").collect();
+        let mut add = |method_name : &str, request : Option<&XmlElement>| {
+            let m = service.add_method(method_name, &path);
+            m.set_request_method(request_method);
+
+            // Values of the request replace the defaults of the parameters.
+            let values : Vec<(&str, &str)> = request.and_then(|r| r.first_child("parameters")).into_iter()
+                .flat_map(|p| p.children_named("entry"))
+                .filter_map(|e| Some((e.get_attribute("key")?, e.get_attribute("value")?)))
+                .collect();
+            for p in &method_parameters {
+                let value = values.iter().find(|(k, _)| *k == p.get_name()).map(|(_, v)| Some(expand(v)));
+                m.add_parameter(match value {
+                    Some(v) => with_value(p, v),
+                    None => p.clone(),
+                });
+            }
+
+            let body = request.map(|r| expand(request_body(r))).unwrap_or_default();
+            if !body.is_empty() {
+                if let Some(media_type) = request.and_then(|r| r.get_attribute("mediaType")) {
+                    m.add_parameter(RequestParameter::Header("Content-Type".to_owned(), Some(media_type.to_owned())));
+                }
+                m.set_body(&body);
+            }
+        };
+
+        match requests.len() {
+            0 => add(name, None),
+            1 => add(name, Some(requests[0])),
+            _ => {
+                for request in requests {
+                    add(&format!("{}: {}", name, request.get_attribute("name").unwrap_or("Request")), Some(request));
+                }
+            },
+        }
+    }
+
+    for child in resource.children_named("resource") {
+        import_resource(service, child, &path, &parameters);
+    }
+}
+
+fn with_value(parameter : &RequestParameter, value : Option<String>) -> RequestParameter {
+    let name = parameter.get_name().to_owned();
+    match parameter {
+        RequestParameter::Url(_, _) => RequestParameter::Url(name, value),
+        RequestParameter::Header(_, _) => RequestParameter::Header(name, value),
+        RequestParameter::Body(_, _) => RequestParameter::Body(name, value),
+        RequestParameter::Path(_, _) => RequestParameter::Path(name, value),
+    }
+}
+
+/// The parameters of a resource or method. Matrix and plain parameters are skipped.
+fn rest_parameters(element : &XmlElement) -> Vec<RequestParameter> {
+    let text = |p : &XmlElement, name : &str| p.first_child(name).map(|e| expand(e.get_text())).filter(|v| !v.is_empty());
+
+    element.first_child("parameters").into_iter()
+        .flat_map(|p| p.children_named("parameter"))
+        .filter_map(|p| {
+            let name = text(p, "name")?;
+            let value = text(p, "value").or_else(|| text(p, "default"));
+            match text(p, "style").as_deref() {
+                Some("QUERY") => Some(RequestParameter::Url(name, value)),
+                Some("HEADER") => Some(RequestParameter::Header(name, value)),
+                Some("TEMPLATE") => Some(RequestParameter::Path(name, value)),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// The body of a call or request is stored in a nested `request` element.
+fn request_body(request : &XmlElement) -> &str {
+    request.first_child("request").map(|r| r.get_text().trim()).unwrap_or_default()
+}
+
+fn properties(element : &XmlElement) -> Vec<(String, String)> {
+    element.first_child("properties").into_iter()
+        .flat_map(|p| p.children_named("property"))
+        .filter_map(|p| {
+            let name = p.first_child("name")?.get_text().to_owned();
+            let value = p.first_child("value").map(|v| expand(v.get_text())).unwrap_or_default();
+            Some((name, value))
+        })
+        .collect()
+}
+
+/// Replace SoapUI property expansions `${#Scope#name}` by `${name}`.
+fn expand(text : &str) -> String {
+    let regex = Regex::new(r"\$\{#(?:Project|TestSuite|TestCase|Global|Env|MockService)#([^}]+)\}").unwrap();
+    regex.replace_all(text, "$${$1}").to_string()
+}
+
+fn import_test_suite(project : &mut RockeryProject, services : &HashMap<String, String>, suite : &XmlElement) -> Result<(), String> {
+    let mut rockery_suite = RockeryTestSuite::new(suite.get_attribute("name").unwrap_or("Test suite"));
+    let mut added_methods : Vec<(String, RockeryServiceMethod)> = Vec::new();
+
+    for test_case in suite.children_named("testCase") {
+        let case_name = test_case.get_attribute("name").unwrap_or("Test case");
+        let mut rockery_case = RockeryTestCase::new(case_name);
+        for (name, value) in properties(test_case) {
+            rockery_case.set_variable(&name, &value);
+        }
+
+        let mut delay = 0;
+        for step in test_case.children_named("testStep") {
+            let step_name = step.get_attribute("name").unwrap_or("Step");
+            let config = match step.first_child("config") {
+                Some(c) => c,
+                None => continue,
+            };
+
+            let (interface, method_name, request) = match step.get_attribute("type") {
+                Some("request") => (
+                    config.first_child("interface").map(|e| e.get_text()).unwrap_or_default(),
+                    config.first_child("operation").map(|e| e.get_text()).unwrap_or_default(),
+                    config.first_child("request"),
+                ),
+                Some("restrequest") => (
+                    config.get_attribute("service").unwrap_or_default(),
+                    config.get_attribute("methodName").unwrap_or_default(),
+                    config.first_child("restRequest"),
+                ),
+                Some("delay") => {
+                    delay += config.first_child("delay").and_then(|d| d.get_text().trim().parse::<u64>().ok()).unwrap_or(0);
+                    continue;
+                },
+                _ => continue,
+            };
+
+            let service_id = services.get(interface)
+                .ok_or(format!("Test step {} refers to interface {} which does not exist within the project.", step_name, interface))?;
+            let service = project.find_service(service_id).unwrap();
+
+            // The method of an operation with several requests is named after the operation and the request.
+            let method = service.get_methods().iter()
+                .find(|m| m.get_name() == method_name || m.get_name().starts_with(&format!("{}: ", method_name)))
+                .ok_or(format!("Test step {} refers to method {} which does not exist within service {}.", step_name, method_name, service_id))?;
+            let mut target = method.get_name().to_owned();
+
+            let body = request.map(|r| expand(request_body(r))).unwrap_or_default();
+            if !body.is_empty() && method.get_body().as_deref() != Some(body.as_str()) {
+                // Steps sending the same body share a copy of the method, every other body gets a copy of its own.
+                let copies = service.get_methods().iter()
+                    .chain(added_methods.iter().filter(|(id, _)| id == service_id).map(|(_, m)| m));
+                let prefix = format!("{} (", method_name);
+                let shared = copies.clone().find(|m| m.get_name().starts_with(&prefix) && m.get_body().as_deref() == Some(body.as_str()));
+                match shared {
+                    Some(m) => target = m.get_name().to_owned(),
+                    None => {
+                        let taken = |name : &str| copies.clone().any(|m| m.get_name() == name);
+                        target = format!("{} ({})", method_name, step_name);
+                        if taken(&target) {
+                            target = format!("{} ({}: {})", method_name, case_name, step_name);
+                        }
+                        let base = target.clone();
+                        let mut i = 2;
+                        while taken(&target) {
+                            target = format!("{} {}", base, i);
+                            i += 1;
+                        }
+
+                        let mut copy = method.clone();
+                        copy.set_name(&target);
+                        copy.set_body(&body);
+                        added_methods.push((service_id.to_owned(), copy));
+                    },
+                }
+            }
+
+            let mut rockery_step = RockeryTestStep::new(step_name, service_id, &target);
+            rockery_step.set_delay(delay);
+            delay = 0;
+
+            if let Some(r) = request {
+                for (k, v) in r.first_child("parameters").into_iter().flat_map(|p| p.children_named("entry"))
+                    .filter_map(|e| Some((e.get_attribute("key")?, e.get_attribute("value")?))) {
+                    if let Some(p) = method.get_parameter().iter().find(|p| p.get_name() == k) {
+                        rockery_step.add_parameter(with_value(p, Some(expand(v))));
+                    }
+                }
+
+                for assertion in r.children_named("assertion") {
+                    if let Some(a) = import_assertion(assertion) {
+                        rockery_step.add_assertion(a);
+                    }
+                }
+            }
+
+            rockery_case.add_step(rockery_step);
+        }
+
+        rockery_suite.add_test_case(rockery_case);
+    }
+
+    for (service_id, method) in added_methods {
+        project.get_services().get_mut(&service_id).unwrap().add_service_method(method);
+    }
+
+    project.add_test_suite(rockery_suite);
+    Ok(())
+}
+
+/// Map a SoapUI assertion onto the assertions rockery supports. Others are skipped.
+fn import_assertion(assertion : &XmlElement) -> Option<RockeryAssertion> {
+    let configuration = assertion.first_child("configuration");
+    let value = |name : &str| configuration.and_then(|c| c.first_child(name)).map(|e| e.get_text().trim().to_owned()).unwrap_or_default();
+    // XPath expressions start with namespace declarations, rockery ignores namespace prefixes.
+    let path = || {
+        let declarations = Regex::new(r"declare\s+namespace\s+[^;]*;").unwrap();
+        declarations.replace_all(&value("path"), "").trim().to_owned()
+    };
+
+    match assertion.get_attribute("type")? {
+        "Valid HTTP Status Codes" => {
+            let codes : Vec<u16> = value("codes").split(',').filter_map(|c| c.trim().parse().ok()).collect();
+            match (codes.iter().min(), codes.iter().max()) {
+                (Some(min), Some(max)) if min == max => Some(RockeryAssertion::StatusCode(*min)),
+                (Some(min), Some(max)) => Some(RockeryAssertion::StatusRange(*min, *max)),
+                _ => None,
+            }
+        },
+        "Simple Contains" => {
+            let token = expand(&value("token"));
+            match value("useRegEx").as_str() {
+                "true" => Some(RockeryAssertion::BodyMatches(token)),
+                _ => Some(RockeryAssertion::BodyContains(token)),
+            }
+        },
+        "XPath Match" => Some(RockeryAssertion::XPathEquals(path(), expand(&value("content")))),
+        "JsonPath Match" => Some(RockeryAssertion::JsonPathEquals(path(), expand(&value("content")))),
+        "Response SLA Assertion" => value("SLA").parse().ok().map(RockeryAssertion::ResponseTimeBelow),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod rockery_soapui_import_tests {
+    use super::*;
+
+    const PROJECT : &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+        <con:soapui-project name="Shop" soapui-version="5.7.0" xmlns:con="http://eviware.com/soapui/config" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+            <con:interface xsi:type="con:WsdlInterface" name="UserBinding" soapVersion="1_1" definition="http://example.com/users?wsdl">
+                <con:endpoints><con:endpoint>http://example.com/ws/users</con:endpoint></con:endpoints>
+                <con:operation name="GetUser" action="urn:GetUser">
+                    <con:call name="Request 1">
+                        <con:request><![CDATA[<soapenv:Envelope><soapenv:Body><GetUser><id>${#Project#user}</id></GetUser></soapenv:Body></soapenv:Envelope>]]></con:request>
+                    </con:call>
+                </con:operation>
+                <con:operation name="DeleteUser" action="urn:DeleteUser"/>
+            </con:interface>
+            <con:interface xsi:type="con:RestService" name="Items API" basePath="/api">
+                <con:endpoints><con:endpoint>https://items.example.com</con:endpoint></con:endpoints>
+                <con:resource name="Items" path="/items">
+                    <con:parameters>
+                        <con:parameter><con:name>lang</con:name><con:style>QUERY</con:style><con:default>en</con:default></con:parameter>
+                    </con:parameters>
+                    <con:method name="List" method="GET">
+                        <con:request name="Request 1"><con:parameters><entry key="lang" value="de" xmlns="http://eviware.com/soapui/config"/></con:parameters></con:request>
+                    </con:method>
+                    <con:resource name="Item" path="{id}">
+                        <con:parameters>
+                            <con:parameter><con:name>id</con:name><con:style>TEMPLATE</con:style></con:parameter>
+                            <con:parameter><con:name>X-Trace</con:name><con:value>1</con:value><con:style>HEADER</con:style></con:parameter>
+                        </con:parameters>
+                        <con:method name="Update" method="PUT">
+                            <con:request name="Request 1" mediaType="application/json"><con:request>{"name": "a"}</con:request></con:request>
+                        </con:method>
+                    </con:resource>
+                </con:resource>
+            </con:interface>
+            <con:testSuite name="Smoke">
+                <con:testCase name="Users">
+                    <con:properties><con:property><con:name>id</con:name><con:value>7</con:value></con:property></con:properties>
+                    <con:testStep type="delay" name="Wait"><con:config><delay>250</delay></con:config></con:testStep>
+                    <con:testStep type="request" name="Get user">
+                        <con:config xsi:type="con:RequestStep">
+                            <con:interface>UserBinding</con:interface>
+                            <con:operation>GetUser</con:operation>
+                            <con:request name="Get user">
+                                <con:request><![CDATA[<soapenv:Envelope><soapenv:Body><GetUser><id>${#TestCase#id}</id></GetUser></soapenv:Body></soapenv:Envelope>]]></con:request>
+                                <con:assertion type="SOAP Response" name="SOAP Response"/>
+                                <con:assertion type="Valid HTTP Status Codes" name="Status"><con:configuration><codes>200,201</codes></con:configuration></con:assertion>
+                                <con:assertion type="XPath Match" name="Id">
+                                    <con:configuration><path>declare namespace soap='http://schemas.xmlsoap.org/soap/envelope/';
+                                        //GetUserResponse/id</path><content>7</content></con:configuration>
+                                </con:assertion>
+                                <con:assertion type="Response SLA Assertion" name="SLA"><con:configuration><SLA>500</SLA></con:configuration></con:assertion>
+                            </con:request>
+                        </con:config>
+                    </con:testStep>
+                    <con:testStep type="restrequest" name="List items">
+                        <con:config service="Items API" resourcePath="/items" methodName="List">
+                            <con:restRequest name="List items">
+                                <con:parameters><entry key="lang" value="fr" xmlns="http://eviware.com/soapui/config"/></con:parameters>
+                                <con:assertion type="Simple Contains" name="Contains"><con:configuration><token>items</token><ignoreCase>false</ignoreCase><useRegEx>false</useRegEx></con:configuration></con:assertion>
+                                <con:assertion type="JsonPath Match" name="Count"><con:configuration><path>$.count</path><content>2</content></con:configuration></con:assertion>
+                                <con:assertion type="Groovy Script" name="Script"/>
+                            </con:restRequest>
+                        </con:config>
+                    </con:testStep>
+                    <con:testStep type="groovy" name="Script"><con:config><script>log.info 'x'</script></con:config></con:testStep>
+                </con:testCase>
+            </con:testSuite>
+            <con:properties><con:property><con:name>user</con:name><con:value>rex</con:value></con:property></con:properties>
+        </con:soapui-project>"#;
+
+    #[test]
+    fn test_import() {
+        let mut project = RockerySoapUiImporter::import_str(PROJECT).unwrap();
+        assert_eq!(project.get_name(), "Shop");
+        assert_eq!(project.get_variables().get("user").unwrap(), "rex");
+
+        let users = project.find_service("UserBinding").unwrap();
+        assert_eq!(users.get_service_type(), &RockeryServiceType::Soap);
+        assert_eq!(users.get_base_url(), "http://example.com");
+        assert_eq!(users.get_definition_url(), &Some("http://example.com/users?wsdl".to_owned()));
+        let methods = users.get_methods();
+        assert_eq!(methods.len(), 3);
+        assert_eq!((methods[0].get_name(), methods[0].get_uri(), methods[0].get_request_method()), ("GetUser", "ws/users", RequestMethod::Post));
+        assert_eq!(methods[0].get_body(), &Some("<soapenv:Envelope><soapenv:Body><GetUser><id>${user}</id></GetUser></soapenv:Body></soapenv:Envelope>".to_owned()));
+        assert_eq!(methods[0].get_parameter(), &vec![
+            RequestParameter::Header("Content-Type".to_owned(), Some("text/xml;charset=UTF-8".to_owned())),
+            RequestParameter::Header("SOAPAction".to_owned(), Some("\"urn:GetUser\"".to_owned())),
+        ]);
+        assert_eq!((methods[1].get_name(), methods[1].get_body()), ("DeleteUser", &None));
+
+        // The step sends another body, so it gets a method of its own.
+        assert_eq!(methods[2].get_name(), "GetUser (Get user)");
+        assert_eq!(methods[2].get_body(), &Some("<soapenv:Envelope><soapenv:Body><GetUser><id>${id}</id></GetUser></soapenv:Body></soapenv:Envelope>".to_owned()));
+
+        let items = project.find_service("Items API").unwrap();
+        assert_eq!(items.get_service_type(), &RockeryServiceType::Rest);
+        assert_eq!(items.get_base_url(), "https://items.example.com");
+        let methods = items.get_methods();
+        assert_eq!((methods[0].get_name(), methods[0].get_uri()), ("List", "api/items"));
+        assert_eq!(methods[0].get_parameter(), &vec![RequestParameter::Url("lang".to_owned(), Some("de".to_owned()))]);
+        assert_eq!((methods[1].get_name(), methods[1].get_uri(), methods[1].get_request_method()), ("Update", "api/items/{id}", RequestMethod::Put));
+        assert_eq!(methods[1].get_parameter(), &vec![
+            RequestParameter::Url("lang".to_owned(), Some("en".to_owned())),
+            RequestParameter::Path("id".to_owned(), None),
+            RequestParameter::Header("X-Trace".to_owned(), Some("1".to_owned())),
+            RequestParameter::Header("Content-Type".to_owned(), Some("application/json".to_owned())),
+        ]);
+        assert_eq!(methods[1].get_body(), &Some(r#"{"name": "a"}"#.to_owned()));
+
+        let suite = &project.get_test_suites()[0];
+        assert_eq!(suite.get_name(), "Smoke");
+        let test_case = &suite.get_test_cases()[0];
+        assert_eq!(test_case.get_variables().get("id").unwrap(), "7");
+        let steps = test_case.get_steps();
+        assert_eq!(steps.len(), 2);
+
+        assert_eq!((steps[0].get_service(), steps[0].get_method(), steps[0].get_delay()), ("UserBinding", "GetUser (Get user)", 250));
+        assert_eq!(steps[0].get_assertions(), &vec![
+            RockeryAssertion::StatusRange(200, 201),
+            RockeryAssertion::XPathEquals("//GetUserResponse/id".to_owned(), "7".to_owned()),
+            RockeryAssertion::ResponseTimeBelow(500),
+        ]);
+
+        assert_eq!((steps[1].get_service(), steps[1].get_method(), steps[1].get_delay()), ("Items API", "List", 0));
+        assert_eq!(steps[1].get_parameter(), &vec![RequestParameter::Url("lang".to_owned(), Some("fr".to_owned()))]);
+        assert_eq!(steps[1].get_assertions(), &vec![
+            RockeryAssertion::BodyContains("items".to_owned()),
+            RockeryAssertion::JsonPathEquals("$.count".to_owned(), "2".to_owned()),
+        ]);
+
+        let service = project.get_services().get("Items API").unwrap().clone();
+        assert_eq!(service.get_request_url(1, &[RequestParameter::Path("id".to_owned(), Some("3".to_owned()))]), "https://items.example.com/api/items/3/?lang=en");
+    }
+
+    #[test]
+    fn test_step_copies() {
+        let step = |case : &str, body : &str| format!(r#"<con:testCase name="{}"><con:testStep type="request" name="Add"><con:config>
+            <con:interface>Calc</con:interface><con:operation>Add</con:operation>
+            <con:request name="Add"><con:request><![CDATA[{}]]></con:request></con:request>
+            </con:config></con:testStep></con:testCase>"#, case, body);
+        let project = format!(r#"<con:soapui-project name="P" xmlns:con="http://eviware.com/soapui/config">
+            <con:interface name="Calc"><con:operation name="Add"><con:call name="Request 1"><con:request>&lt;a&gt;0&lt;/a&gt;</con:request></con:call></con:operation></con:interface>
+            <con:testSuite name="First">{}{}{}</con:testSuite>
+            <con:testSuite name="Second">{}</con:testSuite>
+        </con:soapui-project>"#, step("One", "<a>1</a>"), step("Two", "<a>2</a>"), step("Three", "<a>1</a>"), step("Two", "<a>3</a>"));
+
+        // Steps with the same name but different bodies get copies of their own, the same body shares a copy.
+        let project = RockerySoapUiImporter::import_str(&project).unwrap();
+        let methods : Vec<(&str, &str)> = project.find_service("Calc").unwrap().get_methods().iter()
+            .map(|m| (m.get_name(), m.get_body().as_deref().unwrap_or_default())).collect();
+        assert_eq!(methods, vec![
+            ("Add", "<a>0</a>"), ("Add (Add)", "<a>1</a>"), ("Add (Two: Add)", "<a>2</a>"), ("Add (Two: Add) 2", "<a>3</a>"),
+        ]);
+
+        let targets : Vec<&str> = project.get_test_suites().iter().flat_map(|s| s.get_test_cases()).map(|t| t.get_steps()[0].get_method()).collect();
+        assert_eq!(targets, vec!["Add (Add)", "Add (Two: Add)", "Add (Add)", "Add (Two: Add) 2"]);
+    }
+
+    #[test]
+    fn test_invalid_project() {
+        assert!(RockerySoapUiImporter::import_str("<project/>").is_err());
+        assert!(RockerySoapUiImporter::import_str("no xml").is_err());
+        assert!(RockerySoapUiImporter::import("does_not_exist-soapui-project.xml").is_err());
+
+        let unknown_interface = r#"<con:soapui-project name="P" xmlns:con="http://eviware.com/soapui/config">
+            <con:testSuite name="S"><con:testCase name="C">
+                <con:testStep type="request" name="Step"><con:config><con:interface>Missing</con:interface><con:operation>Op</con:operation></con:config></con:testStep>
+            </con:testCase></con:testSuite>
+        </con:soapui-project>"#;
+        assert!(RockerySoapUiImporter::import_str(unknown_interface).is_err());
+
+        assert!(RockerySoapUiImporter::import_str("").is_err());
+        assert!(RockerySoapUiImporter::import_str(r#"<con:soapui-project name="P" xmlns:con="http://eviware.com/soapui/config">"#).is_err());
+        assert!(RockerySoapUiImporter::import_str(r#"<con:soapui-project xmlns:con="http://eviware.com/soapui/config"><con:interface></con:soapui-project>"#).is_err());
+        assert!(RockerySoapUiImporter::import_str("<soapui-project><interface name=\"I></soapui-project>").is_err());
+
+        let unknown_operation = r#"<con:soapui-project name="P" xmlns:con="http://eviware.com/soapui/config">
+            <con:interface name="Calc"><con:operation name="Add"/></con:interface>
+            <con:testSuite name="S"><con:testCase name="C">
+                <con:testStep type="request" name="Step"><con:config><con:interface>Calc</con:interface><con:operation>Divide</con:operation></con:config></con:testStep>
+            </con:testCase></con:testSuite>
+        </con:soapui-project>"#;
+        assert_eq!(RockerySoapUiImporter::import_str(unknown_operation).unwrap_err(), "Test step Step refers to method Divide which does not exist within service Calc.");
+
+        // Missing endpoints, malformed delays and unknown step types are imported with defaults or skipped.
+        let malformed = r#"<con:soapui-project xmlns:con="http://eviware.com/soapui/config">
+            <con:interface name="Calc"><con:operation name="Add"><con:call/></con:operation></con:interface>
+            <con:testSuite name="S"><con:testCase name="C">
+                <con:testStep type="delay" name="Wait"><con:config><con:delay>soon</con:delay></con:config></con:testStep>
+                <con:testStep type="groovy" name="Script"><con:config><script>x</script></con:config></con:testStep>
+                <con:testStep type="request" name="Step"/>
+                <con:testStep type="request" name="Add"><con:config><con:interface>Calc</con:interface><con:operation>Add</con:operation></con:config></con:testStep>
+            </con:testCase></con:testSuite>
+        </con:soapui-project>"#;
+        let mut project = RockerySoapUiImporter::import_str(malformed).unwrap();
+        assert_eq!(project.get_name(), "SoapUI");
+        let service = project.get_services().get("Calc").unwrap();
+        assert_eq!(service.get_base_url(), "");
+        assert_eq!(service.get_methods()[0].get_body(), &None);
+        let steps = project.get_test_suites()[0].get_test_cases()[0].get_steps();
+        assert_eq!(steps.len(), 1);
+        assert_eq!(steps[0].get_delay(), 0);
+    }
+}
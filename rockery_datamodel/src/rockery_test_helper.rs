@@ -15,4 +15,53 @@ pub(crate) fn get_feiertage_service() -> crate::prelude::RockeryService {
     m.add_parameter(RequestParameter::Url("callback".to_owned(), None));
 
     service
-}
\ No newline at end of file
+}
+
+/// Start a local http server that answers the given number of requests with the given responses
+/// (status, body) in order. Returns the base url of the server and a receiver for the raw requests.
+#[cfg(test)]
+pub(crate) fn serve(responses : Vec<(u16, &'static str)>) -> (String, std::sync::mpsc::Receiver<String>) {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let (sender, receiver) = std::sync::mpsc::channel();
+
+    std::thread::spawn(move || {
+        for (status, body) in responses {
+            let (mut stream, _) = match listener.accept() {
+                Ok(s) => s,
+                Err(_) => return,
+            };
+
+            // Read the head and, if there is one, the body of the request.
+            let mut request = Vec::new();
+            let mut buffer = [0u8; 4096];
+            loop {
+                let n = stream.read(&mut buffer).unwrap_or(0);
+                request.extend_from_slice(&buffer[..n]);
+
+                let text = String::from_utf8_lossy(&request).to_string();
+                if let Some(end) = text.find("\r\n\r\n") {
+                    let length = text.lines()
+                        .find_map(|l| l.to_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse::<usize>().unwrap_or(0)))
+                        .unwrap_or(0);
+                    if request.len() >= end + 4 + length {
+                        break;
+                    }
+                }
+
+                if n == 0 {
+                    break;
+                }
+            }
+
+            let response = format!("HTTP/1.1 {} Status\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", status, body.len(), body);
+            let _ = stream.write_all(response.as_bytes());
+            let _ = sender.send(String::from_utf8_lossy(&request).to_string());
+        }
+    });
+
+    (base_url, receiver)
+}
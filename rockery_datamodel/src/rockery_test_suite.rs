@@ -0,0 +1,576 @@
+use std::{collections::HashMap, fmt, thread, time::{Duration, Instant}};
+
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::*;
+use crate::rockery_property_transfer::substitute_parameter;
+
+/// Maximum number of steps a single test case may execute. Protects against endless goto loops.
+const MAX_EXECUTED_STEPS : usize = 1000;
+
+/// Decides whether a goto is taken after a step has been executed.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum RockeryGotoCondition {
+    Always,
+
+    /// The step passed, i.e. its response matches the schema and all assertions passed.
+    Passed,
+
+    /// The step failed or the request could not be executed.
+    Failed,
+
+    /// The given assertion holds for the response of the step.
+    Matches(RockeryAssertion),
+}
+
+/// Continue a test case with another step instead of the next one.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RockeryGoto {
+    condition : RockeryGotoCondition,
+
+    /// Name of the step to continue with.
+    target : String,
+}
+
+impl RockeryGoto {
+    pub fn new(condition : RockeryGotoCondition, target : &str) -> Self {
+        RockeryGoto { condition, target : target.to_owned() }
+    }
+
+    pub fn get_condition(&self) -> &RockeryGotoCondition { &self.condition }
+    pub fn get_target(&self) -> &str { &self.target }
+
+    fn applies(&self, result : &RockeryStepResult) -> bool {
+        match (&self.condition, result.get_result()) {
+            (RockeryGotoCondition::Always, _) => true,
+            (RockeryGotoCondition::Passed, _) => result.is_passed(),
+            (RockeryGotoCondition::Failed, _) => !result.is_passed(),
+            (RockeryGotoCondition::Matches(a), Some(r)) => a.evaluate(r.get_response()).is_passed(),
+            (RockeryGotoCondition::Matches(_), None) => false,
+        }
+    }
+}
+
+/// A single request within a test case.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RockeryTestStep {
+    name : String,
+
+    /// Id of the service within the project.
+    service : String,
+
+    /// Name of the method within the service.
+    method : String,
+
+    /// Values overriding the defaults of the method parameters.
+    #[serde(default)]
+    parameter : Vec<RequestParameter>,
+
+    /// Checks in addition to the assertions of the method.
+    #[serde(default)]
+    assertions : Vec<RockeryAssertion>,
+
+    /// Time to wait before the request is sent in milliseconds.
+    #[serde(default)]
+    delay : u64,
+
+    #[serde(default)]
+    goto : Option<RockeryGoto>,
+
+    /// Values taken from the response and saved as variables for later steps.
+    #[serde(default)]
+    transfers : Vec<RockeryPropertyTransfer>,
+}
+
+impl RockeryTestStep {
+    pub fn new(name : &str, service : &str, method : &str) -> Self {
+        RockeryTestStep {
+            name : name.to_owned(),
+            service : service.to_owned(),
+            method : method.to_owned(),
+            parameter : Vec::new(),
+            assertions : Vec::new(),
+            delay : 0,
+            goto : None,
+            transfers : Vec::new(),
+        }
+    }
+
+    pub fn get_name(&self) -> &str { &self.name }
+    pub fn set_name(&mut self, name : &str) { self.name = name.to_owned(); }
+
+    pub fn get_service(&self) -> &str { &self.service }
+    pub fn get_method(&self) -> &str { &self.method }
+
+    pub fn get_parameter(&self) -> &Vec<RequestParameter> { &self.parameter }
+    pub fn add_parameter(&mut self, param : RequestParameter) { self.parameter.push(param); }
+
+    pub fn get_assertions(&self) -> &Vec<RockeryAssertion> { &self.assertions }
+    pub fn add_assertion(&mut self, assertion : RockeryAssertion) { self.assertions.push(assertion); }
+
+    pub fn get_delay(&self) -> u64 { self.delay }
+    pub fn set_delay(&mut self, delay : u64) { self.delay = delay; }
+
+    pub fn get_goto(&self) -> &Option<RockeryGoto> { &self.goto }
+    pub fn set_goto(&mut self, goto : Option<RockeryGoto>) { self.goto = goto; }
+
+    pub fn get_transfers(&self) -> &Vec<RockeryPropertyTransfer> { &self.transfers }
+    pub fn add_transfer(&mut self, transfer : RockeryPropertyTransfer) { self.transfers.push(transfer); }
+
+    /// Execute the request of this step. Variables are substituted into the method and the parameter overrides.
+    fn execute(&self, project : &RockeryProject, variables : &HashMap<String, String>) -> Result<RockeryRequestResult, String> {
+        let service = project.find_service(&self.service)
+            .ok_or(format!("A service with the name {} does not exist within project {}.", self.service, project.get_name()))?;
+        let method = service.get_method_index(&self.method)
+            .ok_or(format!("A method with the name {} does not exist within service {}.", self.method, self.service))?;
+
+        if self.delay > 0 {
+            thread::sleep(Duration::from_millis(self.delay));
+        }
+
+        let service = service.with_variables(method, variables);
+        let parameter : Vec<RequestParameter> = self.parameter.iter().map(|p| substitute_parameter(p, variables)).collect();
+        let mut result = service.do_checked_request(method, &parameter)?;
+        result.add_assertions(self.assertions.iter().map(|a| a.evaluate(result.get_response())).collect());
+
+        Ok(result)
+    }
+}
+
+/// An ordered list of steps.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RockeryTestCase {
+    name : String,
+    steps : Vec<RockeryTestStep>,
+
+    /// Initial values of the test case variables.
+    #[serde(default)]
+    variables : HashMap<String, String>,
+
+    /// If set, the test case is run once for every row of the data source.
+    #[serde(default)]
+    data_source : Option<RockeryDataSource>,
+}
+
+impl RockeryTestCase {
+    pub fn new(name : &str) -> Self {
+        RockeryTestCase { name : name.to_owned(), steps : Vec::new(), variables : HashMap::new(), data_source : None }
+    }
+
+    pub fn get_name(&self) -> &str { &self.name }
+    pub fn set_name(&mut self, name : &str) { self.name = name.to_owned(); }
+
+    pub fn get_steps(&self) -> &Vec<RockeryTestStep> { &self.steps }
+    pub fn get_step_mut(&mut self, index : usize) -> Option<&mut RockeryTestStep> { self.steps.get_mut(index) }
+
+    /// Add a step to the end of the test case and return it so it can be configured.
+    pub fn add_step(&mut self, step : RockeryTestStep) -> &mut RockeryTestStep {
+        self.steps.push(step);
+        let index = self.steps.len() - 1;
+
+        &mut self.steps[index]
+    }
+
+    pub fn remove_step(&mut self, index : usize) -> Result<RockeryTestStep, String> {
+        if index >= self.steps.len() {
+            return Err(format!("Invalid index. There are only {} steps but step {} was requested.", self.steps.len(), index));
+        }
+
+        Ok(self.steps.remove(index))
+    }
+
+    pub fn get_variables(&self) -> &HashMap<String, String> { &self.variables }
+    pub fn set_variable(&mut self, name : &str, value : &str) { self.variables.insert(name.to_owned(), value.to_owned()); }
+
+    pub fn get_data_source(&self) -> &Option<RockeryDataSource> { &self.data_source }
+    pub fn set_data_source(&mut self, source : Option<RockeryDataSource>) { self.data_source = source; }
+
+    /// Execute all steps in order, following gotos, and return the result of every executed step.
+    pub fn run(&self, project : &RockeryProject) -> RockeryTestCaseResult {
+        self.run_with_variables(project, &mut project.get_variables().clone())
+    }
+
+    /// Run the test case, once for every row if it has a data source. Returns one result per run.
+    pub fn run_all(&self, project : &RockeryProject, project_variables : &mut HashMap<String, String>) -> Vec<RockeryTestCaseResult> {
+        let source = match &self.data_source {
+            Some(source) => source,
+            None => return vec![self.run_with_variables(project, project_variables)],
+        };
+
+        match source.load(project.get_directory()) {
+            Ok(rows) => self.run_with_data(project, &rows, project_variables).into_test_case_results(),
+            Err(e) => vec![RockeryTestCaseResult {
+                name : self.name.clone(),
+                steps : vec![RockeryStepResult::new("Data source", Err(e))],
+            }],
+        }
+    }
+
+    /// Execute all steps using the given project variables. Property transfers into the project
+    /// scope update `project_variables`. Test case variables take precedence over project variables.
+    pub fn run_with_variables(&self, project : &RockeryProject, project_variables : &mut HashMap<String, String>) -> RockeryTestCaseResult {
+        let mut results = Vec::new();
+        let mut variables = self.variables.clone();
+        let mut index = 0;
+
+        while index < self.steps.len() {
+            let step = &self.steps[index];
+            if results.len() >= MAX_EXECUTED_STEPS {
+                let message = format!("Aborted after {} steps. Check the gotos for endless loops.", MAX_EXECUTED_STEPS);
+                results.push(RockeryStepResult::new(step.get_name(), Err(message)));
+                break;
+            }
+
+            let mut effective = project_variables.clone();
+            effective.extend(variables.clone());
+
+            let start = Instant::now();
+            let mut result = RockeryStepResult::new(step.get_name(), step.execute(project, &effective));
+            result.duration = start.elapsed().as_millis() as u64;
+            if let Some(r) = &result.result {
+                for t in step.get_transfers() {
+                    let value = t.extract(r.get_response()).ok();
+                    if let Some(v) = &value {
+                        match t.get_scope() {
+                            RockeryVariableScope::TestCase => variables.insert(t.get_name().to_owned(), v.clone()),
+                            RockeryVariableScope::Project => project_variables.insert(t.get_name().to_owned(), v.clone()),
+                        };
+                    }
+                    result.transfers.push((t.get_name().to_owned(), value));
+                }
+            }
+
+            let next = match step.get_goto() {
+                Some(goto) if goto.applies(&result) => {
+                    self.steps.iter().position(|s| s.get_name() == goto.get_target())
+                        .ok_or(format!("The goto target {} does not exist.", goto.get_target()))
+                },
+                _ => Ok(index + 1),
+            };
+            results.push(result);
+
+            // The result of the step is kept, the missing target is reported as an additional result.
+            index = match next {
+                Ok(next) => next,
+                Err(message) => {
+                    results.push(RockeryStepResult::new(step.get_name(), Err(message)));
+                    break;
+                },
+            };
+        }
+
+        RockeryTestCaseResult { name : self.name.clone(), steps : results }
+    }
+}
+
+/// A named collection of test cases.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RockeryTestSuite {
+    name : String,
+    test_cases : Vec<RockeryTestCase>,
+}
+
+impl RockeryTestSuite {
+    pub fn new(name : &str) -> Self {
+        RockeryTestSuite { name : name.to_owned(), test_cases : Vec::new() }
+    }
+
+    pub fn get_name(&self) -> &str { &self.name }
+    pub fn set_name(&mut self, name : &str) { self.name = name.to_owned(); }
+
+    pub fn get_test_cases(&self) -> &Vec<RockeryTestCase> { &self.test_cases }
+    pub fn get_test_case_mut(&mut self, index : usize) -> Option<&mut RockeryTestCase> { self.test_cases.get_mut(index) }
+
+    /// Add a test case and return it so it can be configured.
+    pub fn add_test_case(&mut self, test_case : RockeryTestCase) -> &mut RockeryTestCase {
+        self.test_cases.push(test_case);
+        let index = self.test_cases.len() - 1;
+
+        &mut self.test_cases[index]
+    }
+
+    pub fn remove_test_case(&mut self, index : usize) -> Result<RockeryTestCase, String> {
+        if index >= self.test_cases.len() {
+            return Err(format!("Invalid index. There are only {} test cases but test case {} was requested.", self.test_cases.len(), index));
+        }
+
+        Ok(self.test_cases.remove(index))
+    }
+
+    /// Run all test cases of the suite in order.
+    pub fn run(&self, project : &RockeryProject) -> RockeryTestSuiteResult {
+        self.run_with_variables(project, &mut project.get_variables().clone())
+    }
+
+    /// Run all test cases of the suite in order. Project variables set by one test case are visible to the following ones.
+    /// Data driven test cases contribute one result per row.
+    pub fn run_with_variables(&self, project : &RockeryProject, project_variables : &mut HashMap<String, String>) -> RockeryTestSuiteResult {
+        let mut test_cases = Vec::new();
+        for t in &self.test_cases {
+            test_cases.extend(t.run_all(project, project_variables));
+        }
+
+        RockeryTestSuiteResult { name : self.name.clone(), test_cases }
+    }
+}
+
+/// The outcome of a single executed step.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RockeryStepResult {
+    name : String,
+    result : Option<RockeryRequestResult>,
+
+    /// The reason why the request could not be executed.
+    error : Option<String>,
+
+    /// The values of the property transfers, None if nothing could be extracted.
+    #[serde(default)]
+    transfers : Vec<(String, Option<String>)>,
+
+    /// Time it took to execute the step in milliseconds, including its delay.
+    #[serde(default)]
+    duration : u64,
+}
+
+impl RockeryStepResult {
+    fn new(name : &str, result : Result<RockeryRequestResult, String>) -> Self {
+        let (result, error) = match result {
+            Ok(r) => (Some(r), None),
+            Err(e) => (None, Some(e)),
+        };
+
+        RockeryStepResult { name : name.to_owned(), result, error, transfers : Vec::new(), duration : 0 }
+    }
+
+    pub fn get_name(&self) -> &str { &self.name }
+    pub fn get_result(&self) -> &Option<RockeryRequestResult> { &self.result }
+    pub fn get_error(&self) -> &Option<String> { &self.error }
+    pub fn get_transfers(&self) -> &Vec<(String, Option<String>)> { &self.transfers }
+    pub fn get_duration(&self) -> u64 { self.duration }
+
+    /// True if the request has been executed, the response is valid, all assertions passed and
+    /// all property transfers found a value.
+    pub fn is_passed(&self) -> bool {
+        self.result.as_ref().map(|r| r.is_passed()).unwrap_or(false) && self.transfers.iter().all(|(_, v)| v.is_some())
+    }
+}
+
+impl fmt::Display for RockeryStepResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = if self.is_passed() { "PASS" } else { "FAIL" };
+        match (&self.result, &self.error) {
+            (Some(r), _) => {
+                writeln!(f, "[{}] {} ({} {}, {} ms)", state, self.name, r.get_response().get_status(), r.get_response().get_status_text(), r.get_response().get_duration())?;
+                for v in r.get_violations() {
+                    writeln!(f, "    {}", v)?;
+                }
+                for a in r.get_assertions() {
+                    writeln!(f, "    {}", a)?;
+                }
+                for (name, value) in &self.transfers {
+                    match value {
+                        Some(v) => writeln!(f, "    ${{{}}} = {}", name, v)?,
+                        None => writeln!(f, "    ${{{}}}: nothing extracted", name)?,
+                    }
+                }
+                Ok(())
+            },
+            (None, e) => writeln!(f, "[{}] {}: {}", state, self.name, e.clone().unwrap_or_default()),
+        }
+    }
+}
+
+/// The results of all steps executed for a test case, in the order they have been executed.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RockeryTestCaseResult {
+    name : String,
+    steps : Vec<RockeryStepResult>,
+}
+
+impl RockeryTestCaseResult {
+    pub fn get_name(&self) -> &str { &self.name }
+    pub fn get_steps(&self) -> &Vec<RockeryStepResult> { &self.steps }
+    pub fn is_passed(&self) -> bool { self.steps.iter().all(|s| s.is_passed()) }
+
+    /// Time it took to execute all steps in milliseconds.
+    pub fn get_duration(&self) -> u64 { self.steps.iter().map(|s| s.duration).sum() }
+
+    pub(crate) fn with_name(self, name : &str) -> Self {
+        RockeryTestCaseResult { name : name.to_owned(), ..self }
+    }
+}
+
+impl fmt::Display for RockeryTestCaseResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Test case {}: {}", self.name, if self.is_passed() { "PASS" } else { "FAIL" })?;
+        for s in &self.steps {
+            write!(f, "  {}", s)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RockeryTestSuiteResult {
+    name : String,
+    test_cases : Vec<RockeryTestCaseResult>,
+}
+
+impl RockeryTestSuiteResult {
+    pub fn new(name : &str, test_cases : Vec<RockeryTestCaseResult>) -> Self {
+        RockeryTestSuiteResult { name : name.to_owned(), test_cases }
+    }
+
+    pub fn get_name(&self) -> &str { &self.name }
+    pub fn get_test_cases(&self) -> &Vec<RockeryTestCaseResult> { &self.test_cases }
+    pub fn is_passed(&self) -> bool { self.test_cases.iter().all(|t| t.is_passed()) }
+
+    /// Time it took to execute all test cases in milliseconds.
+    pub fn get_duration(&self) -> u64 { self.test_cases.iter().map(|t| t.get_duration()).sum() }
+}
+
+impl fmt::Display for RockeryTestSuiteResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Test suite {}: {}", self.name, if self.is_passed() { "PASS" } else { "FAIL" })?;
+        for t in &self.test_cases {
+            write!(f, "{}", t)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod rockery_test_suite_tests {
+    use crate::rockery_test_helper::serve;
+    use super::*;
+
+    fn get_project(base_url : &str) -> RockeryProject {
+        let mut service = RockeryService::new(RockeryServiceType::Rest, base_url);
+        service.set_name("Jobs");
+        service.add_method("start", "jobs").set_request_method(RequestMethod::Post);
+        service.add_method("status", "jobs/{id}").add_parameter(RequestParameter::Path("id".to_owned(), Some("1".to_owned())));
+
+        let mut project = RockeryProject::new("Test suites");
+        project.add_service(service);
+        project
+    }
+
+    #[test]
+    fn test_run() {
+        let (base_url, requests) = serve(vec![
+            (201, r#"{ "id": 7 }"#),
+            (200, r#"{ "state": "running" }"#),
+            (200, r#"{ "state": "done" }"#),
+        ]);
+        let mut project = get_project(&base_url);
+
+        let mut test_case = RockeryTestCase::new("Run a job");
+        test_case.add_step(RockeryTestStep::new("Start", "Jobs", "start"))
+            .add_assertion(RockeryAssertion::StatusCode(201));
+
+        let poll = test_case.add_step(RockeryTestStep::new("Poll", "Jobs", "status"));
+        poll.add_parameter(RequestParameter::Path("id".to_owned(), Some("7".to_owned())));
+        poll.set_delay(10);
+        poll.set_goto(Some(RockeryGoto::new(RockeryGotoCondition::Matches(RockeryAssertion::BodyContains("running".to_owned())), "Poll")));
+
+        test_case.add_step(RockeryTestStep::new("Missing", "Jobs", "cancel"));
+
+        let mut suite = RockeryTestSuite::new("Jobs");
+        suite.add_test_case(test_case);
+        project.add_test_suite(suite);
+
+        let result = project.get_test_suites()[0].run(&project);
+        assert!(!result.is_passed());
+
+        let steps = result.get_test_cases()[0].get_steps();
+        let names : Vec<&str> = steps.iter().map(|s| s.get_name()).collect();
+        assert_eq!(names, vec!["Start", "Poll", "Poll", "Missing"]);
+        assert!(steps[0].is_passed());
+        assert!(steps[2].is_passed());
+        assert_eq!(steps[3].get_error(), &Some("A method with the name cancel does not exist within service Jobs.".to_owned()));
+
+        assert!(requests.recv().unwrap().starts_with("POST /jobs/ "));
+        assert!(requests.recv().unwrap().starts_with("GET /jobs/7/ "));
+        assert!(result.to_string().contains("[PASS] Start (201 Status"));
+    }
+
+    #[test]
+    fn test_property_transfer() {
+        let (base_url, requests) = serve(vec![
+            (201, r#"{ "id": 7, "token": "abc" }"#),
+            (200, r#"{ "state": "done" }"#),
+        ]);
+        let mut project = get_project(&base_url);
+        project.set_variable("prefix", "job");
+
+        // Variables are substituted into the defaults of the method as well.
+        let service = project.get_services().get_mut("Jobs").unwrap();
+        service.get_method_mut(1).unwrap().add_parameter(RequestParameter::Header("Authorization".to_owned(), Some("Bearer ${token}".to_owned())));
+
+        let mut test_case = RockeryTestCase::new("Transfer");
+        test_case.set_variable("prefix", "task");
+        let start = test_case.add_step(RockeryTestStep::new("Start", "Jobs", "start"));
+        start.add_transfer(RockeryPropertyTransfer::new("id", RockeryPropertySource::JsonPath("$.id".to_owned()), RockeryVariableScope::TestCase));
+        start.add_transfer(RockeryPropertyTransfer::new("token", RockeryPropertySource::JsonPath("$.token".to_owned()), RockeryVariableScope::Project));
+
+        let status = test_case.add_step(RockeryTestStep::new("Status", "Jobs", "status"));
+        status.add_parameter(RequestParameter::Path("id".to_owned(), Some("${prefix}-${id}".to_owned())));
+        status.add_transfer(RockeryPropertyTransfer::new("missing", RockeryPropertySource::Header("Location".to_owned()), RockeryVariableScope::TestCase));
+
+        let mut suite = RockeryTestSuite::new("Transfer");
+        suite.add_test_case(test_case);
+        project.add_test_suite(suite);
+
+        let result = project.run_test_suite(0).unwrap();
+        let steps = result.get_test_cases()[0].get_steps();
+        assert!(steps[0].is_passed());
+        assert_eq!(steps[0].get_transfers(), &vec![("id".to_owned(), Some("7".to_owned())), ("token".to_owned(), Some("abc".to_owned()))]);
+        assert!(!steps[1].is_passed());
+
+        requests.recv().unwrap();
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("GET /jobs/task-7/ "));
+        assert!(request.to_lowercase().contains("authorization: bearer abc"));
+        assert_eq!(project.get_variables().get("token"), Some(&"abc".to_owned()));
+        assert!(!project.get_variables().contains_key("id"));
+    }
+
+    #[test]
+    fn test_endless_loop() {
+        let mut project = get_project("http://127.0.0.1:1");
+        let mut test_case = RockeryTestCase::new("Loop");
+        test_case.add_step(RockeryTestStep::new("Step", "Unknown", "start"))
+            .set_goto(Some(RockeryGoto::new(RockeryGotoCondition::Failed, "Step")));
+
+        let mut suite = RockeryTestSuite::new("Loop");
+        suite.add_test_case(test_case);
+        project.add_test_suite(suite);
+
+        let result = project.run_test_suite(0).unwrap();
+        let steps = result.get_test_cases()[0].get_steps();
+        assert_eq!(steps.len(), MAX_EXECUTED_STEPS + 1);
+        assert!(steps[MAX_EXECUTED_STEPS].get_error().as_ref().unwrap().starts_with("Aborted"));
+        assert!(project.run_test_suite(1).is_err());
+    }
+
+    #[test]
+    fn test_missing_goto_target() {
+        let mut project = get_project("http://127.0.0.1:1");
+        let mut test_case = RockeryTestCase::new("Goto");
+        test_case.add_step(RockeryTestStep::new("Step", "Unknown", "start"))
+            .set_goto(Some(RockeryGoto::new(RockeryGotoCondition::Failed, "Nowhere")));
+
+        let mut suite = RockeryTestSuite::new("Goto");
+        suite.add_test_case(test_case);
+        project.add_test_suite(suite);
+
+        // The result of the step is reported before the missing target.
+        let result = project.run_test_suite(0).unwrap();
+        let steps = result.get_test_cases()[0].get_steps();
+        assert_eq!(steps.len(), 2);
+        assert!(steps[0].get_error().as_ref().unwrap().contains("service with the name Unknown"));
+        assert_eq!(steps[1].get_error(), &Some("The goto target Nowhere does not exist.".to_owned()));
+    }
+}
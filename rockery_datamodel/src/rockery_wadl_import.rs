@@ -0,0 +1,280 @@
+use std::str::FromStr;
+
+use crate::prelude::*;
+use crate::rockery_definition::read_definition;
+use crate::rockery_xml::XmlElement;
+
+/// Creates rest services from wadl documents (https://www.w3.org/submission/wadl/).
+/// Every method of every resource becomes a method of the service. Query, header and template
+/// parameters are taken over including their default values. The base url of the first `resources` element
+/// becomes the base url of the service, further `resources` must be located below it.
+pub struct RockeryWadlImporter;
+
+impl RockeryWadlImporter {
+    /// Read the wadl file from the given url or path and create a service from it.
+    pub fn import(location : &str) -> Result<RockeryService, String> {
+        let content = read_definition(location)?;
+        let mut service = Self::import_str(&content)?;
+        service.set_definition_url(location);
+
+        Ok(service)
+    }
+
+    /// Create a service from the content of a wadl file.
+    pub fn import_str(definition : &str) -> Result<RockeryService, String> {
+        let root = XmlElement::parse(definition)?;
+        if root.get_local_name() != "application" {
+            return Err(format!("Not a wadl document. Expected 'application' as root element but found '{}'.", root.get_name()));
+        }
+
+        let resources = root.first_child("resources")
+            .ok_or("The wadl document does not contain any resources.".to_owned())?;
+
+        let base_url = resources.get_attribute("base").unwrap_or("").trim_end_matches('/');
+        let mut service = RockeryService::new(RockeryServiceType::Rest, base_url);
+
+        let title = root.first_child("doc").and_then(|d| d.get_attribute("title"));
+        let host = base_url.split("://").nth(1).and_then(|r| r.split('/').next());
+        if let Some(name) = title.or(host).filter(|n| !n.is_empty()) {
+            service.set_name(name);
+        }
+
+        // A service has one base url. Further resources must be located below the base url of the first one.
+        for resources in root.children_named("resources") {
+            let base = resources.get_attribute("base").unwrap_or("").trim_end_matches('/');
+            let prefix = match base.strip_prefix(base_url) {
+                Some(p) if p.is_empty() || p.starts_with('/') => p,
+                _ => return Err(format!("The wadl document contains resources with the base urls {} and {}. Resources with different base urls can not be imported into one service.", base_url, base)),
+            };
+
+            for r in resources.children_named("resource") {
+                Self::import_resource(&root, r, prefix, &[], &mut service);
+            }
+        }
+
+        Ok(service)
+    }
+
+    fn import_resource(root : &XmlElement, resource : &XmlElement, parent_path : &str, inherited : &[RequestParameter], service : &mut RockeryService) {
+        let path = join_path(parent_path, resource.get_attribute("path").unwrap_or(""));
+
+        // Resource types contribute parameters and methods as if they were declared on the resource itself.
+        let mut sources = vec![resource];
+        if let Some(types) = resource.get_attribute("type") {
+            for t in types.split_whitespace() {
+                if let Some(rt) = find_by_id(root, "resource_type", t) {
+                    sources.push(rt);
+                }
+            }
+        }
+
+        let mut params = inherited.to_vec();
+        for s in &sources {
+            for p in s.children_named("param") {
+                if let Some(p) = convert_parameter(p, false) {
+                    add_or_replace(&mut params, p);
+                }
+            }
+        }
+
+        for s in &sources {
+            for m in s.children_named("method") {
+                // Methods may only reference a method defined on the application level.
+                let m = match m.get_attribute("href") {
+                    Some(href) => match find_by_id(root, "method", href) {
+                        Some(m) => m,
+                        None => continue,
+                    },
+                    None => m,
+                };
+
+                Self::import_method(m, &path, &params, service);
+            }
+        }
+
+        for r in resource.children_named("resource") {
+            Self::import_resource(root, r, &path, &params, service);
+        }
+    }
+
+    fn import_method(method : &XmlElement, path : &str, params : &[RequestParameter], service : &mut RockeryService) {
+        let http_method = method.get_attribute("name").unwrap_or("GET").to_uppercase();
+        let request_method = match RequestMethod::from_str(&http_method) {
+            Ok(m) => m,
+            Err(_) => return,
+        };
+
+        let mut params = params.to_vec();
+        let mut content_type = None;
+        if let Some(request) = method.first_child("request") {
+            for p in request.children_named("param") {
+                if let Some(p) = convert_parameter(p, false) {
+                    add_or_replace(&mut params, p);
+                }
+            }
+
+            for representation in request.children_named("representation") {
+                if content_type.is_none() {
+                    content_type = representation.get_attribute("mediaType");
+                }
+
+                for p in representation.children_named("param") {
+                    if let Some(p) = convert_parameter(p, true) {
+                        add_or_replace(&mut params, p);
+                    }
+                }
+            }
+        }
+
+        if let Some(content_type) = content_type {
+            let has_content_type = params.iter().any(|p| matches!(p, RequestParameter::Header(k, _) if k.eq_ignore_ascii_case("content-type")));
+            if !has_content_type {
+                params.push(RequestParameter::Header("Content-Type".to_owned(), Some(content_type.to_owned())));
+            }
+        }
+
+        let name = match method.get_attribute("id") {
+            Some(id) => id.to_owned(),
+            None => format!("{} {}", http_method, path),
+        };
+
+        let m = service.add_method(&name, path);
+        m.set_request_method(request_method);
+        for p in params {
+            m.add_parameter(p);
+        }
+    }
+}
+
+/// Find an application level element by its id. References look like `#id`.
+fn find_by_id<'a>(root : &'a XmlElement, element : &'a str, reference : &str) -> Option<&'a XmlElement> {
+    let id = reference.trim_start_matches('#');
+    root.children_named(element).find(|e| e.get_attribute("id") == Some(id))
+}
+
+/// Convert a wadl param element. Matrix parameters are not supported and skipped.
+fn convert_parameter(param : &XmlElement, representation : bool) -> Option<RequestParameter> {
+    let name = param.get_attribute("name")?.to_owned();
+    let default = param.get_attribute("fixed")
+        .or(param.get_attribute("default"))
+        .map(|d| d.to_owned());
+
+    match param.get_attribute("style").unwrap_or(if representation { "query" } else { "" }) {
+        "query" if representation => Some(RequestParameter::Body(name, default)),
+        "query" => Some(RequestParameter::Url(name, default)),
+        "header" => Some(RequestParameter::Header(name, default)),
+        "template" => Some(RequestParameter::Path(name, default)),
+        "plain" => Some(RequestParameter::Body(name, default)),
+        _ => None,
+    }
+}
+
+fn add_or_replace(params : &mut Vec<RequestParameter>, param : RequestParameter) {
+    match params.iter().position(|p| p.is_same_parameter(&param)) {
+        Some(i) => params[i] = param,
+        None => params.push(param),
+    }
+}
+
+fn join_path(parent : &str, path : &str) -> String {
+    parent.split('/')
+        .chain(path.split('/'))
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<&str>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod rockery_wadl_import_tests {
+    use super::*;
+
+    const WADL : &str = r##"<?xml version="1.0" encoding="UTF-8"?>
+        <application xmlns="http://wadl.dev.java.net/2009/02" xmlns:xsd="http://www.w3.org/2001/XMLSchema">
+            <doc title="User API"/>
+            <resources base="https://example.com/api/">
+                <resource path="users" type="#paged">
+                    <param name="X-Token" style="header" default="secret"/>
+                    <method name="GET" id="listUsers">
+                        <request>
+                            <param name="active" style="query" type="xsd:boolean"/>
+                        </request>
+                    </method>
+                    <method name="POST" id="createUser">
+                        <request>
+                            <representation mediaType="application/json"/>
+                        </request>
+                    </method>
+                    <resource path="{id}">
+                        <param name="id" style="template" type="xsd:int" required="true"/>
+                        <method name="GET"/>
+                        <method href="#deleteUser"/>
+                    </resource>
+                </resource>
+            </resources>
+            <resource_type id="paged">
+                <param name="limit" style="query" default="10"/>
+            </resource_type>
+            <method name="DELETE" id="deleteUser"/>
+        </application>"##;
+
+    #[test]
+    fn test_import() {
+        let service = RockeryWadlImporter::import_str(WADL).unwrap();
+        assert_eq!(service.get_name(), "User API");
+        assert_eq!(service.get_base_url(), "https://example.com/api");
+        assert_eq!(service.get_service_type(), &RockeryServiceType::Rest);
+
+        let methods = service.get_methods();
+        assert_eq!(methods.len(), 4);
+
+        assert_eq!(methods[0].get_name(), "listUsers");
+        assert_eq!(methods[0].get_uri(), "users");
+        assert_eq!(methods[0].get_request_method(), RequestMethod::Get);
+        assert_eq!(methods[0].get_parameter(), &vec![
+            RequestParameter::Header("X-Token".to_owned(), Some("secret".to_owned())),
+            RequestParameter::Url("limit".to_owned(), Some("10".to_owned())),
+            RequestParameter::Url("active".to_owned(), None),
+        ]);
+
+        assert_eq!(methods[1].get_request_method(), RequestMethod::Post);
+        assert!(methods[1].get_parameter().contains(&RequestParameter::Header("Content-Type".to_owned(), Some("application/json".to_owned()))));
+
+        assert_eq!(methods[2].get_name(), "GET users/{id}");
+        assert_eq!(methods[2].get_uri(), "users/{id}");
+        assert!(methods[2].get_parameter().contains(&RequestParameter::Path("id".to_owned(), None)));
+
+        assert_eq!(methods[3].get_name(), "deleteUser");
+        assert_eq!(methods[3].get_request_method(), RequestMethod::Delete);
+
+        let params = vec![RequestParameter::Path("id".to_owned(), Some("5".to_owned()))];
+        assert_eq!(service.get_request_url(3, &params), "https://example.com/api/users/5/?limit=10");
+    }
+
+    #[test]
+    fn test_invalid_document() {
+        assert!(RockeryWadlImporter::import_str("<definitions/>").is_err());
+        assert!(RockeryWadlImporter::import_str("<application/>").is_err());
+        assert!(RockeryWadlImporter::import("does_not_exist.wadl").is_err());
+
+        let different_bases = r#"<application xmlns="http://wadl.dev.java.net/2009/02">
+            <resources base="https://example.com/api/"><resource path="users"><method name="GET"/></resource></resources>
+            <resources base="https://other.example.com/"><resource path="items"><method name="GET"/></resource></resources>
+        </application>"#;
+        assert_eq!(RockeryWadlImporter::import_str(different_bases).unwrap_err(),
+            "The wadl document contains resources with the base urls https://example.com/api and https://other.example.com. Resources with different base urls can not be imported into one service.");
+    }
+
+    #[test]
+    fn test_import_several_resources() {
+        let wadl = r#"<application xmlns="http://wadl.dev.java.net/2009/02">
+            <resources base="https://example.com/api/"><resource path="users"><method name="GET"/></resource></resources>
+            <resources base="https://example.com/api/v2"><resource path="items"><method name="GET"/></resource></resources>
+            <resources base="https://example.com/api"><resource path="orders"><method name="GET"/></resource></resources>
+        </application>"#;
+        let service = RockeryWadlImporter::import_str(wadl).unwrap();
+        assert_eq!(service.get_base_url(), "https://example.com/api");
+        let uris : Vec<&str> = service.get_methods().iter().map(|m| m.get_uri()).collect();
+        assert_eq!(uris, vec!["users", "v2/items", "orders"]);
+        assert_eq!(service.get_request_url(1, &[]), "https://example.com/api/v2/items/");
+    }
+}
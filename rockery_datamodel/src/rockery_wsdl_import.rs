@@ -0,0 +1,425 @@
+use crate::prelude::*;
+use crate::rockery_definition::read_definition;
+use crate::rockery_http::split_origin;
+use crate::rockery_xml::{local_name, XmlElement};
+
+const SOAP_11_BINDING : &str = "http://schemas.xmlsoap.org/wsdl/soap/";
+const SOAP_12_BINDING : &str = "http://schemas.xmlsoap.org/wsdl/soap12/";
+const SOAP_11_ENVELOPE : &str = "http://schemas.xmlsoap.org/soap/envelope/";
+const SOAP_12_ENVELOPE : &str = "http://www.w3.org/2003/05/soap-envelope";
+
+/// Nesting depth up to which the child elements of a message are written into the sample request.
+const MAX_SAMPLE_DEPTH : usize = 8;
+
+/// Creates soap services from WSDL 1.1 documents (https://www.w3.org/TR/wsdl). WSDL 2.0 is not supported.
+///
+/// The first port with a SOAP 1.1 or 1.2 binding is imported: its address becomes the base url and every
+/// operation of the binding a POST method with the SOAP action and a request envelope that contains the
+/// elements of the input message. The output element is used as response schema, the wsdl document it is
+/// validated against is stored once as xml schema of the service.
+pub struct RockeryWsdlImporter;
+
+impl RockeryWsdlImporter {
+    /// Read the wsdl file from the given url or path and create a service from it.
+    pub fn import(location : &str) -> Result<RockeryService, String> {
+        let content = read_definition(location)?;
+        let mut service = Self::import_str(&content)?;
+        service.set_definition_url(location);
+
+        Ok(service)
+    }
+
+    /// Create a service from the content of a wsdl file.
+    pub fn import_str(definition : &str) -> Result<RockeryService, String> {
+        let root = XmlElement::parse(definition)?;
+        match root.get_local_name() {
+            "definitions" => { },
+            "description" => return Err("WSDL 2.0 documents are not supported, only WSDL 1.1.".to_owned()),
+            name => return Err(format!("Not a wsdl document. Expected 'definitions' as root element but found '{}'.", name)),
+        }
+
+        let (service_element, port, binding, soap_12) = root.children_named("service")
+            .flat_map(|s| s.children_named("port").map(move |p| (s, p)))
+            .find_map(|(s, p)| {
+                let binding = find_named(&root, "binding", p.get_attribute("binding")?)?;
+                let soap_12 = soap_version(&root, binding)?;
+                Some((s, p, binding, soap_12))
+            })
+            .ok_or("The wsdl document does not contain a port with a soap binding.".to_owned())?;
+
+        let address = port.first_child("address").and_then(|a| a.get_attribute("location")).unwrap_or_default();
+        let (base_url, path, _) = split_origin(address);
+        let path = path.trim_matches('/');
+
+        let mut service = RockeryService::new(RockeryServiceType::Soap, base_url.trim_end_matches('/'));
+        if let Some(name) = service_element.get_attribute("name").or(root.get_attribute("name")) {
+            service.set_name(name);
+        }
+
+        let port_type = binding.get_attribute("type").and_then(|t| find_named(&root, "portType", t));
+        let mut has_schemas = false;
+        for operation in binding.children_named("operation") {
+            let name = operation.get_attribute("name").unwrap_or("Operation");
+            let action = operation.first_child("operation").and_then(|o| o.get_attribute("soapAction")).unwrap_or_default();
+            let abstract_operation = port_type.and_then(|p| p.children_named("operation").find(|o| o.get_attribute("name") == Some(name)));
+
+            let m = service.add_method(name, path);
+            m.set_request_method(RequestMethod::Post);
+            for p in soap_headers(action, soap_12) {
+                m.add_parameter(p);
+            }
+
+            let abstract_operation = match abstract_operation {
+                Some(o) => o,
+                None => continue,
+            };
+
+            if let Some(body) = request_body(&root, binding, operation, abstract_operation, soap_12) {
+                m.set_body(&body);
+            }
+
+            let output = abstract_operation.first_child("output").and_then(|o| message_element(&root, o));
+            if let Some(element) = output {
+                m.add_response_schema(RockeryResponseSchema::new("200", None,
+                    RockerySchema::ServiceXsd(Some(local_name(element).to_owned()))));
+                has_schemas = true;
+            }
+        }
+
+        // The document is stored once with the service, the methods refer to it.
+        if has_schemas {
+            service.set_xml_schema(Some(definition));
+        }
+
+        Ok(service)
+    }
+}
+
+/// The headers a soap request needs. SOAP 1.2 sends the action within the content type.
+pub(crate) fn soap_headers(action : &str, soap_12 : bool) -> Vec<RequestParameter> {
+    if soap_12 {
+        vec![RequestParameter::Header("Content-Type".to_owned(), Some(format!("application/soap+xml;charset=UTF-8;action=\"{}\"", action)))]
+    } else {
+        vec![
+            RequestParameter::Header("Content-Type".to_owned(), Some("text/xml;charset=UTF-8".to_owned())),
+            RequestParameter::Header("SOAPAction".to_owned(), Some(format!("\"{}\"", action))),
+        ]
+    }
+}
+
+/// Find a top level element of the document by its (possibly prefixed) name.
+fn find_named<'a>(root : &'a XmlElement, element : &'a str, name : &str) -> Option<&'a XmlElement> {
+    let name = local_name(name);
+    root.children_named(element).find(|e| e.get_attribute("name") == Some(name))
+}
+
+/// Resolve the namespace of a prefixed name. Declarations on the given elements take precedence over those
+/// on the root element.
+fn namespace<'a>(scopes : &[&'a XmlElement], name : &str) -> Option<&'a str> {
+    let declaration = match name.split_once(':') {
+        Some((prefix, _)) => format!("xmlns:{}", prefix),
+        None => "xmlns".to_owned(),
+    };
+
+    scopes.iter().find_map(|s| s.get_attribute(&declaration))
+}
+
+/// Whether a binding uses SOAP 1.2. Returns None for bindings that are not soap bindings, e.g. http bindings.
+fn soap_version(root : &XmlElement, binding : &XmlElement) -> Option<bool> {
+    let soap_binding = binding.first_child("binding")?;
+    match namespace(&[soap_binding, binding, root], soap_binding.get_name())? {
+        SOAP_11_BINDING => Some(false),
+        SOAP_12_BINDING => Some(true),
+        _ => None,
+    }
+}
+
+/// The element of the first part of the message an input or output refers to.
+fn message_element<'a>(root : &'a XmlElement, io : &XmlElement) -> Option<&'a str> {
+    let message = find_named(root, "message", io.get_attribute("message")?)?;
+    message.children_named("part").find_map(|p| p.get_attribute("element"))
+}
+
+/// Create the sample envelope for an operation. Document style operations contain the elements of the input
+/// message, rpc style operations a wrapper element named like the operation with one element per part.
+/// The default style is document.
+fn request_body(root : &XmlElement, binding : &XmlElement, operation : &XmlElement, abstract_operation : &XmlElement, soap_12 : bool) -> Option<String> {
+    let input = abstract_operation.first_child("input")?;
+    let message = find_named(root, "message", input.get_attribute("message")?)?;
+
+    let rpc = operation.first_child("operation").and_then(|o| o.get_attribute("style"))
+        .or(binding.first_child("binding").and_then(|b| b.get_attribute("style")))
+        == Some("rpc");
+
+    let mut namespaces = Vec::new();
+    let mut content = String::new();
+    if rpc {
+        let namespace = operation.first_child("input")
+            .and_then(|i| i.first_child("body"))
+            .and_then(|b| b.get_attribute("namespace"))
+            .or(root.get_attribute("targetNamespace"))
+            .unwrap_or_default();
+        namespaces.push(namespace.to_owned());
+
+        let name = operation.get_attribute("name").unwrap_or("Operation");
+        content.push_str(&format!("      <ns1:{}>\n", name));
+        for part in message.children_named("part") {
+            content.push_str(&format!("         <{}>?</{0}>\n", part.get_attribute("name").unwrap_or("part")));
+        }
+        content.push_str(&format!("      </ns1:{}>\n", name));
+    } else {
+        for element in message.children_named("part").filter_map(|p| p.get_attribute("element")) {
+            let (schema, declaration) = match find_element(root, element) {
+                Some(e) => e,
+                None => continue,
+            };
+
+            let namespace = namespace(&[root], element)
+                .or(schema.get_attribute("targetNamespace"))
+                .unwrap_or_default();
+            let prefix = match namespaces.iter().position(|n| n == namespace) {
+                Some(i) => format!("ns{}", i + 1),
+                None => {
+                    namespaces.push(namespace.to_owned());
+                    format!("ns{}", namespaces.len())
+                },
+            };
+
+            write_sample(root, schema, declaration, &prefix, 2, &mut content);
+        }
+    }
+
+    let envelope = if soap_12 { SOAP_12_ENVELOPE } else { SOAP_11_ENVELOPE };
+    let declarations : Vec<String> = namespaces.iter().enumerate()
+        .map(|(i, n)| format!(" xmlns:ns{}=\"{}\"", i + 1, n))
+        .collect();
+
+    Some(format!("<soapenv:Envelope xmlns:soapenv=\"{}\"{}>\n   <soapenv:Header/>\n   <soapenv:Body>\n{}   </soapenv:Body>\n</soapenv:Envelope>",
+        envelope, declarations.concat(), content))
+}
+
+/// Find the global declaration of an element within the schemas of the types section.
+fn find_element<'a>(root : &'a XmlElement, name : &str) -> Option<(&'a XmlElement, &'a XmlElement)> {
+    let name = local_name(name);
+    root.first_child("types")?.children_named("schema")
+        .find_map(|s| s.children_named("element").find(|e| e.get_attribute("name") == Some(name)).map(|e| (s, e)))
+}
+
+fn find_complex_type<'a>(root : &'a XmlElement, name : &str) -> Option<&'a XmlElement> {
+    let name = local_name(name);
+    root.first_child("types")?.children_named("schema")
+        .find_map(|s| s.children_named("complexType").find(|e| e.get_attribute("name") == Some(name)))
+}
+
+/// Write an element and its child elements with `?` as placeholder for values. Child elements are qualified
+/// only if the schema says so.
+fn write_sample(root : &XmlElement, schema : &XmlElement, declaration : &XmlElement, prefix : &str, depth : usize, out : &mut String) {
+    let declaration = match declaration.get_attribute("ref").and_then(|r| find_element(root, r)) {
+        Some((_, d)) => d,
+        None => declaration,
+    };
+
+    let name = match declaration.get_attribute("name") {
+        Some(n) if prefix.is_empty() => n.to_owned(),
+        Some(n) => format!("{}:{}", prefix, n),
+        None => return,
+    };
+    let indent = "   ".repeat(depth);
+
+    let complex = declaration.first_child("complexType")
+        .or(declaration.get_attribute("type").and_then(|t| find_complex_type(root, t)));
+    let children : Vec<&XmlElement> = complex
+        .and_then(|c| c.first_child("sequence").or(c.first_child("all")).or(c.first_child("choice")))
+        .map(|g| g.children_named("element").collect())
+        .unwrap_or_default();
+
+    if children.is_empty() || depth >= MAX_SAMPLE_DEPTH {
+        out.push_str(&format!("{}<{}>?</{1}>\n", indent, name));
+        return;
+    }
+
+    let child_prefix = match schema.get_attribute("elementFormDefault") {
+        Some("qualified") => prefix,
+        _ => "",
+    };
+
+    out.push_str(&format!("{}<{}>\n", indent, name));
+    for c in children {
+        write_sample(root, schema, c, child_prefix, depth + 1, out);
+    }
+    out.push_str(&format!("{}</{}>\n", indent, name));
+}
+
+#[cfg(test)]
+mod rockery_wsdl_import_tests {
+    use super::*;
+
+    const WSDL : &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+        <wsdl:definitions name="Users" targetNamespace="urn:users"
+                xmlns:wsdl="http://schemas.xmlsoap.org/wsdl/"
+                xmlns:soap="http://schemas.xmlsoap.org/wsdl/soap/"
+                xmlns:soap12="http://schemas.xmlsoap.org/wsdl/soap12/"
+                xmlns:xs="http://www.w3.org/2001/XMLSchema"
+                xmlns:tns="urn:users">
+            <wsdl:types>
+                <xs:schema targetNamespace="urn:users" elementFormDefault="qualified">
+                    <xs:element name="GetUser">
+                        <xs:complexType>
+                            <xs:sequence>
+                                <xs:element name="id" type="xs:int"/>
+                                <xs:element name="filter" type="tns:Filter"/>
+                            </xs:sequence>
+                        </xs:complexType>
+                    </xs:element>
+                    <xs:complexType name="Filter">
+                        <xs:sequence>
+                            <xs:element name="active" type="xs:boolean"/>
+                        </xs:sequence>
+                    </xs:complexType>
+                    <xs:element name="GetUserResponse">
+                        <xs:complexType>
+                            <xs:sequence>
+                                <xs:element name="name" type="xs:string"/>
+                            </xs:sequence>
+                        </xs:complexType>
+                    </xs:element>
+                    <xs:element name="Ping" type="xs:string"/>
+                </xs:schema>
+            </wsdl:types>
+            <wsdl:message name="GetUserRequest"><wsdl:part name="parameters" element="tns:GetUser"/></wsdl:message>
+            <wsdl:message name="GetUserResponse"><wsdl:part name="parameters" element="tns:GetUserResponse"/></wsdl:message>
+            <wsdl:message name="PingRequest"><wsdl:part name="parameters" element="tns:Ping"/></wsdl:message>
+            <wsdl:portType name="UserPort">
+                <wsdl:operation name="GetUser">
+                    <wsdl:input message="tns:GetUserRequest"/>
+                    <wsdl:output message="tns:GetUserResponse"/>
+                </wsdl:operation>
+                <wsdl:operation name="Ping">
+                    <wsdl:input message="tns:PingRequest"/>
+                </wsdl:operation>
+            </wsdl:portType>
+            <wsdl:binding name="UserHttpBinding" type="tns:UserPort">
+                <http:binding xmlns:http="http://schemas.xmlsoap.org/wsdl/http/" verb="POST"/>
+            </wsdl:binding>
+            <wsdl:binding name="UserSoap12Binding" type="tns:UserPort">
+                <soap12:binding transport="http://schemas.xmlsoap.org/soap/http"/>
+                <wsdl:operation name="GetUser">
+                    <soap12:operation soapAction="urn:users/GetUser"/>
+                </wsdl:operation>
+                <wsdl:operation name="Ping">
+                    <soap12:operation soapAction="urn:users/Ping"/>
+                </wsdl:operation>
+            </wsdl:binding>
+            <wsdl:service name="UserService">
+                <wsdl:port name="UserHttpPort" binding="tns:UserHttpBinding">
+                    <http:address xmlns:http="http://schemas.xmlsoap.org/wsdl/http/" location="https://example.com/http"/>
+                </wsdl:port>
+                <wsdl:port name="UserSoap12Port" binding="tns:UserSoap12Binding">
+                    <soap12:address location="https://example.com/ws/users"/>
+                </wsdl:port>
+            </wsdl:service>
+        </wsdl:definitions>"#;
+
+    #[test]
+    fn test_import() {
+        let service = RockeryWsdlImporter::import_str(WSDL).unwrap();
+        assert_eq!(service.get_name(), "UserService");
+        assert_eq!(service.get_base_url(), "https://example.com");
+        assert_eq!(service.get_service_type(), &RockeryServiceType::Soap);
+
+        let methods = service.get_methods();
+        assert_eq!(methods.len(), 2);
+
+        let get_user = &methods[0];
+        assert_eq!(get_user.get_name(), "GetUser");
+        assert_eq!(get_user.get_uri(), "ws/users");
+        assert_eq!(get_user.get_request_method(), RequestMethod::Post);
+        assert_eq!(get_user.get_parameter(), &vec![
+            RequestParameter::Header("Content-Type".to_owned(), Some("application/soap+xml;charset=UTF-8;action=\"urn:users/GetUser\"".to_owned())),
+        ]);
+
+        let body = get_user.get_body().as_ref().unwrap();
+        assert!(body.starts_with(r#"<soapenv:Envelope xmlns:soapenv="http://www.w3.org/2003/05/soap-envelope" xmlns:ns1="urn:users">"#));
+        assert!(body.contains("<ns1:GetUser>\n         <ns1:id>?</ns1:id>\n         <ns1:filter>\n            <ns1:active>?</ns1:active>"));
+        assert!(XmlElement::parse(body).is_ok());
+
+        assert_eq!(get_user.get_response_schemas().len(), 1);
+        assert_eq!(get_user.get_response_schemas()[0].get_schema(), &RockerySchema::ServiceXsd(Some("GetUserResponse".to_owned())));
+        assert_eq!(service.get_xml_schema(), &Some(WSDL.to_owned()));
+
+        let ping = &methods[1];
+        assert!(ping.get_body().as_ref().unwrap().contains("<ns1:Ping>?</ns1:Ping>"));
+        assert!(ping.get_response_schemas().is_empty());
+    }
+
+    #[test]
+    fn test_validate_with_service_schema() {
+        let service = RockeryWsdlImporter::import_str(WSDL).unwrap();
+        let serialized = serde_json::to_string(&service).unwrap();
+        assert_eq!(serialized.matches("GetUserResponse").count(), WSDL.matches("GetUserResponse").count() + 1);
+
+        let response = |body : &str| RockeryResponse::new(200, "OK", &ehttp::Headers::new(&[]), body.as_bytes(), std::time::Duration::ZERO);
+        let valid = response(r#"<soap:Envelope xmlns:soap="http://www.w3.org/2003/05/soap-envelope"><soap:Body>
+            <GetUserResponse xmlns="urn:users"><name>rex</name></GetUserResponse></soap:Body></soap:Envelope>"#);
+        assert!(service.validate_response(0, &valid).is_empty());
+        let invalid = response(r#"<GetUserResponse xmlns="urn:users"><unknown/></GetUserResponse>"#);
+        assert!(!service.validate_response(0, &invalid).is_empty());
+
+        // Without the schema of the service the response can not be validated.
+        let mut without_schema = service.clone();
+        without_schema.set_xml_schema(None);
+        assert_eq!(without_schema.validate_response(0, &valid).len(), 1);
+    }
+
+    #[test]
+    fn test_import_definition() {
+        let path = std::env::temp_dir().join(format!("rockery_wsdl_{}.wsdl", std::process::id()));
+        std::fs::write(&path, WSDL).unwrap();
+        let location = path.to_str().unwrap();
+
+        let service = crate::rockery_definition::import_definition(location).unwrap();
+        assert_eq!(service.get_methods().len(), 2);
+        assert_eq!(service.get_definition_url(), &Some(location.to_owned()));
+        assert!(service.diff_with(&RockeryWsdlImporter::import(location).unwrap()).is_empty());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_import_rpc() {
+        let wsdl = r#"<definitions name="Calc" targetNamespace="urn:calc" xmlns="http://schemas.xmlsoap.org/wsdl/"
+                xmlns:soap="http://schemas.xmlsoap.org/wsdl/soap/" xmlns:tns="urn:calc">
+            <message name="AddRequest"><part name="a" type="xsd:int"/><part name="b" type="xsd:int"/></message>
+            <portType name="CalcPort"><operation name="Add"><input message="tns:AddRequest"/></operation></portType>
+            <binding name="CalcBinding" type="tns:CalcPort">
+                <soap:binding style="rpc" transport="http://schemas.xmlsoap.org/soap/http"/>
+                <operation name="Add">
+                    <soap:operation soapAction="add"/>
+                    <input><soap:body use="literal" namespace="urn:calc:ops"/></input>
+                </operation>
+            </binding>
+            <service name="CalcService"><port name="CalcPort" binding="tns:CalcBinding"><soap:address location="http://localhost:8080/calc"/></port></service>
+        </definitions>"#;
+
+        let service = RockeryWsdlImporter::import_str(wsdl).unwrap();
+        let add = &service.get_methods()[0];
+        assert_eq!(add.get_parameter(), &soap_headers("add", false));
+
+        let body = add.get_body().as_ref().unwrap();
+        assert!(body.contains(r#"xmlns:soapenv="http://schemas.xmlsoap.org/soap/envelope/" xmlns:ns1="urn:calc:ops""#));
+        assert!(body.contains("<ns1:Add>\n         <a>?</a>\n         <b>?</b>\n      </ns1:Add>"));
+    }
+
+    #[test]
+    fn test_invalid_document() {
+        assert!(RockeryWsdlImporter::import_str("<application/>").unwrap_err().contains("Not a wsdl document"));
+        assert!(RockeryWsdlImporter::import_str("<description/>").unwrap_err().contains("WSDL 2.0"));
+        assert!(RockeryWsdlImporter::import_str("<definitions/>").unwrap_err().contains("soap binding"));
+        assert!(RockeryWsdlImporter::import_str("<definitions><service>").is_err());
+        assert!(RockeryWsdlImporter::import("does_not_exist.wsdl").is_err());
+
+        // A port that refers to a binding that does not exist is not imported.
+        let missing = r#"<definitions xmlns:tns="urn:x"><service name="S"><port binding="tns:Missing"/></service></definitions>"#;
+        assert!(RockeryWsdlImporter::import_str(missing).is_err());
+    }
+}
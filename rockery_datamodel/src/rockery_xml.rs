@@ -0,0 +1,143 @@
+use quick_xml::{events::{BytesStart, Event}, Reader};
+
+/// A very small in memory representation of a xml document. It is used by the importers
+/// to walk definition files (wadl, wsdl, ...) without having to deal with a streaming parser.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub(crate) struct XmlElement {
+    name : String,
+    attributes : Vec<(String, String)>,
+    children : Vec<XmlElement>,
+    text : String,
+}
+
+/// Strips the namespace prefix of a qualified name.
+pub(crate) fn local_name(name : &str) -> &str {
+    match name.rfind(':') {
+        Some(i) => &name[i + 1..],
+        None => name,
+    }
+}
+
+impl XmlElement {
+    fn from_start(start : &BytesStart<'_>) -> Result<Self, String> {
+        let mut element = XmlElement {
+            name : String::from_utf8_lossy(start.name().as_ref()).to_string(),
+            ..Default::default()
+        };
+
+        for a in start.attributes() {
+            let a = a.map_err(|e| format!("Invalid attribute. Error: {}", e))?;
+            let key = String::from_utf8_lossy(a.key.as_ref()).to_string();
+            let value = match a.unescape_value() {
+                Ok(v) => v.to_string(),
+                Err(_) => String::from_utf8_lossy(&a.value).to_string(),
+            };
+            element.attributes.push((key, value));
+        }
+
+        Ok(element)
+    }
+
+    /// Parse a xml document and return its root element.
+    pub fn parse(input : &str) -> Result<Self, String> {
+        let mut reader = Reader::from_str(input);
+        reader.config_mut().trim_text(true);
+
+        let mut stack : Vec<XmlElement> = Vec::new();
+        loop {
+            match reader.read_event() {
+                Ok(Event::Start(e)) => {
+                    stack.push(XmlElement::from_start(&e)?);
+                },
+                Ok(Event::Empty(e)) => {
+                    let element = XmlElement::from_start(&e)?;
+                    match stack.last_mut() {
+                        Some(parent) => parent.children.push(element),
+                        None => return Ok(element),
+                    }
+                },
+                Ok(Event::End(_)) => {
+                    let element = stack.pop().ok_or("Unexpected closing tag.".to_owned())?;
+                    match stack.last_mut() {
+                        Some(parent) => parent.children.push(element),
+                        None => return Ok(element),
+                    }
+                },
+                Ok(Event::Text(t)) => {
+                    if let Some(current) = stack.last_mut() {
+                        match t.unescape() {
+                            Ok(s) => current.text.push_str(&s),
+                            Err(_) => current.text.push_str(&String::from_utf8_lossy(&t)),
+                        }
+                    }
+                },
+                Ok(Event::CData(c)) => {
+                    if let Some(current) = stack.last_mut() {
+                        current.text.push_str(&String::from_utf8_lossy(&c.into_inner()));
+                    }
+                },
+                Ok(Event::Eof) => {
+                    return Err("The document does not contain a root element.".to_owned());
+                },
+                Ok(_) => { },
+                Err(e) => {
+                    return Err(format!("Could not parse xml at position {}. Error: {}", reader.error_position(), e));
+                },
+            }
+        }
+    }
+
+    /// Create a document node, i.e. an unnamed element that contains the given root element.
+    pub fn document(root : &XmlElement) -> Self {
+        XmlElement { children : vec![root.clone()], ..Default::default() }
+    }
+
+    /// The qualified name of the element, including a namespace prefix if there is one.
+    pub fn get_name(&self) -> &str { &self.name }
+
+    /// The name of the element without its namespace prefix.
+    pub fn get_local_name(&self) -> &str { local_name(&self.name) }
+
+    /// Get the value of an attribute. The namespace prefix of the attribute is ignored.
+    pub fn get_attribute(&self, name : &str) -> Option<&str> {
+        self.attributes.iter()
+            .find(|(k, _)| k == name || local_name(k) == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn get_text(&self) -> &str { &self.text }
+    pub fn get_children(&self) -> &Vec<XmlElement> { &self.children }
+
+    /// All direct children with the given local name.
+    pub fn children_named<'a>(&'a self, name : &'a str) -> impl Iterator<Item = &'a XmlElement> + 'a {
+        self.children.iter().filter(move |c| c.get_local_name() == name)
+    }
+
+    /// The first direct child with the given local name.
+    pub fn first_child(&self, name : &str) -> Option<&XmlElement> {
+        self.children.iter().find(|c| c.get_local_name() == name)
+    }
+}
+
+#[cfg(test)]
+mod rockery_xml_tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let subject = XmlElement::parse(r#"<?xml version="1.0"?>
+            <ns:root xmlns:ns="urn:test" ns:id="1">
+                <child name="a">Text &amp; more</child>
+                <child name="b"/>
+                <other><![CDATA[<raw>]]></other>
+            </ns:root>"#).unwrap();
+
+        assert_eq!(subject.get_name(), "ns:root");
+        assert_eq!(subject.get_local_name(), "root");
+        assert_eq!(subject.get_attribute("id"), Some("1"));
+        assert_eq!(subject.children_named("child").count(), 2);
+        assert_eq!(subject.first_child("child").unwrap().text, "Text & more");
+        assert_eq!(subject.first_child("other").unwrap().text, "<raw>");
+        assert!(XmlElement::parse("no xml").is_err());
+    }
+}
@@ -0,0 +1,203 @@
+use crate::rockery_xml::{local_name, XmlElement};
+
+/// A condition within square brackets.
+#[derive(Debug, PartialEq)]
+enum Predicate {
+    Position(usize),
+    Attribute(String, Option<String>),
+    Child(String, String),
+}
+
+#[derive(Debug, PartialEq)]
+enum Step {
+    Element { name : String, descendant : bool, predicates : Vec<Predicate> },
+    Attribute(String),
+    Text,
+}
+
+fn unquote(value : &str) -> Option<String> {
+    let value = value.trim();
+    if value.len() >= 2 && (value.starts_with('\'') && value.ends_with('\'') || value.starts_with('"') && value.ends_with('"')) {
+        return Some(value[1..value.len() - 1].to_owned());
+    }
+
+    None
+}
+
+fn parse_predicate(predicate : &str, path : &str) -> Result<Predicate, String> {
+    let predicate = predicate.trim();
+    if let Ok(position) = predicate.parse::<usize>() {
+        return Ok(Predicate::Position(position));
+    }
+
+    let (left, right) = match predicate.split_once('=') {
+        Some((l, r)) => (l.trim(), Some(unquote(r).ok_or(format!("Invalid predicate in xpath '{}'.", path))?)),
+        None => (predicate, None),
+    };
+
+    match (left.strip_prefix('@'), right) {
+        (Some(attribute), value) => Ok(Predicate::Attribute(attribute.to_owned(), value)),
+        (None, Some(value)) => Ok(Predicate::Child(left.to_owned(), value)),
+        (None, None) => Err(format!("Unsupported predicate '{}' in xpath '{}'.", predicate, path)),
+    }
+}
+
+fn parse(path : &str) -> Result<Vec<Step>, String> {
+    let path = path.trim();
+    if !path.starts_with('/') {
+        return Err(format!("Only absolute xpaths are supported, got '{}'.", path));
+    }
+
+    let mut steps = Vec::new();
+    let mut rest = path;
+    while !rest.is_empty() {
+        let descendant = rest.starts_with("//");
+        rest = rest.trim_start_matches('/');
+
+        // Find the end of the step, ignoring slashes within predicates.
+        let mut depth = 0;
+        let end = rest.char_indices().find(|(_, c)| {
+            match c {
+                '[' => depth += 1,
+                ']' => depth -= 1,
+                '/' if depth == 0 => return true,
+                _ => { },
+            }
+            false
+        }).map(|(i, _)| i).unwrap_or(rest.len());
+
+        let step = &rest[..end];
+        rest = &rest[end..];
+
+        if step.is_empty() {
+            return Err(format!("Invalid xpath '{}'.", path));
+        } else if step == "text()" {
+            steps.push(Step::Text);
+        } else if let Some(attribute) = step.strip_prefix('@') {
+            steps.push(Step::Attribute(attribute.to_owned()));
+        } else {
+            let (name, mut predicates_text) = match step.find('[') {
+                Some(i) => (&step[..i], &step[i..]),
+                None => (step, ""),
+            };
+
+            let mut predicates = Vec::new();
+            while let Some(stripped) = predicates_text.strip_prefix('[') {
+                let close = stripped.find(']').ok_or(format!("Invalid xpath '{}'.", path))?;
+                predicates.push(parse_predicate(&stripped[..close], path)?);
+                predicates_text = &stripped[close + 1..];
+            }
+
+            steps.push(Step::Element { name : local_name(name).to_owned(), descendant, predicates });
+        }
+    }
+
+    Ok(steps)
+}
+
+/// The string value of an element, i.e. the text of the element and all its descendants.
+pub(crate) fn string_value(element : &XmlElement) -> String {
+    let mut value = element.get_text().to_owned();
+    for c in element.get_children() {
+        value.push_str(&string_value(c));
+    }
+
+    value
+}
+
+fn collect_descendants<'a>(element : &'a XmlElement, result : &mut Vec<&'a XmlElement>) {
+    for c in element.get_children() {
+        result.push(c);
+        collect_descendants(c, result);
+    }
+}
+
+fn matches(element : &XmlElement, name : &str, predicates : &[Predicate], position : usize) -> bool {
+    if name != "*" && element.get_local_name() != name {
+        return false;
+    }
+
+    predicates.iter().all(|p| match p {
+        Predicate::Position(i) => *i == position,
+        Predicate::Attribute(a, None) => element.get_attribute(a).is_some(),
+        Predicate::Attribute(a, Some(v)) => element.get_attribute(a) == Some(v.as_str()),
+        Predicate::Child(c, v) => element.children_named(local_name(c)).any(|c| string_value(c) == *v),
+    })
+}
+
+/// Evaluate an absolute xpath against a document and return the string values of all selected nodes.
+/// Supported are child (`/a/b`) and descendant (`//b`) steps, wildcards, positions (`[1]`),
+/// attribute and child value predicates (`[@id='1']`, `[name='x']`), attributes (`/@id`) and `text()`.
+/// Namespace prefixes are ignored.
+pub(crate) fn select(root : &XmlElement, path : &str) -> Result<Vec<String>, String> {
+    let steps = parse(path)?;
+
+    // The document node, so the first step can select the root element.
+    let document = XmlElement::document(root);
+    let mut current : Vec<&XmlElement> = vec![&document];
+    for (i, step) in steps.iter().enumerate() {
+        match step {
+            Step::Element { name, descendant, predicates } => {
+                let mut next : Vec<&XmlElement> = Vec::new();
+                for element in current {
+                    // `//name` selects matching children of the element and of all its descendants.
+                    let mut parents = vec![element];
+                    if *descendant {
+                        collect_descendants(element, &mut parents);
+                    }
+
+                    for parent in parents {
+                        let mut position = 0;
+                        for c in parent.get_children() {
+                            if name == "*" || c.get_local_name() == name {
+                                position += 1;
+                            }
+                            if matches(c, name, predicates, position) && !next.iter().any(|n| std::ptr::eq(*n, c)) {
+                                next.push(c);
+                            }
+                        }
+                    }
+                }
+                current = next;
+            },
+            Step::Attribute(_) | Step::Text if i + 1 < steps.len() => {
+                return Err(format!("Attributes and text() must be the last step of xpath '{}'.", path));
+            },
+            Step::Attribute(a) => {
+                return Ok(current.iter().filter_map(|e| e.get_attribute(a).map(|v| v.to_owned())).collect());
+            },
+            Step::Text => {
+                return Ok(current.iter().map(|e| e.get_text().to_owned()).collect());
+            },
+        }
+    }
+
+    Ok(current.into_iter().map(string_value).collect())
+}
+
+#[cfg(test)]
+mod rockery_xpath_tests {
+    use super::*;
+
+    #[test]
+    fn test_select() {
+        let document = XmlElement::parse(r#"<s:Envelope xmlns:s="urn:soap"><s:Body>
+            <order id="7">
+                <item sku="a"><name>Apple</name><price>1</price></item>
+                <item sku="b"><name>Pear</name><price>2</price></item>
+            </order>
+        </s:Body></s:Envelope>"#).unwrap();
+
+        let select = |path : &str| select(&document, path).unwrap();
+        assert_eq!(select("/Envelope/Body/order/@id"), vec!["7"]);
+        assert_eq!(select("//item/name"), vec!["Apple", "Pear"]);
+        assert_eq!(select("//item[2]/name/text()"), vec!["Pear"]);
+        assert_eq!(select("//item[@sku='a']/price"), vec!["1"]);
+        assert_eq!(select("//item[name='Pear']/@sku"), vec!["b"]);
+        assert_eq!(select("/s:Envelope/*/order/item[1]"), vec!["Apple1"]);
+        assert_eq!(select("//missing"), Vec::<String>::new());
+
+        assert!(super::select(&document, "order").is_err());
+        assert!(super::select(&document, "//@id/name").is_err());
+    }
+}
@@ -0,0 +1,433 @@
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+use crate::prelude::RockerySchemaViolation;
+use crate::rockery_xml::{local_name, XmlElement};
+
+/// How deep derived types are followed. Deeper derivations, e.g. a type that restricts itself, are not checked.
+const MAX_TYPE_DEPTH : usize = 16;
+
+/// Validates xml documents against a xml schema. Only the parts of xsd that are commonly used to
+/// describe service messages are supported: elements with sequence, choice and all content models,
+/// occurrence constraints, attributes, built-in simple types and restrictions with enumerations,
+/// patterns, lengths and ranges. Everything else is accepted as is.
+struct XsdSchema {
+    root : XmlElement,
+}
+
+impl XsdSchema {
+    fn parse(xsd : &str) -> Result<Self, String> {
+        let root = XmlElement::parse(xsd)?;
+
+        // Schemas embedded in a wsdl document are located in the types section.
+        let root = match root.get_local_name() {
+            "schema" => root,
+            _ => root.first_child("types")
+                .and_then(|t| t.first_child("schema"))
+                .cloned()
+                .ok_or("The document does not contain a xml schema.".to_owned())?,
+        };
+
+        Ok(XsdSchema { root })
+    }
+
+    fn find_global<'a>(&'a self, kind : &'a str, name : &str) -> Option<&'a XmlElement> {
+        let name = local_name(name);
+        self.root.children_named(kind).find(|e| e.get_attribute("name") == Some(name))
+    }
+
+    fn validate_element(&self, declaration : &XmlElement, element : &XmlElement, path : &str, violations : &mut Vec<RockerySchemaViolation>) {
+        let declaration = match declaration.get_attribute("ref").and_then(|r| self.find_global("element", r)) {
+            Some(d) => d,
+            None => declaration,
+        };
+
+        if let Some(t) = declaration.get_attribute("type") {
+            match (self.find_global("complexType", t), self.find_global("simpleType", t)) {
+                (Some(complex), _) => self.validate_complex(complex, element, path, violations),
+                (_, Some(simple)) => self.validate_simple(simple, element.get_text(), path, violations, 0),
+                _ => validate_builtin(t, element.get_text(), path, violations),
+            }
+            return;
+        }
+
+        if let Some(complex) = declaration.first_child("complexType") {
+            self.validate_complex(complex, element, path, violations);
+        } else if let Some(simple) = declaration.first_child("simpleType") {
+            self.validate_simple(simple, element.get_text(), path, violations, 0);
+        }
+    }
+
+    fn validate_simple(&self, simple : &XmlElement, text : &str, path : &str, violations : &mut Vec<RockerySchemaViolation>, depth : usize) {
+        let restriction = match simple.first_child("restriction") {
+            Some(r) => r,
+            None => return,
+        };
+
+        if let Some(base) = restriction.get_attribute("base") {
+            match self.find_global("simpleType", base) {
+                Some(s) if depth < MAX_TYPE_DEPTH => self.validate_simple(s, text, path, violations, depth + 1),
+                Some(_) => { },
+                None => validate_builtin(base, text, path, violations),
+            }
+        }
+
+        let values : Vec<&str> = restriction.children_named("enumeration").filter_map(|e| e.get_attribute("value")).collect();
+        if !values.is_empty() && !values.contains(&text) {
+            violations.push(RockerySchemaViolation::new(path, &format!("one of {}", values.join(", ")), text));
+        }
+
+        for facet in restriction.get_children() {
+            let value = facet.get_attribute("value").unwrap_or_default();
+            let length = text.chars().count();
+            let number = text.trim().parse::<f64>().ok();
+            let limit = value.parse::<f64>().ok();
+
+            let violated = match (facet.get_local_name(), number, limit) {
+                ("pattern", _, _) => Regex::new(&format!("^(?:{})$", value)).map(|r| !r.is_match(text)).unwrap_or(false),
+                ("length", _, Some(l)) => length as f64 != l,
+                ("minLength", _, Some(l)) => (length as f64) < l,
+                ("maxLength", _, Some(l)) => length as f64 > l,
+                ("minInclusive", Some(n), Some(l)) => n < l,
+                ("maxInclusive", Some(n), Some(l)) => n > l,
+                ("minExclusive", Some(n), Some(l)) => n <= l,
+                ("maxExclusive", Some(n), Some(l)) => n >= l,
+                _ => false,
+            };
+
+            if violated {
+                violations.push(RockerySchemaViolation::new(path, &format!("{} {}", facet.get_local_name(), value), text));
+            }
+        }
+    }
+
+    /// Collect the attribute declarations and the content model of a complex type, following extensions.
+    fn content_of<'a>(&'a self, complex : &'a XmlElement, attributes : &mut Vec<&'a XmlElement>, particles : &mut Vec<&'a XmlElement>, depth : usize) -> Option<&'a str> {
+        let mut simple_base = None;
+        attributes.extend(complex.children_named("attribute"));
+
+        for c in complex.get_children() {
+            match c.get_local_name() {
+                "sequence" | "choice" | "all" => particles.push(c),
+                "complexContent" | "simpleContent" => {
+                    let derivation = match c.first_child("extension").or(c.first_child("restriction")) {
+                        Some(d) => d,
+                        None => continue,
+                    };
+
+                    let base = derivation.get_attribute("base").unwrap_or_default();
+                    match self.find_global("complexType", base) {
+                        Some(b) if depth < MAX_TYPE_DEPTH && derivation.get_local_name() == "extension" => {
+                            simple_base = self.content_of(b, attributes, particles, depth + 1);
+                        },
+                        Some(_) => { },
+                        None => simple_base = Some(base),
+                    }
+                    simple_base = self.content_of(derivation, attributes, particles, depth + 1).or(simple_base);
+                },
+                _ => { },
+            }
+        }
+
+        simple_base
+    }
+
+    fn validate_complex(&self, complex : &XmlElement, element : &XmlElement, path : &str, violations : &mut Vec<RockerySchemaViolation>) {
+        let mut attributes = Vec::new();
+        let mut particles = Vec::new();
+        let simple_base = self.content_of(complex, &mut attributes, &mut particles, 0);
+
+        for a in attributes {
+            let name = match a.get_attribute("name") {
+                Some(n) => n,
+                None => continue,
+            };
+
+            let attribute_path = format!("{}/@{}", path, name);
+            match element.get_attribute(name) {
+                Some(value) => {
+                    if let Some(t) = a.get_attribute("type") {
+                        match self.find_global("simpleType", t) {
+                            Some(s) => self.validate_simple(s, value, &attribute_path, violations, 0),
+                            None => validate_builtin(t, value, &attribute_path, violations),
+                        }
+                    }
+                },
+                None if a.get_attribute("use") == Some("required") => {
+                    violations.push(RockerySchemaViolation::new(&attribute_path, "a required attribute", "nothing"));
+                },
+                None => { },
+            }
+        }
+
+        if let Some(base) = simple_base {
+            match self.find_global("simpleType", base) {
+                Some(s) => self.validate_simple(s, element.get_text(), path, violations, 0),
+                None => validate_builtin(base, element.get_text(), path, violations),
+            }
+        }
+
+        let children = element.get_children();
+        let mut position = 0;
+        for p in particles {
+            position = self.match_particle(p, children, position, path, violations);
+        }
+
+        for c in &children[position..] {
+            violations.push(RockerySchemaViolation::new(&format!("{}/{}", path, c.get_local_name()), "no further element", &format!("element {}", c.get_local_name())));
+        }
+    }
+
+    /// The name an element particle matches.
+    fn particle_name<'a>(&self, particle : &'a XmlElement) -> Option<&'a str> {
+        particle.get_attribute("name").or(particle.get_attribute("ref").map(local_name))
+    }
+
+    /// Check whether a particle can start with the given element.
+    fn particle_accepts(&self, particle : &XmlElement, element : &XmlElement) -> bool {
+        match particle.get_local_name() {
+            "element" => self.particle_name(particle) == Some(element.get_local_name()),
+            "any" => true,
+            "sequence" | "choice" | "all" => particle.get_children().iter().any(|p| self.particle_accepts(p, element)),
+            _ => false,
+        }
+    }
+
+    /// Match a particle against the children starting at `position`. Returns the position of the
+    /// first child that has not been consumed.
+    fn match_particle(&self, particle : &XmlElement, children : &[XmlElement], position : usize, path : &str, violations : &mut Vec<RockerySchemaViolation>) -> usize {
+        let min = occurs(particle, "minOccurs").unwrap_or(1);
+        let max = occurs(particle, "maxOccurs").unwrap_or(1);
+
+        let mut position = position;
+        let mut count = 0;
+        while count < max && position < children.len() && self.particle_accepts(particle, &children[position]) {
+            let next = match particle.get_local_name() {
+                "element" => {
+                    let name = children[position].get_local_name();
+                    let child_path = match max {
+                        1 => format!("{}/{}", path, name),
+                        _ => format!("{}/{}[{}]", path, name, count + 1),
+                    };
+                    self.validate_element(particle, &children[position], &child_path, violations);
+                    position + 1
+                },
+                "any" => position + 1,
+                "sequence" => {
+                    let mut p = position;
+                    for c in particle.get_children() {
+                        p = self.match_particle(c, children, p, path, violations);
+                    }
+                    p
+                },
+                "choice" => {
+                    let option = particle.get_children().iter().find(|c| self.particle_accepts(c, &children[position])).unwrap();
+                    self.match_particle(option, children, position, path, violations)
+                },
+                _ => self.match_all(particle, children, position, path, violations),
+            };
+            count += 1;
+
+            // A group whose content is optional can match without consuming anything, repeating it would not end.
+            if next == position {
+                break;
+            }
+            position = next;
+        }
+
+        if count < min {
+            let expected = match self.particle_name(particle) {
+                Some(name) if particle.get_local_name() == "element" => format!("element {}", name),
+                _ => format!("a {}", particle.get_local_name()),
+            };
+            let actual = match children.get(position) {
+                Some(c) => format!("element {}", c.get_local_name()),
+                None => "nothing".to_owned(),
+            };
+            violations.push(RockerySchemaViolation::new(path, &expected, &actual));
+        }
+
+        position
+    }
+
+    fn match_all(&self, all : &XmlElement, children : &[XmlElement], position : usize, path : &str, violations : &mut Vec<RockerySchemaViolation>) -> usize {
+        let mut position = position;
+        let mut seen = Vec::new();
+        while let Some(child) = children.get(position) {
+            let declaration = match all.get_children().iter().find(|p| self.particle_accepts(p, child)) {
+                Some(d) => d,
+                None => break,
+            };
+
+            self.validate_element(declaration, child, &format!("{}/{}", path, child.get_local_name()), violations);
+            seen.push(child.get_local_name());
+            position += 1;
+        }
+
+        for p in all.get_children() {
+            let name = self.particle_name(p).unwrap_or_default();
+            if occurs(p, "minOccurs").unwrap_or(1) > 0 && !seen.contains(&name) {
+                violations.push(RockerySchemaViolation::new(path, &format!("element {}", name), "nothing"));
+            }
+        }
+
+        position
+    }
+}
+
+fn occurs(particle : &XmlElement, attribute : &str) -> Option<usize> {
+    match particle.get_attribute(attribute)? {
+        "unbounded" => Some(usize::MAX),
+        n => n.parse().ok(),
+    }
+}
+
+/// Validate a value against one of the built-in xml schema types. Unknown types accept every value.
+fn validate_builtin(t : &str, text : &str, path : &str, violations : &mut Vec<RockerySchemaViolation>) {
+    let value = text.trim();
+    let t = local_name(t);
+    let valid = match t {
+        "int" | "integer" | "long" | "short" | "byte" => value.parse::<i128>().is_ok(),
+        "nonNegativeInteger" | "unsignedInt" | "unsignedLong" | "unsignedShort" | "unsignedByte" => value.parse::<u128>().is_ok(),
+        "positiveInteger" => value.parse::<u128>().map(|n| n > 0).unwrap_or(false),
+        "negativeInteger" => value.parse::<i128>().map(|n| n < 0).unwrap_or(false),
+        "nonPositiveInteger" => value.parse::<i128>().map(|n| n <= 0).unwrap_or(false),
+        "decimal" | "double" | "float" => value.parse::<f64>().is_ok() || ["INF", "-INF", "NaN"].contains(&value),
+        "boolean" => ["true", "false", "1", "0"].contains(&value),
+        "date" => {
+            static DATE : OnceLock<Regex> = OnceLock::new();
+            DATE.get_or_init(|| Regex::new(r"^-?\d{4}-\d{2}-\d{2}(Z|[+-]\d{2}:\d{2})?$").unwrap()).is_match(value)
+        },
+        "dateTime" => {
+            static DATE_TIME : OnceLock<Regex> = OnceLock::new();
+            DATE_TIME.get_or_init(|| Regex::new(r"^-?\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}(\.\d+)?(Z|[+-]\d{2}:\d{2})?$").unwrap()).is_match(value)
+        },
+        _ => true,
+    };
+
+    if !valid {
+        violations.push(RockerySchemaViolation::new(path, &format!("a value of type {}", t), text));
+    }
+}
+
+/// Validate a xml document against a xml schema. If `element` is given, the document (or the body of a
+/// soap envelope) is validated against the global element with that name, otherwise against the global
+/// element named like the root of the document.
+pub(crate) fn validate_xml(xsd : &str, element : Option<&str>, document : &str) -> Vec<RockerySchemaViolation> {
+    let schema = match XsdSchema::parse(xsd) {
+        Ok(s) => s,
+        Err(e) => return vec![RockerySchemaViolation::new("/", "a valid xml schema", &e)],
+    };
+
+    let mut root = match XmlElement::parse(document) {
+        Ok(r) => r,
+        Err(e) => return vec![RockerySchemaViolation::new("/", "a xml document", &e)],
+    };
+
+    if root.get_local_name() == "Envelope" {
+        if let Some(content) = root.first_child("Body").and_then(|b| b.get_children().first()) {
+            root = content.clone();
+        }
+    }
+
+    let path = format!("/{}", root.get_local_name());
+    let name = element.map(local_name).unwrap_or(root.get_local_name());
+    let declaration = match schema.find_global("element", name) {
+        Some(d) => d,
+        None => return vec![RockerySchemaViolation::new(&path, &format!("a declaration for element {}", name), "nothing")],
+    };
+
+    let mut violations = Vec::new();
+    if root.get_local_name() != name {
+        violations.push(RockerySchemaViolation::new(&path, &format!("element {}", name), &format!("element {}", root.get_local_name())));
+        return violations;
+    }
+
+    schema.validate_element(declaration, &root, &path, &mut violations);
+    violations
+}
+
+#[cfg(test)]
+mod rockery_xsd_tests {
+    use super::*;
+
+    const XSD : &str = r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+        <xs:simpleType name="Status">
+            <xs:restriction base="xs:string">
+                <xs:enumeration value="open"/>
+                <xs:enumeration value="closed"/>
+            </xs:restriction>
+        </xs:simpleType>
+        <xs:complexType name="Item">
+            <xs:sequence>
+                <xs:element name="name" type="xs:string"/>
+                <xs:element name="price" type="xs:decimal"/>
+            </xs:sequence>
+            <xs:attribute name="id" type="xs:int" use="required"/>
+        </xs:complexType>
+        <xs:element name="order">
+            <xs:complexType>
+                <xs:sequence>
+                    <xs:element name="status" type="Status"/>
+                    <xs:choice>
+                        <xs:element name="email" type="xs:string"/>
+                        <xs:element name="phone" type="xs:string"/>
+                    </xs:choice>
+                    <xs:element name="item" type="Item" minOccurs="1" maxOccurs="unbounded"/>
+                    <xs:element name="note" type="xs:string" minOccurs="0"/>
+                </xs:sequence>
+            </xs:complexType>
+        </xs:element>
+    </xs:schema>"#;
+
+    #[test]
+    fn test_validate() {
+        let valid = r#"<order><status>open</status><phone>123</phone>
+            <item id="1"><name>a</name><price>1.5</price></item>
+            <item id="2"><name>b</name><price>2</price></item></order>"#;
+        assert!(validate_xml(XSD, None, valid).is_empty());
+
+        let envelope = format!(r#"<s:Envelope xmlns:s="urn:soap"><s:Body>{}</s:Body></s:Envelope>"#, valid);
+        assert!(validate_xml(XSD, Some("tns:order"), &envelope).is_empty());
+
+        let invalid = r#"<order><status>pending</status>
+            <item><name>a</name><price>cheap</price></item><unknown/></order>"#;
+        let violations = validate_xml(XSD, None, invalid);
+        let messages : Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+        assert_eq!(messages, vec![
+            "/order/status: expected one of open, closed, got pending",
+            "/order: expected a choice, got element item",
+            "/order/item[1]/@id: expected a required attribute, got nothing",
+            "/order/item[1]/price: expected a value of type decimal, got cheap",
+            "/order/unknown: expected no further element, got element unknown",
+        ]);
+
+        assert_eq!(validate_xml(XSD, None, "<invoice/>")[0].get_expected(), "a declaration for element invoice");
+        assert_eq!(validate_xml(XSD, None, "no xml")[0].get_expected(), "a xml document");
+    }
+
+    #[test]
+    fn test_recursive_types() {
+        // Types that restrict themselves are followed up to a limited depth only.
+        let xsd = r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+            <xs:simpleType name="A"><xs:restriction base="B"><xs:maxLength value="3"/></xs:restriction></xs:simpleType>
+            <xs:simpleType name="B"><xs:restriction base="A"/></xs:simpleType>
+            <xs:element name="code" type="A"/>
+        </xs:schema>"#;
+        assert!(validate_xml(xsd, None, "<code>abc</code>").is_empty());
+        assert_eq!(validate_xml(xsd, None, "<code>abcd</code>")[0].get_expected(), "maxLength 3");
+
+        // An unbounded group that accepts an element without consuming it must not be repeated forever.
+        let xsd = r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+            <xs:element name="list">
+                <xs:complexType>
+                    <xs:sequence maxOccurs="unbounded">
+                        <xs:element name="item" minOccurs="0" maxOccurs="0"/>
+                    </xs:sequence>
+                </xs:complexType>
+            </xs:element>
+        </xs:schema>"#;
+        assert!(!validate_xml(xsd, None, "<list><item/></list>").is_empty());
+    }
+}
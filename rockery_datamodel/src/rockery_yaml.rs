@@ -0,0 +1,702 @@
+use std::collections::HashMap;
+
+use serde_json::{Map, Number, Value};
+
+/// A small yaml reader that covers the subset of yaml used by api definitions: block mappings and
+/// sequences, flow collections, quoted and plain scalars, block scalars (`|`, `>`) and simple anchors.
+/// The document is converted into a json value so it can be processed like a json document.
+pub(crate) fn parse_yaml(input : &str) -> Result<Value, String> {
+    let mut parser = YamlParser {
+        lines : input.lines().map(|l| {
+            let l = l.trim_end_matches('\r');
+            let content = l.trim_start_matches(' ');
+            (l.len() - content.len(), content.to_owned())
+        }).collect(),
+        pos : 0,
+        anchors : HashMap::new(),
+    };
+
+    // Skip directives and the document start marker.
+    while let Some(i) = parser.next_content() {
+        let content = strip_comment(&parser.lines[i].1);
+        if content.starts_with('%') || content == "---" {
+            parser.pos = i + 1;
+            continue;
+        }
+
+        if let Some(rest) = content.strip_prefix("--- ") {
+            parser.lines[i].1 = rest.to_owned();
+        }
+        break;
+    }
+
+    let value = parser.parse_node(0)?;
+    if let Some(i) = parser.next_content() {
+        let content = strip_comment(&parser.lines[i].1);
+        if content != "---" && content != "..." {
+            return Err(format!("Unexpected content in line {}: '{}'.", i + 1, content));
+        }
+    }
+
+    Ok(value)
+}
+
+struct YamlParser {
+    /// Indentation and content of every line of the document.
+    lines : Vec<(usize, String)>,
+    pos : usize,
+    anchors : HashMap<String, Value>,
+}
+
+impl YamlParser {
+    /// Index of the next line with content, skipping empty lines and comments.
+    fn next_content(&self) -> Option<usize> {
+        (self.pos..self.lines.len()).find(|i| !strip_comment(&self.lines[*i].1).is_empty())
+    }
+
+    fn parse_node(&mut self, min_indent : usize) -> Result<Value, String> {
+        let i = match self.next_content() {
+            Some(i) if self.lines[i].0 >= min_indent => i,
+            _ => return Ok(Value::Null),
+        };
+
+        let (indent, content) = (self.lines[i].0, strip_comment(&self.lines[i].1).to_owned());
+        if is_sequence_item(&content) {
+            return self.parse_sequence(indent);
+        }
+
+        if find_mapping_colon(&content).is_some() {
+            return self.parse_mapping(indent);
+        }
+
+        self.pos = i + 1;
+        self.parse_value(&content, indent)
+    }
+
+    fn parse_mapping(&mut self, indent : usize) -> Result<Value, String> {
+        let mut map = Map::new();
+        while let Some(i) = self.next_content() {
+            if self.lines[i].0 != indent {
+                break;
+            }
+
+            let content = strip_comment(&self.lines[i].1).to_owned();
+            let colon = match find_mapping_colon(&content) {
+                Some(c) if !is_sequence_item(&content) => c,
+                _ => break,
+            };
+
+            let key = parse_scalar_text(content[..colon].trim())?;
+            let rest = content[colon + 1..].trim().to_owned();
+            self.pos = i + 1;
+
+            let value = self.parse_value(&rest, indent)?;
+            map.insert(key, value);
+        }
+
+        Ok(Value::Object(map))
+    }
+
+    fn parse_sequence(&mut self, indent : usize) -> Result<Value, String> {
+        let mut items = Vec::new();
+        while let Some(i) = self.next_content() {
+            let content = strip_comment(&self.lines[i].1).to_owned();
+            if self.lines[i].0 != indent || !is_sequence_item(&content) {
+                break;
+            }
+
+            let rest = content[1..].trim_start();
+            if rest.is_empty() {
+                self.pos = i + 1;
+                items.push(self.parse_node(indent + 1)?);
+                continue;
+            }
+
+            // An item that starts a nested collection is treated as if it was written on its own
+            // line, indented to the column where its content starts.
+            let nested = is_sequence_item(rest) || (find_mapping_colon(rest).is_some() && !rest.starts_with('&'));
+            if nested {
+                let column = indent + content.len() - rest.len();
+                self.lines[i] = (column, rest.to_owned());
+                items.push(self.parse_node(column)?);
+                continue;
+            }
+
+            self.pos = i + 1;
+            items.push(self.parse_value(rest, indent)?);
+        }
+
+        Ok(Value::Array(items))
+    }
+
+    /// Parse the value following a mapping key or a sequence indicator. `indent` is the
+    /// indentation of the line that contains the key or indicator.
+    fn parse_value(&mut self, rest : &str, indent : usize) -> Result<Value, String> {
+        let mut rest = rest;
+        let mut anchor = None;
+        if let Some(a) = rest.strip_prefix('&') {
+            let end = a.find(' ').unwrap_or(a.len());
+            anchor = Some(a[..end].to_owned());
+            rest = a[end..].trim_start();
+        }
+
+        let value = if rest.is_empty() {
+            match self.next_content() {
+                Some(i) if self.lines[i].0 > indent => self.parse_node(indent + 1)?,
+                Some(i) if self.lines[i].0 == indent && is_sequence_item(strip_comment(&self.lines[i].1)) => self.parse_sequence(indent)?,
+                _ => Value::Null,
+            }
+        } else if let Some(alias) = rest.strip_prefix('*') {
+            self.anchors.get(alias.trim()).cloned()
+                .ok_or(format!("Unknown alias '{}'.", alias.trim()))?
+        } else if rest.starts_with('|') || rest.starts_with('>') {
+            self.parse_block_scalar(rest, indent)
+        } else if rest.starts_with('[') || rest.starts_with('{') {
+            // Flow collections may span multiple lines.
+            let mut text = rest.to_owned();
+            while !is_balanced(&text) {
+                match self.next_content() {
+                    Some(i) => {
+                        text.push(' ');
+                        text.push_str(strip_comment(&self.lines[i].1));
+                        self.pos = i + 1;
+                    },
+                    None => return Err(format!("Unterminated flow collection '{}'.", rest)),
+                }
+            }
+
+            let mut flow = FlowParser { chars : text.chars().collect(), pos : 0 };
+            flow.parse_value()?
+        } else if rest.starts_with('"') || rest.starts_with('\'') {
+            Value::String(parse_scalar_text(rest)?)
+        } else {
+            // Plain scalars may be continued on more indented lines.
+            let mut text = rest.to_owned();
+            while let Some(i) = self.next_content() {
+                if self.lines[i].0 <= indent {
+                    break;
+                }
+
+                text.push(' ');
+                text.push_str(strip_comment(&self.lines[i].1));
+                self.pos = i + 1;
+            }
+
+            resolve_plain(&text)
+        };
+
+        if let Some(anchor) = anchor {
+            self.anchors.insert(anchor, value.clone());
+        }
+
+        Ok(value)
+    }
+
+    fn parse_block_scalar(&mut self, header : &str, indent : usize) -> Value {
+        let folded = header.starts_with('>');
+        let keep = header.contains('+');
+        let strip = header.contains('-');
+        let explicit_indent = header.chars().find_map(|c| c.to_digit(10)).map(|d| indent + d as usize);
+
+        let mut block_indent = explicit_indent;
+        let mut lines : Vec<String> = Vec::new();
+        while self.pos < self.lines.len() {
+            let (line_indent, content) = &self.lines[self.pos];
+            if content.is_empty() {
+                lines.push(String::new());
+                self.pos += 1;
+                continue;
+            }
+
+            let bi = *block_indent.get_or_insert(*line_indent);
+            if *line_indent <= indent || *line_indent < bi {
+                break;
+            }
+
+            lines.push(format!("{}{}", " ".repeat(line_indent - bi), content));
+            self.pos += 1;
+        }
+
+        let trailing = lines.iter().rev().take_while(|l| l.is_empty()).count();
+        lines.truncate(lines.len() - trailing);
+
+        let mut text = if folded {
+            let mut text = String::new();
+            let mut previous_empty = true;
+            for l in &lines {
+                if l.is_empty() {
+                    text.push('\n');
+                    previous_empty = true;
+                    continue;
+                }
+
+                if !previous_empty && !l.starts_with(' ') {
+                    text.push(' ');
+                }
+                text.push_str(l);
+                previous_empty = false;
+            }
+            text
+        } else {
+            lines.join("\n")
+        };
+
+        if !strip && !lines.is_empty() {
+            text.push('\n');
+            if keep {
+                text.push_str(&"\n".repeat(trailing));
+            }
+        }
+
+        Value::String(text)
+    }
+}
+
+fn is_sequence_item(content : &str) -> bool {
+    content == "-" || content.starts_with("- ")
+}
+
+/// Remove a trailing comment. A comment starts with `#` at the beginning of the line or after a
+/// whitespace, as long as it is not part of a quoted string.
+fn strip_comment(content : &str) -> &str {
+    let mut quote = None;
+    let mut previous = ' ';
+    for (i, c) in content.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => { },
+            None if c == '"' || c == '\'' => {
+                if previous == ' ' || i == 0 || "[{,:".contains(previous) {
+                    quote = Some(c);
+                }
+            },
+            None if c == '#' && (previous == ' ' || previous == '\t' || i == 0) => {
+                return content[..i].trim_end();
+            },
+            None => { },
+        }
+        previous = c;
+    }
+
+    content.trim_end()
+}
+
+/// Find the colon that separates a mapping key from its value.
+fn find_mapping_colon(content : &str) -> Option<usize> {
+    let bytes = content.as_bytes();
+    let is_separator = |i : usize| bytes.get(i + 1).map_or(true, |b| *b == b' ' || *b == b'\t');
+
+    if content.starts_with('"') || content.starts_with('\'') {
+        let end = quoted_end(content)?;
+        let after = content[end..].trim_start();
+        if after.starts_with(':') {
+            let i = content.len() - after.len();
+            return if is_separator(i) { Some(i) } else { None };
+        }
+        return None;
+    }
+
+    if content.starts_with('[') || content.starts_with('{') {
+        return None;
+    }
+
+    (0..bytes.len()).find(|i| bytes[*i] == b':' && is_separator(*i))
+}
+
+/// Position right after the closing quote of a quoted scalar at the start of `content`.
+fn quoted_end(content : &str) -> Option<usize> {
+    let quote = content.chars().next()?;
+    let mut escaped = false;
+    for (i, c) in content.char_indices().skip(1) {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+
+        if c == quote {
+            // Two single quotes are an escaped single quote.
+            if quote == '\'' && content[i + 1..].starts_with('\'') {
+                escaped = true;
+                continue;
+            }
+            return Some(i + 1);
+        }
+
+        escaped = quote == '"' && c == '\\';
+    }
+
+    None
+}
+
+fn is_balanced(text : &str) -> bool {
+    let mut depth = 0i32;
+    let mut quote = None;
+    for c in text.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => { },
+            None => match c {
+                '"' | '\'' => quote = Some(c),
+                '[' | '{' => depth += 1,
+                ']' | '}' => depth -= 1,
+                _ => { },
+            },
+        }
+    }
+
+    depth <= 0
+}
+
+/// Turn a key or a quoted scalar into its string value.
+fn parse_scalar_text(text : &str) -> Result<String, String> {
+    if text.starts_with('"') {
+        return unescape_double_quoted(text);
+    }
+
+    if text.starts_with('\'') {
+        let end = quoted_end(text).ok_or(format!("Unterminated string {}.", text))?;
+        return Ok(text[1..end - 1].replace("''", "'"));
+    }
+
+    Ok(text.to_owned())
+}
+
+fn unescape_double_quoted(text : &str) -> Result<String, String> {
+    let end = quoted_end(text).ok_or(format!("Unterminated string {}.", text))?;
+    let mut result = String::new();
+    let mut chars = text[1..end - 1].chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('r') => result.push('\r'),
+            Some('0') => result.push('\0'),
+            Some('u') => {
+                let code : String = chars.by_ref().take(4).collect();
+                let c = u32::from_str_radix(&code, 16).ok()
+                    .and_then(char::from_u32)
+                    .ok_or(format!("Invalid escape sequence \\u{}.", code))?;
+                result.push(c);
+            },
+            Some(c) => result.push(c),
+            None => { },
+        }
+    }
+
+    Ok(result)
+}
+
+/// Resolve the type of an unquoted scalar.
+fn resolve_plain(text : &str) -> Value {
+    let text = text.trim();
+    match text {
+        "" | "~" | "null" | "Null" | "NULL" => return Value::Null,
+        "true" | "True" | "TRUE" => return Value::Bool(true),
+        "false" | "False" | "FALSE" => return Value::Bool(false),
+        _ => { },
+    }
+
+    let numeric = text.trim_start_matches(['-', '+']);
+    if !numeric.is_empty() && numeric.chars().all(|c| c.is_ascii_digit()) {
+        if let Ok(i) = text.parse::<i64>() {
+            return Value::Number(i.into());
+        }
+    }
+
+    let looks_like_float = numeric.chars().next().map_or(false, |c| c.is_ascii_digit() || c == '.')
+        && numeric.chars().all(|c| c.is_ascii_digit() || "eE.+-".contains(c));
+    if looks_like_float {
+        if let Some(n) = text.parse::<f64>().ok().and_then(Number::from_f64) {
+            return Value::Number(n);
+        }
+    }
+
+    Value::String(text.to_owned())
+}
+
+/// Parser for flow collections like `[a, b]` or `{ a: 1, b: [x] }`.
+struct FlowParser {
+    chars : Vec<char>,
+    pos : usize,
+}
+
+impl FlowParser {
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.chars.len() && self.chars[self.pos].is_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<char> { self.chars.get(self.pos).copied() }
+
+    fn parse_value(&mut self) -> Result<Value, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('[') => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                loop {
+                    self.skip_whitespace();
+                    match self.peek() {
+                        Some(']') => { self.pos += 1; break; },
+                        Some(',') => { self.pos += 1; },
+                        Some('}') => return Err("Unexpected '}' in flow sequence.".to_owned()),
+                        Some(c) => {
+                            let start = self.pos;
+                            items.push(self.parse_value()?);
+                            if self.pos == start {
+                                return Err(format!("Unexpected '{}' in flow collection.", c));
+                            }
+                        },
+                        None => return Err("Unterminated flow sequence.".to_owned()),
+                    }
+                }
+                Ok(Value::Array(items))
+            },
+            Some('{') => {
+                self.pos += 1;
+                let mut map = Map::new();
+                loop {
+                    self.skip_whitespace();
+                    match self.peek() {
+                        Some('}') => { self.pos += 1; break; },
+                        Some(',') => { self.pos += 1; },
+                        Some(']') => return Err("Unexpected ']' in flow mapping.".to_owned()),
+                        Some(c) => {
+                            let start = self.pos;
+                            let key = match self.parse_scalar(true)? {
+                                Value::String(s) => s,
+                                v => v.to_string(),
+                            };
+                            self.skip_whitespace();
+                            let value = if self.peek() == Some(':') {
+                                self.pos += 1;
+                                self.parse_value()?
+                            } else {
+                                Value::Null
+                            };
+                            if self.pos == start {
+                                return Err(format!("Unexpected '{}' in flow collection.", c));
+                            }
+                            map.insert(key, value);
+                        },
+                        None => return Err("Unterminated flow mapping.".to_owned()),
+                    }
+                }
+                Ok(Value::Object(map))
+            },
+            _ => self.parse_scalar(false),
+        }
+    }
+
+    fn parse_scalar(&mut self, key : bool) -> Result<Value, String> {
+        self.skip_whitespace();
+        if let Some(q) = self.peek().filter(|c| *c == '"' || *c == '\'') {
+            let rest : String = self.chars[self.pos..].iter().collect();
+            let end = quoted_end(&rest).ok_or(format!("Unterminated string {}.", rest))?;
+            self.pos += rest[..end].chars().count();
+            let text = if q == '"' { unescape_double_quoted(&rest[..end])? } else { rest[1..end - 1].replace("''", "'") };
+            return Ok(Value::String(text));
+        }
+
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            let next = self.chars.get(self.pos + 1).copied().unwrap_or(' ');
+            if c == ',' || c == ']' || c == '}' || (key && c == ':' && (next.is_whitespace() || ",}".contains(next))) {
+                break;
+            }
+            self.pos += 1;
+        }
+
+        let text : String = self.chars[start..self.pos].iter().collect();
+        Ok(resolve_plain(&text))
+    }
+}
+
+/// Write a json value as yaml document. Strings are quoted whenever they could be misread.
+pub(crate) fn to_yaml(value : &Value) -> String {
+    let mut out = String::new();
+    match value {
+        Value::Object(o) if !o.is_empty() => write_node(value, 0, &mut out),
+        Value::Array(a) if !a.is_empty() => write_node(value, 0, &mut out),
+        v => {
+            out.push_str(&format_scalar(v));
+            out.push('\n');
+        },
+    }
+
+    out
+}
+
+fn is_collection(value : &Value) -> bool {
+    match value {
+        Value::Object(o) => !o.is_empty(),
+        Value::Array(a) => !a.is_empty(),
+        _ => false,
+    }
+}
+
+fn write_node(value : &Value, indent : usize, out : &mut String) {
+    let padding = " ".repeat(indent);
+    match value {
+        Value::Object(o) => {
+            for (k, v) in o {
+                out.push_str(&format!("{}{}:", padding, format_string(k)));
+                if is_collection(v) {
+                    out.push('\n');
+                    write_node(v, indent + 2, out);
+                } else {
+                    out.push_str(&format!(" {}\n", format_scalar(v)));
+                }
+            }
+        },
+        Value::Array(a) => {
+            for v in a {
+                if is_collection(v) {
+                    // Render the item one level deeper and put the indicator in front of its first line.
+                    let mut item = String::new();
+                    write_node(v, indent + 2, &mut item);
+                    out.push_str(&format!("{}- {}", padding, &item[indent + 2..]));
+                } else {
+                    out.push_str(&format!("{}- {}\n", padding, format_scalar(v)));
+                }
+            }
+        },
+        v => out.push_str(&format!("{}{}\n", padding, format_scalar(v))),
+    }
+}
+
+fn format_scalar(value : &Value) -> String {
+    match value {
+        Value::Null => "null".to_owned(),
+        Value::String(s) => format_string(s),
+        Value::Object(_) => "{}".to_owned(),
+        Value::Array(_) => "[]".to_owned(),
+        v => v.to_string(),
+    }
+}
+
+fn format_string(s : &str) -> String {
+    let needs_quotes = s.is_empty()
+        || resolve_plain(s) != Value::String(s.to_owned())
+        || s.starts_with(|c : char| "-?:,[]{}#&*!|>'\"%@` ".contains(c))
+        || s.ends_with(' ')
+        || s.ends_with(':')
+        || s.contains(": ")
+        || s.contains(" #")
+        || s.chars().any(|c| c.is_control());
+
+    if needs_quotes {
+        // A json string is a valid double quoted yaml scalar.
+        return serde_json::to_string(s).unwrap_or_default();
+    }
+
+    s.to_owned()
+}
+
+#[cfg(test)]
+mod rockery_yaml_tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let subject = parse_yaml(r#"
+# A comment
+openapi: "3.0.1"
+info:
+  title: Test API # trailing comment
+  description: A long
+    description
+  version: 1
+servers:
+  - url: https://example.com/v1
+    description: 'It''s production'
+  - url: http://localhost:8080
+paths:
+  /users/{id}:
+    get:
+      tags: [users, "read only"]
+      parameters:
+      - name: id
+        in: path
+        required: true
+      responses:
+        "200": { description: OK, content: {} }
+text: |
+  line 1
+  line 2
+folded: >-
+  a
+  b
+empty:
+list:
+  -
+    - 1
+    - 2.5
+  - &anchor
+    a: true
+  - *anchor
+"#).unwrap();
+
+        assert_eq!(subject, json!({
+            "openapi": "3.0.1",
+            "info": { "title": "Test API", "description": "A long description", "version": 1 },
+            "servers": [
+                { "url": "https://example.com/v1", "description": "It's production" },
+                { "url": "http://localhost:8080" }
+            ],
+            "paths": {
+                "/users/{id}": {
+                    "get": {
+                        "tags": ["users", "read only"],
+                        "parameters": [{ "name": "id", "in": "path", "required": true }],
+                        "responses": { "200": { "description": "OK", "content": {} } }
+                    }
+                }
+            },
+            "text": "line 1\nline 2\n",
+            "folded": "a b",
+            "empty": null,
+            "list": [[1, 2.5], { "a": true }, { "a": true }]
+        }));
+    }
+
+    #[test]
+    fn test_write() {
+        let subject = json!({
+            "openapi": "3.1.0",
+            "paths": {
+                "/users/{id}": {
+                    "get": {
+                        "parameters": [{ "name": "id", "in": "path" }, { "name": "x" }],
+                        "responses": { "200": { "description": "OK: fine", "content": {} } }
+                    }
+                }
+            },
+            "list": [[1, 2.5], [], "- dash", "true", "", "multi\nline", null, false]
+        });
+
+        let yaml = to_yaml(&subject);
+        assert!(yaml.contains("openapi: 3.1.0\n"));
+        assert!(yaml.contains("      parameters:\n        - in: path\n          name: id\n"));
+        assert_eq!(parse_yaml(&yaml).unwrap(), subject);
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(parse_yaml("a: [1, 2").is_err());
+        assert!(parse_yaml("a: *unknown").is_err());
+
+        // Closing brackets that do not match the open collection.
+        assert_eq!(parse_yaml("info: { ]\n").unwrap_err(), "Unexpected ']' in flow mapping.");
+        assert_eq!(parse_yaml("a: [ }\n").unwrap_err(), "Unexpected '}' in flow sequence.");
+        assert!(parse_yaml("a: [1, {b: 2]]\n").is_err());
+    }
+}
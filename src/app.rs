@@ -8,6 +8,9 @@ use log::{error, info};
 use rockery_datamodel::prelude::*;
 
 use crate::draw_project_ui;
+use crate::project_ui::TestSuiteRuns;
+use crate::load_test_ui::LoadTestUi;
+use crate::history_ui::HistoryUi;
 
 
 /// We derive Deserialize/Serialize so we can persist app state on shutdown.
@@ -16,6 +19,13 @@ pub struct RockeryApp {
     selected_file: Option<PathBuf>,
 
     projects : HashMap<String, RockeryProject>,
+
+    /// Files the projects have been loaded from, by project name.
+    paths : HashMap<String, PathBuf>,
+    test_suite_runs : TestSuiteRuns,
+    load_test : LoadTestUi,
+    history : HistoryUi,
+    show_history : bool,
 }
 
 impl Default for RockeryApp {
@@ -25,6 +35,11 @@ impl Default for RockeryApp {
             selected_file: None,
 
             projects : HashMap::new(),
+            paths : HashMap::new(),
+            test_suite_runs : TestSuiteRuns::default(),
+            load_test : LoadTestUi::default(),
+            history : HistoryUi::default(),
+            show_history : false,
         }
     }
 }
@@ -66,14 +81,22 @@ impl eframe::App for RockeryApp {
 
                     if ui.button("Save").on_hover_text("Save all projects").clicked() {
                         for pro in self.projects.values_mut() {
-                            // match pro.save() {
-                            //     Ok(_) => {
-                            //         info!("{} saved.", pro.get_name())
-                            //     },
-                            //     Err(e) => {
-                            //         error!("Could not save project '{}'. Error: {}", pro.get_name(), e)
-                            //     },
-                            // }
+                            let path = match self.paths.get(pro.get_name()) {
+                                Some(p) => p,
+                                None => {
+                                    error!("Could not save project '{}', because it has not been loaded from a file.", pro.get_name());
+                                    continue;
+                                },
+                            };
+
+                            match pro.save(path) {
+                                Ok(_) => {
+                                    info!("{} saved.", pro.get_name())
+                                },
+                                Err(e) => {
+                                    error!("Could not save project '{}'. Error: {}", pro.get_name(), e)
+                                },
+                            }
                         }
                     }
 
@@ -88,12 +111,17 @@ impl eframe::App for RockeryApp {
             if let Some(path) = self.file_dialog.take_selected() {
                 self.selected_file = Some(path.to_path_buf());
 
-                match RockeryProject::load(path) {
-                    Ok(pro) => {
+                match RockeryProject::load(path.clone()) {
+                    Ok(mut pro) => {
+                        if let Err(e) = pro.enable_history(&RockeryHistory::path_for(&path)) {
+                            error!("Could not load the history of {}. Error: {}", pro.get_name(), e);
+                        }
+
                         if self.projects.contains_key(pro.get_name()) {
                             error!("Could not load {}, because a project with the same name already exists. Project names must be unique.", pro.get_name());
                         } else {
                             info!("Project: {} loaded.", pro.get_name());
+                            self.paths.insert(pro.get_name().to_owned(), path.to_path_buf());
                             self.projects.insert(pro.get_name().to_owned(), pro);
                         }
                     }
@@ -109,7 +137,7 @@ impl eframe::App for RockeryApp {
             .resizable(true)
             .show(ctx, |ui| {
                 for p in &mut self.projects.values_mut() {
-                    draw_project_ui(ui, p);
+                    draw_project_ui(ui, p, &mut self.test_suite_runs);
                 }
         });
 
@@ -120,8 +148,20 @@ impl eframe::App for RockeryApp {
                 ui.label("This should become the console.");
         });
 
-        egui::CentralPanel::default().show(ctx, |_ui| {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut self.show_history, false, "Load test");
+                ui.selectable_value(&mut self.show_history, true, "History");
+            });
+            ui.separator();
 
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                if self.show_history {
+                    self.history.draw(ui, &mut self.projects);
+                } else {
+                    self.load_test.draw(ui, &mut self.projects);
+                }
+            });
         });
     }
 }
@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use egui::{Color32, Ui};
+use eframe::egui;
+use log::{error, info};
+use rockery_datamodel::prelude::*;
+
+/// Browse the request history of a project.
+#[derive(Default)]
+pub struct HistoryUi {
+    project : String,
+    search : String,
+    selected : Option<usize>,
+    message : Option<Result<String, String>>,
+
+    /// A request that is sent again in the background.
+    resend : Option<JoinHandle<Result<String, String>>>,
+}
+
+impl HistoryUi {
+    pub fn draw(&mut self, ui : &mut Ui, projects : &mut HashMap<String, RockeryProject>) {
+        ui.heading("History");
+
+        let mut names : Vec<String> = projects.keys().cloned().collect();
+        names.sort();
+        egui::ComboBox::from_label("Project")
+            .selected_text(self.project.clone())
+            .show_ui(ui, |ui| {
+                for n in names {
+                    ui.selectable_value(&mut self.project, n.clone(), n);
+                }
+            });
+
+        let project = match projects.get_mut(&self.project) {
+            Some(p) => p,
+            None => return,
+        };
+        let history = match project.get_history() {
+            Some(h) => h.clone(),
+            None => {
+                ui.label("The history is not enabled for this project.");
+                return;
+            },
+        };
+
+        ui.horizontal(|ui| {
+            ui.label("Search:");
+            ui.text_edit_singleline(&mut self.search);
+            if ui.button("Export HAR").on_hover_text("Write the history to the temp directory as HTTP Archive").clicked() {
+                if let Ok(h) = history.lock() {
+                    let path = std::env::temp_dir().join(format!("{}_history.har", self.project));
+                    let har = RockeryHarExporter::export_history(h.get_entries()).to_string();
+                    match std::fs::write(&path, har) {
+                        Ok(_) => info!("History written to {}", path.display()),
+                        Err(e) => error!("Could not write {}. Error: {}", path.display(), e),
+                    }
+                }
+            }
+            if ui.button("Clear history").clicked() {
+                if let Ok(mut h) = history.lock() {
+                    if let Err(e) = h.clear() {
+                        error!("{}", e);
+                    }
+                }
+                self.selected = None;
+            }
+        });
+
+        if self.resend.as_ref().map(|r| r.is_finished()).unwrap_or(false) {
+            let resend = self.resend.take().unwrap();
+            self.message = Some(resend.join().unwrap_or(Err("The request has been aborted.".to_owned())));
+        }
+
+        let guard = match history.lock() {
+            Ok(h) => h,
+            Err(_) => return,
+        };
+        let entries : Vec<(usize, &RockeryHistoryEntry)> = guard.get_entries().iter().enumerate().rev()
+            .filter(|(_, e)| self.search.is_empty() || e.matches(&self.search))
+            .collect();
+
+        egui::ScrollArea::vertical().id_salt("history_entries").max_height(250.0).show(ui, |ui| {
+            for (i, e) in &entries {
+                let status = match e.get_response() {
+                    Some(r) => r.get_status().to_string(),
+                    None => "error".to_owned(),
+                };
+                let text = format!("{}  {} {}  {}  {} ms", e.get_timestamp(), e.get_request().get_method(), e.get_request().get_url(), status, e.get_duration());
+                if ui.selectable_label(self.selected == Some(*i), text).clicked() {
+                    self.selected = Some(*i);
+                    self.message = None;
+                }
+            }
+        });
+
+        let entry = match self.selected.and_then(|i| entries.iter().find(|(j, _)| *j == i)) {
+            Some((i, e)) => (*i, *e),
+            None => return,
+        };
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            if self.resend.is_some() {
+                ui.spinner();
+                ui.ctx().request_repaint_after(Duration::from_millis(250));
+            } else if ui.button("Re-send").on_hover_text("Send the request again and check the assertions of its method").clicked() {
+                // The request is sent on a copy of the project, the history is shared with the copy.
+                let (copy, index) = (project.clone(), entry.0);
+                self.resend = Some(thread::spawn(move || copy.resend_history_entry(index).map(|r| {
+                    let response = r.get_response();
+                    let mut message = format!("{} {} in {} ms", response.get_status(), response.get_status_text(), response.get_duration());
+                    for v in r.get_violations() {
+                        message.push_str(&format!("\n{}", v));
+                    }
+                    for a in r.get_assertions() {
+                        message.push_str(&format!("\n{}", a));
+                    }
+                    message
+                })));
+                self.message = None;
+            }
+            if ui.button("Restore").on_hover_text("Take over the parameters and the body into the method").clicked() {
+                self.message = Some(project.restore_history_entry(entry.1)
+                    .map(|_| format!("Restored into {} of {}.", entry.1.get_method(), entry.1.get_service())));
+                if let Some(Ok(m)) = &self.message {
+                    info!("{}", m);
+                }
+            }
+        });
+
+        match &self.message {
+            Some(Ok(m)) => { ui.label(m); },
+            Some(Err(e)) => { ui.colored_label(Color32::RED, e); },
+            None => { },
+        }
+
+        let e = entry.1;
+        ui.label(format!("{} / {}   Environment: {}", e.get_service(), e.get_method(), e.get_environment().as_deref().unwrap_or("-")));
+        let mut request = format!("{} {}\n", e.get_request().get_method(), e.get_request().get_url());
+        for (k, v) in e.get_request().get_headers() {
+            request.push_str(&format!("{}: {}\n", k, v));
+        }
+        request.push('\n');
+        request.push_str(e.get_request().get_body());
+        ui.label("Request");
+        ui.code(request);
+
+        ui.label("Response");
+        match (e.get_response(), e.get_error()) {
+            (Some(r), _) => {
+                let mut response = format!("{} {}\n", r.get_status(), r.get_status_text());
+                for (k, v) in r.get_headers() {
+                    response.push_str(&format!("{}: {}\n", k, v));
+                }
+                response.push('\n');
+                response.push_str(r.get_body());
+                ui.code(response);
+            },
+            (None, Some(error)) => { ui.colored_label(Color32::RED, error); },
+            (None, None) => { },
+        }
+    }
+}
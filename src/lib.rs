@@ -2,6 +2,8 @@
 
 mod app;
 mod project_ui;
+mod load_test_ui;
+mod history_ui;
 
 pub use app::RockeryApp;
 pub use project_ui::draw_project_ui;
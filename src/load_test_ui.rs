@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use egui::{Align2, Color32, FontId, Pos2, Rect, Sense, Shape, Stroke, Ui, Vec2};
+use eframe::egui;
+use rockery_datamodel::prelude::*;
+
+/// Maximum number of latency points drawn, the most recent samples are shown.
+const MAX_POINTS : usize = 5000;
+
+/// Settings and state of the load test panel.
+pub struct LoadTestUi {
+    project : String,
+    test_case : bool,
+    service : String,
+    method : String,
+    suite : String,
+    case : String,
+
+    users : usize,
+    limit_by_duration : bool,
+    duration : u64,
+    iterations : u64,
+    ramp_up : u64,
+    rate_limit : f64,
+
+    run : Option<RockeryLoadRun>,
+    error : Option<String>,
+}
+
+impl Default for LoadTestUi {
+    fn default() -> Self {
+        Self {
+            project : String::new(),
+            test_case : false,
+            service : String::new(),
+            method : String::new(),
+            suite : String::new(),
+            case : String::new(),
+
+            users : 10,
+            limit_by_duration : true,
+            duration : 30,
+            iterations : 1000,
+            ramp_up : 0,
+            rate_limit : 0.0,
+
+            run : None,
+            error : None,
+        }
+    }
+}
+
+impl LoadTestUi {
+    fn combo(ui : &mut Ui, label : &str, selected : &mut String, values : Vec<String>) {
+        egui::ComboBox::from_label(label)
+            .selected_text(selected.clone())
+            .show_ui(ui, |ui| {
+                for v in values {
+                    ui.selectable_value(selected, v.clone(), v);
+                }
+            });
+    }
+
+    fn start(&mut self, projects : &HashMap<String, RockeryProject>) {
+        let project = match projects.get(&self.project) {
+            Some(p) => p,
+            None => {
+                self.error = Some("Select a project first.".to_owned());
+                return;
+            },
+        };
+
+        let target = if self.test_case {
+            RockeryLoadTarget::TestCase(self.suite.clone(), self.case.clone())
+        } else {
+            RockeryLoadTarget::Method(self.service.clone(), self.method.clone())
+        };
+        let limit = if self.limit_by_duration {
+            RockeryLoadLimit::Duration(self.duration * 1000)
+        } else {
+            RockeryLoadLimit::Iterations(self.iterations)
+        };
+
+        let mut test = RockeryLoadTest::new(target, self.users, limit);
+        test.set_ramp_up(self.ramp_up * 1000);
+        test.set_rate_limit(if self.rate_limit > 0.0 { Some(self.rate_limit) } else { None });
+
+        match test.start(project) {
+            Ok(run) => {
+                self.run = Some(run);
+                self.error = None;
+            },
+            Err(e) => self.error = Some(e),
+        }
+    }
+
+    pub fn draw(&mut self, ui : &mut Ui, projects : &mut HashMap<String, RockeryProject>) {
+        ui.heading("Load test");
+
+        let mut names : Vec<String> = projects.keys().cloned().collect();
+        names.sort();
+        Self::combo(ui, "Project", &mut self.project, names);
+
+        if let Some(project) = projects.get_mut(&self.project) {
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut self.test_case, false, "Method");
+                ui.radio_value(&mut self.test_case, true, "Test case");
+            });
+
+            if self.test_case {
+                let suites = project.get_test_suites();
+                Self::combo(ui, "Test suite", &mut self.suite, suites.iter().map(|s| s.get_name().to_owned()).collect());
+                let cases = suites.iter().find(|s| s.get_name() == self.suite)
+                    .map(|s| s.get_test_cases().iter().map(|t| t.get_name().to_owned()).collect())
+                    .unwrap_or_default();
+                Self::combo(ui, "Test case", &mut self.case, cases);
+            } else {
+                let mut services : Vec<String> = project.get_services().keys().cloned().collect();
+                services.sort();
+                Self::combo(ui, "Service", &mut self.service, services);
+                let methods = project.find_service(&self.service)
+                    .map(|s| s.get_methods().iter().map(|m| m.get_name().to_owned()).collect())
+                    .unwrap_or_default();
+                Self::combo(ui, "Method", &mut self.method, methods);
+            }
+        }
+
+        ui.horizontal(|ui| {
+            ui.add(egui::DragValue::new(&mut self.users).range(1..=1000).prefix("Users: "));
+            ui.add(egui::DragValue::new(&mut self.ramp_up).range(0..=3600).prefix("Ramp-up: ").suffix(" s"));
+            ui.add(egui::DragValue::new(&mut self.rate_limit).range(0.0..=100000.0).prefix("Rate limit: ").suffix(" /s"))
+                .on_hover_text("Maximum iterations per second of all users, 0 for no limit");
+        });
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut self.limit_by_duration, true, "Duration");
+            ui.radio_value(&mut self.limit_by_duration, false, "Iterations");
+            if self.limit_by_duration {
+                ui.add(egui::DragValue::new(&mut self.duration).range(1..=86400).suffix(" s"));
+            } else {
+                ui.add(egui::DragValue::new(&mut self.iterations).range(1..=u64::MAX));
+            }
+        });
+
+        let running = self.run.as_ref().map(|r| !r.is_finished()).unwrap_or(false);
+        ui.horizontal(|ui| {
+            if running {
+                if ui.button("Stop").clicked() {
+                    if let Some(run) = &self.run {
+                        run.stop();
+                    }
+                }
+            } else if ui.button("Start").clicked() {
+                self.start(projects);
+            }
+        });
+
+        if let Some(e) = &self.error {
+            ui.colored_label(Color32::RED, e);
+        }
+
+        let run = match &self.run {
+            Some(r) => r,
+            None => return,
+        };
+
+        let statistics = run.get_statistics();
+        ui.label(format!("Iterations: {}   Throughput: {:.1}/s   Errors: {} ({:.1} %)",
+            statistics.get_iterations(), statistics.get_throughput(), statistics.get_errors(), statistics.get_error_rate() * 100.0));
+        ui.label(format!("Latency: p50 {} ms   p90 {} ms   p99 {} ms   max {} ms",
+            statistics.get_p50(), statistics.get_p90(), statistics.get_p99(), statistics.get_max()));
+
+        draw_chart(ui, &run.get_samples(), run.get_elapsed().max(statistics.get_duration()));
+
+        if running {
+            ui.ctx().request_repaint_after(Duration::from_millis(250));
+        }
+    }
+}
+
+/// Draw the latency of every sample as a point and the throughput per second as a line.
+fn draw_chart(ui : &mut Ui, samples : &[RockeryLoadSample], duration : u64) {
+    let size = Vec2::new(ui.available_width(), 220.0);
+    let (response, painter) = ui.allocate_painter(size, Sense::hover());
+    let outer = response.rect;
+    let rect = Rect::from_min_max(outer.min + Vec2::new(50.0, 10.0), outer.max - Vec2::new(50.0, 20.0));
+
+    let visuals = ui.visuals();
+    painter.rect_stroke(rect, 0.0, Stroke::new(1.0, visuals.weak_text_color()));
+
+    let seconds = (duration / 1000 + 1) as usize;
+    let mut throughput = vec![0usize; seconds];
+    for s in samples {
+        let bucket = (s.get_offset() / 1000) as usize;
+        if bucket < seconds {
+            throughput[bucket] += 1;
+        }
+    }
+
+    let max_latency = samples.iter().map(|s| s.get_latency()).max().unwrap_or(0).max(1) as f32;
+    let max_throughput = throughput.iter().copied().max().unwrap_or(0).max(1) as f32;
+    let duration = duration.max(1) as f32;
+    let x = |offset : f32| rect.left() + rect.width() * offset / duration;
+
+    for s in &samples[samples.len().saturating_sub(MAX_POINTS)..] {
+        let position = Pos2::new(x(s.get_offset() as f32), rect.bottom() - rect.height() * s.get_latency() as f32 / max_latency);
+        let color = if s.is_passed() { Color32::from_rgb(60, 160, 90) } else { Color32::from_rgb(220, 60, 60) };
+        painter.circle_filled(position, 1.5, color);
+    }
+
+    // The throughput of a second is drawn at the middle of the second.
+    let points : Vec<Pos2> = throughput.iter().enumerate()
+        .map(|(i, t)| Pos2::new(x(i as f32 * 1000.0 + 500.0).min(rect.right()), rect.bottom() - rect.height() * *t as f32 / max_throughput))
+        .collect();
+    painter.add(Shape::line(points, Stroke::new(1.5, Color32::from_rgb(70, 130, 220))));
+
+    let font = FontId::monospace(10.0);
+    let color = visuals.text_color();
+    painter.text(rect.left_top() - Vec2::new(4.0, 0.0), Align2::RIGHT_TOP, format!("{} ms", max_latency), font.clone(), color);
+    painter.text(rect.left_bottom() - Vec2::new(4.0, 0.0), Align2::RIGHT_BOTTOM, "0 ms", font.clone(), color);
+    painter.text(rect.right_top() + Vec2::new(4.0, 0.0), Align2::LEFT_TOP, format!("{}/s", max_throughput), font.clone(), Color32::from_rgb(70, 130, 220));
+    painter.text(rect.right_bottom() + Vec2::new(0.0, 4.0), Align2::RIGHT_TOP, format!("{:.1} s", duration / 1000.0), font, color);
+}
@@ -1,8 +1,21 @@
+use std::collections::HashMap;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
 use egui::{ CollapsingHeader, Ui };
-use log::info;
+use log::{error, info};
 use rockery_datamodel::prelude::*;
 use eframe::egui;
 
+/// A test suite run that writes its reports and returns the project variables after the run.
+type TestSuiteRun = JoinHandle<Result<HashMap<String, String>, String>>;
+
+/// Test suites running in the background, by project and test suite name.
+#[derive(Default)]
+pub struct TestSuiteRuns {
+    runs : HashMap<(String, String), TestSuiteRun>,
+}
+
 pub fn draw_service_ui(ui: &mut Ui, ser : &mut RockeryService) {
     CollapsingHeader::new(ser.get_name())
         .default_open(false)
@@ -18,11 +31,34 @@ pub fn draw_service_ui(ui: &mut Ui, ser : &mut RockeryService) {
                         ui.selectable_value(&mut service_type, &RockeryServiceType::Soap, RockeryServiceType::Soap.to_string());
                     });
                     // Todo: Set service value
-            })
+            });
+
+            for (index, method) in ser.get_methods().iter().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.label(format!("{} {}", method.get_request_method(), method.get_name()));
+                    if ui.button("Copy as curl").on_hover_text("Copy the request as curl command").clicked() {
+                        match RockeryCurlExporter::export(ser, index, &[]) {
+                            Ok(command) => ui.ctx().copy_text(command),
+                            Err(e) => error!("Could not create curl command. Error: {}", e),
+                        }
+                    }
+                    ui.menu_button("Copy as code", |ui| {
+                        for language in RockeryCodeLanguage::ALL {
+                            if ui.button(language.to_string()).clicked() {
+                                match RockeryCodeGenerator::generate(ser, index, &[], language) {
+                                    Ok(code) => ui.ctx().copy_text(code),
+                                    Err(e) => error!("Could not create {} code. Error: {}", language, e),
+                                }
+                                ui.close_menu();
+                            }
+                        }
+                    });
+                });
+            }
         });
 }
 
-pub fn draw_project_ui(ui: &mut Ui, p : &mut RockeryProject) {
+pub fn draw_project_ui(ui: &mut Ui, p : &mut RockeryProject, runs : &mut TestSuiteRuns) {
     CollapsingHeader::new(p.get_name())
         .default_open(false)
         .show(ui, |ui| {
@@ -40,5 +76,72 @@ pub fn draw_project_ui(ui: &mut Ui, p : &mut RockeryProject) {
             for pro in p.get_services().values_mut() {
                 draw_service_ui(ui,pro);
             }
+
+            for index in 0..p.get_test_suites().len() {
+                draw_test_suite_ui(ui, p, index, runs);
+            }
         });
-}
\ No newline at end of file
+}
+
+/// Shows a test suite with a button to run it. The suite runs in the background on a copy of the project,
+/// variables set by the run are taken over when it has finished. The reports of a run are written to the
+/// temp directory by the run as well.
+pub fn draw_test_suite_ui(ui: &mut Ui, p : &mut RockeryProject, index : usize, runs : &mut TestSuiteRuns) {
+    let name = p.get_test_suites()[index].get_name().to_owned();
+    let key = (p.get_name().to_owned(), name.clone());
+
+    ui.horizontal(|ui| {
+        ui.label(format!("Test suite {}", name));
+
+        match runs.runs.get(&key) {
+            Some(run) if !run.is_finished() => {
+                ui.spinner();
+                ui.ctx().request_repaint_after(Duration::from_millis(250));
+                return;
+            },
+            Some(_) => {
+                let run = runs.runs.remove(&key).unwrap();
+                match run.join() {
+                    Ok(Ok(variables)) => {
+                        for (k, v) in variables {
+                            if p.get_variables().get(&k) != Some(&v) {
+                                p.set_variable(&k, &v);
+                            }
+                        }
+                    },
+                    Ok(Err(e)) => error!("Could not run test suite '{}'. Error: {}", name, e),
+                    Err(_) => error!("Could not run test suite '{}'. The run has been aborted.", name),
+                }
+            },
+            None => { },
+        }
+
+        if ui.button("Run").on_hover_text(format!("Run '{}' and write reports", name)).clicked() {
+            let mut project = p.clone();
+            runs.runs.insert(key, thread::spawn(move || {
+                let result = project.run_test_suite(index)?;
+                write_reports(project.get_name(), &name, result);
+                Ok(project.get_variables().clone())
+            }));
+        }
+    });
+}
+
+fn write_reports(project : &str, name : &str, result : RockeryTestSuiteResult) {
+    let report = RockeryTestReport::new(project, vec![result]);
+    info!("Test suite '{}': {} test cases, {} passed, {} failed", name, report.get_test_cases(), report.get_passed(), report.get_failed());
+
+    let directory = std::env::temp_dir().join("rockery_reports");
+    if let Err(e) = std::fs::create_dir_all(&directory) {
+        error!("Could not create the report directory. Error: {}", e);
+        return;
+    }
+
+    for extension in ["html", "xml", "json", "har"] {
+        let path = directory.join(format!("{}_{}.{}", project, name, extension));
+        match report.save(&path) {
+            Ok(_) => info!("Report written to {}", path.display()),
+            Err(e) => error!("{}", e),
+        }
+    }
+}